@@ -1,17 +1,25 @@
 use base64::Engine;
-use clipboard_rs::{Clipboard, ClipboardContext};
+use clipboard_rs::{common::RustImage, Clipboard, ClipboardContext, RustImageData};
 use ffmpeg_sidecar::download::{download_ffmpeg_package, ffmpeg_download_url, unpack_ffmpeg};
+use image::{ImageFormat as RasterFormat, RgbaImage};
+use memmap2::Mmap;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::{
+    cell::Cell,
     cmp::Ordering as CmpOrdering,
-    collections::{HashMap, HashSet},
+    collections::{
+        hash_map::{DefaultHasher, Entry},
+        BTreeMap, HashMap, HashSet, VecDeque,
+    },
     env, fs,
-    io::{Read, Write},
-    path::{Path, PathBuf},
+    hash::{Hash, Hasher},
+    io::{Cursor, Read, Seek, Write},
+    path::{Component, Path, PathBuf},
     process::{Command, Stdio},
     sync::{
-        atomic::{AtomicUsize, Ordering as AtomicOrdering},
-        mpsc, Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering as AtomicOrdering},
+        mpsc, Arc, Condvar, Mutex, MutexGuard,
     },
     thread,
     time::{Duration, Instant, SystemTime, UNIX_EPOCH},
@@ -21,6 +29,7 @@ use tauri::{
     menu::{MenuBuilder, SubmenuBuilder},
     AppHandle, Emitter, Manager, State,
 };
+use tauri_plugin_opener::OpenerExt;
 use uuid::Uuid;
 use walkdir::WalkDir;
 use zip::ZipArchive;
@@ -28,15 +37,304 @@ use zip::ZipArchive;
 const ROOT_NODE_ID: &str = "root";
 const MAX_SCAN_WORKERS: usize = 4;
 const MAX_EXPORT_WORKERS: usize = 16;
-const SCAN_CACHE_SCHEMA_VERSION: u32 = 1;
-const SCAN_CACHE_MAX_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+const WORKER_LIMIT_CEILING: usize = 64;
+const SCAN_CACHE_SCHEMA_VERSION: u32 = 14;
+const DEFAULT_SCAN_CACHE_MAX_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+const SCAN_CACHE_MIN_BYTES: u64 = 64 * 1024 * 1024;
+const SETTINGS_FILE_NAME: &str = "settings.json";
 const SCAN_CANCEL_CHECK_INTERVAL: usize = 128;
+const SEARCH_CACHE_CAPACITY: usize = 8;
+const LOG_FILE_NAME: &str = "mae.log";
+/// `mae.log` is moved to `mae.log.1` (replacing the previous one) at this size.
+const LOG_FILE_MAX_BYTES: u64 = 5 * 1024 * 1024;
+const NESTED_ARCHIVE_MAX_DEPTH: usize = 3;
+/// Directory containers are split into subtrees this many levels down
+/// (`assets/<namespace>/<category>/<folder>`) for parallel walking.
+const DIRECTORY_SPLIT_DEPTH: usize = 4;
+const THUMBNAIL_PIXEL_ART_MAX_EDGE: u32 = 256;
+/// Mono decode rate for waveforms; plenty for drawing peaks and cheap to pipe.
+const WAVEFORM_SAMPLE_RATE: u32 = 8000;
+const WAVEFORM_MAX_BUCKETS: usize = 4096;
+/// Shader and config dumps can be huge; the preview pane only needs the start.
+const TEXT_PREVIEW_MAX_BYTES: usize = 256 * 1024;
+const MODEL_PARENT_MAX_DEPTH: usize = 32;
+const VANILLA_MISSING_REPORT_LIMIT: usize = 200;
+const EXPORT_MANIFEST_FILE_NAME: &str = "export-manifest.json";
+const JPEG_DEFAULT_BACKGROUND: [u8; 3] = [255, 255, 255];
+const EXPORT_PROGRESS_THROTTLE: Duration = Duration::from_millis(125);
+/// Largest single write an export makes, so byte progress moves within a file.
+const EXPORT_WRITE_CHUNK_BYTES: usize = 256 * 1024;
+/// Bounds the open file descriptors held by `ArchiveHandleCache`.
+const ARCHIVE_HANDLE_CACHE_CAPACITY: usize = 16;
+/// Weight of the newest sample in the smoothed container throughput.
+const SCAN_RATE_SMOOTHING: f64 = 0.3;
 
 #[derive(Default)]
 struct AppState {
     scans: Mutex<HashMap<String, ScanState>>,
     export_operations: Mutex<HashMap<String, ExportOperationState>>,
     temp_paths: Mutex<Vec<PathBuf>>,
+    settings: Mutex<AppSettings>,
+    archive_handles: Mutex<ArchiveHandleCache>,
+    /// Held around every change to the scan cache directory and its manifest.
+    scan_cache_dir: Mutex<()>,
+}
+
+/// Archive handles reused across preview commands, most recently used last.
+/// Handles are checked out while in use so slow decodes never hold the lock,
+/// and are dropped once the archive's size or mtime changes on disk. They read
+/// through the file, never a memory map, since they outlive any one command.
+/// Salvaged archives keep their entry offsets here instead of a handle.
+#[derive(Default)]
+struct ArchiveHandleCache {
+    entries: Vec<CachedArchiveHandle>,
+}
+
+struct CachedArchiveHandle {
+    container_path: String,
+    stamp: (u64, u64),
+    archive: CachedArchive,
+}
+
+/// An opened archive, or the salvaged entries of one whose central directory
+/// is broken.
+enum CachedArchive {
+    Zip(ZipArchive<ArchiveReader>),
+    Salvaged(SalvagedArchive),
+}
+
+/// Archives opened during one operation, keyed by container path.
+type ArchiveCache = HashMap<String, CachedArchive>;
+
+impl ArchiveHandleCache {
+    fn check_out(&mut self, container_path: &str) -> Option<CachedArchive> {
+        let position = self
+            .entries
+            .iter()
+            .position(|entry| entry.container_path == container_path)?;
+        let entry = self.entries.remove(position);
+        (archive_stamp(container_path) == Some(entry.stamp)).then_some(entry.archive)
+    }
+
+    fn check_in(&mut self, container_path: String, archive: CachedArchive) {
+        let Some(stamp) = archive_stamp(&container_path) else {
+            return;
+        };
+        self.entries
+            .retain(|entry| entry.container_path != container_path);
+        if self.entries.len() >= ARCHIVE_HANDLE_CACHE_CAPACITY {
+            self.entries.remove(0);
+        }
+        self.entries.push(CachedArchiveHandle {
+            container_path,
+            stamp,
+            archive,
+        });
+    }
+}
+
+fn archive_stamp(container_path: &str) -> Option<(u64, u64)> {
+    let metadata = fs::metadata(container_path).ok()?;
+    Some((metadata.len(), file_mtime_ms(&metadata)))
+}
+
+/// Runs `read` with the shared, file-backed handle for `container_path`
+/// pre-loaded, then returns it (or the salvaged entries `read` found instead)
+/// to the shared cache. Other archives `read` opens are memory-mapped and
+/// dropped with this call.
+fn with_archive_handles<T>(
+    state: &AppState,
+    container_path: &str,
+    read: impl FnOnce(&mut ArchiveCache) -> T,
+) -> T {
+    let mut archive_cache = HashMap::new();
+    let shared = state
+        .archive_handles
+        .lock()
+        .ok()
+        .and_then(|mut handles| handles.check_out(container_path))
+        .or_else(|| {
+            let reader = ArchiveReader::open_file(Path::new(container_path)).ok()?;
+            ZipArchive::new(reader).ok().map(CachedArchive::Zip)
+        });
+    let is_shared = shared.is_some();
+    if let Some(archive) = shared {
+        archive_cache.insert(container_path.to_string(), archive);
+    }
+
+    let result = read(&mut archive_cache);
+
+    let reusable = archive_cache
+        .remove(container_path)
+        .filter(|archive| is_shared || matches!(archive, CachedArchive::Salvaged(_)));
+    if let Some(archive) = reusable {
+        if let Ok(mut handles) = state.archive_handles.lock() {
+            handles.check_in(container_path.to_string(), archive);
+        }
+    }
+    result
+}
+
+fn extract_shared_asset_bytes(state: &AppState, asset: &AssetRecord) -> Result<Vec<u8>, String> {
+    with_archive_handles(state, &asset.container_path, |archive_cache| {
+        extract_asset_bytes_with_archive_cache(asset, archive_cache)
+    })
+}
+
+/// User preferences persisted to `settings.json`; unset fields use built-in defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct AppSettings {
+    max_cache_bytes: Option<u64>,
+    scan_workers: Option<usize>,
+    export_workers: Option<usize>,
+    fuzzy_mode: Option<FuzzyMode>,
+    default_audio_format: Option<AudioFormat>,
+    default_image_format: Option<ImageFormat>,
+    /// Keyed by `last_scan_options_key`.
+    last_scan_options: BTreeMap<String, LastScanOptions>,
+}
+
+/// Partial update for `update_settings`; omitted fields keep their stored value.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct SettingsPatch {
+    max_cache_bytes: Option<u64>,
+    scan_workers: Option<usize>,
+    export_workers: Option<usize>,
+    fuzzy_mode: Option<FuzzyMode>,
+    default_audio_format: Option<AudioFormat>,
+    default_image_format: Option<ImageFormat>,
+}
+
+/// The checkboxes of the most recent `start_scan` for one instance, stored
+/// as sent so the UI can restore them verbatim.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LastScanOptions {
+    include_vanilla: bool,
+    include_mods: bool,
+    include_resourcepacks: bool,
+    include_server_packs: Option<bool>,
+    include_datapacks: Option<bool>,
+    include_shaderpacks: Option<bool>,
+    #[serde(default)]
+    include_disabled_mods: Option<bool>,
+    collect_dimensions: Option<bool>,
+    launcher_kind: Option<LauncherKind>,
+    vanilla_version_override: Option<String>,
+    #[serde(default)]
+    group_by_instance: Option<bool>,
+    #[serde(default)]
+    follow_symlinks: Option<bool>,
+}
+
+impl LastScanOptions {
+    fn from_request(req: &StartScanRequest) -> Self {
+        Self {
+            include_vanilla: req.include_vanilla,
+            include_mods: req.include_mods,
+            include_resourcepacks: req.include_resourcepacks,
+            include_server_packs: req.include_server_packs,
+            include_datapacks: req.include_datapacks,
+            include_shaderpacks: req.include_shaderpacks,
+            include_disabled_mods: req.include_disabled_mods,
+            collect_dimensions: req.collect_dimensions,
+            launcher_kind: req.launcher_kind,
+            vanilla_version_override: req.vanilla_version_override.clone(),
+            group_by_instance: req.group_by_instance,
+            follow_symlinks: req.follow_symlinks,
+        }
+    }
+
+    fn to_request(&self, prism_root: String, instance_folder: String) -> StartScanRequest {
+        StartScanRequest {
+            prism_root,
+            instance_folder,
+            include_vanilla: self.include_vanilla,
+            include_mods: self.include_mods,
+            include_resourcepacks: self.include_resourcepacks,
+            include_server_packs: self.include_server_packs,
+            include_datapacks: self.include_datapacks,
+            include_shaderpacks: self.include_shaderpacks,
+            include_disabled_mods: self.include_disabled_mods,
+            collect_dimensions: self.collect_dimensions,
+            force_rescan: None,
+            launcher_kind: self.launcher_kind,
+            vanilla_version_override: self.vanilla_version_override.clone(),
+            group_by_instance: self.group_by_instance,
+            follow_symlinks: self.follow_symlinks,
+        }
+    }
+}
+
+/// What `start_scan` would find for an instance, so the UI can disable options
+/// that can't work. Everything past `version_error` stays empty when it is set.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct InstanceValidation {
+    mc_version: Option<String>,
+    version_error: Option<String>,
+    client_jar_path: Option<String>,
+    client_jar_exists: bool,
+    asset_index_exists: bool,
+    mods_dir_exists: bool,
+    /// Includes a Technic pack's `modpack.jar`.
+    mod_count: usize,
+    resourcepacks_dir_exists: bool,
+    resourcepack_count: usize,
+}
+
+fn last_scan_options_key(prism_root: &str, instance_folder: &str) -> String {
+    format!("{}::{}", prism_root.trim(), instance_folder.trim())
+}
+
+impl AppSettings {
+    fn apply(&mut self, patch: SettingsPatch) {
+        if let Some(bytes) = patch.max_cache_bytes {
+            self.max_cache_bytes = Some(bytes.max(SCAN_CACHE_MIN_BYTES));
+        }
+        if patch.scan_workers.is_some() {
+            self.scan_workers = patch.scan_workers;
+        }
+        if patch.export_workers.is_some() {
+            self.export_workers = patch.export_workers;
+        }
+        if patch.fuzzy_mode.is_some() {
+            self.fuzzy_mode = patch.fuzzy_mode;
+        }
+        if patch.default_audio_format.is_some() {
+            self.default_audio_format = patch.default_audio_format;
+        }
+        if patch.default_image_format.is_some() {
+            self.default_image_format = patch.default_image_format;
+        }
+    }
+
+    fn max_cache_bytes(&self) -> u64 {
+        self.max_cache_bytes
+            .unwrap_or(DEFAULT_SCAN_CACHE_MAX_BYTES)
+            .max(SCAN_CACHE_MIN_BYTES)
+    }
+
+    fn worker_limits(&self) -> WorkerLimits {
+        let clamp = |value: Option<usize>, default: usize| {
+            value.unwrap_or(default).clamp(1, WORKER_LIMIT_CEILING)
+        };
+        WorkerLimits {
+            scan_workers: clamp(self.scan_workers, MAX_SCAN_WORKERS),
+            export_workers: clamp(self.export_workers, MAX_EXPORT_WORKERS),
+        }
+    }
+}
+
+/// Upper bounds on worker threads; the actual count is also capped by the
+/// available parallelism and the amount of work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WorkerLimits {
+    scan_workers: usize,
+    export_workers: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -62,11 +360,76 @@ struct ScanState {
     asset_index: HashMap<String, usize>,
     search_records: Vec<AssetSearchRecord>,
     tree_children: HashMap<String, Vec<TreeNode>>,
+    /// Node id to its position among its siblings in `tree_children`, so adding
+    /// an asset doesn't scan them; rebuilt with the tree, never snapshotted.
+    tree_index: HashMap<String, usize>,
     container_assets: HashMap<String, Vec<AssetRecord>>,
     container_signatures: HashMap<String, ContainerSignature>,
+    /// Containers found by the latest scan or refresh; not part of snapshots,
+    /// so it stays empty until a cache hit's refresh has listed them again.
+    containers: Vec<ScanContainer>,
     id_aliases: HashMap<String, String>,
+    source_asset_indices: Option<HashMap<String, Vec<usize>>>,
+    /// Node id to `(parent id, name)`, rebuilt lazily like `source_asset_indices`.
+    tree_parents: Option<HashMap<String, (String, String)>>,
+    /// Most recently used last; cleared together with the other derived indexes.
+    search_cache: Vec<(SearchCacheKey, Arc<SearchOrder>)>,
+    /// Bumped whenever derived indexes are dropped so old search cursors expire.
+    search_generation: u64,
     cache_key: Option<String>,
     last_progress_emit_at: Option<Instant>,
+    timing: ScanTiming,
+    /// Containers skipped because they failed to scan, in the order they failed.
+    warnings: Vec<ScanWarning>,
+    /// Containers that were scanned but contributed no assets.
+    empty_sources: Vec<EmptySource>,
+}
+
+/// Container throughput for the current scan phase, sampled on each progress
+/// event rather than per asset.
+#[derive(Debug, Clone)]
+struct ScanTiming {
+    started_at: Instant,
+    last_sample: (Instant, usize),
+    containers_per_second: Option<f64>,
+}
+
+impl ScanTiming {
+    fn start(now: Instant) -> Self {
+        Self {
+            started_at: now,
+            last_sample: (now, 0),
+            containers_per_second: None,
+        }
+    }
+
+    /// Folds `scanned_containers` at `now` into the smoothed rate. A count of
+    /// zero or one lower than the last sample starts a new phase.
+    fn record(&mut self, now: Instant, scanned_containers: usize) {
+        let (last_at, last_scanned) = self.last_sample;
+        if scanned_containers == 0 || scanned_containers < last_scanned {
+            *self = Self::start(now);
+            self.last_sample.1 = scanned_containers;
+            return;
+        }
+
+        let seconds = now.saturating_duration_since(last_at).as_secs_f64();
+        let completed = scanned_containers - last_scanned;
+        if completed == 0 || seconds <= 0.0 {
+            return;
+        }
+
+        let rate = completed as f64 / seconds;
+        self.containers_per_second = Some(match self.containers_per_second {
+            Some(previous) => previous + SCAN_RATE_SMOOTHING * (rate - previous),
+            None => rate,
+        });
+        self.last_sample = (now, scanned_containers);
+    }
+
+    fn elapsed_ms(&self, now: Instant) -> u64 {
+        now.saturating_duration_since(self.started_at).as_millis() as u64
+    }
 }
 
 impl ScanState {
@@ -85,12 +448,186 @@ impl ScanState {
             asset_index: HashMap::new(),
             search_records: Vec::new(),
             tree_children,
+            tree_index: HashMap::new(),
             container_assets: HashMap::new(),
             container_signatures: HashMap::new(),
+            containers: Vec::new(),
             id_aliases: HashMap::new(),
+            source_asset_indices: None,
+            tree_parents: None,
+            search_cache: Vec::new(),
+            search_generation: 0,
             cache_key: None,
             last_progress_emit_at: None,
+            timing: ScanTiming::start(Instant::now()),
+            warnings: Vec::new(),
+            empty_sources: Vec::new(),
+        }
+    }
+
+    /// Drops everything computed from `assets` and `tree_children`; call after
+    /// either changes.
+    fn invalidate_derived_indexes(&mut self) {
+        self.source_asset_indices = None;
+        self.tree_parents = None;
+        self.search_cache.clear();
+        self.search_generation += 1;
+    }
+
+    /// Files one container's pool outcome: its assets plus a warning for each
+    /// problem it was scanned despite, or only a warning when nothing could be
+    /// read. Returns the warnings added so the caller can emit them.
+    fn record_scanned_container(
+        &mut self,
+        container: &ScanContainer,
+        instances: &[ScanInstance],
+        outcome: PooledScan<ContainerSignature>,
+        key_counts: &mut HashMap<String, usize>,
+    ) -> Vec<ScanWarning> {
+        let container_key = scan_container_key(container);
+        let warning = |error| ScanWarning {
+            container_key: container_key.clone(),
+            source_name: container.source_name.clone(),
+            error,
+        };
+        let (signature, candidates, problems) = match outcome {
+            Ok(scanned) => scanned,
+            Err(error) => {
+                let warnings = vec![warning(error)];
+                self.warnings.extend(warnings.iter().cloned());
+                return warnings;
+            }
+        };
+
+        let warnings = problems.into_iter().map(warning).collect::<Vec<_>>();
+        self.warnings.extend(warnings.iter().cloned());
+        let assets = finalize_container_assets(candidates, instances, key_counts);
+        self.append_container_assets(&container_key, signature, &assets, &container.source_name);
+        warnings
+    }
+
+    fn append_container_assets(
+        &mut self,
+        container_key: &str,
+        signature: ContainerSignature,
+        assets: &[AssetRecord],
+        source_name: &str,
+    ) {
+        self.container_signatures
+            .insert(container_key.to_string(), signature);
+
+        let mut appended_for_container = Vec::<AssetRecord>::new();
+        for asset in assets {
+            if self.asset_index.contains_key(&asset.asset_id) {
+                continue;
+            }
+
+            let index = self.assets.len();
+            self.asset_index.insert(asset.asset_id.clone(), index);
+            self.search_records.push(build_search_record(asset));
+            self.assets.push(asset.clone());
+            appended_for_container.push(asset.clone());
+            add_asset_to_tree(&mut self.tree_children, &mut self.tree_index, asset);
+        }
+        if appended_for_container.is_empty() {
+            self.empty_sources.push(EmptySource {
+                container_key: container_key.to_string(),
+                source_name: source_name.to_string(),
+            });
+        }
+        self.container_assets
+            .insert(container_key.to_string(), appended_for_container);
+        self.invalidate_derived_indexes();
+    }
+
+    /// Throttles progress events; `force` is for the last container and for
+    /// warnings, which should show up at once.
+    fn progress_due(&mut self, force: bool) -> bool {
+        const PROGRESS_THROTTLE: Duration = Duration::from_millis(125);
+
+        let now = Instant::now();
+        let elapsed = self
+            .last_progress_emit_at
+            .map(|last| now.saturating_duration_since(last))
+            .unwrap_or(PROGRESS_THROTTLE);
+        if !force && elapsed < PROGRESS_THROTTLE {
+            return false;
+        }
+        self.last_progress_emit_at = Some(now);
+        true
+    }
+
+    fn cached_search(&mut self, key: &SearchCacheKey) -> Option<Arc<SearchOrder>> {
+        let position = self
+            .search_cache
+            .iter()
+            .position(|(cached, _)| cached == key)?;
+        let entry = self.search_cache.remove(position);
+        let ordered = Arc::clone(&entry.1);
+        self.search_cache.push(entry);
+        Some(ordered)
+    }
+
+    fn cache_search(&mut self, key: SearchCacheKey, ordered: Arc<SearchOrder>) {
+        self.search_cache.retain(|(cached, _)| *cached != key);
+        if self.search_cache.len() >= SEARCH_CACHE_CAPACITY {
+            self.search_cache.remove(0);
+        }
+        self.search_cache.push((key, ordered));
+    }
+
+    /// Returns the sorted asset indices belonging to any of `source_names`,
+    /// building the per-source index on first use after the assets changed.
+    fn indices_for_sources(&mut self, source_names: &[String]) -> Vec<usize> {
+        let assets = &self.assets;
+        let by_source = self.source_asset_indices.get_or_insert_with(|| {
+            let mut by_source = HashMap::<String, Vec<usize>>::new();
+            for (index, asset) in assets.iter().enumerate() {
+                by_source
+                    .entry(asset.source_name.clone())
+                    .or_default()
+                    .push(index);
+            }
+            by_source
+        });
+
+        let mut indices = source_names
+            .iter()
+            .filter_map(|name| by_source.get(name))
+            .flatten()
+            .copied()
+            .collect::<Vec<_>>();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+
+    /// Breadcrumb from the top-level folder down to `node_id`; the synthetic
+    /// root itself is left out, so it resolves to an empty path.
+    fn tree_path(&mut self, node_id: &str) -> Option<Vec<TreeBreadcrumb>> {
+        let tree_children = &self.tree_children;
+        let parents = self.tree_parents.get_or_insert_with(|| {
+            let mut parents = HashMap::new();
+            for (parent_id, children) in tree_children {
+                for child in children {
+                    parents.insert(child.id.clone(), (parent_id.clone(), child.name.clone()));
+                }
+            }
+            parents
+        });
+
+        let mut path = Vec::new();
+        let mut current = node_id;
+        while current != ROOT_NODE_ID {
+            let (parent_id, name) = parents.get(current)?;
+            path.push(TreeBreadcrumb {
+                id: current.to_string(),
+                name: name.clone(),
+            });
+            current = parent_id;
         }
+        path.reverse();
+        Some(path)
     }
 
     fn as_status(&self, scan_id: &str) -> ScanStatus {
@@ -102,6 +639,7 @@ impl ScanState {
             total_containers: self.total_containers,
             asset_count: self.assets.len(),
             error: self.error.clone(),
+            warnings: self.warnings.clone(),
         }
     }
 }
@@ -113,9 +651,12 @@ struct AssetSearchRecord {
     path_tokens: Vec<String>,
     namespace_tokens: Vec<String>,
     source_tokens: Vec<String>,
+    location_tokens: Vec<String>,
     compact_all: String,
     compact_filename: String,
     compact_filename_stem: String,
+    compact_location: String,
+    relative_path: String,
     key: String,
     folder_node_id: String,
 }
@@ -127,6 +668,62 @@ struct PrismRootCandidate {
     exists: bool,
     valid: bool,
     source: String,
+    launcher: LauncherKind,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum LauncherKind {
+    #[default]
+    Prism,
+    MultiMc,
+    Technic,
+    AtLauncher,
+    Vanilla,
+}
+
+impl LauncherKind {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Prism => "Prism",
+            Self::MultiMc => "MultiMC",
+            Self::Technic => "Technic",
+            Self::AtLauncher => "ATLauncher",
+            Self::Vanilla => "Minecraft Launcher",
+        }
+    }
+
+    fn instances_dir_name(self) -> &'static str {
+        match self {
+            Self::Prism | Self::MultiMc | Self::AtLauncher => "instances",
+            Self::Technic => "modpacks",
+            // The vanilla launcher has no instances; each installed version profile stands in.
+            Self::Vanilla => "versions",
+        }
+    }
+
+    fn is_valid_root(self, path: &Path) -> bool {
+        match self {
+            Self::Prism | Self::MultiMc => is_valid_instance_root(path),
+            Self::Technic => path.is_dir() && path.join("modpacks").is_dir(),
+            Self::AtLauncher => {
+                path.is_dir() && path.join("instances").is_dir() && path.join("configs").is_dir()
+            }
+            Self::Vanilla => path.is_dir() && path.join("versions").is_dir(),
+        }
+    }
+}
+
+/// Where an instance keeps its game folder and vanilla files, resolved per launcher
+/// so container collection does not need to know which launcher laid it out.
+#[derive(Debug, Clone)]
+struct InstanceLayout {
+    instance_dir: PathBuf,
+    game_dir: PathBuf,
+    mc_version: String,
+    client_jar: Option<PathBuf>,
+    asset_index_path: Option<PathBuf>,
+    modpack_jar: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -144,6 +741,8 @@ enum AssetSourceType {
     Vanilla,
     Mod,
     ResourcePack,
+    Datapack,
+    ShaderPack,
 }
 
 impl AssetSourceType {
@@ -152,6 +751,8 @@ impl AssetSourceType {
             AssetSourceType::Vanilla => "vanilla",
             AssetSourceType::Mod => "mods",
             AssetSourceType::ResourcePack => "resourcepacks",
+            AssetSourceType::Datapack => "datapacks",
+            AssetSourceType::ShaderPack => "shaderpacks",
         }
     }
 
@@ -160,6 +761,21 @@ impl AssetSourceType {
             AssetSourceType::Vanilla => "vanilla",
             AssetSourceType::Mod => "mod",
             AssetSourceType::ResourcePack => "resourcepack",
+            AssetSourceType::Datapack => "datapack",
+            AssetSourceType::ShaderPack => "shaderpack",
+        }
+    }
+
+    /// Which copy of a namespaced path the game renders: resource packs over
+    /// mods over vanilla. `None` for datapacks, whose `data/` files never
+    /// shadow anything under `assets/`.
+    fn override_priority(&self) -> Option<u8> {
+        match self {
+            AssetSourceType::ResourcePack => Some(2),
+            AssetSourceType::Mod => Some(1),
+            // Shader pack files live outside `assets/` and never shadow anything.
+            AssetSourceType::Vanilla | AssetSourceType::ShaderPack => Some(0),
+            AssetSourceType::Datapack => None,
         }
     }
 }
@@ -185,9 +801,31 @@ struct AssetRecord {
     extension: String,
     is_image: bool,
     is_audio: bool,
+    /// GLSL sources, shown with the text preview.
+    is_shader: bool,
     container_path: String,
     container_type: AssetContainerType,
     entry_path: String,
+    is_extractable: bool,
+    width: Option<u32>,
+    height: Option<u32>,
+    byte_size: Option<u64>,
+    /// Name from the mod's own metadata; `source_name` stays the jar stem.
+    /// Assets of jar-in-jar entries carry the nested mod's name.
+    mod_display_name: Option<String>,
+    /// Id from the same metadata as `mod_display_name`.
+    mod_id: Option<String>,
+    /// Instance the asset came from; set by multi-instance scans and by
+    /// scans that asked for an instance level in the tree.
+    instance_folder: Option<String>,
+    /// Label for the instance tree node; `instance_folder` stays in node ids.
+    instance_display_name: Option<String>,
+    /// Comes from a mod or pack the launcher has disabled (`*.disabled`).
+    disabled: bool,
+    /// Asset id of the entry in the same container whose path differs only in
+    /// letter case and is the one the game loads: the all-lowercase one, or
+    /// the first seen when none is.
+    shadowed_by: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -198,6 +836,15 @@ struct TreeNode {
     node_type: TreeNodeType,
     has_children: bool,
     asset_id: Option<String>,
+    /// Files anywhere below a folder; always 1 for file nodes.
+    asset_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct TreeBreadcrumb {
+    id: String,
+    name: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -215,7 +862,64 @@ struct StartScanRequest {
     include_vanilla: bool,
     include_mods: bool,
     include_resourcepacks: bool,
+    include_server_packs: Option<bool>,
+    include_datapacks: Option<bool>,
+    include_shaderpacks: Option<bool>,
+    /// Also scans `*.jar.disabled` mods and `*.zip.disabled` resource packs.
+    include_disabled_mods: Option<bool>,
+    collect_dimensions: Option<bool>,
     force_rescan: Option<bool>,
+    launcher_kind: Option<LauncherKind>,
+    /// Scans this Minecraft version's client jar and asset index instead of
+    /// the one the instance declares.
+    vanilla_version_override: Option<String>,
+    /// Nests the tree under an instance folder, as multi-instance scans do.
+    group_by_instance: Option<bool>,
+    /// Descends into symlinked folders inside directory containers.
+    follow_symlinks: Option<bool>,
+}
+
+/// Scans several instances of one launcher root into a single scan. These
+/// scans skip the snapshot cache, and containers shared by instances (such as
+/// a common client jar) are scanned once for the first instance listing them.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StartMultiScanRequest {
+    prism_root: String,
+    instance_folders: Vec<String>,
+    include_vanilla: bool,
+    include_mods: bool,
+    include_resourcepacks: bool,
+    include_server_packs: Option<bool>,
+    include_datapacks: Option<bool>,
+    include_shaderpacks: Option<bool>,
+    /// Also scans `*.jar.disabled` mods and `*.zip.disabled` resource packs.
+    include_disabled_mods: Option<bool>,
+    collect_dimensions: Option<bool>,
+    launcher_kind: Option<LauncherKind>,
+    follow_symlinks: Option<bool>,
+}
+
+impl StartMultiScanRequest {
+    fn instance_request(&self, instance_folder: &str) -> StartScanRequest {
+        StartScanRequest {
+            prism_root: self.prism_root.clone(),
+            instance_folder: instance_folder.to_string(),
+            include_vanilla: self.include_vanilla,
+            include_mods: self.include_mods,
+            include_resourcepacks: self.include_resourcepacks,
+            include_server_packs: self.include_server_packs,
+            include_datapacks: self.include_datapacks,
+            include_shaderpacks: self.include_shaderpacks,
+            include_disabled_mods: self.include_disabled_mods,
+            collect_dimensions: self.collect_dimensions,
+            force_rescan: Some(true),
+            launcher_kind: self.launcher_kind,
+            vanilla_version_override: None,
+            group_by_instance: Some(true),
+            follow_symlinks: self.follow_symlinks,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -227,6 +931,28 @@ struct StartScanResponse {
     refresh_mode: Option<String>,
 }
 
+/// Emitted as `scan://cache-hit` once the bootstrap worker knows whether the
+/// snapshot actually loaded; `StartScanResponse` only reflects a file-exists guess.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScanCacheEvent {
+    scan_id: String,
+    cache_hit: bool,
+    refresh_started: bool,
+    refresh_mode: Option<String>,
+}
+
+impl ScanCacheEvent {
+    fn new(scan_id: &str, cache_hit: bool) -> Self {
+        Self {
+            scan_id: scan_id.to_string(),
+            cache_hit,
+            refresh_started: cache_hit,
+            refresh_mode: cache_hit.then(|| "incremental".to_string()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct ScanStatus {
@@ -237,6 +963,33 @@ struct ScanStatus {
     total_containers: usize,
     asset_count: usize,
     error: Option<String>,
+    warnings: Vec<ScanWarning>,
+}
+
+/// A container that could not be read; the scan continues without its assets.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScanWarning {
+    container_key: String,
+    source_name: String,
+    error: String,
+}
+
+/// A scanned container that yielded no assets, such as a code-only mod.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct EmptySource {
+    container_key: String,
+    source_name: String,
+}
+
+/// Emitted on `scan://warning` for each container problem as it's recorded; the
+/// full list is part of the scan status.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScanWarningEvent {
+    scan_id: String,
+    warning: ScanWarning,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -265,6 +1018,11 @@ struct ScanProgressEvent {
     asset_count: usize,
     phase: ScanPhase,
     current_source: Option<String>,
+    /// Time since the current phase started; filled in by `emit_scan_progress`.
+    elapsed_ms: u64,
+    /// Smoothed container throughput, `None` until a container completes.
+    /// Filled in by `emit_scan_progress`.
+    containers_per_second: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -290,4222 +1048,14690 @@ struct SearchRequest {
     query: String,
     offset: Option<usize>,
     limit: Option<usize>,
-    folder_node_id: Option<String>,
-    include_images: Option<bool>,
-    include_audio: Option<bool>,
-    include_other: Option<bool>,
+    match_mode: Option<SearchMatchMode>,
+    /// Adds per-asset match ranges to the response; off by default since it
+    /// allocates for every returned asset.
+    include_highlights: Option<bool>,
+    /// Returns the page bucketed by parent folder in `groups` instead of `assets`.
+    group_by_folder: Option<bool>,
+    /// Only fills in `total`, skipping the sort and the record clones.
+    count_only: Option<bool>,
+    /// `next_cursor` from a previous page of the same query; replaces `offset`.
+    cursor: Option<String>,
+    fuzzy: Option<FuzzyMode>,
+    #[serde(flatten)]
+    filters: AssetFilterOptions,
+}
+
+/// Everything that decides which assets match and in what order; paging and
+/// response shaping options are left out so they share one cached ranking.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct SearchCacheKey {
+    query: String,
+    match_mode: SearchMatchMode,
+    fuzzy: FuzzyMode,
+    filters: AssetFilterOptions,
 }
 
-#[derive(Debug, Clone, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct SearchResponse {
-    total: usize,
-    assets: Vec<AssetRecord>,
-}
+impl SearchCacheKey {
+    fn for_request(req: &SearchRequest) -> Self {
+        Self {
+            query: req.query.trim().to_string(),
+            match_mode: req.match_mode.unwrap_or_default(),
+            fuzzy: req.fuzzy.unwrap_or_default(),
+            filters: req.filters.clone(),
+        }
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct ListTreeChildrenRequest {
-    scan_id: String,
-    node_id: Option<String>,
+    fn signature(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
 }
 
-#[derive(Debug, Clone, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct AssetPreviewResponse {
-    mime: String,
-    base64: String,
+/// Position in a cached ranking, tied to the query and the scan generation it
+/// was issued for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SearchCursor {
+    generation: u64,
+    signature: u64,
+    position: usize,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(rename_all = "lowercase")]
-enum AudioFormat {
-    Original,
-    Mp3,
-    Wav,
-}
+impl SearchCursor {
+    fn encode(&self) -> String {
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(format!(
+            "{}:{:016x}:{}",
+            self.generation, self.signature, self.position
+        ))
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct SaveAssetsRequest {
-    scan_id: String,
-    asset_ids: Vec<String>,
-    destination_dir: String,
-    audio_format: Option<AudioFormat>,
-    operation_id: Option<String>,
+    fn decode(token: &str) -> Option<Self> {
+        let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(token)
+            .ok()?;
+        let text = String::from_utf8(bytes).ok()?;
+        let mut parts = text.split(':');
+        let cursor = Self {
+            generation: parts.next()?.parse().ok()?,
+            signature: u64::from_str_radix(parts.next()?, 16).ok()?,
+            position: parts.next()?.parse().ok()?,
+        };
+        parts.next().is_none().then_some(cursor)
+    }
+
+    /// Checks the cursor against the current scan and query before it's used.
+    fn resolve(token: &str, generation: u64, key: &SearchCacheKey) -> Result<usize, String> {
+        let cursor = Self::decode(token).ok_or_else(|| "Invalid search cursor".to_string())?;
+        if cursor.generation != generation {
+            return Err("Search cursor expired because the scan changed".to_string());
+        }
+        if cursor.signature != key.signature() {
+            return Err("Search cursor belongs to a different query".to_string());
+        }
+        Ok(cursor.position)
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct CopyAssetsRequest {
-    scan_id: String,
-    asset_ids: Vec<String>,
-    audio_format: Option<AudioFormat>,
-    operation_id: Option<String>,
+/// One query's matches with their relevance scores, in result order up to
+/// `sorted_len`. The rest only sorts after that prefix until a page needs it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SearchOrder {
+    matches: Vec<(i64, usize)>,
+    sorted_len: usize,
+    ranked: bool,
 }
 
-#[derive(Debug, Clone, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct ExportFailure {
-    asset_id: String,
-    key: String,
-    error: String,
+impl SearchOrder {
+    fn new(matches: Vec<(i64, usize)>, ranked: bool) -> Self {
+        Self {
+            matches,
+            sorted_len: 0,
+            ranked,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.matches.len()
+    }
+
+    /// Asset indices from `offset` on, in result order; `offset + limit` must
+    /// not pass the sorted prefix.
+    fn page(&self, offset: usize, limit: usize) -> impl Iterator<Item = usize> + '_ {
+        self.matches
+            .iter()
+            .skip(offset)
+            .take(limit)
+            .map(|(_, index)| *index)
+    }
+
+    /// Sorts the first `wanted` matches, selecting them with a partial sort so
+    /// a first page doesn't pay for ordering everything behind it.
+    fn sort_prefix(&mut self, assets: &[AssetRecord], wanted: usize) {
+        let wanted = wanted.min(self.matches.len());
+        if wanted <= self.sorted_len {
+            return;
+        }
+        let ranked = self.ranked;
+        let compare = |left: &(i64, usize), right: &(i64, usize)| {
+            if ranked {
+                right
+                    .0
+                    .cmp(&left.0)
+                    .then_with(|| assets[left.1].key.cmp(&assets[right.1].key))
+            } else {
+                idle_asset_cmp(&assets[left.1], &assets[right.1])
+            }
+        };
+        let unsorted = &mut self.matches[self.sorted_len..];
+        let needed = wanted - self.sorted_len;
+        if needed < unsorted.len() {
+            unsorted.select_nth_unstable_by(needed - 1, compare);
+        }
+        unsorted[..needed].sort_unstable_by(compare);
+        self.sorted_len = wanted;
+    }
+}
+
+/// How far typo tolerance reaches when a query token has no direct match.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum FuzzyMode {
+    Off,
+    #[default]
+    Conservative,
+    /// Accepts shorter tokens and one more edit, at the cost of stray matches.
+    Aggressive,
+}
+
+impl FuzzyMode {
+    fn min_query_len(self) -> usize {
+        match self {
+            FuzzyMode::Aggressive => 3,
+            FuzzyMode::Off | FuzzyMode::Conservative => 4,
+        }
+    }
+
+    fn max_len_delta(self) -> usize {
+        match self {
+            FuzzyMode::Aggressive => 3,
+            FuzzyMode::Off | FuzzyMode::Conservative => 2,
+        }
+    }
+
+    /// Shortest token length at which `distance` edits still count as a match.
+    fn min_len_for_distance(self, distance: usize) -> Option<usize> {
+        match (self, distance) {
+            (FuzzyMode::Off, _) => None,
+            (_, 1) => Some(3),
+            (FuzzyMode::Conservative, 2) => Some(4),
+            (FuzzyMode::Conservative, 3) => Some(9),
+            (FuzzyMode::Aggressive, 2) => Some(3),
+            (FuzzyMode::Aggressive, 3) => Some(6),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SearchMatchMode {
+    /// Fuzzy, relevance-ranked search over keys and paths.
+    #[default]
+    Smart,
+    Regex,
+    /// One shell-style pattern over `relative_asset_path`; `*` stays within one
+    /// folder. Spaces are part of the pattern, so `{a,b}` is how to match either.
+    Glob,
+    Exact,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AssetFilterOptions {
+    folder_node_id: Option<String>,
+    include_images: Option<bool>,
+    include_audio: Option<bool>,
+    include_other: Option<bool>,
+    source_name_filter: Option<Vec<String>>,
+    namespace_filter: Option<Vec<String>>,
+    /// Exact extensions such as `["json", "mcmeta"]`; case and a leading dot are ignored.
+    extensions: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
-struct ExportProgressEvent {
-    operation_id: String,
-    kind: ExportOperationKind,
-    requested_count: usize,
-    processed_count: usize,
-    success_count: usize,
-    failed_count: usize,
-    cancelled: bool,
+struct NamespaceFacet {
+    name: String,
+    count: usize,
 }
 
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
-struct ExportCompletedEvent {
-    operation_id: String,
-    kind: ExportOperationKind,
-    requested_count: usize,
-    processed_count: usize,
-    success_count: usize,
-    failed_count: usize,
-    cancelled: bool,
-    failures: Vec<ExportFailure>,
+struct SourceFacet {
+    name: String,
+    display_name: Option<String>,
+    source_type: AssetSourceType,
+    count: usize,
 }
 
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
-struct SaveAssetsResult {
-    operation_id: String,
-    requested_count: usize,
-    processed_count: usize,
-    success_count: usize,
-    failed_count: usize,
-    cancelled: bool,
-    failures: Vec<ExportFailure>,
-    saved_files: Vec<String>,
+struct ExtensionFacet {
+    ext: String,
+    count: usize,
 }
 
+/// Distinct namespaces, sources and extensions in a scan, for populating filter dropdowns.
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
-struct CopyResult {
-    operation_id: String,
-    requested_count: usize,
-    processed_count: usize,
-    success_count: usize,
-    failed_count: usize,
-    cancelled: bool,
-    failures: Vec<ExportFailure>,
-    copied_files: Vec<String>,
+struct ScanFacets {
+    namespaces: Vec<NamespaceFacet>,
+    sources: Vec<SourceFacet>,
+    extensions: Vec<ExtensionFacet>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Sound objects listed by the scan's asset indexes whose hashed file is absent.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
-struct ConvertAudioRequest {
-    scan_id: String,
-    asset_id: String,
-    format: AudioFormat,
+struct VanillaAssetVerification {
+    checked_count: usize,
+    missing_count: usize,
+    /// Sorted logical paths, capped at `VANILLA_MISSING_REPORT_LIMIT`.
+    missing_paths: Vec<String>,
+}
+
+/// Assets whose extracted bytes are identical, in scan order.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct DuplicateAssetGroup {
+    byte_size: u64,
+    asset_ids: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
-struct ConvertedTempFileRef {
-    path: String,
-    format: AudioFormat,
+struct SearchResponse {
+    total: usize,
+    assets: Vec<AssetRecord>,
+    /// One entry per returned asset, in result order, when `include_highlights`
+    /// was requested.
+    highlights: Option<Vec<AssetHighlights>>,
+    groups: Option<Vec<SearchResultGroup>>,
+    /// Opaque token for the following page; `None` on the last page.
+    next_cursor: Option<String>,
+}
+
+/// Results sharing an immediate parent folder; groups keep the order in which
+/// their best-ranked asset appeared.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchResultGroup {
+    folder_node_id: String,
+    folder_name: String,
+    assets: Vec<AssetRecord>,
+}
+
+/// A matched span in character offsets, `end` exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HighlightRange {
+    start: usize,
+    end: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AssetHighlights {
+    asset_id: String,
+    key: Vec<HighlightRange>,
+    relative_asset_path: Vec<HighlightRange>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct ReconcileAssetIdsRequest {
+struct ListTreeChildrenRequest {
     scan_id: String,
-    asset_ids: Vec<String>,
+    node_id: Option<String>,
+    /// Lists a mod's only namespace in place of the mod folder when it matches
+    /// the mod id, so `create/create/textures` collapses to `create/textures`.
+    collapse_mod_namespaces: Option<bool>,
+    sort: Option<TreeSort>,
+}
+
+/// Child ordering for `list_tree_children`. Names compare case-insensitively;
+/// only `Natural` and `TypeThenName` list folders before files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum TreeSort {
+    NameAsc,
+    NameDesc,
+    /// Folders first, then numbers by value so `frame10` follows `frame2`.
+    Natural,
+    #[default]
+    TypeThenName,
 }
 
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
-struct ReconcileAssetIdsResponse {
-    id_map: HashMap<String, String>,
-    asset_ids: Vec<String>,
+struct AssetPreviewResponse {
+    mime: String,
+    base64: String,
+    animation: Option<TextureAnimation>,
+    /// Set when a text preview was cut at `TEXT_PREVIEW_MAX_BYTES`.
+    truncated: bool,
 }
 
-#[derive(Debug, Deserialize)]
-struct MmcPack {
-    components: Vec<MmcComponent>,
+/// Everything the details pane shows for one asset. Fields that couldn't be
+/// worked out stay `None` and `errors` holds the reason under the field name.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AssetInfo {
+    asset: AssetRecord,
+    resource_location: Option<String>,
+    byte_size: Option<u64>,
+    width: Option<u32>,
+    height: Option<u32>,
+    duration_ms: Option<u64>,
+    has_mcmeta: bool,
+    errors: BTreeMap<String, String>,
 }
 
-#[derive(Debug, Deserialize)]
-struct MmcComponent {
-    uid: String,
-    version: Option<String>,
+/// Serialized as `{ code, ... }` so the UI can offer an export instead of a
+/// preview for formats it knows the webview can't show.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(tag = "code", rename_all = "snake_case")]
+enum PreviewError {
+    UnsupportedPreview { extension: String },
+    Failed { message: String },
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct MinecraftMetaVersion {
-    asset_index: Option<MinecraftMetaAssetIndex>,
-    assets: Option<String>,
+impl From<String> for PreviewError {
+    fn from(message: String) -> Self {
+        Self::Failed { message }
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct MinecraftMetaAssetIndex {
-    id: String,
+/// One entry of a `get_asset_previews` batch; exactly one of `base64` and
+/// `error` is set.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AssetPreviewItem {
+    asset_id: String,
+    mime: Option<String>,
+    base64: Option<String>,
+    error: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
-struct MinecraftAssetIndexFile {
-    objects: HashMap<String, MinecraftAssetIndexObject>,
+/// Frame layout of an animated texture strip; `time` is in game ticks.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct TextureAnimation {
+    frame_width: u32,
+    frame_height: u32,
+    interpolate: bool,
+    frames: Vec<AnimationFrame>,
 }
 
-#[derive(Debug, Deserialize)]
-struct MinecraftAssetIndexObject {
-    hash: String,
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct AnimationFrame {
+    index: u32,
+    time: u32,
 }
 
-#[derive(Debug, Clone)]
-struct ScanContainer {
-    source_type: AssetSourceType,
-    source_name: String,
-    container_type: AssetContainerType,
-    container_path: PathBuf,
+/// Individual base64 PNG frames of a texture strip, indexed like `AnimationFrame::index`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TextureFrames {
+    frame_width: u32,
+    frame_height: u32,
+    frames: Vec<String>,
 }
 
-#[derive(Debug, Clone)]
-struct AssetCandidate {
-    source_type: AssetSourceType,
-    source_name: String,
-    namespace: String,
-    relative_asset_path: String,
-    container_path: PathBuf,
-    container_type: AssetContainerType,
-    entry_path: String,
-    extension: String,
-    is_image: bool,
-    is_audio: bool,
+/// A `namespace:path` location from a model file, with the scan asset it
+/// resolves to when one is indexed.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct ModelReference {
+    location: String,
+    asset_id: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
-struct ContainerSignature {
-    kind: AssetContainerType,
-    path: String,
-    mtime_ms: u64,
-    size: u64,
-    file_count: u64,
-    newest_mtime_ms: u64,
+struct ModelTextureReference {
+    variable: String,
+    #[serde(flatten)]
+    reference: ModelReference,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
-struct ScanSnapshot {
-    schema_version: u32,
-    cache_key: String,
-    prism_root: String,
-    instance_folder: String,
-    include_vanilla: bool,
-    include_mods: bool,
-    include_resourcepacks: bool,
-    created_at: u64,
-    last_used_at: u64,
-    app_version: String,
-    assets: Vec<AssetRecord>,
-    search_records: Vec<AssetSearchRecord>,
-    tree_children: HashMap<String, Vec<TreeNode>>,
-    container_assets: HashMap<String, Vec<AssetRecord>>,
-    container_signatures: HashMap<String, ContainerSignature>,
+struct ModelReferences {
+    /// Nearest parent first.
+    parents: Vec<ModelReference>,
+    textures: Vec<ModelTextureReference>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// One `sounds.json` entry, e.g. `minecraft:block.grass.step`.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
-struct ScanCacheManifest {
-    schema_version: u32,
-    entries: HashMap<String, ScanCacheManifestEntry>,
+struct SoundEvent {
+    event: String,
+    subtitle: Option<String>,
+    variants: Vec<SoundVariant>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
-struct ScanCacheManifestEntry {
-    file_name: String,
-    size_bytes: u64,
-    last_accessed_at: u64,
+struct SoundVariant {
+    kind: SoundVariantKind,
+    /// A sound file location without `sounds/` and `.ogg`, or another event.
+    location: String,
+    /// Only set for files found in the scan.
+    asset_id: Option<String>,
 }
 
-impl Default for ScanCacheManifest {
-    fn default() -> Self {
-        Self {
-            schema_version: SCAN_CACHE_SCHEMA_VERSION,
-            entries: HashMap::new(),
-        }
-    }
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum SoundVariantKind {
+    File,
+    Event,
 }
 
-#[derive(Debug, Clone)]
-struct ScanRefreshPlan {
-    unchanged_keys: Vec<String>,
-    changed_or_new: Vec<ScanContainer>,
-    removed_keys: Vec<String>,
-    signatures_by_key: HashMap<String, ContainerSignature>,
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum AudioFormat {
+    Original,
+    Mp3,
+    Wav,
+    /// Lossless, for archiving.
+    Flac,
+    /// Small files for sharing; written as Ogg Opus.
+    Opus,
 }
 
-#[tauri::command]
-fn detect_prism_roots() -> Result<Vec<PrismRootCandidate>, String> {
-    let mut candidates = Vec::new();
+/// Encoder tuning for lossy targets; lossless formats ignore it.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct AudioQuality {
+    /// LAME VBR level, 0 (best) to 9; MP3 defaults to 2.
+    mp3_vbr_quality: Option<u8>,
+    /// Target bitrate; overrides VBR for MP3 and sets Opus (default 96k).
+    bitrate_kbps: Option<u32>,
+}
 
-    if let Some(home) = home_dir() {
-        candidates.push(build_candidate(
-            home.join("Library/Application Support/PrismLauncher"),
-            "macos-default",
-        ));
-        candidates.push(build_candidate(
-            home.join(".local/share/PrismLauncher"),
-            "linux-default",
-        ));
-        candidates.push(build_candidate(home.join("PrismLauncher"), "portable-home"));
-    }
+const AUDIO_LOUDNORM_FILTER: &str = "loudnorm=I=-16:TP=-1.5:LRA=11";
 
-    if let Some(app_data) = env::var_os("APPDATA") {
-        candidates.push(build_candidate(
-            PathBuf::from(app_data).join("PrismLauncher"),
-            "windows-default",
-        ));
+/// Filters need a re-encode, so normalization is refused for `Original` (stream copy).
+fn audio_normalization_requested(
+    format: &AudioFormat,
+    normalize: Option<bool>,
+) -> Result<bool, String> {
+    let normalize = normalize.unwrap_or(false);
+    if normalize && *format == AudioFormat::Original {
+        return Err("Loudness normalization needs an audio format other than Original".to_string());
     }
+    Ok(normalize)
+}
 
-    if let Ok(custom_root) = env::var("PRISM_ROOT") {
-        candidates.push(build_candidate(
-            PathBuf::from(custom_root),
-            "env-prism-root",
-        ));
-    }
+const AUDIO_BITRATE_RANGE_KBPS: std::ops::RangeInclusive<u32> = 32..=320;
+const AUDIO_SAMPLE_RATE_RANGE_HZ: std::ops::RangeInclusive<u32> = 8_000..=192_000;
+const AUDIO_MAX_CHANNELS: u8 = 8;
 
-    dedupe_candidates(candidates)
+/// Output sample rate and channel count; unset keeps the source's. Ignored
+/// for `AudioFormat::Original`, which copies the stream untouched.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct AudioResample {
+    sample_rate: Option<u32>,
+    channels: Option<u8>,
+}
+
+impl AudioResample {
+    fn validate(self) -> Result<Self, String> {
+        if let Some(sample_rate) = self.sample_rate {
+            if !AUDIO_SAMPLE_RATE_RANGE_HZ.contains(&sample_rate) {
+                return Err(format!(
+                    "Audio sample rate must be {}-{} Hz, got {sample_rate}",
+                    AUDIO_SAMPLE_RATE_RANGE_HZ.start(),
+                    AUDIO_SAMPLE_RATE_RANGE_HZ.end()
+                ));
+            }
+        }
+        if let Some(channels) = self.channels {
+            if !(1..=AUDIO_MAX_CHANNELS).contains(&channels) {
+                return Err(format!(
+                    "Audio channel count must be 1-{AUDIO_MAX_CHANNELS}, got {channels}"
+                ));
+            }
+        }
+        Ok(self)
+    }
 }
 
-#[tauri::command]
-fn list_instances(prism_root: String) -> Result<Vec<InstanceInfo>, String> {
-    let prism_root = expand_home(&prism_root);
-    validate_prism_root(&prism_root)?;
-
-    let instances_dir = prism_root.join("instances");
-    if !instances_dir.exists() {
-        return Ok(Vec::new());
+/// Optional slice of the source audio, in milliseconds from its start.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct AudioTrim {
+    start_ms: Option<u64>,
+    end_ms: Option<u64>,
+}
+
+impl AudioTrim {
+    fn validate(self) -> Result<Self, String> {
+        if let Some(end_ms) = self.end_ms {
+            let start_ms = self.start_ms.unwrap_or(0);
+            if end_ms <= start_ms {
+                return Err(format!(
+                    "Audio trim end ({end_ms} ms) must be after its start ({start_ms} ms)"
+                ));
+            }
+        }
+        Ok(self)
     }
+}
 
-    let mut instances = Vec::new();
-    let entries = fs::read_dir(&instances_dir)
-        .map_err(|error| format!("Failed to read instances directory: {error}"))?;
-
-    for entry in entries {
-        let entry = match entry {
-            Ok(value) => value,
-            Err(_) => continue,
-        };
+fn ffmpeg_seconds(ms: u64) -> String {
+    format!("{}.{:03}", ms / 1000, ms % 1000)
+}
 
-        let instance_path = entry.path();
-        if !instance_path.is_dir() {
-            continue;
+impl AudioQuality {
+    fn validate(self) -> Result<Self, String> {
+        if let Some(quality) = self.mp3_vbr_quality {
+            if quality > 9 {
+                return Err(format!("MP3 VBR quality must be 0-9, got {quality}"));
+            }
         }
-
-        let folder_name = entry.file_name().to_string_lossy().to_string();
-        if folder_name.starts_with('.') {
-            continue;
+        if let Some(bitrate) = self.bitrate_kbps {
+            if !AUDIO_BITRATE_RANGE_KBPS.contains(&bitrate) {
+                return Err(format!(
+                    "Audio bitrate must be {}-{} kbps, got {bitrate}",
+                    AUDIO_BITRATE_RANGE_KBPS.start(),
+                    AUDIO_BITRATE_RANGE_KBPS.end()
+                ));
+            }
         }
+        Ok(self)
+    }
+}
 
-        // Real Prism instances should contain profile metadata and minecraft folder.
-        if !instance_path.join("mmc-pack.json").is_file()
-            || !instance_path.join("minecraft").is_dir()
-        {
-            continue;
+impl AudioFormat {
+    fn extension(&self) -> Option<&'static str> {
+        match self {
+            AudioFormat::Original => None,
+            AudioFormat::Mp3 => Some("mp3"),
+            AudioFormat::Wav => Some("wav"),
+            AudioFormat::Flac => Some("flac"),
+            AudioFormat::Opus => Some("opus"),
         }
-
-        let display_name =
-            instance_display_name(&instance_path).unwrap_or_else(|| folder_name.clone());
-        let minecraft_version = parse_minecraft_version(&instance_path.join("mmc-pack.json"));
-
-        instances.push(InstanceInfo {
-            folder_name,
-            display_name,
-            path: instance_path.to_string_lossy().to_string(),
-            minecraft_version,
-        });
     }
+}
 
-    instances.sort_by(|left, right| left.display_name.cmp(&right.display_name));
-    Ok(instances)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum ImageFormat {
+    Original,
+    Png,
+    Webp,
+    Jpeg,
 }
 
-fn unix_timestamp_ms() -> u64 {
-    match SystemTime::now().duration_since(UNIX_EPOCH) {
-        Ok(duration) => duration.as_millis().min(u128::from(u64::MAX)) as u64,
-        Err(_) => 0,
+impl ImageFormat {
+    fn extension(self) -> Option<&'static str> {
+        match self {
+            ImageFormat::Original => None,
+            ImageFormat::Png => Some("png"),
+            ImageFormat::Webp => Some("webp"),
+            ImageFormat::Jpeg => Some("jpg"),
+        }
     }
 }
 
-fn file_mtime_ms(metadata: &fs::Metadata) -> u64 {
-    metadata
-        .modified()
-        .ok()
-        .and_then(|value| value.duration_since(UNIX_EPOCH).ok())
-        .map(|value| value.as_millis().min(u128::from(u64::MAX)) as u64)
-        .unwrap_or(0)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum ExportNaming {
+    Original,
+    Key,
 }
 
-fn scan_container_key(container: &ScanContainer) -> String {
-    format!(
-        "{}::{}::{}::{}",
-        container.source_type.key_prefix(),
-        container.source_name,
-        container_type_key(&container.container_type),
-        container.container_path.to_string_lossy()
-    )
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SaveAssetsRequest {
+    scan_id: String,
+    asset_ids: Vec<String>,
+    destination_dir: String,
+    audio_format: Option<AudioFormat>,
+    audio_quality: Option<AudioQuality>,
+    /// Applies EBU R128 loudness normalization; requires a non-original `audio_format`.
+    normalize_audio: Option<bool>,
+    #[serde(flatten)]
+    audio_resample: AudioResample,
+    image_format: Option<ImageFormat>,
+    /// `#rrggbb` that JPEG output is flattened onto; defaults to white.
+    background: Option<String>,
+    naming: Option<ExportNaming>,
+    /// Filename stem such as `{namespace}__{stem}`; overrides `naming` when set.
+    /// See `NameTemplate` for the placeholders.
+    name_template: Option<String>,
+    /// Writes `<source_name>/<namespace>/<relative_asset_path>` instead of a flat folder.
+    preserve_structure: Option<bool>,
+    /// Writes `EXPORT_MANIFEST_FILE_NAME` describing where each saved file came from.
+    include_manifest: Option<bool>,
+    /// Plans the export and returns the target paths without writing anything.
+    dry_run: Option<bool>,
+    /// Deletes files this run already wrote if it gets cancelled; defaults to true.
+    cleanup_on_cancel: Option<bool>,
+    operation_id: Option<String>,
 }
 
-fn container_type_key(container_type: &AssetContainerType) -> &'static str {
-    match container_type {
-        AssetContainerType::Directory => "directory",
-        AssetContainerType::Zip => "zip",
-        AssetContainerType::Jar => "jar",
-        AssetContainerType::AssetIndex => "asset_index",
-    }
+/// Saves every asset matching `search`, regardless of its `offset`, `limit`
+/// or `cursor`; the remaining fields mean the same as in `SaveAssetsRequest`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SaveSearchResultsRequest {
+    search: SearchRequest,
+    destination_dir: String,
+    audio_format: Option<AudioFormat>,
+    audio_quality: Option<AudioQuality>,
+    normalize_audio: Option<bool>,
+    #[serde(flatten)]
+    audio_resample: AudioResample,
+    image_format: Option<ImageFormat>,
+    background: Option<String>,
+    naming: Option<ExportNaming>,
+    name_template: Option<String>,
+    preserve_structure: Option<bool>,
+    include_manifest: Option<bool>,
+    dry_run: Option<bool>,
+    cleanup_on_cancel: Option<bool>,
+    operation_id: Option<String>,
 }
 
-fn scan_cache_key_for_request(req: &StartScanRequest) -> String {
-    let prism_root = expand_home(&req.prism_root);
-    let prism_root = prism_root.to_string_lossy();
-    format!(
-        "{}::{}::{}{}{}",
-        prism_root,
-        req.instance_folder.trim(),
-        if req.include_vanilla { 'v' } else { '-' },
-        if req.include_mods { 'm' } else { '-' },
-        if req.include_resourcepacks { 'r' } else { '-' },
-    )
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SaveAssetsAsZipRequest {
+    scan_id: String,
+    asset_ids: Vec<String>,
+    destination_zip_path: String,
+    preserve_structure: Option<bool>,
+    audio_format: Option<AudioFormat>,
+    #[serde(flatten)]
+    audio_resample: AudioResample,
+    image_format: Option<ImageFormat>,
+    /// `#rrggbb` that JPEG output is flattened onto; defaults to white.
+    background: Option<String>,
+    operation_id: Option<String>,
 }
 
-fn fnv1a64(value: &str) -> u64 {
-    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
-    const PRIME: u64 = 0x100000001b3;
-    let mut hash = OFFSET_BASIS;
-    for byte in value.as_bytes() {
-        hash ^= u64::from(*byte);
-        hash = hash.wrapping_mul(PRIME);
-    }
-    hash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportResourcePackRequest {
+    scan_id: String,
+    asset_ids: Vec<String>,
+    destination_dir: String,
+    pack_format: u32,
+    description: String,
+    /// Optional image copied to `pack.png` as the pack icon.
+    pack_icon_path: Option<String>,
+    audio_format: Option<AudioFormat>,
+    #[serde(flatten)]
+    audio_resample: AudioResample,
+    image_format: Option<ImageFormat>,
+    /// `#rrggbb` that JPEG output is flattened onto; defaults to white.
+    background: Option<String>,
+    /// Deletes files this run already wrote if it gets cancelled; defaults to true.
+    cleanup_on_cancel: Option<bool>,
+    operation_id: Option<String>,
 }
 
-fn scan_cache_root(app: &AppHandle) -> Result<PathBuf, String> {
-    let root = app
-        .path()
-        .app_data_dir()
-        .map_err(|error| format!("Failed to resolve app data directory: {error}"))?
-        .join("scan-cache")
-        .join("v1");
-    fs::create_dir_all(&root)
-        .map_err(|error| format!("Failed to create scan cache directory: {error}"))?;
-    Ok(root)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportNamespaceRequest {
+    scan_id: String,
+    namespace: String,
+    destination_dir: String,
+    source_filter: Option<Vec<String>>,
+    audio_format: Option<AudioFormat>,
+    #[serde(flatten)]
+    audio_resample: AudioResample,
+    /// Deletes files this run already wrote if it gets cancelled; defaults to true.
+    cleanup_on_cancel: Option<bool>,
+    operation_id: Option<String>,
 }
 
-fn scan_cache_manifest_path(cache_root: &Path) -> PathBuf {
-    cache_root.join("manifest.json")
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CopyAssetsRequest {
+    scan_id: String,
+    asset_ids: Vec<String>,
+    audio_format: Option<AudioFormat>,
+    audio_quality: Option<AudioQuality>,
+    /// Applies EBU R128 loudness normalization; requires a non-original `audio_format`.
+    normalize_audio: Option<bool>,
+    #[serde(flatten)]
+    audio_resample: AudioResample,
+    image_format: Option<ImageFormat>,
+    /// `#rrggbb` that JPEG output is flattened onto; defaults to white.
+    background: Option<String>,
+    naming: Option<ExportNaming>,
+    operation_id: Option<String>,
 }
 
-fn scan_cache_snapshot_file_name(cache_key: &str) -> String {
-    format!("{:016x}.bin", fnv1a64(cache_key))
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NamespaceExportConflict {
+    relative_asset_path: String,
+    kept_asset_id: String,
+    kept_source_name: String,
+    skipped_source_names: Vec<String>,
 }
 
-fn scan_cache_snapshot_legacy_file_name(cache_key: &str) -> String {
-    format!("{:016x}.json", fnv1a64(cache_key))
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportFailure {
+    asset_id: String,
+    key: String,
+    error: String,
 }
 
-fn scan_cache_snapshot_path(cache_root: &Path, cache_key: &str) -> PathBuf {
-    cache_root.join(scan_cache_snapshot_file_name(cache_key))
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportProgressEvent {
+    operation_id: String,
+    kind: ExportOperationKind,
+    requested_count: usize,
+    processed_count: usize,
+    success_count: usize,
+    failed_count: usize,
+    cancelled: bool,
 }
 
-fn scan_cache_snapshot_legacy_path(cache_root: &Path, cache_key: &str) -> PathBuf {
-    cache_root.join(scan_cache_snapshot_legacy_file_name(cache_key))
+/// Emitted as `export://byte-progress` so exports of a few large files show
+/// movement. Counts source bytes as they are written out or streamed to ffmpeg;
+/// `total_bytes` is set only when every asset's size is known.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportByteProgressEvent {
+    operation_id: String,
+    kind: ExportOperationKind,
+    processed_bytes: u64,
+    total_bytes: Option<u64>,
 }
 
-fn has_cached_snapshot(app: &AppHandle, cache_key: &str) -> bool {
-    let Ok(cache_root) = scan_cache_root(app) else {
-        return false;
-    };
-    scan_cache_snapshot_path(&cache_root, cache_key).is_file()
-        || scan_cache_snapshot_legacy_path(&cache_root, cache_key).is_file()
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportCompletedEvent {
+    operation_id: String,
+    kind: ExportOperationKind,
+    requested_count: usize,
+    processed_count: usize,
+    success_count: usize,
+    failed_count: usize,
+    cancelled: bool,
+    failures: Vec<ExportFailure>,
 }
 
-fn write_json_atomically<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
-    let temp_path = path.with_extension(format!("tmp-{}", Uuid::new_v4()));
-    let bytes = serde_json::to_vec(value)
-        .map_err(|error| format!("Failed to serialize {}: {error}", path.display()))?;
-    fs::write(&temp_path, bytes)
-        .map_err(|error| format!("Failed to write {}: {error}", temp_path.display()))?;
-    fs::rename(&temp_path, path)
-        .map_err(|error| format!("Failed to replace {}: {error}", path.display()))?;
-    Ok(())
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SaveAssetsResult {
+    operation_id: String,
+    requested_count: usize,
+    processed_count: usize,
+    success_count: usize,
+    failed_count: usize,
+    cancelled: bool,
+    failures: Vec<ExportFailure>,
+    saved_files: Vec<String>,
+    /// Set when the files were saved but the requested manifest couldn't be written.
+    manifest_warning: Option<String>,
 }
 
-fn write_bytes_atomically(path: &Path, bytes: &[u8]) -> Result<(), String> {
-    let temp_path = path.with_extension(format!("tmp-{}", Uuid::new_v4()));
-    fs::write(&temp_path, bytes)
-        .map_err(|error| format!("Failed to write {}: {error}", temp_path.display()))?;
-    fs::rename(&temp_path, path)
-        .map_err(|error| format!("Failed to replace {}: {error}", path.display()))?;
-    Ok(())
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SaveAssetsAsZipResult {
+    /// `saved_files` lists the entry names written into the archive.
+    #[serde(flatten)]
+    export: SaveAssetsResult,
+    zip_path: Option<String>,
 }
 
-fn load_scan_cache_manifest(cache_root: &Path) -> Result<ScanCacheManifest, String> {
-    let manifest_path = scan_cache_manifest_path(cache_root);
-    if !manifest_path.is_file() {
-        return Ok(ScanCacheManifest::default());
-    }
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportNamespaceResult {
+    #[serde(flatten)]
+    export: SaveAssetsResult,
+    conflicts: Vec<NamespaceExportConflict>,
+}
 
-    let data = fs::read_to_string(&manifest_path)
-        .map_err(|error| format!("Failed to read cache manifest: {error}"))?;
-    let parsed: ScanCacheManifest = serde_json::from_str(&data)
-        .map_err(|error| format!("Failed to parse cache manifest: {error}"))?;
-    if parsed.schema_version != SCAN_CACHE_SCHEMA_VERSION {
-        return Ok(ScanCacheManifest::default());
-    }
-    Ok(parsed)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CopyResult {
+    operation_id: String,
+    requested_count: usize,
+    processed_count: usize,
+    success_count: usize,
+    failed_count: usize,
+    cancelled: bool,
+    failures: Vec<ExportFailure>,
+    copied_files: Vec<String>,
 }
 
-fn save_scan_cache_manifest(cache_root: &Path, manifest: &ScanCacheManifest) -> Result<(), String> {
-    write_json_atomically(&scan_cache_manifest_path(cache_root), manifest)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConvertAudioRequest {
+    scan_id: String,
+    asset_id: String,
+    format: AudioFormat,
+    quality: Option<AudioQuality>,
+    normalize: Option<bool>,
+    #[serde(flatten)]
+    resample: AudioResample,
+    #[serde(flatten)]
+    trim: AudioTrim,
 }
 
-fn remove_cache_entry(cache_root: &Path, manifest: &mut ScanCacheManifest, cache_key: &str) {
-    let file_name = scan_cache_snapshot_file_name(cache_key);
-    let path = cache_root.join(&file_name);
-    let _ = fs::remove_file(path);
-    let legacy_path = scan_cache_snapshot_legacy_path(cache_root, cache_key);
-    let _ = fs::remove_file(legacy_path);
-    manifest.entries.remove(cache_key);
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AudioWaveform {
+    /// `[min0, max0, min1, max1, ...]` in `-1.0..=1.0`, one pair per bucket.
+    peaks: Vec<f32>,
+    duration_seconds: f64,
 }
 
-fn prune_scan_cache(cache_root: &Path, manifest: &mut ScanCacheManifest) {
-    let mut total_size = manifest
-        .entries
-        .values()
-        .map(|entry| entry.size_bytes)
-        .sum::<u64>();
-    if total_size <= SCAN_CACHE_MAX_BYTES {
-        return;
-    }
-
-    let mut eviction_order = manifest
-        .entries
-        .iter()
-        .map(|(cache_key, entry)| (cache_key.clone(), entry.last_accessed_at))
-        .collect::<Vec<_>>();
-    eviction_order.sort_by(|left, right| left.1.cmp(&right.1));
+/// First audio stream as reported by ffprobe; fields it leaves out stay `None`.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct AudioMetadata {
+    duration_ms: Option<u64>,
+    sample_rate: Option<u32>,
+    channels: Option<u32>,
+    codec: Option<String>,
+    /// Bits per second; the container's overall rate when the stream has none.
+    bitrate: Option<u64>,
+}
 
-    for (cache_key, _) in eviction_order {
-        let Some(entry) = manifest.entries.remove(&cache_key) else {
-            continue;
-        };
-        let path = cache_root.join(&entry.file_name);
-        let _ = fs::remove_file(path);
-        total_size = total_size.saturating_sub(entry.size_bytes);
-        if total_size <= SCAN_CACHE_MAX_BYTES {
-            break;
-        }
-    }
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportManifest {
+    files: Vec<ExportManifestEntry>,
 }
 
-fn load_cached_snapshot(app: &AppHandle, cache_key: &str) -> Result<Option<ScanSnapshot>, String> {
-    let cache_root = scan_cache_root(app)?;
-    let mut manifest = load_scan_cache_manifest(&cache_root)?;
-    let snapshot_path = scan_cache_snapshot_path(&cache_root, cache_key);
-    let legacy_path = scan_cache_snapshot_legacy_path(&cache_root, cache_key);
-    let (mut parsed, source_path, was_legacy_json) = if snapshot_path.is_file() {
-        let bytes = match fs::read(&snapshot_path) {
-            Ok(value) => value,
-            Err(_) => {
-                remove_cache_entry(&cache_root, &mut manifest, cache_key);
-                let _ = save_scan_cache_manifest(&cache_root, &manifest);
-                return Ok(None);
-            }
-        };
-        let parsed: ScanSnapshot = match bincode::deserialize(&bytes) {
-            Ok(value) => value,
-            Err(_) => {
-                remove_cache_entry(&cache_root, &mut manifest, cache_key);
-                let _ = save_scan_cache_manifest(&cache_root, &manifest);
-                return Ok(None);
-            }
-        };
-        (parsed, snapshot_path.clone(), false)
-    } else if legacy_path.is_file() {
-        let data = match fs::read_to_string(&legacy_path) {
-            Ok(value) => value,
-            Err(_) => {
-                manifest.entries.remove(cache_key);
-                let _ = fs::remove_file(&legacy_path);
-                let _ = save_scan_cache_manifest(&cache_root, &manifest);
-                return Ok(None);
-            }
-        };
-        let parsed: ScanSnapshot = match serde_json::from_str(&data) {
-            Ok(value) => value,
-            Err(_) => {
-                manifest.entries.remove(cache_key);
-                let _ = fs::remove_file(&legacy_path);
-                let _ = save_scan_cache_manifest(&cache_root, &manifest);
-                return Ok(None);
-            }
-        };
-        (parsed, legacy_path.clone(), true)
-    } else {
-        manifest.entries.remove(cache_key);
-        let _ = save_scan_cache_manifest(&cache_root, &manifest);
-        return Ok(None);
-    };
+/// Provenance of one saved file; `file` is relative to the export folder.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportManifestEntry {
+    file: String,
+    asset_id: String,
+    key: String,
+    namespace: String,
+    source_name: String,
+    source_type: AssetSourceType,
+    container_path: String,
+    relative_asset_path: String,
+    resource_location: Option<String>,
+}
 
-    if parsed.schema_version != SCAN_CACHE_SCHEMA_VERSION {
-        remove_cache_entry(&cache_root, &mut manifest, cache_key);
-        let _ = fs::remove_file(&legacy_path);
-        let _ = save_scan_cache_manifest(&cache_root, &manifest);
-        return Ok(None);
-    }
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConvertedTempFileRef {
+    path: String,
+    format: AudioFormat,
+}
 
-    parsed.last_used_at = unix_timestamp_ms();
-    if was_legacy_json {
-        if let Ok(bytes) = bincode::serialize(&parsed) {
-            let _ = write_bytes_atomically(&snapshot_path, &bytes);
-            let _ = fs::remove_file(&legacy_path);
-        }
-    }
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReconcileAssetIdsRequest {
+    scan_id: String,
+    asset_ids: Vec<String>,
+}
 
-    let canonical_path = if snapshot_path.is_file() {
-        snapshot_path
-    } else {
-        source_path
-    };
-    let now = unix_timestamp_ms();
-    let entry = manifest
-        .entries
-        .entry(cache_key.to_string())
-        .or_insert(ScanCacheManifestEntry {
-            file_name: canonical_path
-                .file_name()
-                .map(|value| value.to_string_lossy().to_string())
-                .unwrap_or_else(|| scan_cache_snapshot_file_name(cache_key)),
-            size_bytes: fs::metadata(&canonical_path).map(|meta| meta.len()).unwrap_or(0),
-            last_accessed_at: now,
-        });
-    entry.file_name = canonical_path
-        .file_name()
-        .map(|value| value.to_string_lossy().to_string())
-        .unwrap_or_else(|| scan_cache_snapshot_file_name(cache_key));
-    entry.size_bytes = fs::metadata(&canonical_path).map(|meta| meta.len()).unwrap_or(0);
-    entry.last_accessed_at = now;
-    let _ = save_scan_cache_manifest(&cache_root, &manifest);
-    Ok(Some(parsed))
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReconcileAssetIdsResponse {
+    id_map: HashMap<String, String>,
+    asset_ids: Vec<String>,
 }
 
-fn save_snapshot_to_cache(app: &AppHandle, snapshot: &ScanSnapshot) -> Result<(), String> {
-    let cache_root = scan_cache_root(app)?;
-    let mut manifest = load_scan_cache_manifest(&cache_root)?;
-    let snapshot_path = scan_cache_snapshot_path(&cache_root, &snapshot.cache_key);
-    let bytes = bincode::serialize(snapshot)
-        .map_err(|error| format!("Failed to serialize snapshot {}: {error}", snapshot.cache_key))?;
-    write_bytes_atomically(&snapshot_path, &bytes)?;
-    let legacy_path = scan_cache_snapshot_legacy_path(&cache_root, &snapshot.cache_key);
-    let _ = fs::remove_file(legacy_path);
-    let size_bytes = fs::metadata(&snapshot_path)
-        .map(|meta| meta.len())
-        .map_err(|error| format!("Failed to stat cache snapshot {}: {error}", snapshot_path.display()))?;
-    manifest.entries.insert(
-        snapshot.cache_key.clone(),
-        ScanCacheManifestEntry {
-            file_name: scan_cache_snapshot_file_name(&snapshot.cache_key),
-            size_bytes,
-            last_accessed_at: unix_timestamp_ms(),
-        },
-    );
-    prune_scan_cache(&cache_root, &mut manifest);
-    save_scan_cache_manifest(&cache_root, &manifest)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CopyResourceLocationsRequest {
+    scan_id: String,
+    asset_ids: Vec<String>,
 }
 
-fn container_signature_for_path(
-    container_path: &Path,
-    container_type: &AssetContainerType,
-) -> Result<ContainerSignature, String> {
-    let metadata = fs::metadata(container_path)
-        .map_err(|error| format!("Failed to read metadata {}: {error}", container_path.display()))?;
-    let mut file_count = 0u64;
-    let mut total_size: u64;
-    let mut newest_mtime_ms: u64;
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CopyResourceLocationsResult {
+    copied_count: usize,
+    /// Assets without a resource location.
+    skipped_count: usize,
+    /// Assets whose location was already copied for an earlier asset.
+    duplicate_count: usize,
+}
 
-    if matches!(container_type, AssetContainerType::Directory) {
-        total_size = 0;
-        file_count = 0;
-        newest_mtime_ms = 0;
-        for entry in WalkDir::new(container_path)
-            .follow_links(false)
-            .into_iter()
-            .filter_map(Result::ok)
-        {
-            if !entry.file_type().is_file() {
-                continue;
-            }
-            if let Ok(meta) = entry.metadata() {
-                file_count = file_count.saturating_add(1);
-                total_size = total_size.saturating_add(meta.len());
-                newest_mtime_ms = newest_mtime_ms.max(file_mtime_ms(&meta));
-            }
-        }
-    } else {
-        total_size = metadata.len();
-        newest_mtime_ms = file_mtime_ms(&metadata);
-    }
+#[derive(Debug, Deserialize)]
+struct MmcPack {
+    components: Vec<MmcComponent>,
+}
 
-    Ok(ContainerSignature {
-        kind: container_type.clone(),
-        path: container_path.to_string_lossy().to_string(),
-        mtime_ms: file_mtime_ms(&metadata),
-        size: total_size,
-        file_count,
-        newest_mtime_ms,
-    })
+#[derive(Debug, Deserialize)]
+struct MmcComponent {
+    uid: String,
+    version: Option<String>,
 }
 
-fn build_scan_refresh_plan(
-    cached_signatures: &HashMap<String, ContainerSignature>,
-    current_containers: &[ScanContainer],
-) -> Result<ScanRefreshPlan, String> {
-    let mut unchanged_keys = Vec::new();
-    let mut changed_or_new = Vec::new();
-    let mut signatures_by_key = HashMap::new();
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MinecraftMetaVersion {
+    id: Option<String>,
+    inherits_from: Option<String>,
+    asset_index: Option<MinecraftMetaAssetIndex>,
+    assets: Option<String>,
+}
 
-    for container in current_containers {
-        let key = scan_container_key(container);
-        let signature = container_signature_for_path(&container.container_path, &container.container_type)?;
-        let is_unchanged = cached_signatures
-            .get(&key)
-            .map(|cached| cached == &signature)
-            .unwrap_or(false);
-        if is_unchanged {
-            unchanged_keys.push(key.clone());
-        } else {
-            changed_or_new.push(container.clone());
-        }
-        signatures_by_key.insert(key, signature);
+impl MinecraftMetaVersion {
+    fn asset_index_id(self) -> Option<String> {
+        self.asset_index
+            .map(|asset_index| asset_index.id)
+            .or(self.assets)
     }
+}
 
-    let mut removed_keys = cached_signatures
-        .keys()
-        .filter(|key| !signatures_by_key.contains_key(*key))
-        .cloned()
-        .collect::<Vec<_>>();
-    removed_keys.sort();
-    unchanged_keys.sort();
-    changed_or_new.sort_by(|left, right| scan_container_key(left).cmp(&scan_container_key(right)));
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AtLauncherInstance {
+    #[serde(flatten)]
+    version: MinecraftMetaVersion,
+    launcher: Option<AtLauncherMetadata>,
+}
 
-    Ok(ScanRefreshPlan {
-        unchanged_keys,
-        changed_or_new,
-        removed_keys,
-        signatures_by_key,
-    })
+#[derive(Debug, Deserialize)]
+struct AtLauncherMetadata {
+    name: Option<String>,
 }
 
-fn asset_identity(asset: &AssetRecord) -> String {
-    format!(
-        "{}::{}::{}::{}::{}::{}",
-        asset.source_type.key_prefix(),
-        asset.source_name,
-        asset.namespace,
-        asset.relative_asset_path,
-        asset.container_path,
-        asset.entry_path
-    )
+#[derive(Debug, Deserialize)]
+struct MinecraftMetaAssetIndex {
+    id: String,
 }
 
-fn build_asset_reconciliation_map(
-    previous_assets: &[AssetRecord],
-    next_assets: &[AssetRecord],
-) -> HashMap<String, String> {
-    let mut next_by_id = HashSet::new();
-    let mut next_by_identity = HashMap::<String, Vec<String>>::new();
-    for asset in next_assets {
-        next_by_id.insert(asset.asset_id.clone());
-        next_by_identity
-            .entry(asset_identity(asset))
-            .or_default()
-            .push(asset.asset_id.clone());
-    }
-
-    let mut identity_cursor = HashMap::<String, usize>::new();
-    let mut id_map = HashMap::<String, String>::new();
-    for asset in previous_assets {
-        if next_by_id.contains(&asset.asset_id) {
-            id_map.insert(asset.asset_id.clone(), asset.asset_id.clone());
-            continue;
-        }
+#[derive(Debug, Deserialize)]
+struct MinecraftAssetIndexFile {
+    objects: HashMap<String, MinecraftAssetIndexObject>,
+    /// Set by `legacy.json`: launchers copy objects to `assets/virtual/legacy`.
+    #[serde(default, rename = "virtual")]
+    is_virtual: bool,
+    /// Set by `pre-1.6.json`, whose keys have no namespace prefix; the game
+    /// copies its objects to `resources` in the game directory.
+    #[serde(default)]
+    map_to_resources: bool,
+}
 
-        let identity = asset_identity(asset);
-        let Some(candidates) = next_by_identity.get(&identity) else {
-            continue;
-        };
-        let cursor = identity_cursor.entry(identity).or_insert(0);
-        let resolved = candidates
-            .get(*cursor)
-            .or_else(|| candidates.last())
-            .cloned();
-        if let Some(mapped_id) = resolved {
-            id_map.insert(asset.asset_id.clone(), mapped_id);
-            *cursor = cursor.saturating_add(1);
-        }
+impl MinecraftAssetIndexFile {
+    fn is_legacy(&self) -> bool {
+        self.is_virtual || self.map_to_resources
     }
+}
 
-    id_map
+#[derive(Debug, Deserialize)]
+struct MinecraftAssetIndexObject {
+    hash: String,
+    size: Option<u64>,
 }
 
-#[tauri::command]
-fn start_scan(
-    app: AppHandle,
-    state: State<'_, AppState>,
-    req: StartScanRequest,
-) -> Result<StartScanResponse, String> {
-    let scan_id = Uuid::new_v4().to_string();
-    let force_rescan = req.force_rescan.unwrap_or(false);
-    let cache_key = scan_cache_key_for_request(&req);
-    let likely_cache_hit = !force_rescan && has_cached_snapshot(&app, &cache_key);
+#[derive(Debug, Clone)]
+struct ScanContainer {
+    source_type: AssetSourceType,
+    source_name: String,
+    container_type: AssetContainerType,
+    container_path: PathBuf,
+}
 
-    {
-        let mut scans = state
-            .scans
-            .lock()
-            .map_err(|_| "Failed to lock scans state".to_string())?;
+#[derive(Debug, Clone)]
+struct AssetCandidate {
+    source_type: AssetSourceType,
+    source_name: String,
+    namespace: String,
+    relative_asset_path: String,
+    container_path: PathBuf,
+    container_type: AssetContainerType,
+    entry_path: String,
+    extension: String,
+    is_image: bool,
+    is_audio: bool,
+    is_shader: bool,
+    byte_size: Option<u64>,
+    dimensions: Option<(u32, u32)>,
+    mod_display_name: Option<String>,
+    mod_id: Option<String>,
+}
 
-        let mut scan_state = ScanState::new();
-        scan_state.is_refreshing = likely_cache_hit;
-        scans.insert(scan_id.clone(), scan_state);
+/// Per-scan switches that change what scanners read for each entry.
+#[derive(Debug, Clone, Copy, Default)]
+struct ScanOptions {
+    collect_dimensions: bool,
+    follow_symlinks: bool,
+}
+
+impl ScanOptions {
+    fn from_request(req: &StartScanRequest) -> Self {
+        Self {
+            collect_dimensions: req.collect_dimensions.unwrap_or(false),
+            follow_symlinks: req.follow_symlinks.unwrap_or(false),
+        }
     }
+}
 
-    let _ = app.emit(
-        "scan://started",
-        serde_json::json!({
-            "scanId": scan_id,
-        }),
-    );
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct ContainerSignature {
+    kind: AssetContainerType,
+    path: String,
+    mtime_ms: u64,
+    size: u64,
+    file_count: u64,
+    newest_mtime_ms: u64,
+    /// Directory containers only: `(mtime_ms, size)` per normalized entry path,
+    /// so a refresh can re-read just the touched files.
+    entries: BTreeMap<String, (u64, u64)>,
+}
+
+impl ContainerSignature {
+    /// The cheap totals check; `entries` is only consulted when this fails.
+    fn coarse_eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+            && self.path == other.path
+            && self.mtime_ms == other.mtime_ms
+            && self.size == other.size
+            && self.file_count == other.file_count
+            && self.newest_mtime_ms == other.newest_mtime_ms
+    }
+
+    /// Entry paths that were added or modified, and those that disappeared,
+    /// relative to `previous`. `None` when a per-entry diff isn't possible.
+    fn entry_changes(&self, previous: &Self) -> Option<(Vec<String>, Vec<String>)> {
+        if !matches!(self.kind, AssetContainerType::Directory)
+            || self.kind != previous.kind
+            || self.path != previous.path
+            || (previous.entries.is_empty() && previous.file_count > 0)
+        {
+            return None;
+        }
 
-    emit_scan_progress(
-        &app,
-        ScanProgressEvent {
-            scan_id: scan_id.clone(),
-            scanned_containers: 0,
-            total_containers: if likely_cache_hit { 1 } else { 0 },
-            asset_count: 0,
-            phase: if likely_cache_hit {
-                ScanPhase::Refreshing
-            } else {
-                ScanPhase::Estimating
-            },
-            current_source: if likely_cache_hit {
-                Some("cache".to_string())
-            } else {
-                None
-            },
-        },
-    );
+        let changed = self
+            .entries
+            .iter()
+            .filter(|(entry, stamp)| previous.entries.get(*entry) != Some(stamp))
+            .map(|(entry, _)| entry.clone())
+            .collect();
+        let removed = previous
+            .entries
+            .keys()
+            .filter(|entry| !self.entries.contains_key(*entry))
+            .cloned()
+            .collect();
+        Some((changed, removed))
+    }
+}
 
-    let scan_id_for_worker = scan_id.clone();
-    let app_for_worker = app.clone();
-    let req_for_worker = req.clone();
-    let cache_key_for_worker = cache_key.clone();
-    thread::spawn(move || {
-        run_scan_bootstrap_worker(
-            app_for_worker,
-            scan_id_for_worker,
-            req_for_worker,
-            cache_key_for_worker,
-            force_rescan,
-        );
-    });
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScanSnapshot {
+    schema_version: u32,
+    cache_key: String,
+    prism_root: String,
+    instance_folder: String,
+    include_vanilla: bool,
+    include_mods: bool,
+    include_resourcepacks: bool,
+    created_at: u64,
+    last_used_at: u64,
+    app_version: String,
+    assets: Vec<AssetRecord>,
+    search_records: Vec<AssetSearchRecord>,
+    tree_children: HashMap<String, Vec<TreeNode>>,
+    container_assets: HashMap<String, Vec<AssetRecord>>,
+    container_signatures: HashMap<String, ContainerSignature>,
+}
 
-    Ok(StartScanResponse {
-        scan_id,
-        cache_hit: likely_cache_hit,
-        refresh_started: likely_cache_hit,
-        refresh_mode: if likely_cache_hit {
-            Some("incremental".to_string())
-        } else {
-            None
-        },
-    })
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScanCacheManifest {
+    schema_version: u32,
+    entries: HashMap<String, ScanCacheManifestEntry>,
 }
 
-#[tauri::command]
-fn get_scan_status(scan_id: String, state: State<'_, AppState>) -> Result<ScanStatus, String> {
-    let scans = state
-        .scans
-        .lock()
-        .map_err(|_| "Failed to lock scans state".to_string())?;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScanCacheManifestEntry {
+    file_name: String,
+    size_bytes: u64,
+    last_accessed_at: u64,
+    #[serde(default)]
+    created_at: u64,
+}
 
-    let scan = scans
-        .get(&scan_id)
-        .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScanCacheEntryStats {
+    cache_key: String,
+    size_bytes: u64,
+    last_accessed_at: u64,
+    created_at: u64,
+}
 
-    Ok(scan.as_status(&scan_id))
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ClearScanCacheResult {
+    removed_entries: usize,
+    freed_bytes: u64,
 }
 
-#[tauri::command]
-fn cancel_scan(scan_id: String, state: State<'_, AppState>) -> Result<(), String> {
-    let mut scans = state
-        .scans
-        .lock()
-        .map_err(|_| "Failed to lock scans state".to_string())?;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum CacheInvalidationReason {
+    ParseError,
+    SchemaMismatch,
+    MissingFile,
+}
 
-    let scan = scans
-        .get_mut(&scan_id)
-        .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
+/// Emitted as `cache://invalidated` when a scan had to drop its snapshot.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheInvalidatedEvent {
+    scan_id: String,
+    cache_key: String,
+    reason: CacheInvalidationReason,
+}
 
-    scan.cancelled = true;
-    scan.is_refreshing = false;
-    scan.status = ScanLifecycle::Cancelled;
-    Ok(())
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RepairScanCacheResult {
+    removed_files: usize,
+    removed_entries: usize,
+    freed_bytes: u64,
 }
 
-#[tauri::command]
-fn cancel_export(operation_id: String, state: State<'_, AppState>) -> Result<(), String> {
-    let mut operations = state
-        .export_operations
-        .lock()
-        .map_err(|_| "Failed to lock export operations state".to_string())?;
+impl Default for ScanCacheManifest {
+    fn default() -> Self {
+        Self {
+            schema_version: SCAN_CACHE_SCHEMA_VERSION,
+            entries: HashMap::new(),
+        }
+    }
+}
 
-    let operation = operations
-        .get_mut(&operation_id)
-        .ok_or_else(|| format!("Unknown export operation id: {operation_id}"))?;
+#[derive(Debug, Clone)]
+struct ScanRefreshPlan {
+    unchanged_keys: Vec<String>,
+    changed_or_new: Vec<ScanContainer>,
+    removed_keys: Vec<String>,
+    /// Directory containers where only some entries changed.
+    partial: Vec<PartialContainerRefresh>,
+    signatures_by_key: HashMap<String, ContainerSignature>,
+}
 
-    operation.cancelled = true;
-    Ok(())
+#[derive(Debug, Clone)]
+struct PartialContainerRefresh {
+    container: ScanContainer,
+    changed_entries: Vec<String>,
+    removed_entries: Vec<String>,
 }
 
 #[tauri::command]
-fn list_tree_children(
-    req: ListTreeChildrenRequest,
-    state: State<'_, AppState>,
-) -> Result<Vec<TreeNode>, String> {
-    let scans = state
-        .scans
-        .lock()
-        .map_err(|_| "Failed to lock scans state".to_string())?;
+fn detect_prism_roots() -> Result<Vec<PrismRootCandidate>, String> {
+    dedupe_candidates(prism_root_candidates())
+}
 
-    let scan = scans
-        .get(&req.scan_id)
-        .ok_or_else(|| format!("Unknown scan id: {}", req.scan_id))?;
+#[tauri::command]
+fn detect_launcher_roots() -> Result<Vec<PrismRootCandidate>, String> {
+    let mut candidates = prism_root_candidates();
+    candidates.extend(multimc_root_candidates());
+    candidates.extend(vanilla_root_candidates());
+    dedupe_candidates(candidates)
+}
 
-    let node_id = req.node_id.unwrap_or_else(|| ROOT_NODE_ID.to_string());
-    let mut children = scan
-        .tree_children
-        .get(&node_id)
-        .cloned()
-        .unwrap_or_default();
+fn prism_root_candidates() -> Vec<PrismRootCandidate> {
+    let mut candidates = Vec::new();
+    let launcher = LauncherKind::Prism;
 
-    children.sort_by(|left, right| {
-        let left_rank = match left.node_type {
-            TreeNodeType::Folder => 0,
-            TreeNodeType::File => 1,
-        };
-        let right_rank = match right.node_type {
-            TreeNodeType::Folder => 0,
-            TreeNodeType::File => 1,
-        };
+    if let Some(home) = home_dir() {
+        candidates.push(build_candidate(
+            home.join("Library/Application Support/PrismLauncher"),
+            "macos-default",
+            launcher,
+        ));
+        candidates.push(build_candidate(
+            home.join(".local/share/PrismLauncher"),
+            "linux-default",
+            launcher,
+        ));
+        candidates.push(build_candidate(
+            home.join("PrismLauncher"),
+            "portable-home",
+            launcher,
+        ));
+    }
 
-        left_rank
-            .cmp(&right_rank)
-            .then(left.name.to_lowercase().cmp(&right.name.to_lowercase()))
-    });
+    if let Some(app_data) = env::var_os("APPDATA") {
+        candidates.push(build_candidate(
+            PathBuf::from(app_data).join("PrismLauncher"),
+            "windows-default",
+            launcher,
+        ));
+    }
 
-    Ok(children)
+    if let Ok(custom_root) = env::var("PRISM_ROOT") {
+        candidates.push(build_candidate(
+            PathBuf::from(custom_root),
+            "env-prism-root",
+            launcher,
+        ));
+    }
+
+    candidates
 }
 
-#[tauri::command]
-fn search_assets(req: SearchRequest, state: State<'_, AppState>) -> Result<SearchResponse, String> {
-    let scans = state
-        .scans
-        .lock()
-        .map_err(|_| "Failed to lock scans state".to_string())?;
+fn vanilla_root_candidates() -> Vec<PrismRootCandidate> {
+    let mut candidates = Vec::new();
+    let launcher = LauncherKind::Vanilla;
 
-    let scan = scans
-        .get(&req.scan_id)
-        .ok_or_else(|| format!("Unknown scan id: {}", req.scan_id))?;
+    if let Some(home) = home_dir() {
+        candidates.push(build_candidate(
+            home.join("Library/Application Support/minecraft"),
+            "macos-default",
+            launcher,
+        ));
+        candidates.push(build_candidate(
+            home.join(".minecraft"),
+            "linux-default",
+            launcher,
+        ));
+    }
 
-    let offset = req.offset.unwrap_or(0);
-    let limit = req.limit.unwrap_or(200).clamp(1, 1000);
-    let include_images = req.include_images.unwrap_or(true);
-    let include_audio = req.include_audio.unwrap_or(true);
-    let include_other = req.include_other.unwrap_or(true);
-    let folder_filter = req
-        .folder_node_id
-        .as_deref()
-        .filter(|value| !value.trim().is_empty() && *value != ROOT_NODE_ID);
-    let query_tokens = split_tokens(&req.query);
-    let query_compact = compact_text(&req.query);
-    let normalized_query = query_tokens.join(" ");
+    if let Some(app_data) = env::var_os("APPDATA") {
+        candidates.push(build_candidate(
+            PathBuf::from(app_data).join(".minecraft"),
+            "windows-default",
+            launcher,
+        ));
+    }
 
-    if !(include_images || include_audio || include_other) {
-        return Ok(SearchResponse {
-            total: 0,
-            assets: Vec::new(),
-        });
+    candidates
+}
+
+fn multimc_root_candidates() -> Vec<PrismRootCandidate> {
+    let mut candidates = Vec::new();
+    let launcher = LauncherKind::MultiMc;
+
+    if let Some(home) = home_dir() {
+        candidates.push(build_candidate(
+            home.join("Library/Application Support/MultiMC"),
+            "macos-default",
+            launcher,
+        ));
+        candidates.push(build_candidate(
+            home.join(".local/share/multimc"),
+            "linux-default",
+            launcher,
+        ));
     }
 
-    if query_tokens.is_empty() {
-        let mut matched = Vec::<usize>::new();
-        for (index, asset) in scan.assets.iter().enumerate() {
-            if !asset_matches_media(asset, include_images, include_audio, include_other) {
-                continue;
-            }
-            let search_record = &scan.search_records[index];
-            if !asset_matches_folder(search_record, folder_filter) {
-                continue;
-            }
-            matched.push(index);
-        }
+    if let Some(app_data) = env::var_os("APPDATA") {
+        candidates.push(build_candidate(
+            PathBuf::from(app_data).join("MultiMC"),
+            "windows-default",
+            launcher,
+        ));
+    }
 
-        matched.sort_unstable_by(|left, right| {
-            idle_asset_cmp(&scan.assets[*left], &scan.assets[*right])
-        });
-        let total = matched.len();
-        let assets = matched
-            .into_iter()
-            .skip(offset)
-            .take(limit)
-            .map(|index| scan.assets[index].clone())
-            .collect();
+    candidates
+}
+
+#[tauri::command]
+fn list_instances(
+    prism_root: String,
+    launcher_kind: Option<LauncherKind>,
+) -> Result<Vec<InstanceInfo>, String> {
+    let launcher_kind = launcher_kind.unwrap_or_default();
+    let prism_root = expand_home(&prism_root);
+    validate_launcher_root(&prism_root, launcher_kind)?;
 
-        return Ok(SearchResponse { total, assets });
+    let instances_dir = prism_root.join(launcher_kind.instances_dir_name());
+    if !instances_dir.exists() {
+        return Ok(Vec::new());
     }
 
-    let mut ranked = Vec::new();
-    for (index, asset) in scan.assets.iter().enumerate() {
-        if !asset_matches_media(asset, include_images, include_audio, include_other) {
+    let mut instances = Vec::new();
+    let entries = fs::read_dir(&instances_dir)
+        .map_err(|error| format!("Failed to read instances directory: {error}"))?;
+
+    for entry in entries {
+        let entry = match entry {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+
+        let instance_path = entry.path();
+        if !instance_path.is_dir() {
             continue;
         }
 
-        let search_record = &scan.search_records[index];
-        if !asset_matches_folder(search_record, folder_filter) {
+        let folder_name = entry.file_name().to_string_lossy().to_string();
+        if folder_name.starts_with('.') {
             continue;
         }
 
-        if let Some(score) = score_query(
-            search_record,
-            &query_tokens,
-            &query_compact,
-            &normalized_query,
-        ) {
-            ranked.push((score, index));
+        if !is_launcher_instance_dir(&instance_path, launcher_kind) {
+            continue;
         }
-    }
 
-    let total = ranked.len();
-    let wanted = offset.saturating_add(limit).max(1);
-    if ranked.len() > wanted {
-        ranked.select_nth_unstable_by(wanted - 1, |left, right| {
-            right
-                .0
-                .cmp(&left.0)
-                .then_with(|| scan.assets[left.1].key.cmp(&scan.assets[right.1].key))
+        let display_name = launcher_instance_display_name(&instance_path, launcher_kind)
+            .unwrap_or_else(|| folder_name.clone());
+        let minecraft_version = launcher_instance_version(&instance_path, launcher_kind);
+
+        instances.push(InstanceInfo {
+            folder_name,
+            display_name,
+            path: instance_path.to_string_lossy().to_string(),
+            minecraft_version,
         });
-        ranked.truncate(wanted);
     }
 
-    ranked.sort_unstable_by(|left, right| {
-        right
-            .0
-            .cmp(&left.0)
-            .then_with(|| scan.assets[left.1].key.cmp(&scan.assets[right.1].key))
-    });
+    instances.sort_by(|left, right| left.display_name.cmp(&right.display_name));
+    Ok(instances)
+}
 
-    let assets = ranked
-        .into_iter()
-        .skip(offset)
-        .take(limit)
-        .map(|(_, index)| scan.assets[index].clone())
-        .collect();
+fn unix_timestamp_ms() -> u64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_millis().min(u128::from(u64::MAX)) as u64,
+        Err(_) => 0,
+    }
+}
 
-    Ok(SearchResponse { total, assets })
+fn file_mtime_ms(metadata: &fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|value| value.duration_since(UNIX_EPOCH).ok())
+        .map(|value| value.as_millis().min(u128::from(u64::MAX)) as u64)
+        .unwrap_or(0)
 }
 
-#[tauri::command]
-fn get_asset_preview(
-    scan_id: String,
-    asset_id: String,
-    state: State<'_, AppState>,
-) -> Result<AssetPreviewResponse, String> {
-    let asset = get_asset_from_state(&state, &scan_id, &asset_id)?;
+fn scan_container_key(container: &ScanContainer) -> String {
+    format!(
+        "{}::{}::{}::{}",
+        container.source_type.key_prefix(),
+        container.source_name,
+        container_type_key(&container.container_type),
+        container.container_path.to_string_lossy()
+    )
+}
 
-    if !asset.is_image && !asset.is_audio && !is_json_extension(&asset.extension) {
-        return Err("Preview is only available for image, audio or JSON assets".to_string());
+fn container_type_key(container_type: &AssetContainerType) -> &'static str {
+    match container_type {
+        AssetContainerType::Directory => "directory",
+        AssetContainerType::Zip => "zip",
+        AssetContainerType::Jar => "jar",
+        AssetContainerType::AssetIndex => "asset_index",
     }
+}
 
-    let bytes = extract_asset_bytes(&asset)?;
-    let base64 = base64::engine::general_purpose::STANDARD.encode(bytes);
+fn scan_cache_key_for_request(req: &StartScanRequest) -> String {
+    let prism_root = expand_home(&req.prism_root);
+    let prism_root = prism_root.to_string_lossy();
+    // Optional sources only append a flag when enabled so existing cache keys stay stable.
+    let key = format!(
+        "{}::{}::{}{}{}{}{}{}{}{}{}{}",
+        prism_root,
+        req.instance_folder.trim(),
+        if req.include_vanilla { 'v' } else { '-' },
+        if req.include_mods { 'm' } else { '-' },
+        if req.include_resourcepacks { 'r' } else { '-' },
+        if req.include_server_packs.unwrap_or(false) {
+            "s"
+        } else {
+            ""
+        },
+        if req.include_datapacks.unwrap_or(false) {
+            "d"
+        } else {
+            ""
+        },
+        if req.include_shaderpacks.unwrap_or(false) {
+            "h"
+        } else {
+            ""
+        },
+        if req.collect_dimensions.unwrap_or(false) {
+            "x"
+        } else {
+            ""
+        },
+        if req.group_by_instance.unwrap_or(false) {
+            "i"
+        } else {
+            ""
+        },
+        if req.include_disabled_mods.unwrap_or(false) {
+            "o"
+        } else {
+            ""
+        },
+        if req.follow_symlinks.unwrap_or(false) {
+            "l"
+        } else {
+            ""
+        },
+    );
+    let key = match vanilla_version_override(req) {
+        Some(version) => format!("{key}::version={version}"),
+        None => key,
+    };
+    match req.launcher_kind.unwrap_or_default() {
+        LauncherKind::Prism => key,
+        LauncherKind::MultiMc => format!("{key}::multimc"),
+        LauncherKind::Technic => format!("{key}::technic"),
+        LauncherKind::AtLauncher => format!("{key}::atlauncher"),
+        LauncherKind::Vanilla => format!("{key}::vanilla"),
+    }
+}
 
-    Ok(AssetPreviewResponse {
-        mime: mime_for_extension(&asset.extension).to_string(),
-        base64,
-    })
+fn fnv1a64(value: &str) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    let mut hash = OFFSET_BASIS;
+    for byte in value.as_bytes() {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(PRIME);
+    }
+    hash
 }
 
-#[tauri::command]
-fn get_asset_record(
-    scan_id: String,
-    asset_id: String,
-    state: State<'_, AppState>,
-) -> Result<AssetRecord, String> {
-    get_asset_from_state(&state, &scan_id, &asset_id)
+fn scan_cache_root(app: &AppHandle) -> Result<PathBuf, String> {
+    let root = app
+        .path()
+        .app_data_dir()
+        .map_err(|error| format!("Failed to resolve app data directory: {error}"))?
+        .join("scan-cache")
+        .join("v1");
+    fs::create_dir_all(&root)
+        .map_err(|error| format!("Failed to create scan cache directory: {error}"))?;
+    Ok(root)
 }
 
-#[tauri::command]
-fn reconcile_asset_ids(
-    req: ReconcileAssetIdsRequest,
-    state: State<'_, AppState>,
-) -> Result<ReconcileAssetIdsResponse, String> {
-    let scans = state
-        .scans
+/// Saves, loads, prunes and repairs each read-modify-write the manifest, and a
+/// repair would otherwise delete a snapshot whose save hasn't reached it yet.
+fn lock_scan_cache_dir(app: &AppHandle) -> Result<MutexGuard<'_, ()>, String> {
+    app.state::<AppState>()
+        .inner()
+        .scan_cache_dir
         .lock()
-        .map_err(|_| "Failed to lock scans state".to_string())?;
-    let scan = scans
-        .get(&req.scan_id)
-        .ok_or_else(|| format!("Unknown scan id: {}", req.scan_id))?;
+        .map_err(|_| "Failed to lock scan cache directory".to_string())
+}
 
-    let mut id_map = HashMap::<String, String>::new();
-    let mut resolved = Vec::<String>::new();
-    let mut seen = HashSet::<String>::new();
+fn scan_cache_limit(app: &AppHandle) -> u64 {
+    app.state::<AppState>()
+        .settings
+        .lock()
+        .map(|settings| settings.max_cache_bytes())
+        .unwrap_or(DEFAULT_SCAN_CACHE_MAX_BYTES)
+}
 
-    for asset_id in req.asset_ids {
-        let mapped = if scan.asset_index.contains_key(&asset_id) {
-            Some(asset_id.clone())
-        } else {
-            scan.id_aliases
-                .get(&asset_id)
-                .filter(|next| scan.asset_index.contains_key(*next))
-                .cloned()
-        };
+fn worker_limits(app: &AppHandle) -> WorkerLimits {
+    app.state::<AppState>()
+        .settings
+        .lock()
+        .map(|settings| settings.worker_limits())
+        .unwrap_or_else(|_| AppSettings::default().worker_limits())
+}
 
-        if let Some(mapped_id) = mapped {
-            id_map.insert(asset_id, mapped_id.clone());
-            if seen.insert(mapped_id.clone()) {
-                resolved.push(mapped_id);
-            }
-        }
-    }
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let root = app
+        .path()
+        .app_data_dir()
+        .map_err(|error| format!("Failed to resolve app data directory: {error}"))?;
+    fs::create_dir_all(&root)
+        .map_err(|error| format!("Failed to create app data directory: {error}"))?;
+    Ok(root.join(SETTINGS_FILE_NAME))
+}
 
-    Ok(ReconcileAssetIdsResponse {
-        id_map,
-        asset_ids: resolved,
+/// Falls back to defaults when the file is missing or unreadable so a bad
+/// settings file never blocks startup.
+fn load_settings(path: &Path) -> AppSettings {
+    let Ok(data) = fs::read_to_string(path) else {
+        return AppSettings::default();
+    };
+    serde_json::from_str(&data).unwrap_or_else(|error| {
+        log::warn!("ignoring unreadable settings {}: {error}", path.display());
+        AppSettings::default()
     })
 }
 
-#[tauri::command]
-fn save_assets(
-    app: AppHandle,
-    req: SaveAssetsRequest,
-    state: State<'_, AppState>,
-) -> Result<SaveAssetsResult, String> {
-    let operation_id = resolve_operation_id(req.operation_id);
-    let requested_count = req.asset_ids.len();
-
-    if req.asset_ids.is_empty() {
-        return Ok(SaveAssetsResult {
-            operation_id,
-            requested_count,
-            processed_count: 0,
-            success_count: 0,
-            failed_count: 0,
-            cancelled: false,
-            failures: Vec::new(),
-            saved_files: Vec::new(),
-        });
-    }
+fn save_settings(app: &AppHandle, settings: &AppSettings) -> Result<(), String> {
+    write_json_atomically(&settings_path(app)?, settings)
+}
 
-    let destination_dir = expand_home(&req.destination_dir);
-    fs::create_dir_all(&destination_dir)
-        .map_err(|error| format!("Failed to create destination directory: {error}"))?;
+fn scan_cache_manifest_path(cache_root: &Path) -> PathBuf {
+    cache_root.join("manifest.json")
+}
 
-    let requested_assets = collect_assets(&state, &req.scan_id, &req.asset_ids)?;
-    register_export_operation(&state, &operation_id)?;
+fn scan_cache_snapshot_file_name(cache_key: &str) -> String {
+    format!("{:016x}.bin", fnv1a64(cache_key))
+}
 
-    let run_result = run_export_operation(
-        &app,
-        ExportOperationKind::Save,
-        &operation_id,
-        requested_assets,
-        &destination_dir,
-        req.audio_format.unwrap_or(AudioFormat::Original),
-    );
+fn scan_cache_snapshot_legacy_file_name(cache_key: &str) -> String {
+    format!("{:016x}.json", fnv1a64(cache_key))
+}
 
-    unregister_export_operation(&state, &operation_id);
+fn scan_cache_snapshot_path(cache_root: &Path, cache_key: &str) -> PathBuf {
+    cache_root.join(scan_cache_snapshot_file_name(cache_key))
+}
 
-    let outcome = run_result?;
-    Ok(SaveAssetsResult {
-        operation_id,
-        requested_count,
-        processed_count: outcome.processed_count,
-        success_count: outcome.success_count,
-        failed_count: outcome.failed_count,
-        cancelled: outcome.cancelled,
-        failures: outcome.failures,
-        saved_files: outcome.output_files,
-    })
+fn scan_cache_snapshot_legacy_path(cache_root: &Path, cache_key: &str) -> PathBuf {
+    cache_root.join(scan_cache_snapshot_legacy_file_name(cache_key))
 }
 
-#[tauri::command]
-fn copy_assets_to_clipboard(
-    app: AppHandle,
-    req: CopyAssetsRequest,
-    state: State<'_, AppState>,
-) -> Result<CopyResult, String> {
-    let operation_id = resolve_operation_id(req.operation_id);
-    let requested_count = req.asset_ids.len();
+fn has_cached_snapshot(app: &AppHandle, cache_key: &str) -> bool {
+    let Ok(cache_root) = scan_cache_root(app) else {
+        return false;
+    };
+    scan_cache_snapshot_path(&cache_root, cache_key).is_file()
+        || scan_cache_snapshot_legacy_path(&cache_root, cache_key).is_file()
+}
 
-    if req.asset_ids.is_empty() {
-        return Ok(CopyResult {
-            operation_id,
-            requested_count,
-            processed_count: 0,
-            success_count: 0,
-            failed_count: 0,
-            cancelled: false,
-            failures: Vec::new(),
-            copied_files: Vec::new(),
-        });
-    }
+fn write_json_atomically<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let temp_path = path.with_extension(format!("tmp-{}", Uuid::new_v4()));
+    let bytes = serde_json::to_vec(value)
+        .map_err(|error| format!("Failed to serialize {}: {error}", path.display()))?;
+    fs::write(&temp_path, bytes)
+        .map_err(|error| format!("Failed to write {}: {error}", temp_path.display()))?;
+    fs::rename(&temp_path, path)
+        .map_err(|error| format!("Failed to replace {}: {error}", path.display()))?;
+    Ok(())
+}
 
-    let requested_assets = collect_assets(&state, &req.scan_id, &req.asset_ids)?;
-    let temp_root = app
-        .path()
-        .app_cache_dir()
-        .map_err(|error| format!("Failed to get app cache directory: {error}"))?
-        .join("clipboard-assets")
-        .join(Uuid::new_v4().to_string());
+fn write_bytes_atomically(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let temp_path = path.with_extension(format!("tmp-{}", Uuid::new_v4()));
+    fs::write(&temp_path, bytes)
+        .map_err(|error| format!("Failed to write {}: {error}", temp_path.display()))?;
+    fs::rename(&temp_path, path)
+        .map_err(|error| format!("Failed to replace {}: {error}", path.display()))?;
+    Ok(())
+}
 
-    fs::create_dir_all(&temp_root)
-        .map_err(|error| format!("Failed to create temporary copy directory: {error}"))?;
+fn load_scan_cache_manifest(cache_root: &Path) -> Result<ScanCacheManifest, String> {
+    let manifest_path = scan_cache_manifest_path(cache_root);
+    if !manifest_path.is_file() {
+        return Ok(ScanCacheManifest::default());
+    }
 
-    register_export_operation(&state, &operation_id)?;
+    let data = fs::read_to_string(&manifest_path)
+        .map_err(|error| format!("Failed to read cache manifest: {error}"))?;
+    let parsed: ScanCacheManifest = serde_json::from_str(&data)
+        .map_err(|error| format!("Failed to parse cache manifest: {error}"))?;
+    if parsed.schema_version != SCAN_CACHE_SCHEMA_VERSION {
+        return Ok(ScanCacheManifest::default());
+    }
+    Ok(parsed)
+}
 
-    let run_result = run_export_operation(
-        &app,
-        ExportOperationKind::Copy,
-        &operation_id,
-        requested_assets,
-        &temp_root,
-        req.audio_format.unwrap_or(AudioFormat::Original),
-    );
+fn save_scan_cache_manifest(cache_root: &Path, manifest: &ScanCacheManifest) -> Result<(), String> {
+    write_json_atomically(&scan_cache_manifest_path(cache_root), manifest)
+}
 
-    unregister_export_operation(&state, &operation_id);
+fn remove_cache_entry(cache_root: &Path, manifest: &mut ScanCacheManifest, cache_key: &str) {
+    let file_name = scan_cache_snapshot_file_name(cache_key);
+    let path = cache_root.join(&file_name);
+    let _ = fs::remove_file(path);
+    let legacy_path = scan_cache_snapshot_legacy_path(cache_root, cache_key);
+    let _ = fs::remove_file(legacy_path);
+    manifest.entries.remove(cache_key);
+}
 
-    let outcome = run_result?;
-    let copied_paths: Vec<PathBuf> = outcome.output_files.iter().map(PathBuf::from).collect();
+fn prune_scan_cache(cache_root: &Path, manifest: &mut ScanCacheManifest, max_bytes: u64) {
+    let mut total_size = manifest
+        .entries
+        .values()
+        .map(|entry| entry.size_bytes)
+        .sum::<u64>();
+    if total_size <= max_bytes {
+        return;
+    }
 
-    let clipboard = ClipboardContext::new()
-        .map_err(|error| format!("Failed to open clipboard context: {error}"))?;
+    let mut eviction_order = manifest
+        .entries
+        .iter()
+        .map(|(cache_key, entry)| (cache_key.clone(), entry.last_accessed_at))
+        .collect::<Vec<_>>();
+    eviction_order.sort_by(|left, right| left.1.cmp(&right.1));
 
-    if !copied_paths.is_empty() {
-        clipboard
-            .set_files(
-                copied_paths
-                    .iter()
-                    .map(|path| path.to_string_lossy().to_string())
-                    .collect(),
-            )
-            .map_err(|error| format!("Failed to copy files to clipboard: {error}"))?;
+    for (cache_key, _) in eviction_order {
+        let Some(entry) = manifest.entries.remove(&cache_key) else {
+            continue;
+        };
+        let path = cache_root.join(&entry.file_name);
+        let _ = fs::remove_file(path);
+        total_size = total_size.saturating_sub(entry.size_bytes);
+        if total_size <= max_bytes {
+            break;
+        }
     }
+}
 
-    {
-        let mut temp_paths = state
-            .temp_paths
-            .lock()
-            .map_err(|_| "Failed to lock temp paths".to_string())?;
-        temp_paths.push(temp_root);
+/// Removes every snapshot (current and legacy) plus stray temp files and resets
+/// the manifest, returning how many snapshots were removed and their size.
+fn clear_scan_cache_dir(cache_root: &Path) -> Result<ClearScanCacheResult, String> {
+    let manifest_path = scan_cache_manifest_path(cache_root);
+    let read_dir = fs::read_dir(cache_root)
+        .map_err(|error| format!("Failed to read scan cache directory: {error}"))?;
+
+    let mut result = ClearScanCacheResult {
+        removed_entries: 0,
+        freed_bytes: 0,
+    };
+    for entry in read_dir.flatten() {
+        let path = entry.path();
+        if path == manifest_path || !path.is_file() {
+            continue;
+        }
+        let is_snapshot = matches!(
+            path.extension().and_then(|value| value.to_str()),
+            Some("bin" | "json")
+        );
+        let size = entry.metadata().map(|meta| meta.len()).unwrap_or(0);
+        if fs::remove_file(&path).is_ok() {
+            result.freed_bytes += size;
+            if is_snapshot {
+                result.removed_entries += 1;
+            }
+        }
     }
 
-    Ok(CopyResult {
-        operation_id,
-        requested_count,
-        processed_count: outcome.processed_count,
-        success_count: outcome.success_count,
-        failed_count: outcome.failed_count,
-        cancelled: outcome.cancelled,
-        failures: outcome.failures,
-        copied_files: outcome.output_files,
-    })
+    save_scan_cache_manifest(cache_root, &ScanCacheManifest::default())?;
+    Ok(result)
 }
 
-#[tauri::command]
-fn convert_audio_asset(
-    app: AppHandle,
-    req: ConvertAudioRequest,
-    state: State<'_, AppState>,
-) -> Result<ConvertedTempFileRef, String> {
-    if req.format == AudioFormat::Original {
-        return Err("Use save/copy with original format instead of convert command".to_string());
-    }
+/// Deletes snapshot files no manifest entry points at and drops entries whose
+/// file is gone. Temp files are left alone since a save may be in flight.
+fn repair_scan_cache_dir(
+    cache_root: &Path,
+    manifest: &mut ScanCacheManifest,
+) -> Result<RepairScanCacheResult, String> {
+    let mut result = RepairScanCacheResult::default();
+    let before = manifest.entries.len();
+    manifest
+        .entries
+        .retain(|_, entry| cache_root.join(&entry.file_name).is_file());
+    result.removed_entries = before - manifest.entries.len();
 
-    let asset = get_asset_from_state(&state, &req.scan_id, &req.asset_id)?;
-    if !asset.is_audio {
-        return Err("Selected asset is not an audio file".to_string());
+    let referenced = manifest
+        .entries
+        .values()
+        .map(|entry| entry.file_name.as_str())
+        .collect::<HashSet<_>>();
+    let manifest_path = scan_cache_manifest_path(cache_root);
+    let read_dir = fs::read_dir(cache_root)
+        .map_err(|error| format!("Failed to read scan cache directory: {error}"))?;
+    for entry in read_dir.flatten() {
+        let path = entry.path();
+        let is_snapshot = matches!(
+            path.extension().and_then(|value| value.to_str()),
+            Some("bin" | "json")
+        );
+        if path == manifest_path || !is_snapshot || !path.is_file() {
+            continue;
+        }
+        if referenced.contains(entry.file_name().to_string_lossy().as_ref()) {
+            continue;
+        }
+        let size = entry.metadata().map(|meta| meta.len()).unwrap_or(0);
+        if fs::remove_file(&path).is_ok() {
+            result.removed_files += 1;
+            result.freed_bytes += size;
+        }
     }
+    Ok(result)
+}
 
-    let temp_root = app
-        .path()
-        .app_cache_dir()
-        .map_err(|error| format!("Failed to get app cache directory: {error}"))?
-        .join("converted-audio")
-        .join(Uuid::new_v4().to_string());
+fn scan_cache_entry_stats(manifest: &ScanCacheManifest) -> Vec<ScanCacheEntryStats> {
+    let mut stats = manifest
+        .entries
+        .iter()
+        .map(|(cache_key, entry)| ScanCacheEntryStats {
+            cache_key: cache_key.clone(),
+            size_bytes: entry.size_bytes,
+            last_accessed_at: entry.last_accessed_at,
+            created_at: entry.created_at,
+        })
+        .collect::<Vec<_>>();
+    stats.sort_by(|left, right| {
+        right
+            .last_accessed_at
+            .cmp(&left.last_accessed_at)
+            .then_with(|| left.cache_key.cmp(&right.cache_key))
+    });
+    stats
+}
 
-    fs::create_dir_all(&temp_root)
-        .map_err(|error| format!("Failed to create temporary conversion directory: {error}"))?;
+/// Outcome of `load_cached_snapshot`; `Invalidated` means a snapshot existed
+/// (or the manifest claimed one did) but had to be dropped.
+enum CachedSnapshotLoad {
+    Hit(Box<ScanSnapshot>),
+    Miss,
+    Invalidated(CacheInvalidationReason),
+}
 
-    let original_name = Path::new(&asset.relative_asset_path)
-        .file_name()
-        .map(|value| value.to_string_lossy().to_string())
-        .unwrap_or_else(|| asset.asset_id.clone());
-    let (base_stem, _) = split_file_name(&original_name);
-    let extension = match req.format {
-        AudioFormat::Original => asset.extension.clone(),
-        AudioFormat::Mp3 => "mp3".to_string(),
-        AudioFormat::Wav => "wav".to_string(),
+fn load_cached_snapshot(app: &AppHandle, cache_key: &str) -> Result<CachedSnapshotLoad, String> {
+    let cache_root = scan_cache_root(app)?;
+    let _cache_dir = lock_scan_cache_dir(app)?;
+    let mut manifest = load_scan_cache_manifest(&cache_root)?;
+    let snapshot_path = scan_cache_snapshot_path(&cache_root, cache_key);
+    let legacy_path = scan_cache_snapshot_legacy_path(&cache_root, cache_key);
+    let loaded = if snapshot_path.is_file() {
+        fs::read(&snapshot_path)
+            .map_err(|_| CacheInvalidationReason::ParseError)
+            .and_then(|bytes| {
+                // `schema_version` is the first field, so it still decodes when
+                // the rest of an older snapshot no longer does.
+                bincode::deserialize::<ScanSnapshot>(&bytes).map_err(
+                    |_| match bincode::deserialize::<u32>(&bytes) {
+                        Ok(version) if version != SCAN_CACHE_SCHEMA_VERSION => {
+                            CacheInvalidationReason::SchemaMismatch
+                        }
+                        _ => CacheInvalidationReason::ParseError,
+                    },
+                )
+            })
+            .map(|parsed| (parsed, snapshot_path.clone(), false))
+    } else if legacy_path.is_file() {
+        fs::read_to_string(&legacy_path)
+            .map_err(|_| CacheInvalidationReason::ParseError)
+            .and_then(|data| {
+                serde_json::from_str::<ScanSnapshot>(&data).map_err(|_| {
+                    match serde_json::from_str::<serde_json::Value>(&data)
+                        .ok()
+                        .and_then(|value| value.get("schemaVersion")?.as_u64())
+                    {
+                        Some(version) if version != u64::from(SCAN_CACHE_SCHEMA_VERSION) => {
+                            CacheInvalidationReason::SchemaMismatch
+                        }
+                        _ => CacheInvalidationReason::ParseError,
+                    }
+                })
+            })
+            .map(|parsed| (parsed, legacy_path.clone(), true))
+    } else {
+        let had_entry = manifest.entries.remove(cache_key).is_some();
+        let _ = save_scan_cache_manifest(&cache_root, &manifest);
+        return Ok(if had_entry {
+            CachedSnapshotLoad::Invalidated(CacheInvalidationReason::MissingFile)
+        } else {
+            CachedSnapshotLoad::Miss
+        });
     };
 
-    let mut used_names = HashSet::new();
-    let output_name = dedupe_file_name(&base_stem, &extension, &temp_root, &mut used_names);
-    let output_path = temp_root.join(output_name);
-
-    let ffmpeg_path = resolve_ffmpeg_path(&app)?;
-    let mut archive_cache = HashMap::<String, ZipArchive<fs::File>>::new();
-    let bytes = extract_asset_bytes_with_archive_cache(&asset, &mut archive_cache)?;
-    convert_audio_bytes_to_file(&ffmpeg_path, &bytes, &output_path, &req.format)?;
+    let loaded = loaded.and_then(|loaded| {
+        if loaded.0.schema_version == SCAN_CACHE_SCHEMA_VERSION {
+            Ok(loaded)
+        } else {
+            Err(CacheInvalidationReason::SchemaMismatch)
+        }
+    });
+    let (mut parsed, source_path, was_legacy_json) = match loaded {
+        Ok(loaded) => loaded,
+        Err(reason) => {
+            remove_cache_entry(&cache_root, &mut manifest, cache_key);
+            let _ = save_scan_cache_manifest(&cache_root, &manifest);
+            return Ok(CachedSnapshotLoad::Invalidated(reason));
+        }
+    };
 
-    {
-        let mut temp_paths = state
-            .temp_paths
-            .lock()
-            .map_err(|_| "Failed to lock temp paths".to_string())?;
-        temp_paths.push(temp_root);
+    parsed.last_used_at = unix_timestamp_ms();
+    if was_legacy_json {
+        if let Ok(bytes) = bincode::serialize(&parsed) {
+            let _ = write_bytes_atomically(&snapshot_path, &bytes);
+            let _ = fs::remove_file(&legacy_path);
+        }
     }
 
-    Ok(ConvertedTempFileRef {
-        path: output_path.to_string_lossy().to_string(),
-        format: req.format,
-    })
+    let canonical_path = if snapshot_path.is_file() {
+        snapshot_path
+    } else {
+        source_path
+    };
+    let now = unix_timestamp_ms();
+    let entry = manifest
+        .entries
+        .entry(cache_key.to_string())
+        .or_insert(ScanCacheManifestEntry {
+            file_name: canonical_path
+                .file_name()
+                .map(|value| value.to_string_lossy().to_string())
+                .unwrap_or_else(|| scan_cache_snapshot_file_name(cache_key)),
+            size_bytes: fs::metadata(&canonical_path)
+                .map(|meta| meta.len())
+                .unwrap_or(0),
+            last_accessed_at: now,
+            created_at: parsed.created_at,
+        });
+    entry.file_name = canonical_path
+        .file_name()
+        .map(|value| value.to_string_lossy().to_string())
+        .unwrap_or_else(|| scan_cache_snapshot_file_name(cache_key));
+    entry.size_bytes = fs::metadata(&canonical_path)
+        .map(|meta| meta.len())
+        .unwrap_or(0);
+    entry.last_accessed_at = now;
+    let _ = save_scan_cache_manifest(&cache_root, &manifest);
+    Ok(CachedSnapshotLoad::Hit(Box::new(parsed)))
 }
 
-fn run_scan_bootstrap_worker(
-    app: AppHandle,
-    scan_id: String,
-    req: StartScanRequest,
-    cache_key: String,
-    force_rescan: bool,
-) {
-    let result = run_scan_bootstrap_worker_inner(&app, &scan_id, &req, &cache_key, force_rescan);
-    if let Err(error) = result {
-        update_scan_error(&app, &scan_id, &error);
-        let _ = app.emit(
-            "scan://error",
-            serde_json::json!({
-                "scanId": scan_id,
-                "error": error,
-            }),
-        );
-    }
+fn save_snapshot_to_cache(app: &AppHandle, snapshot: &ScanSnapshot) -> Result<(), String> {
+    let cache_root = scan_cache_root(app)?;
+    let _cache_dir = lock_scan_cache_dir(app)?;
+    let mut manifest = load_scan_cache_manifest(&cache_root)?;
+    let snapshot_path = scan_cache_snapshot_path(&cache_root, &snapshot.cache_key);
+    let bytes = bincode::serialize(snapshot).map_err(|error| {
+        format!(
+            "Failed to serialize snapshot {}: {error}",
+            snapshot.cache_key
+        )
+    })?;
+    write_bytes_atomically(&snapshot_path, &bytes)?;
+    let legacy_path = scan_cache_snapshot_legacy_path(&cache_root, &snapshot.cache_key);
+    let _ = fs::remove_file(legacy_path);
+    let size_bytes = fs::metadata(&snapshot_path)
+        .map(|meta| meta.len())
+        .map_err(|error| {
+            format!(
+                "Failed to stat cache snapshot {}: {error}",
+                snapshot_path.display()
+            )
+        })?;
+    manifest.entries.insert(
+        snapshot.cache_key.clone(),
+        ScanCacheManifestEntry {
+            file_name: scan_cache_snapshot_file_name(&snapshot.cache_key),
+            size_bytes,
+            last_accessed_at: unix_timestamp_ms(),
+            created_at: snapshot.created_at,
+        },
+    );
+    prune_scan_cache(&cache_root, &mut manifest, scan_cache_limit(app));
+    save_scan_cache_manifest(&cache_root, &manifest)
 }
 
-fn run_scan_bootstrap_worker_inner(
-    app: &AppHandle,
-    scan_id: &str,
-    req: &StartScanRequest,
-    cache_key: &str,
-    force_rescan: bool,
-) -> Result<(), String> {
-    if !force_rescan {
-        if let Some(snapshot) = load_cached_snapshot(app, cache_key)? {
-            let cached_asset_count = snapshot.assets.len();
-            {
-                let state = app.state::<AppState>();
-                let mut scans = state
-                    .scans
-                    .lock()
-                    .map_err(|_| "Failed to lock scans state".to_string())?;
-                if let Some(scan) = scans.get_mut(scan_id) {
-                    scan.status = ScanLifecycle::Completed;
-                    scan.is_refreshing = true;
-                    scan.scanned_containers = snapshot.container_signatures.len();
-                    scan.total_containers = snapshot.container_signatures.len();
-                    scan.error = None;
-                    scan.cancelled = false;
-                    scan.assets = snapshot.assets;
-                    scan.asset_index = scan
-                        .assets
-                        .iter()
-                        .enumerate()
-                        .map(|(index, asset)| (asset.asset_id.clone(), index))
-                        .collect();
-                    scan.search_records = if snapshot.search_records.len() == scan.assets.len() {
-                        snapshot.search_records
-                    } else {
-                        scan.assets.iter().map(build_search_record).collect()
-                    };
-                    scan.tree_children = snapshot.tree_children;
-                    scan.container_assets = snapshot.container_assets;
-                    scan.container_signatures = snapshot.container_signatures;
-                    scan.id_aliases = HashMap::new();
-                    scan.cache_key = Some(cache_key.to_string());
+/// With `follow_symlinks`, `WalkDir` keeps its loop detection and skips
+/// entries that would re-enter an ancestor.
+fn container_signature_for_path(
+    container_path: &Path,
+    container_type: &AssetContainerType,
+    follow_symlinks: bool,
+) -> Result<ContainerSignature, String> {
+    let metadata = fs::metadata(container_path).map_err(|error| {
+        format!(
+            "Failed to read metadata {}: {error}",
+            container_path.display()
+        )
+    })?;
+    let mut file_count = 0u64;
+    let mut total_size: u64;
+    let mut newest_mtime_ms: u64;
+    let mut entries = BTreeMap::new();
+
+    if matches!(container_type, AssetContainerType::Directory) {
+        total_size = 0;
+        file_count = 0;
+        newest_mtime_ms = 0;
+        for entry in WalkDir::new(container_path)
+            .follow_links(follow_symlinks)
+            .into_iter()
+            .filter_map(Result::ok)
+        {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            if let Ok(meta) = entry.metadata() {
+                let mtime_ms = file_mtime_ms(&meta);
+                file_count = file_count.saturating_add(1);
+                total_size = total_size.saturating_add(meta.len());
+                newest_mtime_ms = newest_mtime_ms.max(mtime_ms);
+                if let Ok(relative) = entry.path().strip_prefix(container_path) {
+                    entries.insert(normalize_archive_path(relative), (mtime_ms, meta.len()));
                 }
             }
-
-            let _ = app.emit(
-                "scan://completed",
-                ScanCompletedEvent {
-                    scan_id: scan_id.to_string(),
-                    lifecycle: ScanLifecycle::Completed,
-                    asset_count: cached_asset_count,
-                    error: None,
-                },
-            );
-
-            run_refresh_worker_inner(app, scan_id, req, cache_key)?;
-            return Ok(());
         }
+    } else {
+        total_size = metadata.len();
+        newest_mtime_ms = file_mtime_ms(&metadata);
     }
 
-    run_scan_worker(app.clone(), scan_id.to_string(), req.clone(), cache_key.to_string());
-    Ok(())
+    Ok(ContainerSignature {
+        kind: container_type.clone(),
+        path: container_path.to_string_lossy().to_string(),
+        mtime_ms: file_mtime_ms(&metadata),
+        size: total_size,
+        file_count,
+        newest_mtime_ms,
+        entries,
+    })
 }
 
-fn run_scan_worker(app: AppHandle, scan_id: String, req: StartScanRequest, cache_key: String) {
-    let result = run_scan_worker_inner(&app, &scan_id, &req, &cache_key);
-
-    if let Err(error) = result {
-        update_scan_error(&app, &scan_id, &error);
-        let _ = app.emit(
-            "scan://error",
-            serde_json::json!({
-                "scanId": scan_id,
-                "error": error,
-            }),
-        );
-    }
-}
+fn build_scan_refresh_plan(
+    cached_signatures: &HashMap<String, ContainerSignature>,
+    current_containers: &[ScanContainer],
+    follow_symlinks: bool,
+) -> Result<ScanRefreshPlan, String> {
+    let mut unchanged_keys = Vec::new();
+    let mut changed_or_new = Vec::new();
+    let mut partial = Vec::new();
+    let mut signatures_by_key = HashMap::new();
 
-fn run_scan_worker_inner(
-    app: &AppHandle,
-    scan_id: &str,
-    req: &StartScanRequest,
-    cache_key: &str,
-) -> Result<(), String> {
-    {
-        let state = app.state::<AppState>();
-        let lock_result = state.scans.lock();
-        if let Ok(mut scans) = lock_result {
-            if let Some(scan) = scans.get_mut(scan_id) {
-                scan.cache_key = Some(cache_key.to_string());
-                scan.is_refreshing = false;
-            }
+    for container in current_containers {
+        let key = scan_container_key(container);
+        let signature = container_signature_for_path(
+            &container.container_path,
+            &container.container_type,
+            follow_symlinks,
+        )?;
+        match cached_signatures.get(&key) {
+            Some(cached) if cached.coarse_eq(&signature) => unchanged_keys.push(key.clone()),
+            Some(cached) => match signature.entry_changes(cached) {
+                Some((changed, removed)) if changed.is_empty() && removed.is_empty() => {
+                    unchanged_keys.push(key.clone())
+                }
+                Some((changed_entries, removed_entries)) => partial.push(PartialContainerRefresh {
+                    container: container.clone(),
+                    changed_entries,
+                    removed_entries,
+                }),
+                None => changed_or_new.push(container.clone()),
+            },
+            None => changed_or_new.push(container.clone()),
         }
+        signatures_by_key.insert(key, signature);
     }
 
-    let prism_root = expand_home(&req.prism_root);
-    validate_prism_root(&prism_root)?;
+    let mut removed_keys = cached_signatures
+        .keys()
+        .filter(|key| !signatures_by_key.contains_key(*key))
+        .cloned()
+        .collect::<Vec<_>>();
+    removed_keys.sort();
+    unchanged_keys.sort();
+    changed_or_new.sort_by(|left, right| scan_container_key(left).cmp(&scan_container_key(right)));
 
-    let instance_dir = resolve_instance_dir(&prism_root, &req.instance_folder)?;
-    let mc_version = parse_minecraft_version(&instance_dir.join("mmc-pack.json"))
-        .ok_or_else(|| "Failed to resolve Minecraft version from mmc-pack.json".to_string())?;
+    partial.sort_by(|left, right| {
+        scan_container_key(&left.container).cmp(&scan_container_key(&right.container))
+    });
 
-    let containers = collect_scan_containers(&prism_root, &instance_dir, &mc_version, req)?;
-    let total_containers = containers.len();
+    Ok(ScanRefreshPlan {
+        unchanged_keys,
+        changed_or_new,
+        removed_keys,
+        partial,
+        signatures_by_key,
+    })
+}
 
-    emit_scan_progress(
-        app,
-        ScanProgressEvent {
-            scan_id: scan_id.to_string(),
-            scanned_containers: 0,
-            total_containers,
-            asset_count: 0,
-            phase: ScanPhase::Scanning,
-            current_source: None,
-        },
-    );
+fn asset_identity(asset: &AssetRecord) -> String {
+    format!(
+        "{}::{}::{}::{}::{}::{}",
+        asset.source_type.key_prefix(),
+        asset.source_name,
+        asset.namespace,
+        asset.relative_asset_path,
+        asset.container_path,
+        asset.entry_path
+    )
+}
 
-    if total_containers == 0 {
-        complete_scan_with_lifecycle(app, scan_id, ScanLifecycle::Completed, None)?;
-        persist_scan_snapshot(app, scan_id, req, cache_key)?;
-        return Ok(());
+fn build_asset_reconciliation_map(
+    previous_assets: &[AssetRecord],
+    next_assets: &[AssetRecord],
+) -> HashMap<String, String> {
+    let mut next_by_id = HashSet::new();
+    let mut next_by_identity = HashMap::<String, Vec<String>>::new();
+    for asset in next_assets {
+        next_by_id.insert(asset.asset_id.clone());
+        next_by_identity
+            .entry(asset_identity(asset))
+            .or_default()
+            .push(asset.asset_id.clone());
     }
 
-    enum ScanWorkerResult {
-        Container {
-            container_key: String,
-            source_name: String,
-            signature: ContainerSignature,
-            candidates: Vec<AssetCandidate>,
-        },
-        Error(String),
+    let mut identity_cursor = HashMap::<String, usize>::new();
+    let mut id_map = HashMap::<String, String>::new();
+    for asset in previous_assets {
+        if next_by_id.contains(&asset.asset_id) {
+            id_map.insert(asset.asset_id.clone(), asset.asset_id.clone());
+            continue;
+        }
+
+        let identity = asset_identity(asset);
+        let Some(candidates) = next_by_identity.get(&identity) else {
+            continue;
+        };
+        let cursor = identity_cursor.entry(identity).or_insert(0);
+        let resolved = candidates
+            .get(*cursor)
+            .or_else(|| candidates.last())
+            .cloned();
+        if let Some(mapped_id) = resolved {
+            id_map.insert(asset.asset_id.clone(), mapped_id);
+            *cursor = cursor.saturating_add(1);
+        }
     }
 
-    let workers = thread::available_parallelism()
-        .map(|value| value.get().saturating_sub(2))
-        .unwrap_or(1)
-        .clamp(1, MAX_SCAN_WORKERS)
-        .min(total_containers);
+    id_map
+}
 
-    let (sender, receiver) = mpsc::channel::<ScanWorkerResult>();
-    let next_index = Arc::new(AtomicUsize::new(0));
-    let containers = Arc::new(containers);
-    let scan_id_owned = scan_id.to_string();
+#[tauri::command]
+fn start_scan(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    req: StartScanRequest,
+) -> Result<StartScanResponse, String> {
+    let scan_id = Uuid::new_v4().to_string();
+    let force_rescan = req.force_rescan.unwrap_or(false);
+    let cache_key = scan_cache_key_for_request(&req);
+    let likely_cache_hit = !force_rescan && has_cached_snapshot(&app, &cache_key);
 
-    for _ in 0..workers {
-        let sender = sender.clone();
-        let next_index = Arc::clone(&next_index);
-        let containers = Arc::clone(&containers);
-        let app = app.clone();
-        let scan_id = scan_id_owned.clone();
+    {
+        let mut scans = state
+            .scans
+            .lock()
+            .map_err(|_| "Failed to lock scans state".to_string())?;
 
-        thread::spawn(move || loop {
-            if is_scan_cancelled(&app, &scan_id).unwrap_or(true) {
-                break;
-            }
+        let mut scan_state = ScanState::new();
+        scan_state.is_refreshing = likely_cache_hit;
+        scans.insert(scan_id.clone(), scan_state);
+    }
 
-            let index = next_index.fetch_add(1, AtomicOrdering::Relaxed);
-            if index >= containers.len() {
-                break;
-            }
+    let _ = app.emit(
+        "scan://started",
+        serde_json::json!({
+            "scanId": scan_id,
+        }),
+    );
 
-            let container = &containers[index];
-            let container_key = scan_container_key(container);
-            let signature =
-                match container_signature_for_path(&container.container_path, &container.container_type)
-                {
-                    Ok(value) => value,
-                    Err(error) => {
-                        let _ = sender.send(ScanWorkerResult::Error(error));
-                        break;
-                    }
-                };
-            match scan_container(container, &|| is_scan_cancelled(&app, &scan_id).unwrap_or(true)) {
-                Ok(candidates) => {
-                    if sender
-                        .send(ScanWorkerResult::Container {
-                            container_key,
-                            source_name: container.source_name.clone(),
-                            signature,
-                            candidates,
-                        })
-                        .is_err()
-                    {
-                        break;
-                    }
-                }
-                Err(error) => {
-                    if is_scan_cancelled(&app, &scan_id).unwrap_or(true) {
-                        break;
-                    }
-                    let _ = sender.send(ScanWorkerResult::Error(error));
-                    break;
-                }
-            }
-        });
-    }
+    emit_scan_progress(
+        &app,
+        ScanProgressEvent {
+            scan_id: scan_id.clone(),
+            scanned_containers: 0,
+            total_containers: if likely_cache_hit { 1 } else { 0 },
+            asset_count: 0,
+            phase: if likely_cache_hit {
+                ScanPhase::Refreshing
+            } else {
+                ScanPhase::Estimating
+            },
+            current_source: if likely_cache_hit {
+                Some("cache".to_string())
+            } else {
+                None
+            },
+            elapsed_ms: 0,
+            containers_per_second: None,
+        },
+    );
 
-    drop(sender);
+    remember_scan_options(&app, &state, &req);
 
-    let mut key_counts = HashMap::<String, usize>::new();
-    let mut scanned_containers = 0usize;
+    let scan_id_for_worker = scan_id.clone();
+    let app_for_worker = app.clone();
+    let req_for_worker = req.clone();
+    let cache_key_for_worker = cache_key.clone();
+    thread::spawn(move || {
+        run_scan_bootstrap_worker(
+            app_for_worker,
+            scan_id_for_worker,
+            req_for_worker,
+            cache_key_for_worker,
+            force_rescan,
+        );
+    });
 
-    while scanned_containers < total_containers {
-        if is_scan_cancelled(app, scan_id)? {
-            complete_scan_with_lifecycle(app, scan_id, ScanLifecycle::Cancelled, None)?;
-            return Ok(());
+    Ok(StartScanResponse {
+        scan_id,
+        cache_hit: likely_cache_hit,
+        refresh_started: likely_cache_hit,
+        refresh_mode: if likely_cache_hit {
+            Some("incremental".to_string())
+        } else {
+            None
+        },
+    })
+}
+
+#[tauri::command]
+fn start_multi_scan(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    req: StartMultiScanRequest,
+) -> Result<StartScanResponse, String> {
+    if req.instance_folders.is_empty() {
+        return Err("Select at least one instance to scan".to_string());
+    }
+    let scan_id = Uuid::new_v4().to_string();
+    {
+        let mut scans = state
+            .scans
+            .lock()
+            .map_err(|_| "Failed to lock scans state".to_string())?;
+        scans.insert(scan_id.clone(), ScanState::new());
+    }
+
+    let _ = app.emit(
+        "scan://started",
+        serde_json::json!({
+            "scanId": scan_id,
+        }),
+    );
+    emit_scan_progress(
+        &app,
+        ScanProgressEvent {
+            scan_id: scan_id.clone(),
+            scanned_containers: 0,
+            total_containers: 0,
+            asset_count: 0,
+            phase: ScanPhase::Estimating,
+            current_source: None,
+            elapsed_ms: 0,
+            containers_per_second: None,
+        },
+    );
+
+    let scan_id_for_worker = scan_id.clone();
+    thread::spawn(move || {
+        log::info!(
+            "scan {scan_id_for_worker}: started for {} instances",
+            req.instance_folders.len()
+        );
+        if let Err(error) = run_multi_scan_worker_inner(&app, &scan_id_for_worker, &req) {
+            log::error!("scan {scan_id_for_worker}: failed: {error}");
+            update_scan_error(&app, &scan_id_for_worker, &error);
+            let _ = app.emit(
+                "scan://error",
+                serde_json::json!({
+                    "scanId": scan_id_for_worker,
+                    "error": error,
+                }),
+            );
         }
+    });
 
-        match receiver.recv_timeout(Duration::from_millis(100)) {
-            Ok(ScanWorkerResult::Container {
-                container_key,
-                source_name,
-                signature,
-                candidates,
-            }) => {
-                scanned_containers += 1;
-                let assets = finalize_assets(candidates, &mut key_counts);
-                append_assets_chunk(
-                    app,
-                    scan_id,
-                    &container_key,
-                    &signature,
-                    &assets,
-                    scanned_containers,
-                    total_containers,
-                    ScanPhase::Scanning,
-                    Some(source_name),
-                )?;
+    Ok(StartScanResponse {
+        scan_id,
+        cache_hit: false,
+        refresh_started: false,
+        refresh_mode: None,
+    })
+}
+
+/// Failing to persist the options never blocks the scan itself.
+fn remember_scan_options(app: &AppHandle, state: &State<'_, AppState>, req: &StartScanRequest) {
+    let key = last_scan_options_key(&req.prism_root, &req.instance_folder);
+    let options = LastScanOptions::from_request(req);
+    let updated = match state.settings.lock() {
+        Ok(mut settings) => {
+            if settings.last_scan_options.get(&key) == Some(&options) {
+                return;
             }
-            Ok(ScanWorkerResult::Error(error)) => return Err(error),
-            Err(mpsc::RecvTimeoutError::Timeout) => continue,
-            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            settings.last_scan_options.insert(key, options);
+            settings.clone()
         }
+        Err(_) => return,
+    };
+    if let Err(error) = save_settings(app, &updated) {
+        log::warn!("failed to remember scan options: {error}");
     }
+}
 
-    if scanned_containers < total_containers && !is_scan_cancelled(app, scan_id)? {
-        return Err("Scan workers disconnected before processing all containers".to_string());
-    }
-
-    complete_scan_with_lifecycle(app, scan_id, ScanLifecycle::Completed, None)?;
-    persist_scan_snapshot(app, scan_id, req, cache_key)?;
+#[tauri::command]
+fn get_last_scan_options(
+    prism_root: String,
+    instance_folder: String,
+    state: State<'_, AppState>,
+) -> Result<Option<LastScanOptions>, String> {
+    let settings = state
+        .settings
+        .lock()
+        .map_err(|_| "Failed to lock settings state".to_string())?;
+    Ok(settings
+        .last_scan_options
+        .get(&last_scan_options_key(&prism_root, &instance_folder))
+        .cloned())
+}
 
-    Ok(())
+/// Fails only when the launcher root or instance folder is invalid; problems
+/// further down are reported in the returned `InstanceValidation`.
+#[tauri::command]
+fn validate_instance(
+    prism_root: String,
+    instance_folder: String,
+    options: LastScanOptions,
+) -> Result<InstanceValidation, String> {
+    let req = options.to_request(prism_root, instance_folder);
+    let launcher_kind = req.launcher_kind.unwrap_or_default();
+    let launcher_root = expand_home(&req.prism_root);
+    validate_launcher_root(&launcher_root, launcher_kind)?;
+    resolve_instance_dir(
+        &launcher_root,
+        launcher_kind.instances_dir_name(),
+        &req.instance_folder,
+    )?;
+
+    // Vanilla is left out so a missing client jar is reported below instead of
+    // failing the layout; only mods and resource packs are counted.
+    let probe = StartScanRequest {
+        include_vanilla: false,
+        include_mods: true,
+        include_resourcepacks: true,
+        include_server_packs: Some(false),
+        include_datapacks: Some(false),
+        include_shaderpacks: Some(false),
+        ..req
+    };
+    let layout = match resolve_instance_layout(&probe) {
+        Ok(layout) => layout,
+        Err(error) => {
+            return Ok(InstanceValidation {
+                version_error: Some(error),
+                ..InstanceValidation::default()
+            })
+        }
+    };
+    let containers = collect_scan_containers(&layout, &probe)?;
+    let mod_count = containers
+        .iter()
+        .filter(|container| matches!(container.source_type, AssetSourceType::Mod))
+        .count();
+    let resourcepack_count = containers
+        .iter()
+        .filter(|container| matches!(container.source_type, AssetSourceType::ResourcePack))
+        .count();
+
+    Ok(InstanceValidation {
+        mc_version: Some(layout.mc_version.clone()),
+        version_error: None,
+        client_jar_path: layout
+            .client_jar
+            .as_ref()
+            .map(|path| path.to_string_lossy().to_string()),
+        client_jar_exists: layout
+            .client_jar
+            .as_ref()
+            .is_some_and(|path| path.is_file()),
+        asset_index_exists: layout
+            .asset_index_path
+            .as_ref()
+            .is_some_and(|path| path.is_file()),
+        mods_dir_exists: layout.game_dir.join("mods").is_dir(),
+        mod_count,
+        resourcepacks_dir_exists: layout.game_dir.join("resourcepacks").is_dir(),
+        resourcepack_count,
+    })
 }
 
-fn persist_scan_snapshot(
-    app: &AppHandle,
-    scan_id: &str,
-    req: &StartScanRequest,
-    cache_key: &str,
+/// Containers the scan read without finding any assets, sorted by source name.
+#[tauri::command]
+fn get_empty_sources(
+    scan_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<EmptySource>, String> {
+    let scans = state
+        .scans
+        .lock()
+        .map_err(|_| "Failed to lock scans state".to_string())?;
+    let scan = scans
+        .get(&scan_id)
+        .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
+
+    let mut empty_sources = scan.empty_sources.clone();
+    empty_sources.sort_by(|left, right| left.source_name.cmp(&right.source_name));
+    Ok(empty_sources)
+}
+
+#[tauri::command]
+fn get_scan_status(scan_id: String, state: State<'_, AppState>) -> Result<ScanStatus, String> {
+    let scans = state
+        .scans
+        .lock()
+        .map_err(|_| "Failed to lock scans state".to_string())?;
+
+    let scan = scans
+        .get(&scan_id)
+        .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
+
+    Ok(scan.as_status(&scan_id))
+}
+
+#[tauri::command]
+fn cancel_scan(scan_id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let mut scans = state
+        .scans
+        .lock()
+        .map_err(|_| "Failed to lock scans state".to_string())?;
+
+    let scan = scans
+        .get_mut(&scan_id)
+        .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
+
+    scan.cancelled = true;
+    scan.is_refreshing = false;
+    scan.status = ScanLifecycle::Cancelled;
+    Ok(())
+}
+
+#[tauri::command]
+fn cancel_export(operation_id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let mut operations = state
+        .export_operations
+        .lock()
+        .map_err(|_| "Failed to lock export operations state".to_string())?;
+
+    let operation = operations
+        .get_mut(&operation_id)
+        .ok_or_else(|| format!("Unknown export operation id: {operation_id}"))?;
+
+    operation.cancelled = true;
+    Ok(())
+}
+
+#[tauri::command]
+fn open_cache_directory(app: AppHandle) -> Result<(), String> {
+    let cache_root = scan_cache_root(&app)?;
+    open_directory(&app, &cache_root)
+}
+
+#[tauri::command]
+fn open_config_directory(app: AppHandle) -> Result<(), String> {
+    let config_root = app_config_root(&app)?;
+    open_directory(&app, &config_root)
+}
+
+#[tauri::command]
+fn reveal_asset_in_explorer(
+    app: AppHandle,
+    scan_id: String,
+    asset_id: String,
+    state: State<'_, AppState>,
 ) -> Result<(), String> {
-    let snapshot = {
-        let state = app.state::<AppState>();
-        let scans = state
-            .scans
-            .lock()
-            .map_err(|_| "Failed to lock scans state".to_string())?;
-        let scan = scans
-            .get(scan_id)
-            .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
+    let asset = get_asset_from_state(&state, &scan_id, &asset_id)?;
+    let path = asset_reveal_path(&asset);
+    if !path.exists() {
+        return Err(format!(
+            "{} no longer exists; rescan to refresh this asset",
+            path.display()
+        ));
+    }
 
-        ScanSnapshot {
-            schema_version: SCAN_CACHE_SCHEMA_VERSION,
-            cache_key: cache_key.to_string(),
-            prism_root: req.prism_root.clone(),
-            instance_folder: req.instance_folder.clone(),
-            include_vanilla: req.include_vanilla,
-            include_mods: req.include_mods,
-            include_resourcepacks: req.include_resourcepacks,
-            created_at: unix_timestamp_ms(),
-            last_used_at: unix_timestamp_ms(),
-            app_version: env!("CARGO_PKG_VERSION").to_string(),
-            assets: scan.assets.clone(),
-            search_records: scan.search_records.clone(),
-            tree_children: scan.tree_children.clone(),
-            container_assets: scan.container_assets.clone(),
-            container_signatures: scan.container_signatures.clone(),
+    app.opener()
+        .reveal_item_in_dir(&path)
+        .map_err(|error| format!("Failed to reveal {}: {error}", path.display()))
+}
+
+/// Loose files are revealed directly; archive entries reveal the archive.
+fn asset_reveal_path(asset: &AssetRecord) -> PathBuf {
+    let container_path = PathBuf::from(&asset.container_path);
+    match asset.container_type {
+        AssetContainerType::Directory => container_path.join(Path::new(&asset.entry_path)),
+        AssetContainerType::Zip | AssetContainerType::Jar | AssetContainerType::AssetIndex => {
+            container_path
         }
-    };
+    }
+}
 
-    save_snapshot_to_cache(app, &snapshot)
+fn app_config_root(app: &AppHandle) -> Result<PathBuf, String> {
+    let root = app
+        .path()
+        .app_config_dir()
+        .map_err(|error| format!("Failed to resolve app config directory: {error}"))?;
+    fs::create_dir_all(&root)
+        .map_err(|error| format!("Failed to create app config directory: {error}"))?;
+    Ok(root)
 }
 
-fn build_scan_indexes(
-    assets: &[AssetRecord],
-) -> (
-    HashMap<String, usize>,
-    Vec<AssetSearchRecord>,
-    HashMap<String, Vec<TreeNode>>,
-) {
-    let mut asset_index = HashMap::<String, usize>::new();
-    let mut search_records = Vec::<AssetSearchRecord>::new();
-    let mut tree_children = HashMap::<String, Vec<TreeNode>>::new();
-    tree_children.insert(ROOT_NODE_ID.to_string(), Vec::new());
+#[tauri::command]
+fn clear_scan_cache(app: AppHandle) -> Result<ClearScanCacheResult, String> {
+    let cache_root = scan_cache_root(&app)?;
+    let _cache_dir = lock_scan_cache_dir(&app)?;
+    let result = clear_scan_cache_dir(&cache_root)?;
+    log::info!(
+        "cleared scan cache: {} entries, {} bytes",
+        result.removed_entries,
+        result.freed_bytes
+    );
+    Ok(result)
+}
 
-    for (index, asset) in assets.iter().enumerate() {
-        asset_index.insert(asset.asset_id.clone(), index);
-        search_records.push(build_search_record(asset));
-        add_asset_to_tree(&mut tree_children, asset);
-    }
+#[tauri::command]
+fn repair_scan_cache(app: AppHandle) -> Result<RepairScanCacheResult, String> {
+    let cache_root = scan_cache_root(&app)?;
+    let _cache_dir = lock_scan_cache_dir(&app)?;
+    let mut manifest = load_scan_cache_manifest(&cache_root)?;
+    let result = repair_scan_cache_dir(&cache_root, &mut manifest)?;
+    save_scan_cache_manifest(&cache_root, &manifest)?;
+    log::info!(
+        "repaired scan cache: {} orphaned files, {} dangling entries, {} bytes",
+        result.removed_files,
+        result.removed_entries,
+        result.freed_bytes
+    );
+    Ok(result)
+}
 
-    (asset_index, search_records, tree_children)
+#[tauri::command]
+fn get_scan_cache_stats(app: AppHandle) -> Result<Vec<ScanCacheEntryStats>, String> {
+    let cache_root = scan_cache_root(&app)?;
+    let manifest = load_scan_cache_manifest(&cache_root)?;
+    Ok(scan_cache_entry_stats(&manifest))
 }
 
-fn run_refresh_worker_inner(
-    app: &AppHandle,
-    scan_id: &str,
-    req: &StartScanRequest,
-    cache_key: &str,
-) -> Result<(), String> {
-    {
-        let state = app.state::<AppState>();
-        let mut scans = state
-            .scans
+#[tauri::command]
+fn get_cache_limit(state: State<'_, AppState>) -> Result<u64, String> {
+    state
+        .settings
+        .lock()
+        .map(|settings| settings.max_cache_bytes())
+        .map_err(|_| "Failed to lock settings state".to_string())
+}
+
+/// Persists the new limit and prunes right away when it shrank; returns the
+/// effective limit after clamping to `SCAN_CACHE_MIN_BYTES`.
+#[tauri::command]
+fn set_cache_limit(app: AppHandle, bytes: u64, state: State<'_, AppState>) -> Result<u64, String> {
+    let (previous, updated) = {
+        let mut settings = state
+            .settings
             .lock()
-            .map_err(|_| "Failed to lock scans state".to_string())?;
-        let scan = scans
-            .get_mut(scan_id)
-            .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
-        if scan.cancelled {
-            scan.is_refreshing = false;
-            return Ok(());
-        }
-        scan.is_refreshing = true;
-        scan.status = ScanLifecycle::Completed;
-        scan.error = None;
-        scan.cache_key = Some(cache_key.to_string());
+            .map_err(|_| "Failed to lock settings state".to_string())?;
+        let previous = settings.max_cache_bytes();
+        settings.max_cache_bytes = Some(bytes.max(SCAN_CACHE_MIN_BYTES));
+        (previous, settings.clone())
+    };
+    save_settings(&app, &updated)?;
+
+    let limit = updated.max_cache_bytes();
+    prune_scan_cache_if_shrunk(&app, previous, limit)?;
+    Ok(limit)
+}
+
+fn prune_scan_cache_if_shrunk(app: &AppHandle, previous: u64, limit: u64) -> Result<(), String> {
+    if limit < previous {
+        let cache_root = scan_cache_root(app)?;
+        let _cache_dir = lock_scan_cache_dir(app)?;
+        let mut manifest = load_scan_cache_manifest(&cache_root)?;
+        prune_scan_cache(&cache_root, &mut manifest, limit);
+        save_scan_cache_manifest(&cache_root, &manifest)?;
     }
+    Ok(())
+}
 
-    let prism_root = expand_home(&req.prism_root);
-    validate_prism_root(&prism_root)?;
-    let instance_dir = resolve_instance_dir(&prism_root, &req.instance_folder)?;
-    let mc_version = parse_minecraft_version(&instance_dir.join("mmc-pack.json"))
-        .ok_or_else(|| "Failed to resolve Minecraft version from mmc-pack.json".to_string())?;
-    let containers = collect_scan_containers(&prism_root, &instance_dir, &mc_version, req)?;
+#[tauri::command]
+fn get_settings(state: State<'_, AppState>) -> Result<AppSettings, String> {
+    state
+        .settings
+        .lock()
+        .map(|settings| settings.clone())
+        .map_err(|_| "Failed to lock settings state".to_string())
+}
 
-    let (cached_container_assets, cached_signatures, previous_assets) = {
-        let state = app.state::<AppState>();
-        let scans = state
-            .scans
+/// Merges `patch` into the stored settings, persists them and returns the
+/// result. A lowered cache limit prunes right away, as in `set_cache_limit`.
+#[tauri::command]
+fn update_settings(
+    app: AppHandle,
+    patch: SettingsPatch,
+    state: State<'_, AppState>,
+) -> Result<AppSettings, String> {
+    let (previous, updated) = {
+        let mut settings = state
+            .settings
             .lock()
-            .map_err(|_| "Failed to lock scans state".to_string())?;
-        let scan = scans
-            .get(scan_id)
-            .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
-        (
-            scan.container_assets.clone(),
-            scan.container_signatures.clone(),
-            scan.assets.clone(),
-        )
+            .map_err(|_| "Failed to lock settings state".to_string())?;
+        let previous = settings.max_cache_bytes();
+        settings.apply(patch);
+        (previous, settings.clone())
     };
+    save_settings(&app, &updated)?;
+    prune_scan_cache_if_shrunk(&app, previous, updated.max_cache_bytes())?;
+    Ok(updated)
+}
 
-    let plan = build_scan_refresh_plan(&cached_signatures, &containers)?;
-    let mut containers_by_key = HashMap::<String, ScanContainer>::new();
-    for container in &containers {
-        containers_by_key.insert(scan_container_key(container), container.clone());
+#[tauri::command]
+fn get_worker_limits(state: State<'_, AppState>) -> Result<WorkerLimits, String> {
+    state
+        .settings
+        .lock()
+        .map(|settings| settings.worker_limits())
+        .map_err(|_| "Failed to lock settings state".to_string())
+}
+
+/// Passing `None` for either limit restores its built-in default. Takes effect
+/// for the next scan, refresh or export.
+#[tauri::command]
+fn set_worker_limits(
+    app: AppHandle,
+    scan_workers: Option<usize>,
+    export_workers: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<WorkerLimits, String> {
+    let updated = {
+        let mut settings = state
+            .settings
+            .lock()
+            .map_err(|_| "Failed to lock settings state".to_string())?;
+        settings.scan_workers = scan_workers;
+        settings.export_workers = export_workers;
+        settings.clone()
+    };
+    save_settings(&app, &updated)?;
+    Ok(updated.worker_limits())
+}
+
+#[tauri::command]
+fn get_log_path(app: AppHandle) -> Result<String, String> {
+    Ok(app_log_path(&app)?.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+fn set_log_level(level: String) -> Result<(), String> {
+    log::set_max_level(parse_log_level(&level)?);
+    Ok(())
+}
+
+fn app_log_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let root = app
+        .path()
+        .app_log_dir()
+        .map_err(|error| format!("Failed to resolve app log directory: {error}"))?;
+    fs::create_dir_all(&root)
+        .map_err(|error| format!("Failed to create app log directory: {error}"))?;
+    Ok(root.join(LOG_FILE_NAME))
+}
+
+fn parse_log_level(level: &str) -> Result<log::LevelFilter, String> {
+    level
+        .trim()
+        .parse::<log::LevelFilter>()
+        .map_err(|_| format!("Unsupported log level: {level}"))
+}
+
+/// Appends log records to `mae.log`; the level stays at warn until the UI
+/// raises it through `set_log_level`.
+struct FileLogger {
+    file: Mutex<LogFile>,
+}
+
+impl FileLogger {
+    fn install(app: &AppHandle) -> Result<(), String> {
+        let file = LogFile::open(app_log_path(app)?, LOG_FILE_MAX_BYTES)?;
+        log::set_boxed_logger(Box::new(Self {
+            file: Mutex::new(file),
+        }))
+        .map_err(|error| format!("Failed to install logger: {error}"))?;
+        log::set_max_level(log::LevelFilter::Warn);
+        Ok(())
     }
+}
 
-    let mut unchanged_keys = Vec::new();
-    let mut changed_containers = plan.changed_or_new;
-    for key in plan.unchanged_keys {
-        if cached_container_assets.contains_key(&key) {
-            unchanged_keys.push(key);
-        } else if let Some(container) = containers_by_key.get(&key) {
-            changed_containers.push(container.clone());
+/// The open log file plus its size, so it can be rotated once it reaches `max_bytes`.
+struct LogFile {
+    path: PathBuf,
+    file: fs::File,
+    len: u64,
+    max_bytes: u64,
+}
+
+impl LogFile {
+    fn open(path: PathBuf, max_bytes: u64) -> Result<Self, String> {
+        let mut len = fs::metadata(&path)
+            .map(|metadata| metadata.len())
+            .unwrap_or(0);
+        if len >= max_bytes {
+            rotate_log_file(&path)?;
+            len = 0;
         }
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|error| format!("Failed to open log file {}: {error}", path.display()))?;
+        Ok(Self {
+            len,
+            path,
+            file,
+            max_bytes,
+        })
     }
-    changed_containers.sort_by(|left, right| scan_container_key(left).cmp(&scan_container_key(right)));
 
-    let mut merged_container_assets = HashMap::<String, Vec<AssetRecord>>::new();
-    let mut unchanged_assets = Vec::<AssetRecord>::new();
-    for key in &unchanged_keys {
-        if let Some(assets) = cached_container_assets.get(key) {
-            unchanged_assets.extend(assets.clone());
-            merged_container_assets.insert(key.clone(), assets.clone());
+    fn write_line(&mut self, line: &str) -> Result<(), String> {
+        if self.len >= self.max_bytes {
+            let _ = self.file.flush();
+            *self = Self::open(self.path.clone(), self.max_bytes)?;
         }
+        writeln!(self.file, "{line}")
+            .map_err(|error| format!("Failed to write log file: {error}"))?;
+        self.len += line.len() as u64 + 1;
+        Ok(())
     }
+}
 
-    let changed_total = changed_containers.len();
-    let mut changed_scanned = 0usize;
-    let mut changed_asset_count = 0usize;
-    let mut key_counts = rebuild_key_counts_from_assets(&unchanged_assets);
-
-    if changed_total > 0 {
-        emit_scan_progress(
-            app,
-            ScanProgressEvent {
-                scan_id: scan_id.to_string(),
-                scanned_containers: 0,
-                total_containers: changed_total,
-                asset_count: unchanged_assets.len(),
-                phase: ScanPhase::Refreshing,
-                current_source: None,
-            },
-        );
-    }
+fn rotate_log_file(path: &Path) -> Result<(), String> {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".1");
+    fs::rename(path, &backup)
+        .map_err(|error| format!("Failed to rotate log file {}: {error}", path.display()))
+}
 
-    enum RefreshWorkerResult {
-        Container {
-            container_key: String,
-            source_name: String,
-            candidates: Vec<AssetCandidate>,
-        },
-        Error(String),
+impl log::Log for FileLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
     }
 
-    if changed_total > 0 {
-        let workers = thread::available_parallelism()
-            .map(|value| value.get().saturating_sub(2))
-            .unwrap_or(1)
-            .clamp(1, MAX_SCAN_WORKERS)
-            .min(changed_total);
-        let (sender, receiver) = mpsc::channel::<RefreshWorkerResult>();
-        let next_index = Arc::new(AtomicUsize::new(0));
-        let changed_containers = Arc::new(changed_containers);
-        let scan_id_owned = scan_id.to_string();
-
-        for _ in 0..workers {
-            let sender = sender.clone();
-            let next_index = Arc::clone(&next_index);
-            let changed_containers = Arc::clone(&changed_containers);
-            let app = app.clone();
-            let scan_id = scan_id_owned.clone();
-            thread::spawn(move || loop {
-                if is_scan_cancelled(&app, &scan_id).unwrap_or(true) {
-                    break;
-                }
-                let index = next_index.fetch_add(1, AtomicOrdering::Relaxed);
-                if index >= changed_containers.len() {
-                    break;
-                }
-                let container = &changed_containers[index];
-                let container_key = scan_container_key(container);
-                match scan_container(container, &|| is_scan_cancelled(&app, &scan_id).unwrap_or(true)) {
-                    Ok(candidates) => {
-                        if sender
-                            .send(RefreshWorkerResult::Container {
-                                container_key,
-                                source_name: container.source_name.clone(),
-                                candidates,
-                            })
-                            .is_err()
-                        {
-                            break;
-                        }
-                    }
-                    Err(error) => {
-                        if is_scan_cancelled(&app, &scan_id).unwrap_or(true) {
-                            break;
-                        }
-                        let _ = sender.send(RefreshWorkerResult::Error(error));
-                        break;
-                    }
-                }
-            });
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
         }
-
-        drop(sender);
-
-        while changed_scanned < changed_total {
-            if is_scan_cancelled(app, scan_id)? {
-                let state = app.state::<AppState>();
-                if let Ok(mut scans) = state.scans.lock() {
-                    if let Some(scan) = scans.get_mut(scan_id) {
-                        scan.is_refreshing = false;
-                    }
-                }
-                return Ok(());
-            }
-
-            match receiver.recv_timeout(Duration::from_millis(100)) {
-                Ok(RefreshWorkerResult::Container {
-                    container_key,
-                    source_name,
-                    candidates,
-                }) => {
-                    changed_scanned += 1;
-                    let assets = finalize_assets(candidates, &mut key_counts);
-                    changed_asset_count = changed_asset_count.saturating_add(assets.len());
-                    merged_container_assets.insert(container_key, assets);
-                    emit_scan_progress(
-                        app,
-                        ScanProgressEvent {
-                            scan_id: scan_id.to_string(),
-                            scanned_containers: changed_scanned,
-                            total_containers: changed_total,
-                            asset_count: unchanged_assets.len().saturating_add(changed_asset_count),
-                            phase: ScanPhase::Refreshing,
-                            current_source: Some(source_name),
-                        },
-                    );
-                }
-                Ok(RefreshWorkerResult::Error(error)) => return Err(error),
-                Err(mpsc::RecvTimeoutError::Timeout) => continue,
-                Err(mpsc::RecvTimeoutError::Disconnected) => break,
-            }
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.write_line(&format!(
+                "{} {:<5} {}",
+                unix_timestamp_ms(),
+                record.level(),
+                record.args()
+            ));
         }
     }
 
-    let mut merged_signatures = HashMap::<String, ContainerSignature>::new();
-    for (container_key, signature) in plan.signatures_by_key {
-        if merged_container_assets.contains_key(&container_key) {
-            merged_signatures.insert(container_key, signature);
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.file.flush();
         }
     }
-    for removed in plan.removed_keys {
-        merged_container_assets.remove(&removed);
-        merged_signatures.remove(&removed);
-    }
+}
 
-    let mut container_keys = merged_container_assets.keys().cloned().collect::<Vec<_>>();
-    container_keys.sort();
-    let mut next_assets = Vec::<AssetRecord>::new();
-    for container_key in container_keys {
-        if let Some(assets) = merged_container_assets.get(&container_key) {
-            next_assets.extend(assets.clone());
-        }
-    }
+fn open_directory(app: &AppHandle, path: &Path) -> Result<(), String> {
+    app.opener()
+        .open_path(path.to_string_lossy(), None::<&str>)
+        .map_err(|error| format!("Failed to open {}: {error}", path.display()))
+}
 
-    let (asset_index, search_records, tree_children) = build_scan_indexes(&next_assets);
-    let id_aliases = build_asset_reconciliation_map(&previous_assets, &next_assets);
-    let total_containers = merged_signatures.len();
-    let asset_count = next_assets.len();
+#[tauri::command]
+fn list_tree_children(
+    req: ListTreeChildrenRequest,
+    state: State<'_, AppState>,
+) -> Result<Vec<TreeNode>, String> {
+    let scans = state
+        .scans
+        .lock()
+        .map_err(|_| "Failed to lock scans state".to_string())?;
 
-    {
-        let state = app.state::<AppState>();
-        let mut scans = state
-            .scans
-            .lock()
-            .map_err(|_| "Failed to lock scans state".to_string())?;
-        let scan = scans
-            .get_mut(scan_id)
-            .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
-        if scan.cancelled {
-            scan.is_refreshing = false;
-            return Ok(());
+    let scan = scans
+        .get(&req.scan_id)
+        .ok_or_else(|| format!("Unknown scan id: {}", req.scan_id))?;
+
+    let mut node_id = req.node_id.unwrap_or_else(|| ROOT_NODE_ID.to_string());
+    if req.collapse_mod_namespaces.unwrap_or(false) {
+        let parsed_mod_id = |source_name: &str| {
+            scan.assets
+                .iter()
+                .find(|asset| {
+                    matches!(asset.source_type, AssetSourceType::Mod)
+                        && asset.instance_folder.is_none()
+                        && asset.source_name == source_name
+                        && split_nested_entry_path(&asset.entry_path).len() == 1
+                })
+                .and_then(|asset| asset.mod_id.clone())
+        };
+        if let Some(namespace_node_id) =
+            collapsible_namespace_node(&scan.tree_children, &node_id, parsed_mod_id)
+        {
+            node_id = namespace_node_id;
         }
-        scan.status = ScanLifecycle::Completed;
-        scan.is_refreshing = false;
-        scan.error = None;
-        scan.scanned_containers = total_containers;
-        scan.total_containers = total_containers;
-        scan.assets = next_assets;
-        scan.asset_index = asset_index;
-        scan.search_records = search_records;
-        scan.tree_children = tree_children;
-        scan.container_assets = merged_container_assets;
-        scan.container_signatures = merged_signatures;
-        scan.id_aliases = id_aliases;
-        scan.cache_key = Some(cache_key.to_string());
     }
+    let mut children = scan
+        .tree_children
+        .get(&node_id)
+        .cloned()
+        .unwrap_or_default();
 
-    let _ = app.emit(
-        "scan://completed",
-        ScanCompletedEvent {
-            scan_id: scan_id.to_string(),
-            lifecycle: ScanLifecycle::Completed,
-            asset_count,
-            error: None,
-        },
-    );
-    persist_scan_snapshot(app, scan_id, req, cache_key)?;
-    Ok(())
+    sort_tree_nodes(&mut children, req.sort.unwrap_or_default());
+
+    Ok(children)
 }
 
-fn rebuild_key_counts_from_assets(assets: &[AssetRecord]) -> HashMap<String, usize> {
-    let mut counts = HashMap::<String, usize>::new();
+#[tauri::command]
+fn resolve_tree_path(
+    scan_id: String,
+    node_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<TreeBreadcrumb>, String> {
+    let mut scans = state
+        .scans
+        .lock()
+        .map_err(|_| "Failed to lock scans state".to_string())?;
 
-    for asset in assets {
-        let (base_key, suffix) = parse_dup_suffix(&asset.key);
-        let required = suffix.map(|index| index + 1).unwrap_or(1);
-        let current = counts.entry(base_key).or_insert(0);
-        *current = (*current).max(required);
-    }
+    let scan = scans
+        .get_mut(&scan_id)
+        .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
 
-    counts
+    scan.tree_path(&node_id)
+        .ok_or_else(|| format!("Unknown tree node id: {node_id}"))
 }
 
-fn parse_dup_suffix(key: &str) -> (String, Option<usize>) {
-    if let Some((base, suffix)) = key.rsplit_once(".dup") {
-        if !base.is_empty() && !suffix.is_empty() && suffix.chars().all(|ch| ch.is_ascii_digit()) {
-            if let Ok(index) = suffix.parse::<usize>() {
-                return (base.to_string(), Some(index));
-            }
-        }
-    }
+fn sort_tree_nodes(children: &mut [TreeNode], sort: TreeSort) {
+    let rank = |node: &TreeNode| match node.node_type {
+        TreeNodeType::Folder => 0,
+        TreeNodeType::File => 1,
+    };
+    let by_name = |left: &TreeNode, right: &TreeNode| {
+        left.name.to_lowercase().cmp(&right.name.to_lowercase())
+    };
 
-    (key.to_string(), None)
+    match sort {
+        TreeSort::NameAsc => children.sort_by(by_name),
+        TreeSort::NameDesc => children.sort_by(|left, right| by_name(right, left)),
+        TreeSort::Natural => children.sort_by(|left, right| {
+            rank(left)
+                .cmp(&rank(right))
+                .then_with(|| natural_compare(&left.name, &right.name))
+        }),
+        TreeSort::TypeThenName => children.sort_by(|left, right| {
+            rank(left)
+                .cmp(&rank(right))
+                .then_with(|| by_name(left, right))
+        }),
+    }
 }
 
-fn append_assets_chunk(
-    app: &AppHandle,
-    scan_id: &str,
-    container_key: &str,
-    signature: &ContainerSignature,
-    chunk: &[AssetRecord],
-    scanned_containers: usize,
-    total_containers: usize,
-    phase: ScanPhase,
-    current_source: Option<String>,
-) -> Result<(), String> {
-    const PROGRESS_THROTTLE: Duration = Duration::from_millis(125);
-
-    let asset_count;
-    let mut should_emit_progress = false;
-
-    {
-        let state = app.state::<AppState>();
-        let mut scans = state
-            .scans
-            .lock()
-            .map_err(|_| "Failed to lock scans state".to_string())?;
+#[tauri::command]
+fn search_assets(req: SearchRequest, state: State<'_, AppState>) -> Result<SearchResponse, String> {
+    let mut scans = state
+        .scans
+        .lock()
+        .map_err(|_| "Failed to lock scans state".to_string())?;
 
-        let scan = scans
-            .get_mut(scan_id)
-            .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
+    let scan = scans
+        .get_mut(&req.scan_id)
+        .ok_or_else(|| format!("Unknown scan id: {}", req.scan_id))?;
 
-        scan.scanned_containers = scanned_containers;
-        scan.total_containers = total_containers;
-        scan.container_signatures
-            .insert(container_key.to_string(), signature.clone());
+    let offset = req.offset.unwrap_or(0);
+    let limit = req.limit.unwrap_or(200).clamp(1, 1000);
+    let search = CompiledSearch::new(&req)?;
+
+    let respond = |total: usize, assets: Vec<AssetRecord>, next_cursor: Option<String>| {
+        let highlights = req.include_highlights.unwrap_or(false).then(|| {
+            assets
+                .iter()
+                .map(|asset| build_asset_highlights(asset, &search.query, search.pattern.as_ref()))
+                .collect()
+        });
+        if req.group_by_folder.unwrap_or(false) {
+            return SearchResponse {
+                total,
+                assets: Vec::new(),
+                highlights,
+                groups: Some(group_assets_by_folder(assets)),
+                next_cursor,
+            };
+        }
+        SearchResponse {
+            total,
+            assets,
+            highlights,
+            groups: None,
+            next_cursor,
+        }
+    };
 
-        let mut appended_for_container = Vec::<AssetRecord>::new();
+    if search.filter.excludes_all_media() {
+        return Ok(SearchResponse {
+            total: 0,
+            assets: Vec::new(),
+            highlights: None,
+            groups: None,
+            next_cursor: None,
+        });
+    }
 
-        for asset in chunk {
-            if scan.asset_index.contains_key(&asset.asset_id) {
-                continue;
-            }
+    let cache_key = SearchCacheKey::for_request(&req);
+    let cache_key_signature = cache_key.signature();
+    let offset = match &req.cursor {
+        Some(token) => SearchCursor::resolve(token, scan.search_generation, &cache_key)?,
+        None => offset,
+    };
+    if req.count_only.unwrap_or(false) {
+        let total = match scan.cached_search(&cache_key) {
+            Some(ordered) => ordered.len(),
+            None => matching_asset_indices(scan, &search).len(),
+        };
+        return Ok(respond(total, Vec::new(), None));
+    }
 
-            let index = scan.assets.len();
-            scan.asset_index.insert(asset.asset_id.clone(), index);
-            scan.search_records.push(build_search_record(asset));
-            scan.assets.push(asset.clone());
-            appended_for_container.push(asset.clone());
-            add_asset_to_tree(&mut scan.tree_children, asset);
+    // A cold first page only orders its own rows; following a cursor sorts the rest.
+    let wanted = match &req.cursor {
+        Some(_) => usize::MAX,
+        None => offset.saturating_add(limit),
+    };
+    let ordered = ordered_search_matches(scan, &req, wanted)?;
+    let assets = ordered
+        .page(offset, limit)
+        .map(|index| scan.assets[index].clone())
+        .collect::<Vec<_>>();
+    let next_position = offset.saturating_add(assets.len());
+    let next_cursor = (next_position < ordered.len()).then(|| {
+        SearchCursor {
+            generation: scan.search_generation,
+            signature: cache_key_signature,
+            position: next_position,
         }
-        scan.container_assets
-            .insert(container_key.to_string(), appended_for_container);
-
-        let now = Instant::now();
-        let force_emit = scanned_containers >= total_containers;
-        let elapsed = scan
-            .last_progress_emit_at
-            .map(|last| now.saturating_duration_since(last))
-            .unwrap_or(PROGRESS_THROTTLE);
+        .encode()
+    });
 
-        if force_emit || elapsed >= PROGRESS_THROTTLE {
-            should_emit_progress = true;
-            scan.last_progress_emit_at = Some(now);
+    Ok(respond(ordered.len(), assets, next_cursor))
+}
+
+/// Every match of `req`, ignoring paging, with at least the first `wanted` in
+/// result order. Served from the scan's search cache when the same query
+/// already ran, sorting further into it when that order is too short.
+fn ordered_search_matches(
+    scan: &mut ScanState,
+    req: &SearchRequest,
+    wanted: usize,
+) -> Result<Arc<SearchOrder>, String> {
+    let cache_key = SearchCacheKey::for_request(req);
+    let mut ordered = match scan.cached_search(&cache_key) {
+        Some(ordered) if ordered.sorted_len >= wanted.min(ordered.len()) => return Ok(ordered),
+        Some(ordered) => Arc::unwrap_or_clone(ordered),
+        None => {
+            let search = CompiledSearch::new(req)?;
+            SearchOrder::new(matching_asset_indices(scan, &search), search.ranked())
         }
-
-        asset_count = scan.assets.len();
-    }
-
-    if should_emit_progress {
-        emit_scan_progress(
-            app,
-            ScanProgressEvent {
-                scan_id: scan_id.to_string(),
-                scanned_containers,
-                total_containers,
-                asset_count,
-                phase,
-                current_source,
+    };
+    ordered.sort_prefix(&scan.assets, wanted);
+    let ordered = Arc::new(ordered);
+    scan.cache_search(cache_key, Arc::clone(&ordered));
+    Ok(ordered)
+}
+
+/// The query, pattern and filters of a `SearchRequest`, parsed once per call.
+struct CompiledSearch<'a> {
+    options: &'a AssetFilterOptions,
+    filter: AssetFilter<'a>,
+    query: SearchQuery,
+    pattern: Option<KeyPattern>,
+}
+
+impl<'a> CompiledSearch<'a> {
+    fn new(req: &'a SearchRequest) -> Result<Self, String> {
+        Ok(Self {
+            options: &req.filters,
+            filter: AssetFilter::from_options(&req.filters),
+            query: SearchQuery {
+                fuzzy: req.fuzzy.unwrap_or_default(),
+                ..SearchQuery::parse(&req.query)
             },
-        );
+            pattern: KeyPattern::compile(req.match_mode.unwrap_or_default(), &req.query)?,
+        })
     }
 
-    Ok(())
+    /// Pattern modes have no relevance score, so they share the idle ordering.
+    fn ranked(&self) -> bool {
+        !self.query.is_unranked() && self.pattern.is_none()
+    }
 }
 
-fn update_scan_error(app: &AppHandle, scan_id: &str, error: &str) {
-    let state = app.state::<AppState>();
-    let lock_result = state.scans.lock();
-    if let Ok(mut scans) = lock_result {
-        if let Some(scan) = scans.get_mut(scan_id) {
-            scan.status = ScanLifecycle::Error;
-            scan.is_refreshing = false;
-            scan.error = Some(error.to_string());
-        }
+/// Every asset index passing the filters and query, unordered and unpaged,
+/// each with its relevance score (zero when the search is unranked). All
+/// search entry points go through here so they agree on what matches.
+fn matching_asset_indices(scan: &mut ScanState, search: &CompiledSearch) -> Vec<(i64, usize)> {
+    if search.filter.excludes_all_media() {
+        return Vec::new();
     }
-}
 
-fn complete_scan_with_lifecycle(
-    app: &AppHandle,
-    scan_id: &str,
-    lifecycle: ScanLifecycle,
-    error: Option<String>,
-) -> Result<(), String> {
-    let asset_count;
+    let ranked = search.ranked();
+    scoped_candidate_indices(scan, search.options)
+        .filter_map(|index| {
+            let asset = &scan.assets[index];
+            let search_record = &scan.search_records[index];
+            if !search.filter.matches(asset, search_record) {
+                return None;
+            }
+            match &search.pattern {
+                Some(pattern) => pattern.matches(asset).then_some((0, index)),
+                None if ranked => {
+                    score_query(search_record, &search.query).map(|score| (score, index))
+                }
+                None => (!search.query.excludes(search_record)).then_some((0, index)),
+            }
+        })
+        .collect()
+}
 
-    {
-        let state = app.state::<AppState>();
-        let mut scans = state
-            .scans
-            .lock()
-            .map_err(|_| "Failed to lock scans state".to_string())?;
-
-        let scan = scans
-            .get_mut(scan_id)
-            .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
-
-        scan.status = lifecycle.clone();
-        scan.is_refreshing = false;
-        scan.error = error.clone();
-        scan.scanned_containers = scan.total_containers;
-        asset_count = scan.assets.len();
-    }
-
-    let _ = app.emit(
-        "scan://completed",
-        ScanCompletedEvent {
-            scan_id: scan_id.to_string(),
-            lifecycle,
-            asset_count,
-            error,
-        },
-    );
-
-    Ok(())
-}
-
-fn is_scan_cancelled(app: &AppHandle, scan_id: &str) -> Result<bool, String> {
-    let state = app.state::<AppState>();
+#[tauri::command]
+fn get_scan_facets(scan_id: String, state: State<'_, AppState>) -> Result<ScanFacets, String> {
     let scans = state
         .scans
         .lock()
         .map_err(|_| "Failed to lock scans state".to_string())?;
 
     let scan = scans
-        .get(scan_id)
+        .get(&scan_id)
         .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
 
-    Ok(scan.cancelled)
+    Ok(collect_scan_facets(&scan.assets))
 }
 
-fn collect_scan_containers(
-    prism_root: &Path,
-    instance_dir: &Path,
-    mc_version: &str,
-    req: &StartScanRequest,
-) -> Result<Vec<ScanContainer>, String> {
-    let mut containers = Vec::new();
-    let minecraft_dir = instance_dir.join("minecraft");
-
-    if req.include_mods {
-        let mods_dir = minecraft_dir.join("mods");
-        if mods_dir.is_dir() {
-            let entries = fs::read_dir(&mods_dir)
-                .map_err(|error| format!("Failed to read mods directory: {error}"))?;
+#[tauri::command]
+fn find_duplicate_assets(
+    scan_id: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<DuplicateAssetGroup>, String> {
+    let assets = {
+        let scans = state
+            .scans
+            .lock()
+            .map_err(|_| "Failed to lock scans state".to_string())?;
+        let scan = scans
+            .get(&scan_id)
+            .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
+        scan.assets.clone()
+    };
 
-            for entry in entries {
-                let entry = match entry {
-                    Ok(value) => value,
-                    Err(_) => continue,
-                };
+    Ok(group_duplicate_assets(
+        &assets,
+        worker_limits(&app).export_workers,
+    ))
+}
 
-                let path = entry.path();
-                let extension = path
-                    .extension()
-                    .map(|value| value.to_string_lossy().to_ascii_lowercase())
-                    .unwrap_or_default();
+#[tauri::command]
+fn verify_vanilla_assets(
+    scan_id: String,
+    state: State<'_, AppState>,
+) -> Result<VanillaAssetVerification, String> {
+    let index_paths = {
+        let scans = state
+            .scans
+            .lock()
+            .map_err(|_| "Failed to lock scans state".to_string())?;
+        let scan = scans
+            .get(&scan_id)
+            .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
+        scan_asset_index_paths(scan.containers.iter())
+    };
 
-                if extension == "jar" {
-                    let source_name = path
-                        .file_stem()
-                        .map(|value| value.to_string_lossy().to_string())
-                        .unwrap_or_else(|| "unknown-mod".to_string());
+    verify_asset_index_objects(&index_paths)
+}
 
-                    containers.push(ScanContainer {
-                        source_type: AssetSourceType::Mod,
-                        source_name,
-                        container_type: AssetContainerType::Jar,
-                        container_path: path,
-                    });
-                }
+fn verify_asset_index_objects(index_paths: &[PathBuf]) -> Result<VanillaAssetVerification, String> {
+    let mut verification = VanillaAssetVerification {
+        checked_count: 0,
+        missing_count: 0,
+        missing_paths: Vec::new(),
+    };
+    for index_path in index_paths {
+        let index = read_vanilla_asset_index(index_path)?;
+        for (logical_path, object) in &index.file.objects {
+            let Some(entry) = index.sound_entry(logical_path, object) else {
+                continue;
+            };
+            verification.checked_count += 1;
+            if !index.objects_root.join(&entry.entry_path).is_file() {
+                verification.missing_count += 1;
+                verification.missing_paths.push(logical_path.clone());
             }
         }
     }
 
-    if req.include_resourcepacks {
-        let resourcepacks_dir = minecraft_dir.join("resourcepacks");
-        if resourcepacks_dir.is_dir() {
-            let entries = fs::read_dir(&resourcepacks_dir)
-                .map_err(|error| format!("Failed to read resourcepacks directory: {error}"))?;
-
-            for entry in entries {
-                let entry = match entry {
-                    Ok(value) => value,
-                    Err(_) => continue,
-                };
-
-                let path = entry.path();
-                if path.is_dir() {
-                    let source_name = path
-                        .file_name()
-                        .map(|value| value.to_string_lossy().to_string())
-                        .unwrap_or_else(|| "resourcepack".to_string());
-
-                    containers.push(ScanContainer {
-                        source_type: AssetSourceType::ResourcePack,
-                        source_name,
-                        container_type: AssetContainerType::Directory,
-                        container_path: path,
-                    });
-                } else if path
-                    .extension()
-                    .map(|value| value.to_string_lossy().to_ascii_lowercase())
-                    .unwrap_or_default()
-                    == "zip"
-                {
-                    let source_name = path
-                        .file_stem()
-                        .map(|value| value.to_string_lossy().to_string())
-                        .unwrap_or_else(|| "resourcepack".to_string());
+    verification.missing_paths.sort();
+    verification.missing_paths.dedup();
+    verification
+        .missing_paths
+        .truncate(VANILLA_MISSING_REPORT_LIMIT);
+    Ok(verification)
+}
 
-                    containers.push(ScanContainer {
-                        source_type: AssetSourceType::ResourcePack,
-                        source_name,
-                        container_type: AssetContainerType::Zip,
-                        container_path: path,
-                    });
-                }
-            }
+/// Asset index files among the scanned `containers`.
+fn scan_asset_index_paths<'a>(containers: impl Iterator<Item = &'a ScanContainer>) -> Vec<PathBuf> {
+    let mut paths = containers
+        .filter(|container| matches!(container.container_type, AssetContainerType::AssetIndex))
+        .map(|container| container.container_path.clone())
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/// Hashes only assets that could have a twin: those sharing a known size with
+/// another asset, plus those whose size wasn't recorded during the scan.
+/// Largest savings come first.
+fn group_duplicate_assets(assets: &[AssetRecord], max_workers: usize) -> Vec<DuplicateAssetGroup> {
+    // Sizes come from file metadata and central directories, so only assets
+    // sharing a size with another one (or of unknown size) get read and hashed.
+    let mut archives = HashMap::new();
+    let sized = assets
+        .iter()
+        .filter(|asset| asset.is_extractable)
+        .map(|asset| (asset, asset_source_size(asset, &mut archives)))
+        .collect::<Vec<_>>();
+    drop(archives);
+    let mut size_counts = HashMap::<u64, usize>::new();
+    for (_, size) in &sized {
+        if let Some(size) = size {
+            *size_counts.entry(*size).or_default() += 1;
         }
     }
+    let candidates = sized
+        .into_iter()
+        .filter(|(_, size)| size.is_none_or(|size| size_counts[&size] > 1))
+        .map(|(asset, _)| asset)
+        .collect::<Vec<_>>();
+    if candidates.is_empty() {
+        return Vec::new();
+    }
 
-    if req.include_vanilla {
-        let client_jar = prism_root
-            .join("libraries")
-            .join("com")
-            .join("mojang")
-            .join("minecraft")
-            .join(mc_version)
-            .join(format!("minecraft-{mc_version}-client.jar"));
-
-        if client_jar.is_file() {
-            containers.push(ScanContainer {
-                source_type: AssetSourceType::Vanilla,
-                source_name: format!("minecraft-{mc_version}"),
-                container_type: AssetContainerType::Jar,
-                container_path: client_jar,
-            });
-        }
+    let workers = thread::available_parallelism()
+        .map(|value| value.get())
+        .unwrap_or(1)
+        .clamp(1, max_workers.max(1))
+        .min(candidates.len());
+    let next_index = AtomicUsize::new(0);
+    let mut digests = thread::scope(|scope| {
+        let handles = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut archive_cache = ArchiveCache::new();
+                    let mut digests = Vec::new();
+                    loop {
+                        let index = next_index.fetch_add(1, AtomicOrdering::Relaxed);
+                        let Some(asset) = candidates.get(index) else {
+                            break;
+                        };
+                        // Unreadable assets can't be compared; leave them out.
+                        if let Ok(bytes) =
+                            extract_asset_bytes_with_archive_cache(asset, &mut archive_cache)
+                        {
+                            let digest: [u8; 32] = Sha256::digest(&bytes).into();
+                            digests.push((index, bytes.len() as u64, digest));
+                        }
+                    }
+                    digests
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect::<Vec<_>>()
+    });
+    digests.sort_unstable_by_key(|(index, _, _)| *index);
 
-        if let Some(asset_index_path) = resolve_vanilla_asset_index_path(prism_root, mc_version) {
-            containers.push(ScanContainer {
-                source_type: AssetSourceType::Vanilla,
-                source_name: format!("minecraft-{mc_version}"),
-                container_type: AssetContainerType::AssetIndex,
-                container_path: asset_index_path,
-            });
-        }
+    let mut groups = HashMap::<(u64, [u8; 32]), Vec<String>>::new();
+    for (index, size, digest) in digests {
+        groups
+            .entry((size, digest))
+            .or_default()
+            .push(candidates[index].asset_id.clone());
     }
-
-    containers.sort_by(|left, right| scan_container_key(left).cmp(&scan_container_key(right)));
-    Ok(containers)
+    let mut groups = groups
+        .into_iter()
+        .filter(|(_, asset_ids)| asset_ids.len() > 1)
+        .map(|((byte_size, _), asset_ids)| DuplicateAssetGroup {
+            byte_size,
+            asset_ids,
+        })
+        .collect::<Vec<_>>();
+    groups.sort_by(|left, right| {
+        let wasted =
+            |group: &DuplicateAssetGroup| group.byte_size * (group.asset_ids.len() as u64 - 1);
+        wasted(right)
+            .cmp(&wasted(left))
+            .then_with(|| left.asset_ids[0].cmp(&right.asset_ids[0]))
+    });
+    groups
 }
 
-fn scan_container(
-    container: &ScanContainer,
-    should_cancel: &dyn Fn() -> bool,
-) -> Result<Vec<AssetCandidate>, String> {
-    match container.container_type {
-        AssetContainerType::Directory => scan_directory_container(container, should_cancel),
-        AssetContainerType::Zip | AssetContainerType::Jar => {
-            scan_archive_container(container, should_cancel)
-        }
-        AssetContainerType::AssetIndex => scan_vanilla_asset_index_container(container, should_cancel),
+fn collect_scan_facets(assets: &[AssetRecord]) -> ScanFacets {
+    let mut namespaces = BTreeMap::<&str, usize>::new();
+    let mut sources = BTreeMap::<(&str, &str), (AssetSourceType, Option<&str>, usize)>::new();
+    let mut extensions = BTreeMap::<&str, usize>::new();
+    for asset in assets {
+        *namespaces.entry(asset.namespace.as_str()).or_default() += 1;
+        let source = sources
+            .entry((
+                asset.source_name.as_str(),
+                asset.source_type.tree_root_name(),
+            ))
+            .or_insert((asset.source_type.clone(), None, 0));
+        source.1 = source.1.or(source_display_name(asset));
+        source.2 += 1;
+        *extensions.entry(asset.extension.as_str()).or_default() += 1;
+    }
+
+    ScanFacets {
+        namespaces: namespaces
+            .into_iter()
+            .map(|(name, count)| NamespaceFacet {
+                name: name.to_string(),
+                count,
+            })
+            .collect(),
+        sources: sources
+            .into_iter()
+            .map(
+                |((name, _), (source_type, display_name, count))| SourceFacet {
+                    name: name.to_string(),
+                    display_name: display_name.map(str::to_string),
+                    source_type,
+                    count,
+                },
+            )
+            .collect(),
+        extensions: extensions
+            .into_iter()
+            .map(|(ext, count)| ExtensionFacet {
+                ext: ext.to_string(),
+                count,
+            })
+            .collect(),
     }
 }
 
-fn scan_vanilla_asset_index_container(
-    container: &ScanContainer,
-    should_cancel: &dyn Fn() -> bool,
-) -> Result<Vec<AssetCandidate>, String> {
-    let content = fs::read_to_string(&container.container_path).map_err(|error| {
-        format!(
-            "Failed to read vanilla asset index {}: {error}",
-            container.container_path.display()
-        )
-    })?;
+#[tauri::command]
+fn count_assets(
+    scan_id: String,
+    filters: AssetFilterOptions,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let mut scans = state
+        .scans
+        .lock()
+        .map_err(|_| "Failed to lock scans state".to_string())?;
 
-    let parsed: MinecraftAssetIndexFile = serde_json::from_str(&content).map_err(|error| {
-        format!(
-            "Failed to parse vanilla asset index {}: {error}",
-            container.container_path.display()
-        )
-    })?;
+    let scan = scans
+        .get_mut(&scan_id)
+        .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
 
-    let assets_root = container
-        .container_path
-        .parent()
-        .and_then(Path::parent)
-        .ok_or_else(|| {
-            format!(
-                "Invalid asset index path (cannot resolve assets root): {}",
-                container.container_path.display()
-            )
-        })?;
-    let objects_root = assets_root.join("objects");
+    let filter = AssetFilter::from_options(&filters);
+    if filter.excludes_all_media() {
+        return Ok(0);
+    }
 
-    let mut assets = Vec::new();
-    let mut processed = 0usize;
-    for (logical_path, object) in parsed.objects {
-        processed = processed.saturating_add(1);
-        if processed % SCAN_CANCEL_CHECK_INTERVAL == 0 && should_cancel() {
-            return Err("Scan cancelled".to_string());
-        }
+    let candidate_indices = scoped_candidate_indices(scan, &filters);
+    Ok(candidate_indices
+        .filter(|index| filter.matches(&scan.assets[*index], &scan.search_records[*index]))
+        .count())
+}
 
-        let Some((namespace, relative_asset_path)) = logical_path.split_once('/') else {
-            continue;
-        };
+#[tauri::command]
+fn get_asset_preview(
+    scan_id: String,
+    asset_id: String,
+    background: Option<String>,
+    pretty: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<AssetPreviewResponse, PreviewError> {
+    let asset = get_asset_from_state(&state, &scan_id, &asset_id)?;
 
-        // Vanilla sounds are shipped via asset indexes/objects, not client jar entries.
-        if !relative_asset_path.starts_with("sounds/") {
-            continue;
-        }
+    if is_unpreviewable_extension(&asset.extension) {
+        return Err(PreviewError::UnsupportedPreview {
+            extension: asset.extension.clone(),
+        });
+    }
+    let is_text = asset.is_shader || is_text_extension(&asset.extension);
+    if !asset.is_image && !asset.is_audio && !is_json_extension(&asset.extension) && !is_text {
+        return Err(
+            "Preview is only available for image, audio, JSON or text assets"
+                .to_string()
+                .into(),
+        );
+    }
 
-        let extension = relative_asset_path
-            .rsplit('.')
-            .next()
-            .unwrap_or("")
-            .to_ascii_lowercase();
+    let (bytes, animation) =
+        with_archive_handles(&state, &asset.container_path, |archive_cache| {
+            let bytes = extract_asset_bytes_with_archive_cache(&asset, archive_cache)?;
+            let animation = if asset.is_image {
+                image::ImageReader::new(Cursor::new(&bytes))
+                    .with_guessed_format()
+                    .ok()
+                    .and_then(|reader| reader.into_dimensions().ok())
+                    .and_then(|(width, height)| {
+                        read_texture_animation(&asset, archive_cache, width, height)
+                    })
+            } else {
+                None
+            };
+            Ok::<_, String>((bytes, animation))
+        })?;
+    if is_text {
+        let (text, truncated) = text_preview(&bytes);
+        return Ok(AssetPreviewResponse {
+            mime: "text/plain".to_string(),
+            base64: base64::engine::general_purpose::STANDARD.encode(text),
+            animation: None,
+            truncated,
+        });
+    }
+    if let Some(background) = background.filter(|_| asset.is_image) {
+        let color = parse_background_color(&background)?;
+        let image = decode_rgba_image(&bytes)?;
+        let flattened = composite_over_background(&image, color);
+        return Ok(AssetPreviewResponse {
+            mime: "image/png".to_string(),
+            base64: base64::engine::general_purpose::STANDARD
+                .encode(encode_rgba_image(&flattened, RasterFormat::Png)?),
+            animation,
+            truncated: false,
+        });
+    }
 
-        if !is_audio_extension(&extension) {
-            continue;
-        }
+    // Webviews can't render TGA, so it's previewed as PNG.
+    if asset.extension == "tga" {
+        let image = decode_rgba_image(&bytes)?;
+        return Ok(AssetPreviewResponse {
+            mime: "image/png".to_string(),
+            base64: base64::engine::general_purpose::STANDARD
+                .encode(encode_rgba_image(&image, RasterFormat::Png)?),
+            animation,
+            truncated: false,
+        });
+    }
 
-        if object.hash.len() < 2 {
-            continue;
-        }
+    let bytes = if pretty.unwrap_or(false) && is_json_extension(&asset.extension) {
+        pretty_print_json(&bytes)?
+    } else {
+        bytes
+    };
+    let base64 = base64::engine::general_purpose::STANDARD.encode(bytes);
 
-        let entry_path = format!("{}/{}", &object.hash[0..2], object.hash);
-        let absolute_path = objects_root.join(&entry_path);
-        if !absolute_path.is_file() {
-            continue;
-        }
+    Ok(AssetPreviewResponse {
+        mime: mime_for_extension(&asset.extension).to_string(),
+        base64,
+        animation,
+        truncated: false,
+    })
+}
 
-        assets.push(AssetCandidate {
-            source_type: container.source_type.clone(),
-            source_name: container.source_name.clone(),
-            namespace: namespace.to_string(),
-            relative_asset_path: relative_asset_path.to_string(),
-            container_path: objects_root.clone(),
-            container_type: AssetContainerType::Directory,
-            entry_path,
-            extension,
-            is_image: false,
-            is_audio: true,
-        });
+#[tauri::command]
+fn get_asset_thumbnail(
+    scan_id: String,
+    asset_id: String,
+    max_edge: u32,
+    background: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<AssetPreviewResponse, String> {
+    let asset = get_asset_from_state(&state, &scan_id, &asset_id)?;
+
+    if !asset.is_image {
+        return Err("Thumbnails are only available for image assets".to_string());
+    }
+    if max_edge == 0 {
+        return Err("Thumbnail max edge must be greater than zero".to_string());
     }
 
-    Ok(assets)
+    let background = background
+        .map(|value| parse_background_color(&value))
+        .transpose()?;
+    let png = with_archive_handles(&state, &asset.container_path, |archive_cache| {
+        render_asset_thumbnail(&asset, archive_cache, max_edge, background)
+    })?;
+
+    Ok(AssetPreviewResponse {
+        mime: "image/png".to_string(),
+        base64: base64::engine::general_purpose::STANDARD.encode(png),
+        animation: None,
+        truncated: false,
+    })
 }
 
-fn scan_directory_container(
-    container: &ScanContainer,
-    should_cancel: &dyn Fn() -> bool,
-) -> Result<Vec<AssetCandidate>, String> {
-    let mut assets = Vec::new();
-    let mut processed = 0usize;
+/// Thumbnails a page of assets in one call. Per-asset failures, including
+/// unknown ids and non-image assets, are reported inline.
+#[tauri::command]
+fn get_asset_previews(
+    scan_id: String,
+    asset_ids: Vec<String>,
+    max_edge: u32,
+    background: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<AssetPreviewItem>, String> {
+    if max_edge == 0 {
+        return Err("Thumbnail max edge must be greater than zero".to_string());
+    }
+    let background = background
+        .map(|value| parse_background_color(&value))
+        .transpose()?;
+    let assets = {
+        let scans = state
+            .scans
+            .lock()
+            .map_err(|_| "Failed to lock scans state".to_string())?;
+        let scan = scans
+            .get(&scan_id)
+            .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
+        asset_ids
+            .iter()
+            .map(|asset_id| {
+                scan.asset_index
+                    .get(asset_id)
+                    .map(|index| scan.assets[*index].clone())
+                    .ok_or_else(|| format!("Unknown asset id: {asset_id}"))
+            })
+            .collect::<Vec<_>>()
+    };
+    if assets.is_empty() {
+        return Ok(Vec::new());
+    }
 
-    for entry in WalkDir::new(&container.container_path)
-        .follow_links(false)
-        .into_iter()
-        .filter_map(Result::ok)
-    {
-        processed = processed.saturating_add(1);
-        if processed % SCAN_CANCEL_CHECK_INTERVAL == 0 && should_cancel() {
-            return Err("Scan cancelled".to_string());
-        }
+    let workers = thread::available_parallelism()
+        .map(|value| value.get())
+        .unwrap_or(1)
+        .clamp(1, worker_limits(&app).export_workers)
+        .min(assets.len());
+    let next_index = AtomicUsize::new(0);
+    let app_state: &AppState = &state;
+    let mut rendered = thread::scope(|scope| {
+        let handles = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut rendered = Vec::new();
+                    loop {
+                        let index = next_index.fetch_add(1, AtomicOrdering::Relaxed);
+                        let Some(asset) = assets.get(index) else {
+                            break;
+                        };
+                        let png = asset.as_ref().map_err(Clone::clone).and_then(|asset| {
+                            if !asset.is_image {
+                                return Err(
+                                    "Thumbnails are only available for image assets".to_string()
+                                );
+                            }
+                            with_archive_handles(app_state, &asset.container_path, |cache| {
+                                render_asset_thumbnail(asset, cache, max_edge, background)
+                            })
+                        });
+                        rendered.push((index, png));
+                    }
+                    rendered
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect::<Vec<_>>()
+    });
+    rendered.sort_unstable_by_key(|(index, _)| *index);
 
-        if !entry.file_type().is_file() {
-            continue;
-        }
+    Ok(rendered
+        .into_iter()
+        .map(|(index, png)| {
+            let asset_id = asset_ids[index].clone();
+            match png {
+                Ok(png) => AssetPreviewItem {
+                    asset_id,
+                    mime: Some("image/png".to_string()),
+                    base64: Some(base64::engine::general_purpose::STANDARD.encode(png)),
+                    error: None,
+                },
+                Err(error) => AssetPreviewItem {
+                    asset_id,
+                    mime: None,
+                    base64: None,
+                    error: Some(error),
+                },
+            }
+        })
+        .collect())
+}
 
-        let Ok(relative) = entry.path().strip_prefix(&container.container_path) else {
-            continue;
-        };
+/// Encodes the first animation frame as a PNG scaled to fit `max_edge`.
+fn render_asset_thumbnail(
+    asset: &AssetRecord,
+    archive_cache: &mut ArchiveCache,
+    max_edge: u32,
+    background: Option<[u8; 3]>,
+) -> Result<Vec<u8>, String> {
+    let image = decode_rgba_image(&extract_asset_bytes_with_archive_cache(
+        asset,
+        archive_cache,
+    )?)?;
+    let frame = read_texture_animation(asset, archive_cache, image.width(), image.height())
+        .map(|animation| (animation.frame_width, animation.frame_height));
+    let mut thumbnail = build_thumbnail(&image, frame, max_edge);
+    if let Some(background) = background {
+        thumbnail = composite_over_background(&thumbnail, background);
+    }
+    encode_rgba_image(&thumbnail, RasterFormat::Png)
+}
 
-        let relative_normalized = normalize_archive_path(relative);
-        let Some(parsed) = parse_asset_relative_path(&relative_normalized) else {
-            continue;
-        };
+#[tauri::command]
+fn split_texture_frames(
+    scan_id: String,
+    asset_id: String,
+    state: State<'_, AppState>,
+) -> Result<TextureFrames, String> {
+    let asset = get_asset_from_state(&state, &scan_id, &asset_id)?;
+    if !asset.is_image {
+        return Err("Frames can only be split from image assets".to_string());
+    }
+
+    let (image, animation) =
+        with_archive_handles(&state, &asset.container_path, |archive_cache| {
+            let image = decode_rgba_image(&extract_asset_bytes_with_archive_cache(
+                &asset,
+                archive_cache,
+            )?)?;
+            let (width, height) = image.dimensions();
+            let animation = read_texture_animation(&asset, archive_cache, width, height);
+            Ok::<_, String>((image, animation))
+        })?;
+    let (width, height) = image.dimensions();
+    let animation = animation
+        .or_else(|| parse_texture_animation(&serde_json::json!({}), width, height))
+        .ok_or_else(|| "Texture has no frames to split".to_string())?;
 
-        let extension = parsed
-            .relative_asset_path
-            .rsplit('.')
-            .next()
-            .unwrap_or("")
-            .to_ascii_lowercase();
+    let frames = slice_texture_frames(&image, animation.frame_width, animation.frame_height)
+        .iter()
+        .map(|frame| {
+            encode_rgba_image(frame, RasterFormat::Png)
+                .map(|bytes| base64::engine::general_purpose::STANDARD.encode(bytes))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
 
-        assets.push(AssetCandidate {
-            source_type: container.source_type.clone(),
-            source_name: container.source_name.clone(),
-            namespace: parsed.namespace,
-            relative_asset_path: parsed.relative_asset_path,
-            container_path: container.container_path.clone(),
-            container_type: container.container_type.clone(),
-            entry_path: relative_normalized,
-            is_image: is_image_extension(&extension),
-            is_audio: is_audio_extension(&extension),
-            extension,
-        });
-    }
+    Ok(TextureFrames {
+        frame_width: animation.frame_width,
+        frame_height: animation.frame_height,
+        frames,
+    })
+}
 
-    Ok(assets)
+/// Cuts every whole frame in strip order: left to right, then top to bottom,
+/// the order `.mcmeta` frame indices refer to.
+fn slice_texture_frames(image: &RgbaImage, frame_width: u32, frame_height: u32) -> Vec<RgbaImage> {
+    if frame_width == 0 || frame_height == 0 {
+        return Vec::new();
+    }
+    let columns = image.width() / frame_width;
+    let rows = image.height() / frame_height;
+    (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (column, row)))
+        .map(|(column, row)| {
+            image::imageops::crop_imm(
+                image,
+                column * frame_width,
+                row * frame_height,
+                frame_width,
+                frame_height,
+            )
+            .to_image()
+        })
+        .collect()
 }
 
-fn scan_archive_container(
-    container: &ScanContainer,
-    should_cancel: &dyn Fn() -> bool,
-) -> Result<Vec<AssetCandidate>, String> {
-    let file = fs::File::open(&container.container_path).map_err(|error| {
-        format!(
-            "Failed to open archive {}: {error}",
-            container.container_path.display()
-        )
-    })?;
+#[tauri::command]
+fn get_model_references(
+    scan_id: String,
+    asset_id: String,
+    state: State<'_, AppState>,
+) -> Result<ModelReferences, String> {
+    let model = get_asset_from_state(&state, &scan_id, &asset_id)?;
+    if !is_json_extension(&model.extension) || !model.relative_asset_path.starts_with("models/") {
+        return Err("Selected asset is not a JSON model".to_string());
+    }
 
-    let mut archive = ZipArchive::new(file).map_err(|error| {
-        format!(
-            "Failed to read archive {}: {error}",
-            container.container_path.display()
+    let lookup = |location: &(String, String), prefix: &str, suffix: &str| {
+        let scans = state
+            .scans
+            .lock()
+            .map_err(|_| "Failed to lock scans state".to_string())?;
+        let scan = scans
+            .get(&scan_id)
+            .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
+        let relative_asset_path = format!("{prefix}{}{suffix}", location.1);
+        Ok::<_, String>(
+            find_asset_by_location(
+                &scan.assets,
+                &location.0,
+                &relative_asset_path,
+                &model.source_name,
+            )
+            .cloned(),
         )
-    })?;
+    };
 
-    let mut assets = Vec::new();
+    let mut archive_cache = ArchiveCache::new();
+    let mut value = read_model_json(&model, &mut archive_cache)?;
+    let mut visited = HashSet::from([model.asset_id.clone()]);
+    let mut parents = Vec::new();
+    let mut textures = Vec::new();
+    loop {
+        collect_model_textures(&value, &mut textures);
+        let Some(parent) = value.get("parent").and_then(serde_json::Value::as_str) else {
+            break;
+        };
+        let location = parse_resource_location(parent);
+        let resolved = lookup(&location, "models/", ".json")?;
+        parents.push(ModelReference {
+            location: format!("{}:{}", location.0, location.1),
+            asset_id: resolved.as_ref().map(|asset| asset.asset_id.clone()),
+        });
 
-    for index in 0..archive.len() {
-        if index % SCAN_CANCEL_CHECK_INTERVAL == 0 && should_cancel() {
-            return Err("Scan cancelled".to_string());
+        // Unindexed parents (e.g. `builtin/generated`) and cycles end the chain.
+        let Some(parent) = resolved.filter(|asset| visited.insert(asset.asset_id.clone())) else {
+            break;
+        };
+        if parents.len() >= MODEL_PARENT_MAX_DEPTH {
+            break;
         }
-
-        let Ok(entry) = archive.by_index(index) else {
-            continue;
+        let Ok(parent_value) = read_model_json(&parent, &mut archive_cache) else {
+            break;
         };
+        value = parent_value;
+    }
 
-        if entry.is_dir() {
-            continue;
-        }
+    let textures = resolve_model_textures(&textures)
+        .into_iter()
+        .map(|(variable, texture)| {
+            let location = parse_resource_location(&texture);
+            let resolved = lookup(&location, "textures/", ".png")?;
+            Ok(ModelTextureReference {
+                variable,
+                reference: ModelReference {
+                    location: format!("{}:{}", location.0, location.1),
+                    asset_id: resolved.map(|asset| asset.asset_id),
+                },
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
 
-        let path = normalize_archive_path(Path::new(entry.name()));
-        let Some(parsed) = parse_asset_relative_path(&path) else {
-            continue;
-        };
+    Ok(ModelReferences { parents, textures })
+}
 
-        let extension = parsed
-            .relative_asset_path
-            .rsplit('.')
-            .next()
-            .unwrap_or("")
-            .to_ascii_lowercase();
+fn read_model_json(
+    asset: &AssetRecord,
+    archive_cache: &mut ArchiveCache,
+) -> Result<serde_json::Value, String> {
+    let bytes = extract_asset_bytes_with_archive_cache(asset, archive_cache)?;
+    serde_json::from_slice(&bytes)
+        .map_err(|error| format!("Invalid model JSON in {}: {error}", asset.key))
+}
 
-        assets.push(AssetCandidate {
-            source_type: container.source_type.clone(),
-            source_name: container.source_name.clone(),
-            namespace: parsed.namespace,
-            relative_asset_path: parsed.relative_asset_path,
-            container_path: container.container_path.clone(),
-            container_type: container.container_type.clone(),
-            entry_path: path,
-            is_image: is_image_extension(&extension),
-            is_audio: is_audio_extension(&extension),
-            extension,
-        });
+/// Groups sound files by the events the source's `sounds.json` files define.
+/// Vanilla sources read theirs from the asset index; unreadable files are
+/// skipped so one broken mod doesn't hide the rest.
+#[tauri::command]
+fn get_sound_events(
+    scan_id: String,
+    source_name: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<SoundEvent>, String> {
+    let (definitions, index_paths) = {
+        let mut scans = state
+            .scans
+            .lock()
+            .map_err(|_| "Failed to lock scans state".to_string())?;
+        let scan = scans
+            .get_mut(&scan_id)
+            .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
+        let definitions = scan
+            .indices_for_sources(std::slice::from_ref(&source_name))
+            .into_iter()
+            .map(|index| &scan.assets[index])
+            .filter(|asset| asset.relative_asset_path == "sounds.json")
+            .cloned()
+            .collect::<Vec<_>>();
+        let index_paths = scan_asset_index_paths(
+            scan.containers
+                .iter()
+                .filter(|container| container.source_name == source_name),
+        );
+        (definitions, index_paths)
+    };
+
+    let mut events = Vec::new();
+    for asset in &definitions {
+        match extract_shared_asset_bytes(&state, asset)
+            .and_then(|bytes| parse_sound_events(&asset.namespace, &bytes))
+        {
+            Ok(parsed) => events.extend(parsed),
+            Err(error) => log::warn!("skipping sound definitions {}: {error}", asset.key),
+        }
+    }
+    for index_path in &index_paths {
+        let index = match read_vanilla_asset_index(index_path) {
+            Ok(index) => index,
+            Err(error) => {
+                log::warn!("skipping asset index {}: {error}", index_path.display());
+                continue;
+            }
+        };
+        for (namespace, path) in index.sound_definition_files() {
+            match fs::read(&path)
+                .map_err(|error| error.to_string())
+                .and_then(|bytes| parse_sound_events(&namespace, &bytes))
+            {
+                Ok(parsed) => events.extend(parsed),
+                Err(error) => {
+                    log::warn!("skipping sound definitions {}: {error}", path.display())
+                }
+            }
+        }
     }
+    events.sort_by(|left, right| left.event.cmp(&right.event));
 
-    Ok(assets)
+    let scans = state
+        .scans
+        .lock()
+        .map_err(|_| "Failed to lock scans state".to_string())?;
+    let scan = scans
+        .get(&scan_id)
+        .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
+    for variant in events
+        .iter_mut()
+        .flat_map(|event| event.variants.iter_mut())
+        .filter(|variant| variant.kind == SoundVariantKind::File)
+    {
+        let (namespace, path) = parse_resource_location(&variant.location);
+        variant.asset_id = find_asset_by_location(
+            &scan.assets,
+            &namespace,
+            &format!("sounds/{path}.ogg"),
+            &source_name,
+        )
+        .map(|asset| asset.asset_id.clone());
+    }
+    Ok(events)
 }
 
-#[derive(Debug, Clone)]
-struct ParsedAssetPath {
-    namespace: String,
-    relative_asset_path: String,
-}
+/// Parses one `assets/<namespace>/sounds.json`; variant asset ids are left
+/// for the caller to resolve.
+fn parse_sound_events(namespace: &str, bytes: &[u8]) -> Result<Vec<SoundEvent>, String> {
+    let definitions: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_slice(bytes).map_err(|error| format!("Invalid sounds.json: {error}"))?;
 
-fn parse_asset_relative_path(path: &str) -> Option<ParsedAssetPath> {
-    let segments: Vec<&str> = path
-        .split('/')
-        .filter(|segment| !segment.is_empty())
-        .collect();
-    let assets_index = segments.iter().position(|segment| *segment == "assets")?;
+    Ok(definitions
+        .into_iter()
+        .map(|(name, definition)| {
+            let variants = definition
+                .get("sounds")
+                .and_then(serde_json::Value::as_array)
+                .map(Vec::as_slice)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|sound| {
+                    let (sound_name, kind) = match sound {
+                        serde_json::Value::String(sound_name) => {
+                            (sound_name.as_str(), SoundVariantKind::File)
+                        }
+                        serde_json::Value::Object(fields) => {
+                            let kind = match fields.get("type").and_then(|kind| kind.as_str()) {
+                                Some("event") => SoundVariantKind::Event,
+                                _ => SoundVariantKind::File,
+                            };
+                            (fields.get("name")?.as_str()?, kind)
+                        }
+                        _ => return None,
+                    };
+                    let (sound_namespace, path) = parse_resource_location(sound_name);
+                    Some(SoundVariant {
+                        kind,
+                        location: format!("{sound_namespace}:{path}"),
+                        asset_id: None,
+                    })
+                })
+                .collect();
+            SoundEvent {
+                event: format!("{namespace}:{name}"),
+                subtitle: definition
+                    .get("subtitle")
+                    .and_then(serde_json::Value::as_str)
+                    .map(str::to_string),
+                variants,
+            }
+        })
+        .collect())
+}
 
-    if segments.len() <= assets_index + 2 {
-        return None;
+/// Splits `namespace:path`, defaulting to `minecraft` like the game does.
+fn parse_resource_location(raw: &str) -> (String, String) {
+    match raw.split_once(':') {
+        Some((namespace, path)) => (namespace.to_string(), path.to_string()),
+        None => ("minecraft".to_string(), raw.to_string()),
     }
+}
 
-    let namespace = segments.get(assets_index + 1)?.to_string();
-    let relative_asset_path = segments[assets_index + 2..].join("/");
+/// Prefers the copy shipped by `preferred_source` so a mod's model resolves to
+/// its own textures before another source's.
+fn find_asset_by_location<'a>(
+    assets: &'a [AssetRecord],
+    namespace: &str,
+    relative_asset_path: &str,
+    preferred_source: &str,
+) -> Option<&'a AssetRecord> {
+    let mut matches = assets.iter().filter(|asset| {
+        asset.namespace == namespace && asset.relative_asset_path == relative_asset_path
+    });
+    let first = matches.next()?;
+    if first.source_name == preferred_source {
+        return Some(first);
+    }
+    Some(
+        matches
+            .find(|asset| asset.source_name == preferred_source)
+            .unwrap_or(first),
+    )
+}
 
-    if relative_asset_path.is_empty() {
-        return None;
+/// Appends the `textures` entries of one model; walking child to parent means
+/// the first definition of a variable is the one that wins.
+fn collect_model_textures(model: &serde_json::Value, textures: &mut Vec<(String, String)>) {
+    let Some(entries) = model.get("textures").and_then(serde_json::Value::as_object) else {
+        return;
+    };
+    for (variable, value) in entries {
+        // Newer versions also accept `{"sprite": "..."}` objects.
+        let texture = value
+            .as_str()
+            .or_else(|| value.get("sprite").and_then(serde_json::Value::as_str));
+        if let Some(texture) = texture {
+            if !textures.iter().any(|(name, _)| name == variable) {
+                textures.push((variable.clone(), texture.to_string()));
+            }
+        }
     }
-
-    Some(ParsedAssetPath {
-        namespace,
-        relative_asset_path,
-    })
 }
 
-fn normalize_archive_path(path: &Path) -> String {
-    path.to_string_lossy().replace('\\', "/")
+/// Follows `#variable` references; dangling or cyclic references are dropped.
+fn resolve_model_textures(textures: &[(String, String)]) -> Vec<(String, String)> {
+    textures
+        .iter()
+        .filter_map(|(variable, value)| {
+            let mut value = value.as_str();
+            for _ in 0..=textures.len() {
+                let Some(reference) = value.strip_prefix('#') else {
+                    return Some((variable.clone(), value.to_string()));
+                };
+                value = textures
+                    .iter()
+                    .find(|(name, _)| name == reference)?
+                    .1
+                    .as_str();
+            }
+            None
+        })
+        .collect()
 }
 
-fn finalize_assets(
-    candidates: Vec<AssetCandidate>,
-    key_counts: &mut HashMap<String, usize>,
-) -> Vec<AssetRecord> {
-    candidates
-        .into_iter()
-        .map(|candidate| {
-            let base_key = build_base_key(&candidate);
-            let key = unique_key(base_key, key_counts);
-
-            AssetRecord {
-                asset_id: key.clone(),
-                key,
-                source_type: candidate.source_type,
-                source_name: candidate.source_name,
-                namespace: candidate.namespace,
-                relative_asset_path: candidate.relative_asset_path,
-                extension: candidate.extension,
-                is_image: candidate.is_image,
-                is_audio: candidate.is_audio,
-                container_path: candidate.container_path.to_string_lossy().to_string(),
-                container_type: candidate.container_type,
-                entry_path: candidate.entry_path,
-            }
-        })
-        .collect()
-}
-
-fn build_base_key(candidate: &AssetCandidate) -> String {
-    let source = normalize_key_segment(&candidate.source_name);
-    let namespace = normalize_key_segment(&candidate.namespace);
-    let path = candidate
-        .relative_asset_path
-        .split('/')
-        .map(normalize_key_segment)
-        .collect::<Vec<_>>()
-        .join(".");
-
-    format!(
-        "{}.{}.{}.{}",
-        candidate.source_type.key_prefix(),
-        source,
-        namespace,
-        path
-    )
-}
+/// Animated textures are frame strips described by a sibling `.mcmeta` in the
+/// same container; `None` means the texture is static (or its metadata is unusable).
+fn read_texture_animation(
+    asset: &AssetRecord,
+    archive_cache: &mut ArchiveCache,
+    width: u32,
+    height: u32,
+) -> Option<TextureAnimation> {
+    let mut mcmeta = asset.clone();
+    mcmeta.entry_path = format!("{}.mcmeta", asset.entry_path);
+    let bytes = extract_asset_bytes_with_archive_cache(&mcmeta, archive_cache).ok()?;
+    let value = serde_json::from_slice::<serde_json::Value>(&bytes).ok()?;
+    parse_texture_animation(value.get("animation")?, width, height)
+}
+
+/// Frames default to squares of the shorter edge, each shown for `frametime`
+/// ticks in strip order, matching how the game reads the metadata.
+fn parse_texture_animation(
+    animation: &serde_json::Value,
+    width: u32,
+    height: u32,
+) -> Option<TextureAnimation> {
+    let number =
+        |value: &serde_json::Value| value.as_u64().and_then(|value| u32::try_from(value).ok());
+    let field = |key: &str| animation.get(key).and_then(number);
+
+    let frame_width = field("width").unwrap_or(width.min(height)).min(width);
+    let frame_height = field("height").unwrap_or(frame_width).min(height);
+    if frame_width == 0 || frame_height == 0 {
+        return None;
+    }
+    let frame_count = (width / frame_width) * (height / frame_height);
+    let default_time = field("frametime").unwrap_or(1).max(1);
 
-fn unique_key(base_key: String, key_counts: &mut HashMap<String, usize>) -> String {
-    let counter = key_counts.entry(base_key.clone()).or_insert(0);
-    if *counter == 0 {
-        *counter = 1;
-        return base_key;
+    let frames = match animation
+        .get("frames")
+        .and_then(serde_json::Value::as_array)
+    {
+        Some(entries) => entries
+            .iter()
+            .filter_map(|entry| match entry {
+                serde_json::Value::Object(object) => Some(AnimationFrame {
+                    index: object.get("index").and_then(number)?,
+                    time: object.get("time").and_then(number).unwrap_or(default_time),
+                }),
+                other => Some(AnimationFrame {
+                    index: number(other)?,
+                    time: default_time,
+                }),
+            })
+            .filter(|frame| frame.index < frame_count)
+            .collect(),
+        None => (0..frame_count)
+            .map(|index| AnimationFrame {
+                index,
+                time: default_time,
+            })
+            .collect::<Vec<_>>(),
+    };
+    if frames.is_empty() {
+        return None;
     }
 
-    let key = format!("{}.dup{}", base_key, *counter);
-    *counter += 1;
-    key
+    Some(TextureAnimation {
+        frame_width,
+        frame_height,
+        interpolate: animation
+            .get("interpolate")
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or(false),
+        frames,
+    })
 }
 
-fn normalize_key_segment(value: &str) -> String {
-    let mut output = String::new();
-    let mut previous_was_separator = false;
-
-    for character in value.chars() {
-        if character.is_ascii_alphanumeric() {
-            output.push(character.to_ascii_lowercase());
-            previous_was_separator = false;
-        } else if !previous_was_separator {
-            output.push('_');
-            previous_was_separator = true;
-        }
+/// Crops to the first animation frame and shrinks so the longest edge fits
+/// `max_edge`; small pixel-art sources use nearest-neighbor to stay crisp.
+fn build_thumbnail(image: &RgbaImage, frame: Option<(u32, u32)>, max_edge: u32) -> RgbaImage {
+    let (width, height) = frame.unwrap_or((image.width(), image.height()));
+    let frame = image::imageops::crop_imm(image, 0, 0, width, height).to_image();
+    let longest = width.max(height);
+    if longest <= max_edge || longest == 0 {
+        return frame;
     }
 
-    output.trim_matches('_').to_string()
+    let scale = f64::from(max_edge) / f64::from(longest);
+    let target_width = ((f64::from(width) * scale).round() as u32).max(1);
+    let target_height = ((f64::from(height) * scale).round() as u32).max(1);
+    let filter = if longest <= THUMBNAIL_PIXEL_ART_MAX_EDGE {
+        image::imageops::FilterType::Nearest
+    } else {
+        image::imageops::FilterType::Triangle
+    };
+    image::imageops::resize(&frame, target_width, target_height, filter)
 }
 
-fn build_search_record(asset: &AssetRecord) -> AssetSearchRecord {
-    let filename = Path::new(&asset.relative_asset_path)
-        .file_name()
-        .map(|value| value.to_string_lossy().to_string())
-        .unwrap_or_else(|| asset.relative_asset_path.clone());
-
-    let filename_stem = Path::new(&filename)
-        .file_stem()
-        .map(|value| value.to_string_lossy().to_string())
-        .unwrap_or_else(|| filename.clone());
+#[tauri::command]
+fn get_asset_record(
+    scan_id: String,
+    asset_id: String,
+    state: State<'_, AppState>,
+) -> Result<AssetRecord, String> {
+    get_asset_from_state(&state, &scan_id, &asset_id)
+}
 
-    let filename_tokens = split_tokens(&filename);
-    let path_tokens = split_tokens(&asset.relative_asset_path);
-    let namespace_tokens = split_tokens(&asset.namespace);
-    let source_tokens = split_tokens(&asset.source_name);
+#[tauri::command]
+fn reconcile_asset_ids(
+    req: ReconcileAssetIdsRequest,
+    state: State<'_, AppState>,
+) -> Result<ReconcileAssetIdsResponse, String> {
+    let scans = state
+        .scans
+        .lock()
+        .map_err(|_| "Failed to lock scans state".to_string())?;
+    let scan = scans
+        .get(&req.scan_id)
+        .ok_or_else(|| format!("Unknown scan id: {}", req.scan_id))?;
 
-    let mut token_set = HashSet::new();
-    for token in split_tokens(&asset.key) {
-        token_set.insert(token);
-    }
-    for token in &path_tokens {
-        token_set.insert(token.clone());
-    }
-    for token in &namespace_tokens {
-        token_set.insert(token.clone());
-    }
-    for token in &source_tokens {
-        token_set.insert(token.clone());
-    }
+    let mut id_map = HashMap::<String, String>::new();
+    let mut resolved = Vec::<String>::new();
+    let mut seen = HashSet::<String>::new();
 
-    let mut all_tokens = token_set.into_iter().collect::<Vec<_>>();
-    all_tokens.sort();
+    for asset_id in req.asset_ids {
+        let mapped = if scan.asset_index.contains_key(&asset_id) {
+            Some(asset_id.clone())
+        } else {
+            scan.id_aliases
+                .get(&asset_id)
+                .filter(|next| scan.asset_index.contains_key(*next))
+                .cloned()
+        };
 
-    AssetSearchRecord {
-        all_tokens,
-        filename_tokens,
-        path_tokens,
-        namespace_tokens,
-        source_tokens,
-        compact_all: compact_text(&format!(
-            "{} {} {} {}",
-            asset.key, asset.source_name, asset.namespace, asset.relative_asset_path
-        )),
-        compact_filename: compact_text(&filename),
-        compact_filename_stem: compact_text(&filename_stem),
-        key: asset.key.to_lowercase(),
-        folder_node_id: asset_folder_node_id(asset),
+        if let Some(mapped_id) = mapped {
+            id_map.insert(asset_id, mapped_id.clone());
+            if seen.insert(mapped_id.clone()) {
+                resolved.push(mapped_id);
+            }
+        }
     }
-}
 
-fn compact_text(value: &str) -> String {
-    value
-        .chars()
-        .filter(|character| character.is_ascii_alphanumeric())
-        .map(|character| character.to_ascii_lowercase())
-        .collect()
+    Ok(ReconcileAssetIdsResponse {
+        id_map,
+        asset_ids: resolved,
+    })
 }
 
-fn split_tokens(value: &str) -> Vec<String> {
-    let mut tokens = Vec::new();
-    let mut current = String::new();
+#[tauri::command]
+fn save_assets(
+    app: AppHandle,
+    req: SaveAssetsRequest,
+    state: State<'_, AppState>,
+) -> Result<SaveAssetsResult, String> {
+    let operation_id = resolve_operation_id(req.operation_id);
+    let requested_count = req.asset_ids.len();
+    let audio_quality = req.audio_quality.unwrap_or_default().validate()?;
+    let normalize_audio = audio_normalization_requested(
+        req.audio_format.as_ref().unwrap_or(&AudioFormat::Original),
+        req.normalize_audio,
+    )?;
+    let audio_resample = req.audio_resample.validate()?;
+    let image_background = export_image_background(req.background.as_deref())?;
+    let name_template = req
+        .name_template
+        .as_deref()
+        .map(str::trim)
+        .filter(|template| !template.is_empty())
+        .map(NameTemplate::parse)
+        .transpose()?;
 
-    for character in value.chars() {
-        if character.is_ascii_alphanumeric() {
-            current.push(character.to_ascii_lowercase());
-        } else if !current.is_empty() {
-            tokens.push(current.clone());
-            current.clear();
-        }
+    if req.asset_ids.is_empty() {
+        return Ok(SaveAssetsResult {
+            operation_id,
+            requested_count,
+            processed_count: 0,
+            success_count: 0,
+            failed_count: 0,
+            cancelled: false,
+            failures: Vec::new(),
+            saved_files: Vec::new(),
+            manifest_warning: None,
+        });
     }
 
-    if !current.is_empty() {
-        tokens.push(current);
+    let destination_dir = expand_home(&req.destination_dir);
+    let requested_assets = collect_assets(&state, &req.scan_id, &req.asset_ids)?;
+    let options = ExportOptions {
+        audio_format: req.audio_format.unwrap_or(AudioFormat::Original),
+        audio_quality,
+        normalize_audio,
+        audio_resample,
+        image_format: req.image_format.unwrap_or(ImageFormat::Original),
+        image_background,
+        naming: req.naming.unwrap_or(ExportNaming::Original),
+        name_template,
+        layout: if req.preserve_structure.unwrap_or(false) {
+            ExportLayout::SourceTree
+        } else {
+            ExportLayout::Flat
+        },
+        cleanup_on_cancel: req.cleanup_on_cancel.unwrap_or(true),
+        defer_completion: false,
+    };
+
+    // Planning already dedupes against existing files, so the names shown here
+    // are the ones a real run would write right now.
+    if req.dry_run.unwrap_or(false) {
+        let planned_files = plan_export_jobs(requested_assets, &destination_dir, &options)
+            .into_iter()
+            .map(|job| job.output_path.to_string_lossy().to_string())
+            .collect();
+        return Ok(SaveAssetsResult {
+            operation_id,
+            requested_count,
+            processed_count: 0,
+            success_count: 0,
+            failed_count: 0,
+            cancelled: false,
+            failures: Vec::new(),
+            saved_files: planned_files,
+            manifest_warning: None,
+        });
     }
 
-    tokens
-}
+    fs::create_dir_all(&destination_dir)
+        .map_err(|error| format!("Failed to create destination directory: {error}"))?;
+    ensure_free_space(&destination_dir, estimated_export_bytes(&requested_assets))?;
+    let manifest_assets = req
+        .include_manifest
+        .unwrap_or(false)
+        .then(|| requested_assets.clone());
+    register_export_operation(&state, &operation_id)?;
 
-fn score_query(
-    index: &AssetSearchRecord,
-    query_tokens: &[String],
-    query_compact: &str,
-    normalized_query: &str,
-) -> Option<i64> {
-    if query_tokens.is_empty() {
-        return Some(0);
-    }
+    let run_result = run_export_operation(
+        &app,
+        ExportOperationKind::Save,
+        &operation_id,
+        requested_assets,
+        &destination_dir,
+        options,
+    );
 
-    let mut score = 0i64;
-    let mut matched_tokens = 0usize;
+    unregister_export_operation(&state, &operation_id);
 
-    for query_token in query_tokens {
-        let mut token_score = 0i64;
+    let outcome = run_result?;
+    let manifest_warning = manifest_assets.and_then(|assets| {
+        let error = write_export_manifest(&destination_dir, &assets, &outcome).err()?;
+        log::warn!("export {operation_id}: {error}");
+        Some(error)
+    });
+    Ok(SaveAssetsResult {
+        operation_id,
+        requested_count,
+        processed_count: outcome.processed_count,
+        success_count: outcome.success_count,
+        failed_count: outcome.failed_count,
+        cancelled: outcome.cancelled,
+        failures: outcome.failures,
+        saved_files: outcome.output_files,
+        manifest_warning,
+    })
+}
 
-        token_score = token_score.max(score_token_group_fast(
-            &index.filename_tokens,
-            query_token,
-            320,
-            250,
-            180,
-        ));
-        token_score = token_score.max(score_token_group_fast(
-            &index.path_tokens,
-            query_token,
-            170,
-            130,
-            95,
-        ));
-        token_score = token_score.max(score_token_group_fast(
-            &index.namespace_tokens,
-            query_token,
-            140,
-            110,
-            80,
-        ));
-        token_score = token_score.max(score_token_group_fast(
-            &index.source_tokens,
-            query_token,
-            130,
-            100,
-            76,
-        ));
-        token_score = token_score.max(score_token_group_fast(
-            &index.all_tokens,
-            query_token,
-            100,
-            80,
-            60,
-        ));
-
-        if token_score == 0 {
-            token_score = token_score.max(score_fuzzy_token_group(
-                &index.filename_tokens,
-                query_token,
-                72,
-            ));
-            token_score =
-                token_score.max(score_fuzzy_token_group(&index.path_tokens, query_token, 48));
-        }
+#[tauri::command]
+fn save_search_results(
+    app: AppHandle,
+    req: SaveSearchResultsRequest,
+    state: State<'_, AppState>,
+) -> Result<SaveAssetsResult, String> {
+    let asset_ids = {
+        let mut scans = state
+            .scans
+            .lock()
+            .map_err(|_| "Failed to lock scans state".to_string())?;
+        let scan = scans
+            .get_mut(&req.search.scan_id)
+            .ok_or_else(|| format!("Unknown scan id: {}", req.search.scan_id))?;
+        ordered_search_matches(scan, &req.search, usize::MAX)?
+            .page(0, usize::MAX)
+            .map(|index| scan.assets[index].asset_id.clone())
+            .collect::<Vec<_>>()
+    };
+    save_assets(
+        app,
+        SaveAssetsRequest {
+            scan_id: req.search.scan_id,
+            asset_ids,
+            destination_dir: req.destination_dir,
+            audio_format: req.audio_format,
+            audio_quality: req.audio_quality,
+            normalize_audio: req.normalize_audio,
+            audio_resample: req.audio_resample,
+            image_format: req.image_format,
+            background: req.background,
+            naming: req.naming,
+            name_template: req.name_template,
+            preserve_structure: req.preserve_structure,
+            include_manifest: req.include_manifest,
+            dry_run: req.dry_run,
+            cleanup_on_cancel: req.cleanup_on_cancel,
+            operation_id: req.operation_id,
+        },
+        state,
+    )
+}
 
-        if token_score == 0 {
-            score -= 100;
-            continue;
+/// Size of an asset's bytes as recorded on disk or in the archive's central
+/// directory, without extracting anything. `None` when that isn't cheap to get
+/// (entries of nested jars or salvaged archives, asset-index objects).
+fn asset_source_size(
+    asset: &AssetRecord,
+    archives: &mut HashMap<String, Option<ZipArchive<ArchiveReader>>>,
+) -> Option<u64> {
+    if let Some(byte_size) = asset.byte_size {
+        return Some(byte_size);
+    }
+    match asset.container_type {
+        AssetContainerType::Directory => {
+            fs::metadata(Path::new(&asset.container_path).join(&asset.entry_path))
+                .ok()
+                .map(|metadata| metadata.len())
         }
-
-        matched_tokens += 1;
-        score += token_score;
+        AssetContainerType::Zip | AssetContainerType::Jar => archives
+            .entry(asset.container_path.clone())
+            .or_insert_with(|| {
+                ArchiveReader::open(Path::new(&asset.container_path))
+                    .ok()
+                    .and_then(|reader| ZipArchive::new(reader).ok())
+            })
+            .as_mut()?
+            .by_name(&asset.entry_path)
+            .ok()
+            .map(|entry| entry.size()),
+        AssetContainerType::AssetIndex => None,
     }
+}
 
-    let token_count = query_tokens.len();
-    let required_matches = if token_count <= 2 {
-        token_count
-    } else {
-        (token_count * 3).div_ceil(5)
+/// Lower bound on the bytes an export writes; unknown sizes count as zero, so
+/// only exports that clearly won't fit get refused.
+fn estimated_export_bytes(assets: &[AssetRecord]) -> u64 {
+    let mut archives = HashMap::new();
+    assets
+        .iter()
+        .map(|asset| asset_source_size(asset, &mut archives).unwrap_or(0))
+        .sum()
+}
+
+/// Checks the volume of the nearest existing ancestor, since destinations are
+/// often created by the export itself. Skips the check when the volume can't
+/// report its free space.
+fn ensure_free_space(destination_dir: &Path, required_bytes: u64) -> Result<(), String> {
+    let probe = destination_dir
+        .ancestors()
+        .find(|path| path.exists())
+        .unwrap_or(destination_dir);
+    let available_bytes = match available_space(probe) {
+        Ok(value) => value,
+        Err(error) => {
+            log::debug!(
+                "could not read free space of {}: {error}",
+                destination_dir.display()
+            );
+            return Ok(());
+        }
     };
-
-    if matched_tokens < required_matches {
-        return None;
+    if required_bytes > available_bytes {
+        return Err(format!(
+            "Not enough free space in {}: the export needs about {required_bytes} bytes \
+             but only {available_bytes} are available",
+            destination_dir.display()
+        ));
     }
+    Ok(())
+}
 
-    let missing_tokens = token_count.saturating_sub(matched_tokens);
-    if missing_tokens > 0 {
-        score -= (missing_tokens as i64) * 70;
-    } else {
-        score += 90;
+/// Bytes available to this user on the volume holding `path`.
+#[cfg(unix)]
+fn available_space(path: &Path) -> std::io::Result<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes())
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `path` is NUL-terminated and `stat` is only read once statvfs succeeded.
+    let stat = unsafe {
+        if libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        stat.assume_init()
+    };
+    Ok((stat.f_bavail as u64).saturating_mul(stat.f_frsize as u64))
+}
+
+/// Bytes available to this user on the volume holding `path`.
+#[cfg(windows)]
+fn available_space(path: &Path) -> std::io::Result<u64> {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetDiskFreeSpaceExW(
+            directory_name: *const u16,
+            free_bytes_available: *mut u64,
+            total_number_of_bytes: *mut u64,
+            total_number_of_free_bytes: *mut u64,
+        ) -> i32;
+    }
+
+    let wide = path
+        .as_os_str()
+        .encode_wide()
+        .chain(Some(0))
+        .collect::<Vec<u16>>();
+    let mut available = 0u64;
+    // SAFETY: `wide` is NUL-terminated; the totals are optional out-params.
+    let succeeded = unsafe {
+        GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut available,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    if succeeded == 0 {
+        return Err(std::io::Error::last_os_error());
     }
+    Ok(available)
+}
 
-    score += (matched_tokens as i64) * 48;
+#[cfg(not(any(unix, windows)))]
+fn available_space(_path: &Path) -> std::io::Result<u64> {
+    Err(std::io::Error::from(std::io::ErrorKind::Unsupported))
+}
 
-    if !query_compact.is_empty() {
-        if index.compact_filename_stem == query_compact {
-            score += 450;
-        } else if index.compact_filename_stem.starts_with(query_compact) {
-            score += 240;
-        } else if index.compact_filename.contains(query_compact) {
-            score += 190;
-        }
+#[tauri::command]
+fn save_assets_as_zip(
+    app: AppHandle,
+    req: SaveAssetsAsZipRequest,
+    state: State<'_, AppState>,
+) -> Result<SaveAssetsAsZipResult, String> {
+    let operation_id = resolve_operation_id(req.operation_id);
+    let requested_count = req.asset_ids.len();
+    let audio_resample = req.audio_resample.validate()?;
+    let image_background = export_image_background(req.background.as_deref())?;
 
-        if index.compact_all.contains(query_compact) {
-            score += 120;
-        }
+    if req.asset_ids.is_empty() {
+        return Ok(SaveAssetsAsZipResult {
+            export: SaveAssetsResult {
+                operation_id,
+                requested_count,
+                processed_count: 0,
+                success_count: 0,
+                failed_count: 0,
+                cancelled: false,
+                failures: Vec::new(),
+                saved_files: Vec::new(),
+                manifest_warning: None,
+            },
+            zip_path: None,
+        });
     }
 
-    if !normalized_query.is_empty() && index.key.contains(normalized_query) {
-        score += 80;
+    let requested_assets = collect_assets(&state, &req.scan_id, &req.asset_ids)?;
+    let zip_path = expand_home(&req.destination_zip_path);
+    let staging_root = app
+        .path()
+        .app_cache_dir()
+        .map_err(|error| format!("Failed to get app cache directory: {error}"))?
+        .join("zip-export")
+        .join(Uuid::new_v4().to_string());
+    fs::create_dir_all(&staging_root)
+        .map_err(|error| format!("Failed to create temporary export directory: {error}"))?;
+    // Files are staged in full before being archived, so both volumes need room.
+    let required_bytes = estimated_export_bytes(&requested_assets);
+    let space_check = ensure_free_space(&staging_root, required_bytes)
+        .and_then(|_| ensure_free_space(zip_path.parent().unwrap_or(&zip_path), required_bytes));
+    if let Err(error) = space_check {
+        let _ = fs::remove_dir_all(&staging_root);
+        return Err(error);
     }
 
-    let extra_filename_tokens = index.filename_tokens.len().saturating_sub(matched_tokens);
-    if extra_filename_tokens > 0 {
-        score -= (extra_filename_tokens as i64) * 8;
-    }
+    register_export_operation(&state, &operation_id)?;
+
+    // Workers materialize files into a staging folder in parallel; the archive is
+    // then written sequentially since ZipWriter is single-threaded. The staging
+    // folder always goes away, and a cancelled archive is never left half-written.
+    // Completion is reported only once the archive is, so the UI can't show
+    // success while the zip is still being written.
+    let run_result = run_export_operation(
+        &app,
+        ExportOperationKind::Save,
+        &operation_id,
+        requested_assets,
+        &staging_root,
+        ExportOptions {
+            audio_format: req.audio_format.unwrap_or(AudioFormat::Original),
+            audio_quality: AudioQuality::default(),
+            normalize_audio: false,
+            audio_resample,
+            image_format: req.image_format.unwrap_or(ImageFormat::Original),
+            image_background,
+            naming: ExportNaming::Original,
+            name_template: None,
+            layout: if req.preserve_structure.unwrap_or(false) {
+                ExportLayout::ResourcePack
+            } else {
+                ExportLayout::Flat
+            },
+            cleanup_on_cancel: true,
+            defer_completion: true,
+        },
+    );
+
+    let zip_result = run_result.and_then(|mut outcome| {
+        if outcome.cancelled || outcome.output_files.is_empty() {
+            return Ok((outcome, Vec::new(), None));
+        }
+        let written = write_export_zip(&staging_root, &outcome.output_files, &zip_path, &|| {
+            is_export_cancelled(&app, &operation_id)
+        });
+        if written.is_err() || matches!(written, Ok(None)) {
+            let _ = fs::remove_file(&zip_path);
+        }
+        match written? {
+            Some(entries) => Ok((
+                outcome,
+                entries,
+                Some(zip_path.to_string_lossy().to_string()),
+            )),
+            None => {
+                outcome.cancelled = true;
+                Ok((outcome, Vec::new(), None))
+            }
+        }
+    });
+    unregister_export_operation(&state, &operation_id);
+    let _ = fs::remove_dir_all(&staging_root);
 
-    Some(score)
+    let (outcome, entries, zip_path) = zip_result?;
+    emit_export_completed(
+        &app,
+        ExportCompletedEvent {
+            operation_id: operation_id.clone(),
+            kind: ExportOperationKind::Save,
+            requested_count,
+            processed_count: outcome.processed_count,
+            success_count: outcome.success_count,
+            failed_count: outcome.failed_count,
+            cancelled: outcome.cancelled,
+            failures: outcome.failures.clone(),
+        },
+    );
+    Ok(SaveAssetsAsZipResult {
+        export: SaveAssetsResult {
+            operation_id,
+            requested_count,
+            processed_count: outcome.processed_count,
+            success_count: outcome.success_count,
+            failed_count: outcome.failed_count,
+            cancelled: outcome.cancelled,
+            failures: outcome.failures,
+            saved_files: entries,
+            manifest_warning: None,
+        },
+        zip_path,
+    })
 }
 
-fn score_token_group_fast(
-    tokens: &[String],
-    query_token: &str,
-    exact_weight: i64,
-    prefix_weight: i64,
-    contains_weight: i64,
-) -> i64 {
-    let mut best = 0;
-    for token in tokens {
-        if token == query_token {
-            return exact_weight;
-        } else if token.starts_with(query_token) || query_token.starts_with(token) {
-            best = best.max(prefix_weight);
-        } else if token.contains(query_token) || query_token.contains(token) {
-            best = best.max(contains_weight);
+/// Returns `None` when `should_cancel` fires between entries; the caller
+/// removes the unfinished archive.
+fn write_export_zip(
+    staging_root: &Path,
+    files: &[String],
+    zip_path: &Path,
+    should_cancel: &dyn Fn() -> bool,
+) -> Result<Option<Vec<String>>, String> {
+    if let Some(parent) = zip_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|error| format!("Failed to create destination directory: {error}"))?;
+    }
+    let file = fs::File::create(zip_path)
+        .map_err(|error| format!("Failed to create {}: {error}", zip_path.display()))?;
+    let mut writer = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    let mut entries = Vec::with_capacity(files.len());
+    for file in files {
+        if should_cancel() {
+            return Ok(None);
         }
+        let path = Path::new(file);
+        let entry_name = normalize_archive_path(path.strip_prefix(staging_root).unwrap_or(path));
+        let bytes = fs::read(path)
+            .map_err(|error| format!("Failed to read staged file {}: {error}", path.display()))?;
+        writer
+            .start_file(entry_name.as_str(), options)
+            .and_then(|_| writer.write_all(&bytes).map_err(Into::into))
+            .map_err(|error| format!("Failed to write zip entry {entry_name}: {error}"))?;
+        entries.push(entry_name);
     }
-    best
+
+    writer
+        .finish()
+        .map_err(|error| format!("Failed to finish {}: {error}", zip_path.display()))?;
+    Ok(Some(entries))
 }
 
-fn score_fuzzy_token_group(tokens: &[String], query_token: &str, max_weight: i64) -> i64 {
-    if query_token.len() < 4 {
-        return 0;
-    }
+#[tauri::command]
+fn export_as_resource_pack(
+    app: AppHandle,
+    req: ExportResourcePackRequest,
+    state: State<'_, AppState>,
+) -> Result<SaveAssetsResult, String> {
+    let operation_id = resolve_operation_id(req.operation_id);
+    let requested_count = req.asset_ids.len();
+    let audio_resample = req.audio_resample.validate()?;
+    let image_background = export_image_background(req.background.as_deref())?;
+    let destination_dir = expand_home(&req.destination_dir);
+    fs::create_dir_all(&destination_dir)
+        .map_err(|error| format!("Failed to create destination directory: {error}"))?;
 
-    let mut best = 0i64;
-    for token in tokens {
-        let score = score_fuzzy_token(token, query_token);
-        if score > 0 {
-            best = best.max(max_weight.min(score));
-        }
+    let requested_assets = collect_assets(&state, &req.scan_id, &req.asset_ids)?;
+    ensure_free_space(&destination_dir, estimated_export_bytes(&requested_assets))?;
+    // The icon is read up front so a bad path fails before any asset is written.
+    let pack_icon = req
+        .pack_icon_path
+        .as_deref()
+        .map(|path| read_pack_icon(&expand_home(path)))
+        .transpose()?;
+
+    if requested_assets.is_empty() {
+        let pack_files = write_pack_metadata(
+            &destination_dir,
+            req.pack_format,
+            &req.description,
+            pack_icon.as_ref(),
+        )?;
+        return Ok(SaveAssetsResult {
+            operation_id,
+            requested_count,
+            processed_count: 0,
+            success_count: 0,
+            failed_count: 0,
+            cancelled: false,
+            failures: Vec::new(),
+            saved_files: pack_files,
+            manifest_warning: None,
+        });
     }
 
-    best
+    register_export_operation(&state, &operation_id)?;
+
+    let run_result = run_export_operation(
+        &app,
+        ExportOperationKind::Save,
+        &operation_id,
+        requested_assets,
+        &destination_dir,
+        ExportOptions {
+            audio_format: req.audio_format.unwrap_or(AudioFormat::Original),
+            audio_quality: AudioQuality::default(),
+            normalize_audio: false,
+            audio_resample,
+            image_format: req.image_format.unwrap_or(ImageFormat::Original),
+            image_background,
+            naming: ExportNaming::Original,
+            name_template: None,
+            layout: ExportLayout::ResourcePack,
+            cleanup_on_cancel: req.cleanup_on_cancel.unwrap_or(true),
+            defer_completion: false,
+        },
+    );
+
+    unregister_export_operation(&state, &operation_id);
+
+    let outcome = run_result?;
+    // Metadata goes in last, so cancelling never touches an existing pack's own
+    // pack.mcmeta or pack.png.
+    let mut pack_files = if outcome.cancelled {
+        Vec::new()
+    } else {
+        write_pack_metadata(
+            &destination_dir,
+            req.pack_format,
+            &req.description,
+            pack_icon.as_ref(),
+        )?
+    };
+    pack_files.extend(outcome.output_files);
+    Ok(SaveAssetsResult {
+        operation_id,
+        requested_count,
+        processed_count: outcome.processed_count,
+        success_count: outcome.success_count,
+        failed_count: outcome.failed_count,
+        cancelled: outcome.cancelled,
+        failures: outcome.failures,
+        saved_files: pack_files,
+        manifest_warning: None,
+    })
 }
 
-fn score_fuzzy_token(token: &str, query_token: &str) -> i64 {
-    let token_len = token.len();
-    let query_len = query_token.len();
-    if token_len < 3 || query_len < 3 {
-        return 0;
-    }
+/// Writes `pack.mcmeta` (and `pack.png` when an icon is given) into a pack root.
+fn write_pack_metadata(
+    pack_root: &Path,
+    pack_format: u32,
+    description: &str,
+    icon: Option<&image::DynamicImage>,
+) -> Result<Vec<String>, String> {
+    let mcmeta = serde_json::json!({
+        "pack": {
+            "pack_format": pack_format,
+            "description": description,
+        }
+    });
+    let mcmeta_path = pack_root.join("pack.mcmeta");
+    let bytes = serde_json::to_vec_pretty(&mcmeta)
+        .map_err(|error| format!("Failed to serialize pack.mcmeta: {error}"))?;
+    fs::write(&mcmeta_path, bytes)
+        .map_err(|error| format!("Failed to write {}: {error}", mcmeta_path.display()))?;
+    let mut written = vec![mcmeta_path.to_string_lossy().to_string()];
 
-    let len_delta = token_len.abs_diff(query_len);
-    if len_delta > 2 {
-        return 0;
+    if let Some(icon) = icon {
+        let icon_target = pack_root.join("pack.png");
+        icon.save_with_format(&icon_target, image::ImageFormat::Png)
+            .map_err(|error| format!("Failed to write {}: {error}", icon_target.display()))?;
+        written.push(icon_target.to_string_lossy().to_string());
     }
 
-    let token_bytes = token.as_bytes();
-    let query_bytes = query_token.as_bytes();
-    let same_start = token_bytes.first() == query_bytes.first();
-    let swap_start = token_bytes.len() > 1
-        && query_bytes.len() > 1
-        && token_bytes[0] == query_bytes[1]
-        && token_bytes[1] == query_bytes[0];
-    if !same_start && !swap_start {
-        return 0;
-    }
+    Ok(written)
+}
 
-    let distance = damerau_levenshtein(token, query_token);
-    match distance {
-        1 => 72,
-        2 if token_len >= 4 && query_len >= 4 => 54,
-        3 if token_len >= 9 && query_len >= 9 => 40,
-        _ => 0,
-    }
+fn read_pack_icon(icon_path: &Path) -> Result<image::DynamicImage, String> {
+    image::open(icon_path)
+        .map_err(|error| format!("Failed to read pack icon {}: {error}", icon_path.display()))
 }
 
-fn idle_asset_cmp(left: &AssetRecord, right: &AssetRecord) -> CmpOrdering {
-    let left_name = idle_filename(left);
-    let right_name = idle_filename(right);
-    let left_token = last_filename_token(&left_name);
-    let right_token = last_filename_token(&right_name);
-
-    natural_compare(&left_token, &right_token)
-        .then_with(|| {
-            natural_compare(
-                &left_name.to_ascii_lowercase(),
-                &right_name.to_ascii_lowercase(),
-            )
-        })
-        .then_with(|| left.key.cmp(&right.key))
-}
+#[tauri::command]
+fn export_namespace(
+    app: AppHandle,
+    req: ExportNamespaceRequest,
+    state: State<'_, AppState>,
+) -> Result<ExportNamespaceResult, String> {
+    let operation_id = resolve_operation_id(req.operation_id);
+    let audio_resample = req.audio_resample.validate()?;
+    let (assets, conflicts) = {
+        let mut scans = state
+            .scans
+            .lock()
+            .map_err(|_| "Failed to lock scans state".to_string())?;
+        let scan = scans
+            .get_mut(&req.scan_id)
+            .ok_or_else(|| format!("Unknown scan id: {}", req.scan_id))?;
+        let candidates = match req.source_filter.as_deref() {
+            Some(sources) if !sources.is_empty() => {
+                let indices = scan.indices_for_sources(sources);
+                indices
+                    .into_iter()
+                    .map(|index| &scan.assets[index])
+                    .collect::<Vec<_>>()
+            }
+            _ => scan.assets.iter().collect(),
+        };
+        select_namespace_assets(candidates, &req.namespace)
+    };
 
-fn idle_filename(asset: &AssetRecord) -> String {
-    Path::new(&asset.relative_asset_path)
-        .file_name()
-        .map(|value| value.to_string_lossy().to_string())
-        .unwrap_or_else(|| asset.relative_asset_path.clone())
-}
+    let requested_count = assets.len();
+    let destination_dir = expand_home(&req.destination_dir);
+    fs::create_dir_all(&destination_dir)
+        .map_err(|error| format!("Failed to create destination directory: {error}"))?;
+    ensure_free_space(&destination_dir, estimated_export_bytes(&assets))?;
+    register_export_operation(&state, &operation_id)?;
 
-fn last_filename_token(file_name: &str) -> String {
-    let stem = Path::new(file_name)
-        .file_stem()
-        .map(|value| value.to_string_lossy().to_string())
-        .unwrap_or_else(|| file_name.to_string());
-    split_tokens(&stem)
-        .pop()
-        .unwrap_or_else(|| stem.to_ascii_lowercase())
-}
+    let run_result = run_export_operation(
+        &app,
+        ExportOperationKind::Save,
+        &operation_id,
+        assets,
+        &destination_dir,
+        ExportOptions {
+            audio_format: req.audio_format.unwrap_or(AudioFormat::Original),
+            audio_quality: AudioQuality::default(),
+            normalize_audio: false,
+            audio_resample,
+            image_format: ImageFormat::Original,
+            image_background: JPEG_DEFAULT_BACKGROUND,
+            naming: ExportNaming::Original,
+            name_template: None,
+            layout: ExportLayout::AssetPath,
+            cleanup_on_cancel: req.cleanup_on_cancel.unwrap_or(true),
+            defer_completion: false,
+        },
+    );
 
-fn natural_compare(left: &str, right: &str) -> CmpOrdering {
-    let left_chunks = natural_chunks(left);
-    let right_chunks = natural_chunks(right);
-    let max_len = left_chunks.len().max(right_chunks.len());
+    unregister_export_operation(&state, &operation_id);
 
-    for index in 0..max_len {
-        let left_chunk = left_chunks.get(index);
-        let right_chunk = right_chunks.get(index);
-        let ordering = match (left_chunk, right_chunk) {
-            (Some(NaturalChunk::Number(a)), Some(NaturalChunk::Number(b))) => a.cmp(b),
-            (Some(NaturalChunk::Text(a)), Some(NaturalChunk::Text(b))) => a.cmp(b),
-            (Some(NaturalChunk::Number(_)), Some(NaturalChunk::Text(_))) => CmpOrdering::Less,
-            (Some(NaturalChunk::Text(_)), Some(NaturalChunk::Number(_))) => CmpOrdering::Greater,
-            (Some(_), None) => CmpOrdering::Greater,
-            (None, Some(_)) => CmpOrdering::Less,
-            (None, None) => CmpOrdering::Equal,
-        };
+    let outcome = run_result?;
+    Ok(ExportNamespaceResult {
+        export: SaveAssetsResult {
+            operation_id,
+            requested_count,
+            processed_count: outcome.processed_count,
+            success_count: outcome.success_count,
+            failed_count: outcome.failed_count,
+            cancelled: outcome.cancelled,
+            failures: outcome.failures,
+            saved_files: outcome.output_files,
+            manifest_warning: None,
+        },
+        conflicts,
+    })
+}
 
-        if ordering != CmpOrdering::Equal {
-            return ordering;
-        }
-    }
+/// Every resource source providing `namespace:relative_asset_path`, the one the
+/// game uses first. Resource packs among themselves stay in scan order, which
+/// is alphabetical rather than the enabled order from `options.txt`.
+#[tauri::command]
+fn get_asset_overrides(
+    scan_id: String,
+    namespace: String,
+    relative_asset_path: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<AssetRecord>, String> {
+    let scans = state
+        .scans
+        .lock()
+        .map_err(|_| "Failed to lock scans state".to_string())?;
+    let scan = scans
+        .get(&scan_id)
+        .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
 
-    CmpOrdering::Equal
+    Ok(
+        asset_override_providers(&scan.assets, &namespace, &relative_asset_path)
+            .into_iter()
+            .cloned()
+            .collect(),
+    )
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-enum NaturalChunk {
-    Number(u64),
-    Text(String),
+fn asset_override_providers<'a>(
+    assets: &'a [AssetRecord],
+    namespace: &str,
+    relative_asset_path: &str,
+) -> Vec<&'a AssetRecord> {
+    let mut providers = assets
+        .iter()
+        .filter(|asset| {
+            asset.namespace == namespace
+                && asset.relative_asset_path == relative_asset_path
+                && asset.source_type.override_priority().is_some()
+        })
+        .collect::<Vec<_>>();
+    sort_by_override_priority(&mut providers);
+    providers
 }
 
-fn natural_chunks(value: &str) -> Vec<NaturalChunk> {
-    let mut chunks = Vec::<NaturalChunk>::new();
-    let mut current = String::new();
-    let mut is_number = false;
+fn sort_by_override_priority(assets: &mut [&AssetRecord]) {
+    // Stable sort keeps scan order among sources of the same kind.
+    assets.sort_by_key(|asset| std::cmp::Reverse(asset.source_type.override_priority()));
+}
 
-    for ch in value.chars() {
-        if ch.is_ascii_digit() {
-            if !is_number && !current.is_empty() {
-                chunks.push(NaturalChunk::Text(current.to_ascii_lowercase()));
-                current.clear();
-            }
-            is_number = true;
-            current.push(ch);
-        } else {
-            if is_number && !current.is_empty() {
-                chunks.push(NaturalChunk::Number(
-                    current.parse::<u64>().unwrap_or(u64::MAX),
-                ));
-                current.clear();
-            }
-            is_number = false;
-            current.push(ch);
+/// Keeps one asset per relative path within `namespace`, preferring the source that
+/// wins in game (resource packs over mods over vanilla) and recording the rest.
+fn select_namespace_assets(
+    candidates: Vec<&AssetRecord>,
+    namespace: &str,
+) -> (Vec<AssetRecord>, Vec<NamespaceExportConflict>) {
+    let mut order = Vec::<String>::new();
+    let mut by_path = HashMap::<String, Vec<&AssetRecord>>::new();
+    for asset in candidates {
+        if asset.namespace != namespace {
+            continue;
         }
+        by_path
+            .entry(asset.relative_asset_path.clone())
+            .or_insert_with(|| {
+                order.push(asset.relative_asset_path.clone());
+                Vec::new()
+            })
+            .push(asset);
     }
 
-    if !current.is_empty() {
-        if is_number {
-            chunks.push(NaturalChunk::Number(
-                current.parse::<u64>().unwrap_or(u64::MAX),
-            ));
-        } else {
-            chunks.push(NaturalChunk::Text(current.to_ascii_lowercase()));
+    let mut selected = Vec::new();
+    let mut conflicts = Vec::new();
+    for relative_asset_path in order {
+        let Some(mut entries) = by_path.remove(&relative_asset_path) else {
+            continue;
+        };
+        sort_by_override_priority(&mut entries);
+        let kept = entries[0];
+        if entries.len() > 1 {
+            conflicts.push(NamespaceExportConflict {
+                relative_asset_path,
+                kept_asset_id: kept.asset_id.clone(),
+                kept_source_name: kept.source_name.clone(),
+                skipped_source_names: entries[1..]
+                    .iter()
+                    .map(|asset| asset.source_name.clone())
+                    .collect(),
+            });
         }
+        selected.push(kept.clone());
     }
 
-    chunks
-}
-
-fn asset_matches_folder(index: &AssetSearchRecord, folder_filter: Option<&str>) -> bool {
-    let Some(folder_filter) = folder_filter else {
-        return true;
-    };
-
-    index.folder_node_id == folder_filter
-        || index
-            .folder_node_id
-            .starts_with(&format!("{folder_filter}/"))
+    (selected, conflicts)
 }
 
-fn asset_matches_media(
-    asset: &AssetRecord,
-    include_images: bool,
-    include_audio: bool,
-    include_other: bool,
-) -> bool {
-    if asset.is_image {
-        return include_images;
-    }
+#[tauri::command]
+fn copy_assets_to_clipboard(
+    app: AppHandle,
+    req: CopyAssetsRequest,
+    state: State<'_, AppState>,
+) -> Result<CopyResult, String> {
+    let operation_id = resolve_operation_id(req.operation_id);
+    let requested_count = req.asset_ids.len();
+    let audio_quality = req.audio_quality.unwrap_or_default().validate()?;
+    let normalize_audio = audio_normalization_requested(
+        req.audio_format.as_ref().unwrap_or(&AudioFormat::Original),
+        req.normalize_audio,
+    )?;
+    let audio_resample = req.audio_resample.validate()?;
+    let image_background = export_image_background(req.background.as_deref())?;
 
-    if asset.is_audio {
-        return include_audio;
+    if req.asset_ids.is_empty() {
+        return Ok(CopyResult {
+            operation_id,
+            requested_count,
+            processed_count: 0,
+            success_count: 0,
+            failed_count: 0,
+            cancelled: false,
+            failures: Vec::new(),
+            copied_files: Vec::new(),
+        });
     }
 
-    include_other
-}
-
-fn add_asset_to_tree(tree_children: &mut HashMap<String, Vec<TreeNode>>, asset: &AssetRecord) {
-    let mut parent_id = ROOT_NODE_ID.to_string();
-    let folders = build_asset_folder_segments(asset);
-
-    for segment in folders {
-        let node_name = if segment.is_empty() {
-            "(root)"
-        } else {
-            &segment
-        };
-        let node_id = build_folder_node_id(&parent_id, node_name);
-
-        upsert_tree_node(
-            tree_children,
-            &parent_id,
-            TreeNode {
-                id: node_id.clone(),
-                name: node_name.to_string(),
-                node_type: TreeNodeType::Folder,
-                has_children: true,
-                asset_id: None,
-            },
-        );
+    let requested_assets = collect_assets(&state, &req.scan_id, &req.asset_ids)?;
+    let temp_root = app
+        .path()
+        .app_cache_dir()
+        .map_err(|error| format!("Failed to get app cache directory: {error}"))?
+        .join("clipboard-assets")
+        .join(Uuid::new_v4().to_string());
 
-        tree_children.entry(node_id.clone()).or_default();
-        parent_id = node_id;
-    }
+    fs::create_dir_all(&temp_root)
+        .map_err(|error| format!("Failed to create temporary copy directory: {error}"))?;
 
-    let file_name = Path::new(&asset.relative_asset_path)
-        .file_name()
-        .map(|value| value.to_string_lossy().to_string())
-        .unwrap_or_else(|| asset.relative_asset_path.clone());
+    register_export_operation(&state, &operation_id)?;
 
-    let file_node_id = format!("{parent_id}/file:{}", asset.asset_id);
-    upsert_tree_node(
-        tree_children,
-        &parent_id,
-        TreeNode {
-            id: file_node_id,
-            name: file_name,
-            node_type: TreeNodeType::File,
-            has_children: false,
-            asset_id: Some(asset.asset_id.clone()),
+    let run_result = run_export_operation(
+        &app,
+        ExportOperationKind::Copy,
+        &operation_id,
+        requested_assets,
+        &temp_root,
+        ExportOptions {
+            audio_format: req.audio_format.unwrap_or(AudioFormat::Original),
+            audio_quality,
+            normalize_audio,
+            audio_resample,
+            image_format: req.image_format.unwrap_or(ImageFormat::Original),
+            image_background,
+            naming: req.naming.unwrap_or(ExportNaming::Original),
+            name_template: None,
+            layout: ExportLayout::Flat,
+            cleanup_on_cancel: true,
+            defer_completion: false,
         },
     );
-}
 
-fn asset_folder_node_id(asset: &AssetRecord) -> String {
-    let mut node_id = ROOT_NODE_ID.to_string();
-    for segment in build_asset_folder_segments(asset) {
-        let node_name = if segment.is_empty() {
-            "(root)"
-        } else {
-            &segment
-        };
-        node_id = build_folder_node_id(&node_id, node_name);
-    }
-    node_id
-}
+    unregister_export_operation(&state, &operation_id);
 
-fn build_asset_folder_segments(asset: &AssetRecord) -> Vec<String> {
-    let mut folders = Vec::new();
+    let outcome = run_result?;
+    let copied_paths: Vec<PathBuf> = outcome.output_files.iter().map(PathBuf::from).collect();
 
-    folders.push(asset.source_type.tree_root_name().to_string());
-    folders.push(asset.source_name.clone());
-    folders.push(asset.namespace.clone());
+    let clipboard = ClipboardContext::new()
+        .map_err(|error| format!("Failed to open clipboard context: {error}"))?;
 
-    let path = Path::new(&asset.relative_asset_path);
-    if let Some(parent) = path.parent() {
-        for segment in parent.iter() {
-            folders.push(segment.to_string_lossy().to_string());
-        }
+    if !copied_paths.is_empty() {
+        clipboard
+            .set_files(
+                copied_paths
+                    .iter()
+                    .map(|path| path.to_string_lossy().to_string())
+                    .collect(),
+            )
+            .map_err(|error| format!("Failed to copy files to clipboard: {error}"))?;
     }
 
-    folders
+    {
+        let mut temp_paths = state
+            .temp_paths
+            .lock()
+            .map_err(|_| "Failed to lock temp paths".to_string())?;
+        temp_paths.push(temp_root);
+    }
+
+    Ok(CopyResult {
+        operation_id,
+        requested_count,
+        processed_count: outcome.processed_count,
+        success_count: outcome.success_count,
+        failed_count: outcome.failed_count,
+        cancelled: outcome.cancelled,
+        failures: outcome.failures,
+        copied_files: outcome.output_files,
+    })
 }
 
-fn build_folder_node_id(parent: &str, segment: &str) -> String {
-    let escaped = segment.replace('/', "∕");
-    if parent == ROOT_NODE_ID {
-        format!("{ROOT_NODE_ID}/{escaped}")
-    } else {
-        format!("{parent}/{escaped}")
+#[tauri::command]
+fn copy_resource_locations(
+    req: CopyResourceLocationsRequest,
+    state: State<'_, AppState>,
+) -> Result<CopyResourceLocationsResult, String> {
+    let assets = collect_assets(&state, &req.scan_id, &req.asset_ids)?;
+    let (locations, result) = collect_resource_locations(&assets);
+    if !locations.is_empty() {
+        set_clipboard_text(locations.join("\n"))?;
     }
+    Ok(result)
 }
 
-fn upsert_tree_node(
-    tree_children: &mut HashMap<String, Vec<TreeNode>>,
-    parent_id: &str,
-    node: TreeNode,
-) {
-    let children = tree_children.entry(parent_id.to_string()).or_default();
-    if children.iter().any(|child| child.id == node.id) {
-        return;
+fn collect_resource_locations(
+    assets: &[AssetRecord],
+) -> (Vec<String>, CopyResourceLocationsResult) {
+    let mut seen = HashSet::<String>::new();
+    let mut locations = Vec::<String>::new();
+    let mut skipped_count = 0;
+    let mut duplicate_count = 0;
+    for asset in assets {
+        match asset_resource_location(asset) {
+            Some(location) if seen.insert(location.clone()) => locations.push(location),
+            Some(_) => duplicate_count += 1,
+            None => skipped_count += 1,
+        }
     }
 
-    children.push(node);
+    let result = CopyResourceLocationsResult {
+        copied_count: locations.len(),
+        skipped_count,
+        duplicate_count,
+    };
+    (locations, result)
 }
 
-fn collect_assets(
-    state: &State<'_, AppState>,
-    scan_id: &str,
-    asset_ids: &[String],
-) -> Result<Vec<AssetRecord>, String> {
-    let scans = state
-        .scans
-        .lock()
-        .map_err(|_| "Failed to lock scans state".to_string())?;
-
-    let scan = scans
-        .get(scan_id)
-        .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
+#[tauri::command]
+fn copy_text_to_clipboard(text: String) -> Result<(), String> {
+    set_clipboard_text(text)
+}
 
-    let mut assets = Vec::new();
+#[tauri::command]
+fn get_asset_resource_location(
+    scan_id: String,
+    asset_id: String,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, String> {
+    let asset = get_asset_from_state(&state, &scan_id, &asset_id)?;
+    Ok(asset_resource_location(&asset))
+}
 
-    for asset_id in asset_ids {
-        let index = scan
-            .asset_index
-            .get(asset_id)
-            .ok_or_else(|| format!("Unknown asset id: {asset_id}"))?;
+/// Only fails for an unknown asset; the asset bytes are read once and only
+/// when a field isn't already known from the scan.
+#[tauri::command]
+fn get_asset_info(
+    scan_id: String,
+    asset_id: String,
+    state: State<'_, AppState>,
+) -> Result<AssetInfo, String> {
+    let asset = get_asset_from_state(&state, &scan_id, &asset_id)?;
+    let mut byte_size = asset.byte_size;
+    let mut dimensions = asset.width.zip(asset.height);
+    let mut duration_ms = None;
+    let mut errors = BTreeMap::new();
 
-        assets.push(scan.assets[*index].clone());
+    let mut pending_fields = Vec::new();
+    if byte_size.is_none() {
+        pending_fields.push("byteSize");
+    }
+    if asset.is_image && dimensions.is_none() {
+        pending_fields.extend(["width", "height"]);
+    }
+    if asset.is_audio {
+        pending_fields.push("durationMs");
     }
 
-    Ok(assets)
-}
-
-fn get_asset_from_state(
-    state: &State<'_, AppState>,
-    scan_id: &str,
-    asset_id: &str,
-) -> Result<AssetRecord, String> {
-    let asset_ids = vec![asset_id.to_string()];
-
-    collect_assets(state, scan_id, &asset_ids)
-        .map(|mut assets| assets.remove(0))
-        .map_err(|error| error.to_string())
-}
+    let (bytes, has_mcmeta) = with_archive_handles(&state, &asset.container_path, |cache| {
+        let bytes = (!pending_fields.is_empty())
+            .then(|| extract_asset_bytes_with_archive_cache(&asset, cache));
+        let mut mcmeta = asset.clone();
+        mcmeta.entry_path = format!("{}.mcmeta", asset.entry_path);
+        let has_mcmeta = extract_asset_bytes_with_archive_cache(&mcmeta, cache).is_ok();
+        (bytes, has_mcmeta)
+    });
 
-fn resolve_operation_id(operation_id: Option<String>) -> String {
-    operation_id
-        .map(|value| value.trim().to_string())
-        .filter(|value| !value.is_empty())
-        .unwrap_or_else(|| Uuid::new_v4().to_string())
+    match bytes {
+        Some(Ok(bytes)) => {
+            byte_size.get_or_insert(bytes.len() as u64);
+            if asset.is_image && dimensions.is_none() {
+                match image_dimensions(&bytes) {
+                    Ok(value) => dimensions = Some(value),
+                    Err(error) => {
+                        errors.insert("width".to_string(), error.clone());
+                        errors.insert("height".to_string(), error);
+                    }
+                }
+            }
+            if asset.is_audio {
+                duration_ms = audio_duration_ms(&bytes);
+                if duration_ms.is_none() {
+                    errors.insert(
+                        "durationMs".to_string(),
+                        format!("Cannot read the duration of this .{} file", asset.extension),
+                    );
+                }
+            }
+        }
+        Some(Err(error)) => {
+            for field in pending_fields {
+                errors.insert(field.to_string(), error.clone());
+            }
+        }
+        None => {}
+    }
+
+    Ok(AssetInfo {
+        resource_location: asset_resource_location(&asset),
+        byte_size,
+        width: dimensions.map(|(width, _)| width),
+        height: dimensions.map(|(_, height)| height),
+        duration_ms,
+        has_mcmeta,
+        errors,
+        asset,
+    })
 }
 
-fn register_export_operation(
-    state: &State<'_, AppState>,
-    operation_id: &str,
-) -> Result<(), String> {
-    let mut operations = state
-        .export_operations
-        .lock()
-        .map_err(|_| "Failed to lock export operations state".to_string())?;
-    operations.insert(operation_id.to_string(), ExportOperationState::new());
-    Ok(())
+/// Reads the header when the format can be guessed, decoding only formats
+/// without a signature such as TGA.
+fn image_dimensions(bytes: &[u8]) -> Result<(u32, u32), String> {
+    let header_dimensions = image::ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_dimensions().ok());
+    match header_dimensions {
+        Some(dimensions) => Ok(dimensions),
+        None => decode_rgba_image(bytes).map(|image| image.dimensions()),
+    }
 }
 
-fn unregister_export_operation(state: &State<'_, AppState>, operation_id: &str) {
-    if let Ok(mut operations) = state.export_operations.lock() {
-        operations.remove(operation_id);
+/// Duration from the container headers alone; covers the Ogg Vorbis/Opus and
+/// WAV files Minecraft ships, other formats return `None`.
+fn audio_duration_ms(bytes: &[u8]) -> Option<u64> {
+    if bytes.starts_with(b"OggS") {
+        ogg_duration_ms(bytes)
+    } else if bytes.starts_with(b"RIFF") {
+        wav_duration_ms(bytes)
+    } else {
+        None
     }
 }
 
-fn is_export_cancelled(app: &AppHandle, operation_id: &str) -> bool {
-    let state = app.state::<AppState>();
-    let Ok(operations) = state.export_operations.lock() else {
-        return true;
+/// The first packet names the codec and its sample clock; the granule
+/// position of the last page is the stream's final sample.
+fn ogg_duration_ms(bytes: &[u8]) -> Option<u64> {
+    let segment_count = usize::from(*bytes.get(26)?);
+    let first_packet = bytes.get(27 + segment_count..)?;
+    let (sample_rate, pre_skip) = if first_packet.starts_with(b"\x01vorbis") {
+        let sample_rate = u32::from_le_bytes(first_packet.get(12..16)?.try_into().ok()?);
+        (sample_rate, 0)
+    } else if first_packet.starts_with(b"OpusHead") {
+        let pre_skip = u16::from_le_bytes(first_packet.get(10..12)?.try_into().ok()?);
+        (48_000, u64::from(pre_skip))
+    } else {
+        return None;
     };
+    if sample_rate == 0 {
+        return None;
+    }
 
-    operations
-        .get(operation_id)
-        .map(|operation| operation.cancelled)
-        .unwrap_or(false)
+    let last_page = bytes.windows(4).rposition(|window| window == b"OggS")?;
+    let granule = u64::from_le_bytes(bytes.get(last_page + 6..last_page + 14)?.try_into().ok()?);
+    if granule == u64::MAX {
+        return None;
+    }
+    // Corrupt granules can be near `u64::MAX`; treat an overflow as unknown.
+    Some(granule.saturating_sub(pre_skip).checked_mul(1000)? / u64::from(sample_rate))
 }
 
-fn emit_scan_progress(app: &AppHandle, event: ScanProgressEvent) {
-    let state = app.state::<AppState>();
-    if let Ok(mut scans) = state.scans.lock() {
-        if let Some(scan) = scans.get_mut(&event.scan_id) {
-            scan.scanned_containers = event.scanned_containers;
-            scan.total_containers = event.total_containers;
-            if matches!(scan.status, ScanLifecycle::Scanning) {
-                scan.error = None;
+fn wav_duration_ms(bytes: &[u8]) -> Option<u64> {
+    if bytes.get(8..12)? != b"WAVE" {
+        return None;
+    }
+    let mut byte_rate = None;
+    let mut offset = 12usize;
+    while let Some(header) = bytes.get(offset..offset + 8) {
+        let size = u32::from_le_bytes(header[4..8].try_into().ok()?) as usize;
+        match &header[..4] {
+            b"fmt " => {
+                byte_rate = Some(u32::from_le_bytes(
+                    bytes.get(offset + 16..offset + 20)?.try_into().ok()?,
+                ));
+            }
+            b"data" => {
+                let byte_rate = byte_rate.filter(|rate| *rate > 0)?;
+                return Some(size as u64 * 1000 / u64::from(byte_rate));
             }
+            _ => {}
         }
+        // Chunks are padded to an even length.
+        offset = offset.checked_add(8 + size + (size & 1))?;
     }
-
-    let _ = app.emit("scan://progress", event);
+    None
 }
 
-fn emit_export_progress(app: &AppHandle, event: ExportProgressEvent) {
-    let _ = app.emit("export://progress", event);
+fn set_clipboard_text(text: String) -> Result<(), String> {
+    let clipboard = ClipboardContext::new()
+        .map_err(|error| format!("Failed to open clipboard context: {error}"))?;
+    clipboard
+        .set_text(text)
+        .map_err(|error| format!("Failed to copy text to clipboard: {error}"))
 }
 
-fn emit_export_completed(app: &AppHandle, event: ExportCompletedEvent) {
-    let _ = app.emit("export://completed", event);
-}
+#[tauri::command]
+fn copy_image_to_clipboard(
+    scan_id: String,
+    asset_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let asset = get_asset_from_state(&state, &scan_id, &asset_id)?;
+    if !asset.is_image {
+        return Err("Selected asset is not an image".to_string());
+    }
 
-#[derive(Debug, Clone)]
-struct ExportJob {
-    index: usize,
-    asset: AssetRecord,
-    output_path: PathBuf,
+    let image = decode_rgba_image(&extract_shared_asset_bytes(&state, &asset)?)?;
+    let clipboard = ClipboardContext::new()
+        .map_err(|error| format!("Failed to open clipboard context: {error}"))?;
+    clipboard
+        .set_image(RustImageData::from_dynamic_image(
+            image::DynamicImage::ImageRgba8(image),
+        ))
+        .map_err(|error| format!("Failed to copy image to clipboard: {error}"))
 }
 
-#[derive(Debug)]
-struct ExportRunOutcome {
-    output_files: Vec<String>,
-    processed_count: usize,
-    success_count: usize,
-    failed_count: usize,
-    cancelled: bool,
-    failures: Vec<ExportFailure>,
-}
+#[tauri::command]
+fn get_audio_waveform(
+    app: AppHandle,
+    scan_id: String,
+    asset_id: String,
+    bucket_count: usize,
+    state: State<'_, AppState>,
+) -> Result<AudioWaveform, String> {
+    let asset = get_asset_from_state(&state, &scan_id, &asset_id)?;
+    if !asset.is_audio {
+        return Err("Selected asset is not an audio file".to_string());
+    }
 
-#[derive(Debug)]
-enum ExportWorkerResult {
-    Success {
-        index: usize,
-        output_path: PathBuf,
-    },
-    Failure {
-        index: usize,
-        failure: ExportFailure,
-    },
+    let bytes = extract_shared_asset_bytes(&state, &asset)?;
+    let ffmpeg_path = resolve_ffmpeg_path(&app)?;
+    let mut command = Command::new(ffmpeg_path);
+    command.args(["-hide_banner", "-loglevel", "error", "-i", "pipe:0", "-vn"]);
+    command.args(["-ac", "1", "-ar", &WAVEFORM_SAMPLE_RATE.to_string()]);
+    command.args(["-f", "s16le", "pipe:1"]);
+    let pcm = run_ffmpeg_capture(command, &bytes)?;
+
+    let samples = pcm
+        .chunks_exact(2)
+        .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+        .collect::<Vec<_>>();
+    Ok(AudioWaveform {
+        peaks: waveform_peaks(&samples, bucket_count.clamp(1, WAVEFORM_MAX_BUCKETS)),
+        duration_seconds: samples.len() as f64 / f64::from(WAVEFORM_SAMPLE_RATE),
+    })
 }
 
-fn plan_export_jobs(
-    assets: Vec<AssetRecord>,
-    destination_dir: &Path,
-    audio_format: AudioFormat,
-) -> Vec<ExportJob> {
-    let mut used_names = HashSet::new();
-    let mut jobs = Vec::new();
-
-    for (index, asset) in assets.into_iter().enumerate() {
-        let original_name = Path::new(&asset.relative_asset_path)
-            .file_name()
-            .map(|value| value.to_string_lossy().to_string())
-            .unwrap_or_else(|| asset.asset_id.clone());
+#[tauri::command]
+fn get_audio_metadata(
+    app: AppHandle,
+    scan_id: String,
+    asset_id: String,
+    state: State<'_, AppState>,
+) -> Result<AudioMetadata, String> {
+    let asset = get_asset_from_state(&state, &scan_id, &asset_id)?;
+    if !asset.is_audio {
+        return Err("Selected asset is not an audio file".to_string());
+    }
 
-        let (base_stem, mut extension) = split_file_name(&original_name);
-        if asset.is_audio {
-            match audio_format {
-                AudioFormat::Original => {}
-                AudioFormat::Mp3 => extension = "mp3".to_string(),
-                AudioFormat::Wav => extension = "wav".to_string(),
-            }
+    let bytes = extract_shared_asset_bytes(&state, &asset)?;
+    let ffprobe_path = resolve_ffprobe_path(&app)?;
+    let mut command = Command::new(ffprobe_path);
+    command.args(["-v", "error", "-select_streams", "a:0", "-of", "json"]);
+    command.args([
+        "-show_entries",
+        "stream=codec_name,sample_rate,channels,bit_rate:format=duration,bit_rate",
+        "-i",
+        "pipe:0",
+    ]);
+    let mut metadata = parse_ffprobe_metadata(&run_ffmpeg_capture(command, &bytes)?)?;
+    // Piped Ogg streams can't be seeked to the end, so ffprobe may not know
+    // the duration; the last page's granule position still does.
+    if metadata.duration_ms.is_none() {
+        metadata.duration_ms = audio_duration_ms(&bytes);
+    }
+    Ok(metadata)
+}
+
+fn parse_ffprobe_metadata(output: &[u8]) -> Result<AudioMetadata, String> {
+    let value = serde_json::from_slice::<serde_json::Value>(output)
+        .map_err(|error| format!("Failed to parse ffprobe output: {error}"))?;
+    let stream = value
+        .get("streams")
+        .and_then(|streams| streams.get(0))
+        .ok_or_else(|| "No audio stream found".to_string())?;
+    let format = value.get("format");
+    // ffprobe prints most numbers as strings.
+    let number = |value: Option<&serde_json::Value>| -> Option<f64> {
+        match value? {
+            serde_json::Value::String(text) => text.parse().ok(),
+            other => other.as_f64(),
         }
+    };
 
-        let target_name =
-            dedupe_file_name(&base_stem, &extension, destination_dir, &mut used_names);
-        jobs.push(ExportJob {
-            index,
-            asset,
-            output_path: destination_dir.join(target_name),
-        });
-    }
+    Ok(AudioMetadata {
+        duration_ms: number(format.and_then(|format| format.get("duration")))
+            .map(|seconds| (seconds * 1000.0).round() as u64),
+        sample_rate: number(stream.get("sample_rate")).map(|rate| rate as u32),
+        channels: number(stream.get("channels")).map(|channels| channels as u32),
+        codec: stream
+            .get("codec_name")
+            .and_then(serde_json::Value::as_str)
+            .map(str::to_string),
+        bitrate: number(stream.get("bit_rate"))
+            .or_else(|| number(format.and_then(|format| format.get("bit_rate"))))
+            .map(|bitrate| bitrate as u64),
+    })
+}
 
-    jobs
+/// Splits `samples` into `bucket_count` even spans and records each span's
+/// min and max; buckets past the end of short clips stay silent.
+fn waveform_peaks(samples: &[i16], bucket_count: usize) -> Vec<f32> {
+    let mut peaks = Vec::with_capacity(bucket_count * 2);
+    for bucket in 0..bucket_count {
+        let start = bucket * samples.len() / bucket_count;
+        let end = ((bucket + 1) * samples.len() / bucket_count).max(start);
+        let span = &samples[start..end];
+        let min = span.iter().copied().min().unwrap_or(0);
+        let max = span.iter().copied().max().unwrap_or(0);
+        peaks.push(f32::from(min) / 32768.0);
+        peaks.push(f32::from(max) / 32768.0);
+    }
+    peaks
 }
 
-fn run_export_operation(
-    app: &AppHandle,
-    kind: ExportOperationKind,
-    operation_id: &str,
-    assets: Vec<AssetRecord>,
+#[tauri::command]
+fn convert_audio_asset(
+    app: AppHandle,
+    req: ConvertAudioRequest,
+    state: State<'_, AppState>,
+) -> Result<ConvertedTempFileRef, String> {
+    if req.format == AudioFormat::Original {
+        return Err("Use save/copy with original format instead of convert command".to_string());
+    }
+    let quality = req.quality.unwrap_or_default().validate()?;
+    let normalize = req.normalize.unwrap_or(false);
+    let resample = req.resample.validate()?;
+    let trim = req.trim.validate()?;
+
+    let asset = get_asset_from_state(&state, &req.scan_id, &req.asset_id)?;
+    if !asset.is_audio {
+        return Err("Selected asset is not an audio file".to_string());
+    }
+
+    let temp_root = app
+        .path()
+        .app_cache_dir()
+        .map_err(|error| format!("Failed to get app cache directory: {error}"))?
+        .join("converted-audio")
+        .join(Uuid::new_v4().to_string());
+
+    fs::create_dir_all(&temp_root)
+        .map_err(|error| format!("Failed to create temporary conversion directory: {error}"))?;
+
+    let original_name = Path::new(&asset.relative_asset_path)
+        .file_name()
+        .map(|value| value.to_string_lossy().to_string())
+        .unwrap_or_else(|| asset.asset_id.clone());
+    let (base_stem, _) = split_file_name(&original_name);
+    let extension = req
+        .format
+        .extension()
+        .map(str::to_string)
+        .unwrap_or_else(|| asset.extension.clone());
+
+    let mut used_names = HashSet::new();
+    let output_name = dedupe_file_name(&base_stem, &extension, &temp_root, &mut used_names);
+    let output_path = temp_root.join(output_name);
+
+    let ffmpeg_path = resolve_ffmpeg_path(&app)?;
+    let bytes = extract_shared_asset_bytes(&state, &asset)?;
+    convert_audio_bytes_to_file(
+        &ffmpeg_path,
+        &bytes,
+        &output_path,
+        AudioConversion {
+            format: &req.format,
+            quality,
+            normalize,
+            resample,
+            trim,
+        },
+        None,
+    )?;
+
+    {
+        let mut temp_paths = state
+            .temp_paths
+            .lock()
+            .map_err(|_| "Failed to lock temp paths".to_string())?;
+        temp_paths.push(temp_root);
+    }
+
+    Ok(ConvertedTempFileRef {
+        path: output_path.to_string_lossy().to_string(),
+        format: req.format,
+    })
+}
+
+fn run_scan_bootstrap_worker(
+    app: AppHandle,
+    scan_id: String,
+    req: StartScanRequest,
+    cache_key: String,
+    force_rescan: bool,
+) {
+    log::info!(
+        "scan {scan_id}: started for instance {} (force_rescan={force_rescan})",
+        req.instance_folder
+    );
+    let result = run_scan_bootstrap_worker_inner(&app, &scan_id, &req, &cache_key, force_rescan);
+    if let Err(error) = result {
+        log::error!("scan {scan_id}: failed: {error}");
+        update_scan_error(&app, &scan_id, &error);
+        let _ = app.emit(
+            "scan://error",
+            serde_json::json!({
+                "scanId": scan_id,
+                "error": error,
+            }),
+        );
+    }
+}
+
+fn run_scan_bootstrap_worker_inner(
+    app: &AppHandle,
+    scan_id: &str,
+    req: &StartScanRequest,
+    cache_key: &str,
+    force_rescan: bool,
+) -> Result<(), String> {
+    if !force_rescan {
+        let loaded = load_cached_snapshot(app, cache_key)?;
+        if let CachedSnapshotLoad::Invalidated(reason) = loaded {
+            log::warn!("scan {scan_id}: dropped cache {cache_key}: {reason:?}");
+            let _ = app.emit(
+                "cache://invalidated",
+                CacheInvalidatedEvent {
+                    scan_id: scan_id.to_string(),
+                    cache_key: cache_key.to_string(),
+                    reason,
+                },
+            );
+        }
+        if let CachedSnapshotLoad::Hit(snapshot) = loaded {
+            let snapshot = *snapshot;
+            let cached_asset_count = snapshot.assets.len();
+            log::info!("scan {scan_id}: loaded {cached_asset_count} assets from cache {cache_key}");
+            {
+                let state = app.state::<AppState>();
+                let mut scans = state
+                    .scans
+                    .lock()
+                    .map_err(|_| "Failed to lock scans state".to_string())?;
+                if let Some(scan) = scans.get_mut(scan_id) {
+                    scan.status = ScanLifecycle::Completed;
+                    scan.is_refreshing = true;
+                    scan.scanned_containers = snapshot.container_signatures.len();
+                    scan.total_containers = snapshot.container_signatures.len();
+                    scan.error = None;
+                    scan.cancelled = false;
+                    scan.assets = snapshot.assets;
+                    scan.asset_index = scan
+                        .assets
+                        .iter()
+                        .enumerate()
+                        .map(|(index, asset)| (asset.asset_id.clone(), index))
+                        .collect();
+                    scan.search_records = if snapshot.search_records.len() == scan.assets.len() {
+                        snapshot.search_records
+                    } else {
+                        scan.assets.iter().map(build_search_record).collect()
+                    };
+                    scan.tree_index = build_tree_index(&snapshot.tree_children);
+                    scan.tree_children = snapshot.tree_children;
+                    scan.container_assets = snapshot.container_assets;
+                    scan.container_signatures = snapshot.container_signatures;
+                    scan.id_aliases = HashMap::new();
+                    scan.invalidate_derived_indexes();
+                    scan.cache_key = Some(cache_key.to_string());
+                }
+            }
+
+            let _ = app.emit("scan://cache-hit", ScanCacheEvent::new(scan_id, true));
+            let _ = app.emit(
+                "scan://completed",
+                ScanCompletedEvent {
+                    scan_id: scan_id.to_string(),
+                    lifecycle: ScanLifecycle::Completed,
+                    asset_count: cached_asset_count,
+                    error: None,
+                },
+            );
+
+            run_refresh_worker_inner(app, scan_id, req, cache_key)?;
+            return Ok(());
+        }
+    }
+
+    let _ = app.emit("scan://cache-hit", ScanCacheEvent::new(scan_id, false));
+    run_scan_worker(
+        app.clone(),
+        scan_id.to_string(),
+        req.clone(),
+        cache_key.to_string(),
+    );
+    Ok(())
+}
+
+fn run_scan_worker(app: AppHandle, scan_id: String, req: StartScanRequest, cache_key: String) {
+    let result = run_scan_worker_inner(&app, &scan_id, &req, &cache_key);
+
+    if let Err(error) = result {
+        log::error!("scan {scan_id}: failed: {error}");
+        update_scan_error(&app, &scan_id, &error);
+        let _ = app.emit(
+            "scan://error",
+            serde_json::json!({
+                "scanId": scan_id,
+                "error": error,
+            }),
+        );
+    }
+}
+
+fn run_scan_worker_inner(
+    app: &AppHandle,
+    scan_id: &str,
+    req: &StartScanRequest,
+    cache_key: &str,
+) -> Result<(), String> {
+    {
+        let state = app.state::<AppState>();
+        let lock_result = state.scans.lock();
+        if let Ok(mut scans) = lock_result {
+            if let Some(scan) = scans.get_mut(scan_id) {
+                scan.cache_key = Some(cache_key.to_string());
+                scan.is_refreshing = false;
+            }
+        }
+    }
+
+    let layout = resolve_instance_layout(req)?;
+    let containers = collect_scan_containers(&layout, req)?;
+    log::info!(
+        "scan {scan_id}: found {} containers for Minecraft {}",
+        containers.len(),
+        layout.mc_version
+    );
+    if containers.is_empty() {
+        log::warn!(
+            "scan {scan_id}: no containers found in {}",
+            layout.instance_dir.display()
+        );
+    }
+
+    let instances = Vec::from_iter(requested_scan_instance(req, &layout));
+    let containers = containers
+        .into_iter()
+        .map(|container| (container, instances.clone()))
+        .collect();
+    if scan_containers_into_state(app, scan_id, ScanOptions::from_request(req), containers)? {
+        persist_scan_snapshot(app, scan_id, req, cache_key)?;
+    }
+
+    Ok(())
+}
+
+fn run_multi_scan_worker_inner(
+    app: &AppHandle,
+    scan_id: &str,
+    req: &StartMultiScanRequest,
+) -> Result<(), String> {
+    let mut instance_containers = Vec::new();
+    for instance_folder in &req.instance_folders {
+        let instance_req = req.instance_request(instance_folder);
+        let layout = resolve_instance_layout(&instance_req)?;
+        instance_containers.push((
+            ScanInstance::for_request(&instance_req, &layout),
+            collect_scan_containers(&layout, &instance_req)?,
+        ));
+    }
+    let containers = merge_instance_containers(instance_containers);
+    log::info!(
+        "scan {scan_id}: found {} containers across {} instances",
+        containers.len(),
+        req.instance_folders.len()
+    );
+
+    let scan_options = ScanOptions {
+        collect_dimensions: req.collect_dimensions.unwrap_or(false),
+        follow_symlinks: req.follow_symlinks.unwrap_or(false),
+    };
+    scan_containers_into_state(app, scan_id, scan_options, containers)?;
+    Ok(())
+}
+
+/// Top tree level for one instance's assets.
+#[derive(Debug, Clone)]
+struct ScanInstance {
+    folder: String,
+    display_name: Option<String>,
+}
+
+impl ScanInstance {
+    fn for_request(req: &StartScanRequest, layout: &InstanceLayout) -> Self {
+        Self {
+            folder: req.instance_folder.trim().to_string(),
+            display_name: launcher_instance_display_name(
+                &layout.instance_dir,
+                req.launcher_kind.unwrap_or_default(),
+            ),
+        }
+    }
+}
+
+/// Scans every container once, even when several instances share it (e.g. a
+/// common asset index), remembering each instance it belongs to.
+fn merge_instance_containers(
+    instance_containers: Vec<(ScanInstance, Vec<ScanContainer>)>,
+) -> Vec<(ScanContainer, Vec<ScanInstance>)> {
+    let mut positions = HashMap::<String, usize>::new();
+    let mut merged = Vec::<(ScanContainer, Vec<ScanInstance>)>::new();
+    for (instance, containers) in instance_containers {
+        for container in containers {
+            match positions.entry(scan_container_key(&container)) {
+                Entry::Occupied(position) => {
+                    merged[*position.get()].1.push(instance.clone());
+                }
+                Entry::Vacant(position) => {
+                    position.insert(merged.len());
+                    merged.push((container, vec![instance.clone()]));
+                }
+            }
+        }
+    }
+    merged
+}
+
+/// Finalizes a container's candidates once per instance it belongs to, so a
+/// shared container shows up in each instance's subtree. Untagged scans pass
+/// no instances.
+fn finalize_container_assets(
+    candidates: Vec<AssetCandidate>,
+    instances: &[ScanInstance],
+    key_counts: &mut HashMap<String, usize>,
+) -> Vec<AssetRecord> {
+    let Some((last, shared_with)) = instances.split_last() else {
+        return finalize_assets(candidates, key_counts);
+    };
+    let mut assets = Vec::new();
+    for instance in shared_with {
+        assets.extend(finalize_instance_assets(
+            candidates.clone(),
+            Some(instance),
+            key_counts,
+        ));
+    }
+    assets.extend(finalize_instance_assets(candidates, Some(last), key_counts));
+    assets
+}
+
+fn requested_scan_instance(
+    req: &StartScanRequest,
+    layout: &InstanceLayout,
+) -> Option<ScanInstance> {
+    req.group_by_instance
+        .unwrap_or(false)
+        .then(|| ScanInstance::for_request(req, layout))
+}
+
+/// Scans `containers` into the scan's state, tagging each container's assets
+/// with its instance folders. Returns `false` when the scan was cancelled.
+fn scan_containers_into_state(
+    app: &AppHandle,
+    scan_id: &str,
+    scan_options: ScanOptions,
+    containers: Vec<(ScanContainer, Vec<ScanInstance>)>,
+) -> Result<bool, String> {
+    let total_containers = containers.len();
+    {
+        let state = app.state::<AppState>();
+        let mut scans = state
+            .scans
+            .lock()
+            .map_err(|_| "Failed to lock scans state".to_string())?;
+        if let Some(scan) = scans.get_mut(scan_id) {
+            scan.containers = containers
+                .iter()
+                .map(|(container, _)| container.clone())
+                .collect();
+        }
+    }
+    emit_scan_progress(
+        app,
+        ScanProgressEvent {
+            scan_id: scan_id.to_string(),
+            scanned_containers: 0,
+            total_containers,
+            asset_count: 0,
+            phase: ScanPhase::Scanning,
+            current_source: None,
+            elapsed_ms: 0,
+            containers_per_second: None,
+        },
+    );
+
+    if total_containers == 0 {
+        complete_scan_with_lifecycle(app, scan_id, ScanLifecycle::Completed, None)?;
+        return Ok(true);
+    }
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let follow_symlinks = scan_options.follow_symlinks;
+    let receiver = spawn_scan_pool(
+        Arc::new(
+            containers
+                .iter()
+                .map(|(container, _)| (container.clone(), None))
+                .collect(),
+        ),
+        scan_options,
+        worker_limits(app).scan_workers,
+        Arc::clone(&cancelled),
+        move |container| {
+            container_signature_for_path(
+                &container.container_path,
+                &container.container_type,
+                follow_symlinks,
+            )
+        },
+    );
+
+    let mut key_counts = HashMap::<String, usize>::new();
+    let mut scanned_containers = 0usize;
+
+    while scanned_containers < total_containers {
+        if is_scan_cancelled(app, scan_id)? {
+            cancelled.store(true, AtomicOrdering::Relaxed);
+            log::info!("scan {scan_id}: cancelled after {scanned_containers} containers");
+            complete_scan_with_lifecycle(app, scan_id, ScanLifecycle::Cancelled, None)?;
+            return Ok(false);
+        }
+
+        match receiver.recv_timeout(Duration::from_millis(100)) {
+            Ok((index, outcome)) => {
+                scanned_containers += 1;
+                let (container, instances) = &containers[index];
+                if let Err(error) = &outcome {
+                    log::warn!(
+                        "scan {scan_id}: failed to scan {}: {error}",
+                        container.container_path.display()
+                    );
+                }
+                let (warnings, asset_count, progress_due) = {
+                    let state = app.state::<AppState>();
+                    let mut scans = state
+                        .scans
+                        .lock()
+                        .map_err(|_| "Failed to lock scans state".to_string())?;
+                    let scan = scans
+                        .get_mut(scan_id)
+                        .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
+                    let previous_count = scan.assets.len();
+                    let warnings = scan.record_scanned_container(
+                        container,
+                        instances,
+                        outcome,
+                        &mut key_counts,
+                    );
+                    log::debug!(
+                        "scan {scan_id}: {} yielded {} assets",
+                        container.source_name,
+                        scan.assets.len() - previous_count
+                    );
+                    scan.scanned_containers = scanned_containers;
+                    scan.total_containers = total_containers;
+                    let force = scanned_containers >= total_containers || !warnings.is_empty();
+                    (warnings, scan.assets.len(), scan.progress_due(force))
+                };
+                for warning in warnings {
+                    emit_scan_warning(app, scan_id, warning);
+                }
+                if progress_due {
+                    emit_scan_progress(
+                        app,
+                        ScanProgressEvent {
+                            scan_id: scan_id.to_string(),
+                            scanned_containers,
+                            total_containers,
+                            asset_count,
+                            phase: ScanPhase::Scanning,
+                            current_source: Some(container.source_name.clone()),
+                            elapsed_ms: 0,
+                            containers_per_second: None,
+                        },
+                    );
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => continue,
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    if scanned_containers < total_containers && !is_scan_cancelled(app, scan_id)? {
+        return Err("Scan workers disconnected before processing all containers".to_string());
+    }
+
+    log::info!("scan {scan_id}: completed {scanned_containers} containers");
+    complete_scan_with_lifecycle(app, scan_id, ScanLifecycle::Completed, None)?;
+
+    Ok(true)
+}
+
+fn persist_scan_snapshot(
+    app: &AppHandle,
+    scan_id: &str,
+    req: &StartScanRequest,
+    cache_key: &str,
+) -> Result<(), String> {
+    let snapshot = {
+        let state = app.state::<AppState>();
+        let scans = state
+            .scans
+            .lock()
+            .map_err(|_| "Failed to lock scans state".to_string())?;
+        let scan = scans
+            .get(scan_id)
+            .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
+
+        ScanSnapshot {
+            schema_version: SCAN_CACHE_SCHEMA_VERSION,
+            cache_key: cache_key.to_string(),
+            prism_root: req.prism_root.clone(),
+            instance_folder: req.instance_folder.clone(),
+            include_vanilla: req.include_vanilla,
+            include_mods: req.include_mods,
+            include_resourcepacks: req.include_resourcepacks,
+            created_at: unix_timestamp_ms(),
+            last_used_at: unix_timestamp_ms(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            assets: scan.assets.clone(),
+            search_records: scan.search_records.clone(),
+            tree_children: scan.tree_children.clone(),
+            container_assets: scan.container_assets.clone(),
+            container_signatures: scan.container_signatures.clone(),
+        }
+    };
+
+    save_snapshot_to_cache(app, &snapshot)
+}
+
+fn build_scan_indexes(
+    assets: &[AssetRecord],
+) -> (
+    HashMap<String, usize>,
+    Vec<AssetSearchRecord>,
+    HashMap<String, Vec<TreeNode>>,
+    HashMap<String, usize>,
+) {
+    let mut asset_index = HashMap::<String, usize>::new();
+    let mut search_records = Vec::<AssetSearchRecord>::new();
+    let mut tree_children = HashMap::<String, Vec<TreeNode>>::new();
+    tree_children.insert(ROOT_NODE_ID.to_string(), Vec::new());
+    let mut tree_index = HashMap::<String, usize>::new();
+
+    for (index, asset) in assets.iter().enumerate() {
+        asset_index.insert(asset.asset_id.clone(), index);
+        search_records.push(build_search_record(asset));
+        add_asset_to_tree(&mut tree_children, &mut tree_index, asset);
+    }
+
+    (asset_index, search_records, tree_children, tree_index)
+}
+
+fn run_refresh_worker_inner(
+    app: &AppHandle,
+    scan_id: &str,
+    req: &StartScanRequest,
+    cache_key: &str,
+) -> Result<(), String> {
+    {
+        let state = app.state::<AppState>();
+        let mut scans = state
+            .scans
+            .lock()
+            .map_err(|_| "Failed to lock scans state".to_string())?;
+        let scan = scans
+            .get_mut(scan_id)
+            .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
+        if scan.cancelled {
+            scan.is_refreshing = false;
+            return Ok(());
+        }
+        scan.is_refreshing = true;
+        scan.status = ScanLifecycle::Completed;
+        scan.error = None;
+        scan.warnings.clear();
+        scan.cache_key = Some(cache_key.to_string());
+    }
+
+    let layout = resolve_instance_layout(req)?;
+    let containers = collect_scan_containers(&layout, req)?;
+    let instance = requested_scan_instance(req, &layout);
+
+    let (cached_container_assets, cached_signatures, previous_assets) = {
+        let state = app.state::<AppState>();
+        let mut scans = state
+            .scans
+            .lock()
+            .map_err(|_| "Failed to lock scans state".to_string())?;
+        let scan = scans
+            .get_mut(scan_id)
+            .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
+        scan.containers.clone_from(&containers);
+        (
+            scan.container_assets.clone(),
+            scan.container_signatures.clone(),
+            scan.assets.clone(),
+        )
+    };
+
+    let plan = build_scan_refresh_plan(
+        &cached_signatures,
+        &containers,
+        req.follow_symlinks.unwrap_or(false),
+    )?;
+    let mut containers_by_key = HashMap::<String, ScanContainer>::new();
+    for container in &containers {
+        containers_by_key.insert(scan_container_key(container), container.clone());
+    }
+
+    // `None` rescans the whole container; `Some(entries)` re-reads only those files.
+    let mut unchanged_keys = Vec::new();
+    let mut changed_containers = plan
+        .changed_or_new
+        .into_iter()
+        .map(|container| (container, None))
+        .collect::<Vec<(ScanContainer, Option<Vec<String>>)>>();
+    for key in plan.unchanged_keys {
+        if cached_container_assets.contains_key(&key) {
+            unchanged_keys.push(key);
+        } else if let Some(container) = containers_by_key.get(&key) {
+            changed_containers.push((container.clone(), None));
+        }
+    }
+
+    let mut merged_container_assets = HashMap::<String, Vec<AssetRecord>>::new();
+    let mut unchanged_assets = Vec::<AssetRecord>::new();
+    for key in &unchanged_keys {
+        if let Some(assets) = cached_container_assets.get(key) {
+            unchanged_assets.extend(assets.clone());
+            merged_container_assets.insert(key.clone(), assets.clone());
+        }
+    }
+
+    let mut kept_partial_assets = HashMap::<String, Vec<AssetRecord>>::new();
+    for refresh in plan.partial {
+        let key = scan_container_key(&refresh.container);
+        let Some(cached_assets) = cached_container_assets.get(&key) else {
+            changed_containers.push((refresh.container, None));
+            continue;
+        };
+        let stale = refresh
+            .changed_entries
+            .iter()
+            .chain(&refresh.removed_entries)
+            .map(String::as_str)
+            .collect::<HashSet<_>>();
+        let kept = cached_assets
+            .iter()
+            .filter(|asset| !stale.contains(asset.entry_path.as_str()))
+            .cloned()
+            .collect::<Vec<_>>();
+        log::debug!(
+            "scan {scan_id}: {} has {} changed and {} removed entries",
+            refresh.container.source_name,
+            refresh.changed_entries.len(),
+            refresh.removed_entries.len()
+        );
+        unchanged_assets.extend(kept.iter().cloned());
+        kept_partial_assets.insert(key, kept);
+        changed_containers.push((refresh.container, Some(refresh.changed_entries)));
+    }
+    changed_containers
+        .sort_by(|left, right| scan_container_key(&left.0).cmp(&scan_container_key(&right.0)));
+
+    let changed_total = changed_containers.len();
+    let mut changed_scanned = 0usize;
+    let mut changed_asset_count = 0usize;
+    let mut key_counts = rebuild_key_counts_from_assets(&unchanged_assets);
+
+    if changed_total > 0 {
+        emit_scan_progress(
+            app,
+            ScanProgressEvent {
+                scan_id: scan_id.to_string(),
+                scanned_containers: 0,
+                total_containers: changed_total,
+                asset_count: unchanged_assets.len(),
+                phase: ScanPhase::Refreshing,
+                current_source: None,
+                elapsed_ms: 0,
+                containers_per_second: None,
+            },
+        );
+    }
+
+    if changed_total > 0 {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let changed_containers = Arc::new(changed_containers);
+        let receiver = spawn_scan_pool(
+            Arc::clone(&changed_containers),
+            ScanOptions::from_request(req),
+            worker_limits(app).scan_workers,
+            Arc::clone(&cancelled),
+            |_| Ok(()),
+        );
+
+        while changed_scanned < changed_total {
+            if is_scan_cancelled(app, scan_id)? {
+                cancelled.store(true, AtomicOrdering::Relaxed);
+                let state = app.state::<AppState>();
+                if let Ok(mut scans) = state.scans.lock() {
+                    if let Some(scan) = scans.get_mut(scan_id) {
+                        scan.is_refreshing = false;
+                    }
+                }
+                return Ok(());
+            }
+
+            match receiver.recv_timeout(Duration::from_millis(100)) {
+                Ok((index, Ok(((), candidates, warnings)))) => {
+                    changed_scanned += 1;
+                    let container = &changed_containers[index].0;
+                    let container_key = scan_container_key(container);
+                    let source_name = container.source_name.clone();
+                    for error in warnings {
+                        let warning = ScanWarning {
+                            container_key: container_key.clone(),
+                            source_name: source_name.clone(),
+                            error,
+                        };
+                        record_scan_warning(app, scan_id, warning)?;
+                    }
+                    let mut assets = kept_partial_assets
+                        .remove(&container_key)
+                        .unwrap_or_default();
+                    let kept_count = assets.len();
+                    assets.extend(finalize_instance_assets(
+                        candidates,
+                        instance.as_ref(),
+                        &mut key_counts,
+                    ));
+                    log::debug!(
+                        "scan {scan_id}: {source_name} yielded {} assets on refresh",
+                        assets.len()
+                    );
+                    changed_asset_count =
+                        changed_asset_count.saturating_add(assets.len() - kept_count);
+                    merged_container_assets.insert(container_key, assets);
+                    emit_scan_progress(
+                        app,
+                        ScanProgressEvent {
+                            scan_id: scan_id.to_string(),
+                            scanned_containers: changed_scanned,
+                            total_containers: changed_total,
+                            asset_count: unchanged_assets.len().saturating_add(changed_asset_count),
+                            phase: ScanPhase::Refreshing,
+                            current_source: Some(source_name),
+                            elapsed_ms: 0,
+                            containers_per_second: None,
+                        },
+                    );
+                }
+                // The container is left out of the merged signatures, so the
+                // next refresh tries it again.
+                Ok((index, Err(error))) => {
+                    changed_scanned += 1;
+                    let container = &changed_containers[index].0;
+                    log::warn!(
+                        "scan {scan_id}: failed to rescan {}: {error}",
+                        container.container_path.display()
+                    );
+                    let source_name = container.source_name.clone();
+                    let warning = ScanWarning {
+                        container_key: scan_container_key(container),
+                        source_name: source_name.clone(),
+                        error,
+                    };
+                    record_scan_warning(app, scan_id, warning)?;
+                    emit_scan_progress(
+                        app,
+                        ScanProgressEvent {
+                            scan_id: scan_id.to_string(),
+                            scanned_containers: changed_scanned,
+                            total_containers: changed_total,
+                            asset_count: unchanged_assets.len().saturating_add(changed_asset_count),
+                            phase: ScanPhase::Refreshing,
+                            current_source: Some(source_name),
+                            elapsed_ms: 0,
+                            containers_per_second: None,
+                        },
+                    );
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => continue,
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    }
+
+    let mut merged_signatures = HashMap::<String, ContainerSignature>::new();
+    for (container_key, signature) in plan.signatures_by_key {
+        if merged_container_assets.contains_key(&container_key) {
+            merged_signatures.insert(container_key, signature);
+        }
+    }
+    for removed in plan.removed_keys {
+        merged_container_assets.remove(&removed);
+        merged_signatures.remove(&removed);
+    }
+
+    let mut container_keys = merged_container_assets.keys().cloned().collect::<Vec<_>>();
+    container_keys.sort();
+    let mut next_assets = Vec::<AssetRecord>::new();
+    for container_key in container_keys {
+        if let Some(assets) = merged_container_assets.get(&container_key) {
+            next_assets.extend(assets.clone());
+        }
+    }
+
+    let (asset_index, search_records, tree_children, tree_index) = build_scan_indexes(&next_assets);
+    let id_aliases = build_asset_reconciliation_map(&previous_assets, &next_assets);
+    let empty_sources = collect_empty_sources(&merged_container_assets, &containers_by_key);
+    let total_containers = merged_signatures.len();
+    let asset_count = next_assets.len();
+    log::info!(
+        "scan {scan_id}: refreshed {changed_total} changed containers, {asset_count} assets total"
+    );
+
+    {
+        let state = app.state::<AppState>();
+        let mut scans = state
+            .scans
+            .lock()
+            .map_err(|_| "Failed to lock scans state".to_string())?;
+        let scan = scans
+            .get_mut(scan_id)
+            .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
+        if scan.cancelled {
+            scan.is_refreshing = false;
+            return Ok(());
+        }
+        scan.status = ScanLifecycle::Completed;
+        scan.is_refreshing = false;
+        scan.error = None;
+        scan.scanned_containers = total_containers;
+        scan.total_containers = total_containers;
+        scan.assets = next_assets;
+        scan.asset_index = asset_index;
+        scan.search_records = search_records;
+        scan.tree_children = tree_children;
+        scan.tree_index = tree_index;
+        scan.container_assets = merged_container_assets;
+        scan.container_signatures = merged_signatures;
+        scan.id_aliases = id_aliases;
+        scan.empty_sources = empty_sources;
+        scan.invalidate_derived_indexes();
+        scan.cache_key = Some(cache_key.to_string());
+    }
+
+    let _ = app.emit(
+        "scan://completed",
+        ScanCompletedEvent {
+            scan_id: scan_id.to_string(),
+            lifecycle: ScanLifecycle::Completed,
+            asset_count,
+            error: None,
+        },
+    );
+    persist_scan_snapshot(app, scan_id, req, cache_key)?;
+    Ok(())
+}
+
+fn collect_empty_sources(
+    container_assets: &HashMap<String, Vec<AssetRecord>>,
+    containers_by_key: &HashMap<String, ScanContainer>,
+) -> Vec<EmptySource> {
+    container_assets
+        .iter()
+        .filter(|(_, assets)| assets.is_empty())
+        .filter_map(|(container_key, _)| {
+            let container = containers_by_key.get(container_key)?;
+            Some(EmptySource {
+                container_key: container_key.clone(),
+                source_name: container.source_name.clone(),
+            })
+        })
+        .collect()
+}
+
+fn rebuild_key_counts_from_assets(assets: &[AssetRecord]) -> HashMap<String, usize> {
+    let mut counts = HashMap::<String, usize>::new();
+
+    for asset in assets {
+        let (base_key, suffix) = parse_dup_suffix(&asset.key);
+        let required = suffix.map(|index| index + 1).unwrap_or(1);
+        let current = counts.entry(base_key).or_insert(0);
+        *current = (*current).max(required);
+    }
+
+    counts
+}
+
+fn parse_dup_suffix(key: &str) -> (String, Option<usize>) {
+    if let Some((base, suffix)) = key.rsplit_once(".dup") {
+        if !base.is_empty() && !suffix.is_empty() && suffix.chars().all(|ch| ch.is_ascii_digit()) {
+            if let Ok(index) = suffix.parse::<usize>() {
+                return (base.to_string(), Some(index));
+            }
+        }
+    }
+
+    (key.to_string(), None)
+}
+
+/// Stores `warning` on the scan and emits it on `scan://warning`. Returns the
+/// scan's current asset count for the caller's progress event.
+fn record_scan_warning(
+    app: &AppHandle,
+    scan_id: &str,
+    warning: ScanWarning,
+) -> Result<usize, String> {
+    let asset_count = {
+        let state = app.state::<AppState>();
+        let mut scans = state
+            .scans
+            .lock()
+            .map_err(|_| "Failed to lock scans state".to_string())?;
+        let scan = scans
+            .get_mut(scan_id)
+            .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
+        scan.warnings.push(warning.clone());
+        scan.assets.len()
+    };
+
+    emit_scan_warning(app, scan_id, warning);
+    Ok(asset_count)
+}
+
+fn emit_scan_warning(app: &AppHandle, scan_id: &str, warning: ScanWarning) {
+    let _ = app.emit(
+        "scan://warning",
+        ScanWarningEvent {
+            scan_id: scan_id.to_string(),
+            warning,
+        },
+    );
+}
+
+fn update_scan_error(app: &AppHandle, scan_id: &str, error: &str) {
+    let state = app.state::<AppState>();
+    let lock_result = state.scans.lock();
+    if let Ok(mut scans) = lock_result {
+        if let Some(scan) = scans.get_mut(scan_id) {
+            scan.status = ScanLifecycle::Error;
+            scan.is_refreshing = false;
+            scan.error = Some(error.to_string());
+        }
+    }
+}
+
+fn complete_scan_with_lifecycle(
+    app: &AppHandle,
+    scan_id: &str,
+    lifecycle: ScanLifecycle,
+    error: Option<String>,
+) -> Result<(), String> {
+    let asset_count;
+
+    {
+        let state = app.state::<AppState>();
+        let mut scans = state
+            .scans
+            .lock()
+            .map_err(|_| "Failed to lock scans state".to_string())?;
+
+        let scan = scans
+            .get_mut(scan_id)
+            .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
+
+        scan.status = lifecycle.clone();
+        scan.is_refreshing = false;
+        scan.error = error.clone();
+        scan.scanned_containers = scan.total_containers;
+        asset_count = scan.assets.len();
+    }
+
+    let _ = app.emit(
+        "scan://completed",
+        ScanCompletedEvent {
+            scan_id: scan_id.to_string(),
+            lifecycle,
+            asset_count,
+            error,
+        },
+    );
+
+    Ok(())
+}
+
+fn is_scan_cancelled(app: &AppHandle, scan_id: &str) -> Result<bool, String> {
+    let state = app.state::<AppState>();
+    let scans = state
+        .scans
+        .lock()
+        .map_err(|_| "Failed to lock scans state".to_string())?;
+
+    let scan = scans
+        .get(scan_id)
+        .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
+
+    Ok(scan.cancelled)
+}
+
+fn resolve_instance_layout(req: &StartScanRequest) -> Result<InstanceLayout, String> {
+    let launcher_kind = req.launcher_kind.unwrap_or_default();
+    let launcher_root = expand_home(&req.prism_root);
+    validate_launcher_root(&launcher_root, launcher_kind)?;
+    let instance_dir = resolve_instance_dir(
+        &launcher_root,
+        launcher_kind.instances_dir_name(),
+        &req.instance_folder,
+    )?;
+    let version_override = vanilla_version_override(req);
+    if let Some(version) = version_override {
+        // The override becomes a path component under versions/ and libraries/.
+        if version.contains(['/', '\\']) || version.contains("..") {
+            return Err(format!("Invalid Minecraft version override: {version}"));
+        }
+    }
+    if version_override.is_some() && matches!(launcher_kind, LauncherKind::Technic) {
+        return Err(
+            "Technic packs bundle their own client jar; version overrides aren't supported"
+                .to_string(),
+        );
+    }
+    let mc_version = match version_override {
+        Some(version) => version.to_string(),
+        None => launcher_instance_version(&instance_dir, launcher_kind).ok_or_else(|| {
+            format!(
+                "Failed to resolve Minecraft version for {} instance {}",
+                launcher_kind.label(),
+                instance_dir.display()
+            )
+        })?,
+    };
+
+    let layout = match launcher_kind {
+        LauncherKind::Prism | LauncherKind::MultiMc => InstanceLayout {
+            game_dir: instance_game_dir(&instance_dir),
+            client_jar: Some(
+                launcher_root
+                    .join("libraries")
+                    .join("com")
+                    .join("mojang")
+                    .join("minecraft")
+                    .join(&mc_version)
+                    .join(format!("minecraft-{mc_version}-client.jar")),
+            ),
+            asset_index_path: resolve_vanilla_asset_index_path(&launcher_root, &mc_version),
+            modpack_jar: None,
+            instance_dir,
+            mc_version,
+        },
+        // Technic packs are self-contained: the client jar, Forge's modpack.jar and
+        // version.json live in bin/, with the shared asset store at the launcher root.
+        LauncherKind::Technic => {
+            let bin_dir = instance_dir.join("bin");
+            InstanceLayout {
+                game_dir: instance_dir.clone(),
+                client_jar: Some(bin_dir.join("minecraft.jar")),
+                asset_index_path: read_minecraft_meta_version(&bin_dir.join("version.json"))
+                    .and_then(MinecraftMetaVersion::asset_index_id)
+                    .and_then(|index_id| shared_asset_index_path(&launcher_root, &index_id)),
+                modpack_jar: Some(bin_dir.join("modpack.jar")),
+                instance_dir,
+                mc_version,
+            }
+        }
+        // The instance's bin/minecraft.jar is only a fallback for its own version;
+        // an override must come from the shared versions/ store.
+        LauncherKind::AtLauncher => InstanceLayout {
+            game_dir: instance_dir.clone(),
+            client_jar: {
+                let versions_jar = launcher_root
+                    .join("versions")
+                    .join(&mc_version)
+                    .join(format!("{mc_version}.jar"));
+                if version_override.is_some() || versions_jar.is_file() {
+                    Some(versions_jar)
+                } else {
+                    Some(instance_dir.join("bin").join("minecraft.jar"))
+                        .filter(|path| path.is_file())
+                }
+            },
+            asset_index_path: if version_override.is_some() {
+                read_minecraft_meta_version(&vanilla_profile_path(
+                    &launcher_root.join("versions").join(&mc_version),
+                ))
+                .and_then(MinecraftMetaVersion::asset_index_id)
+            } else {
+                read_at_launcher_instance(&instance_dir)
+                    .and_then(|instance| instance.version.asset_index_id())
+            }
+            .and_then(|index_id| shared_asset_index_path(&launcher_root, &index_id)),
+            modpack_jar: None,
+            instance_dir,
+            mc_version,
+        },
+        // Mods and packs are shared across profiles in the .minecraft root; loader
+        // profiles inherit their client jar and asset index from the base version.
+        LauncherKind::Vanilla => {
+            let base_dir = launcher_root.join("versions").join(&mc_version);
+            InstanceLayout {
+                game_dir: launcher_root.clone(),
+                client_jar: Some(base_dir.join(format!("{mc_version}.jar"))),
+                asset_index_path: read_minecraft_meta_version(&vanilla_profile_path(&base_dir))
+                    .and_then(MinecraftMetaVersion::asset_index_id)
+                    .and_then(|index_id| shared_asset_index_path(&launcher_root, &index_id)),
+                modpack_jar: None,
+                instance_dir,
+                mc_version,
+            }
+        }
+    };
+
+    if version_override.is_some() && req.include_vanilla {
+        match &layout.client_jar {
+            Some(client_jar) if client_jar.is_file() => {}
+            Some(client_jar) => {
+                return Err(format!(
+                    "Minecraft {} client jar not found at {}; launch that version once so the launcher downloads it",
+                    layout.mc_version,
+                    client_jar.display()
+                ))
+            }
+            None => {
+                return Err(format!(
+                    "Minecraft {} client jar not found; launch that version once so the launcher downloads it",
+                    layout.mc_version
+                ))
+            }
+        }
+    }
+
+    Ok(layout)
+}
+
+fn vanilla_version_override(req: &StartScanRequest) -> Option<&str> {
+    req.vanilla_version_override
+        .as_deref()
+        .map(str::trim)
+        .filter(|version| !version.is_empty())
+}
+
+/// Splits `mod.jar.disabled` into `("mod.jar", true)`; other names pass through.
+fn strip_disabled_suffix(file_name: &str) -> (&str, bool) {
+    let suffix_start = file_name.len().saturating_sub(".disabled".len());
+    match file_name.get(suffix_start..) {
+        Some(suffix) if suffix.eq_ignore_ascii_case(".disabled") && suffix_start > 0 => {
+            (&file_name[..suffix_start], true)
+        }
+        _ => (file_name, false),
+    }
+}
+
+fn is_disabled_container_path(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| strip_disabled_suffix(&name.to_string_lossy()).1)
+}
+
+fn collect_scan_containers(
+    layout: &InstanceLayout,
+    req: &StartScanRequest,
+) -> Result<Vec<ScanContainer>, String> {
+    let mut containers = Vec::new();
+    let minecraft_dir = &layout.game_dir;
+    let mc_version = &layout.mc_version;
+    let include_disabled = req.include_disabled_mods.unwrap_or(false);
+
+    if req.include_mods {
+        let mods_dir = minecraft_dir.join("mods");
+        if mods_dir.is_dir() {
+            let entries = fs::read_dir(&mods_dir)
+                .map_err(|error| format!("Failed to read mods directory: {error}"))?;
+
+            for entry in entries {
+                let entry = match entry {
+                    Ok(value) => value,
+                    Err(_) => continue,
+                };
+
+                let path = entry.path();
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                let (file_name, disabled) = strip_disabled_suffix(&file_name);
+                if disabled && !include_disabled {
+                    continue;
+                }
+                let extension = Path::new(file_name)
+                    .extension()
+                    .map(|value| value.to_string_lossy().to_ascii_lowercase())
+                    .unwrap_or_default();
+
+                // Some mods ship as `.zip`; archives without an `assets/` tree
+                // (such as backups) simply yield no candidates.
+                let container_type = match extension.as_str() {
+                    "jar" => AssetContainerType::Jar,
+                    "zip" => AssetContainerType::Zip,
+                    _ => continue,
+                };
+                if !path.is_file() {
+                    continue;
+                }
+                let source_name = Path::new(file_name)
+                    .file_stem()
+                    .map(|value| value.to_string_lossy().to_string())
+                    .unwrap_or_else(|| "unknown-mod".to_string());
+
+                containers.push(ScanContainer {
+                    source_type: AssetSourceType::Mod,
+                    source_name,
+                    container_type,
+                    container_path: path,
+                });
+            }
+        }
+    }
+
+    if req.include_resourcepacks {
+        let resourcepacks_dir = minecraft_dir.join("resourcepacks");
+        if resourcepacks_dir.is_dir() {
+            let entries = fs::read_dir(&resourcepacks_dir)
+                .map_err(|error| format!("Failed to read resourcepacks directory: {error}"))?;
+
+            for entry in entries {
+                let entry = match entry {
+                    Ok(value) => value,
+                    Err(_) => continue,
+                };
+
+                let path = entry.path();
+                if path.is_dir() {
+                    let source_name = path
+                        .file_name()
+                        .map(|value| value.to_string_lossy().to_string())
+                        .unwrap_or_else(|| "resourcepack".to_string());
+
+                    containers.push(ScanContainer {
+                        source_type: AssetSourceType::ResourcePack,
+                        source_name,
+                        container_type: AssetContainerType::Directory,
+                        container_path: path,
+                    });
+                    continue;
+                }
+
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                let (file_name, disabled) = strip_disabled_suffix(&file_name);
+                let is_zip = Path::new(file_name)
+                    .extension()
+                    .is_some_and(|value| value.eq_ignore_ascii_case("zip"));
+                if is_zip && (include_disabled || !disabled) {
+                    let source_name = Path::new(file_name)
+                        .file_stem()
+                        .map(|value| value.to_string_lossy().to_string())
+                        .unwrap_or_else(|| "resourcepack".to_string());
+
+                    containers.push(ScanContainer {
+                        source_type: AssetSourceType::ResourcePack,
+                        source_name,
+                        container_type: AssetContainerType::Zip,
+                        container_path: path,
+                    });
+                }
+            }
+        }
+    }
+
+    if req.include_server_packs.unwrap_or(false) {
+        containers.extend(collect_server_resource_pack_containers(minecraft_dir));
+    }
+
+    if req.include_datapacks.unwrap_or(false) {
+        containers.extend(collect_datapack_containers(minecraft_dir));
+    }
+
+    if req.include_shaderpacks.unwrap_or(false) {
+        containers.extend(collect_shaderpack_containers(minecraft_dir));
+    }
+
+    if req.include_mods {
+        if let Some(modpack_jar) = layout.modpack_jar.as_ref().filter(|path| path.is_file()) {
+            containers.push(ScanContainer {
+                source_type: AssetSourceType::Mod,
+                source_name: "modpack".to_string(),
+                container_type: AssetContainerType::Jar,
+                container_path: modpack_jar.clone(),
+            });
+        }
+    }
+
+    if req.include_vanilla {
+        if let Some(client_jar) = layout.client_jar.as_ref().filter(|path| path.is_file()) {
+            containers.push(ScanContainer {
+                source_type: AssetSourceType::Vanilla,
+                source_name: format!("minecraft-{mc_version}"),
+                container_type: AssetContainerType::Jar,
+                container_path: client_jar.clone(),
+            });
+        }
+
+        if let Some(asset_index_path) = &layout.asset_index_path {
+            containers.push(ScanContainer {
+                source_type: AssetSourceType::Vanilla,
+                source_name: format!("minecraft-{mc_version}"),
+                container_type: AssetContainerType::AssetIndex,
+                container_path: asset_index_path.clone(),
+            });
+        }
+    }
+
+    containers.sort_by(|left, right| scan_container_key(left).cmp(&scan_container_key(right)));
+    Ok(containers)
+}
+
+/// Datapacks live in the game folder's `datapacks` (used by some modpack loaders)
+/// and in each world's `saves/<world>/datapacks`; world packs are suffixed with the
+/// world name so identically named packs in different saves stay distinct.
+fn collect_datapack_containers(minecraft_dir: &Path) -> Vec<ScanContainer> {
+    let mut pack_dirs = vec![(minecraft_dir.join("datapacks"), None)];
+    if let Ok(saves) = fs::read_dir(minecraft_dir.join("saves")) {
+        for save in saves.filter_map(Result::ok) {
+            let world = save.file_name().to_string_lossy().to_string();
+            pack_dirs.push((save.path().join("datapacks"), Some(world)));
+        }
+    }
+
+    let mut containers = Vec::new();
+    for (pack_dir, world) in pack_dirs {
+        let Ok(entries) = fs::read_dir(&pack_dir) else {
+            continue;
+        };
+
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            let container_type = if path.is_dir() {
+                AssetContainerType::Directory
+            } else if path
+                .extension()
+                .map(|value| value.to_string_lossy().eq_ignore_ascii_case("zip"))
+                .unwrap_or(false)
+            {
+                AssetContainerType::Zip
+            } else {
+                continue;
+            };
+
+            let pack_name = match container_type {
+                AssetContainerType::Directory => path.file_name(),
+                _ => path.file_stem(),
+            }
+            .map(|value| value.to_string_lossy().to_string())
+            .unwrap_or_else(|| "datapack".to_string());
+
+            containers.push(ScanContainer {
+                source_type: AssetSourceType::Datapack,
+                source_name: match &world {
+                    Some(world) => format!("{pack_name} ({world})"),
+                    None => pack_name,
+                },
+                container_type,
+                container_path: path,
+            });
+        }
+    }
+
+    containers
+}
+
+/// Iris and OptiFine read shader packs from `shaderpacks/` as folders or zips.
+fn collect_shaderpack_containers(minecraft_dir: &Path) -> Vec<ScanContainer> {
+    let Ok(entries) = fs::read_dir(minecraft_dir.join("shaderpacks")) else {
+        return Vec::new();
+    };
+
+    let mut containers = Vec::new();
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        let (container_type, source_name) = if path.is_dir() {
+            (AssetContainerType::Directory, path.file_name())
+        } else if path
+            .extension()
+            .map(|value| value.to_string_lossy().eq_ignore_ascii_case("zip"))
+            .unwrap_or(false)
+        {
+            (AssetContainerType::Zip, path.file_stem())
+        } else {
+            continue;
+        };
+
+        containers.push(ScanContainer {
+            source_type: AssetSourceType::ShaderPack,
+            source_name: source_name
+                .map(|value| value.to_string_lossy().to_string())
+                .unwrap_or_else(|| "shaderpack".to_string()),
+            container_type,
+            container_path: path,
+        });
+    }
+
+    containers
+}
+
+/// Server-pushed packs are cached as hash-named zips (usually without an extension),
+/// so entries are recognized by their zip signature rather than by file name.
+fn collect_server_resource_pack_containers(minecraft_dir: &Path) -> Vec<ScanContainer> {
+    let mut containers = Vec::new();
+
+    for folder in ["server-resource-packs", "downloadedPacks"] {
+        let cache_dir = minecraft_dir.join(folder);
+        if !cache_dir.is_dir() {
+            continue;
+        }
+
+        for entry in WalkDir::new(&cache_dir)
+            .follow_links(false)
+            .max_depth(2)
+            .into_iter()
+            .filter_map(Result::ok)
+        {
+            if !entry.file_type().is_file() || !has_zip_signature(entry.path()) {
+                continue;
+            }
+
+            let source_name = entry
+                .path()
+                .file_stem()
+                .map(|value| value.to_string_lossy().to_string())
+                .unwrap_or_else(|| "server-pack".to_string());
+
+            containers.push(ScanContainer {
+                source_type: AssetSourceType::ResourcePack,
+                source_name: format!("server-{source_name}"),
+                container_type: AssetContainerType::Zip,
+                container_path: entry.into_path(),
+            });
+        }
+    }
+
+    containers
+}
+
+fn has_zip_signature(path: &Path) -> bool {
+    let mut signature = [0u8; 4];
+    fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut signature))
+        .map(|_| signature == *b"PK\x03\x04")
+        .unwrap_or(false)
+}
+
+/// A container for the scan pool, with the entries to re-read when only part of
+/// it changed since the last scan.
+type PoolContainer = (ScanContainer, Option<Vec<String>>);
+
+/// What the pool produced for one container: the value `prepare` returned, its
+/// candidates, and problems it was scanned despite. `Err` means nothing could
+/// be read at all.
+type PooledScan<S> = Result<(S, Vec<AssetCandidate>, Vec<String>), String>;
+
+enum ScanJob {
+    Container(usize),
+    /// One subtree of a split directory container, by container index, with the
+    /// directories the split entered.
+    Subtree(usize, PathBuf, Arc<VisitedDirectories>),
+}
+
+/// A split directory container whose subtree jobs are still running.
+struct PendingDirectory<S> {
+    remaining: usize,
+    prepared: S,
+    candidates: Vec<AssetCandidate>,
+    error: Option<String>,
+}
+
+struct ScanQueue<S> {
+    jobs: VecDeque<ScanJob>,
+    /// Container jobs being worked on; each may still queue subtrees, so idle
+    /// workers wait for them rather than exiting.
+    running_containers: usize,
+    pending: HashMap<usize, PendingDirectory<S>>,
+}
+
+/// Worker threads sharing one job queue. Directory containers are split into
+/// subtrees that go back onto the queue, so a huge folder is spread over the
+/// same bounded set of threads instead of spawning its own.
+struct ScanPool<S, P> {
+    containers: Arc<Vec<PoolContainer>>,
+    options: ScanOptions,
+    cancelled: Arc<AtomicBool>,
+    prepare: P,
+    queue: Mutex<ScanQueue<S>>,
+    job_queued: Condvar,
+}
+
+/// Scans `containers` on up to `max_workers` threads, sending each container's
+/// index and outcome once its last job is done. `prepare` runs before a
+/// container is read (the scan takes its signature there); setting `cancelled`
+/// stops the workers between entries.
+fn spawn_scan_pool<S, P>(
+    containers: Arc<Vec<PoolContainer>>,
+    options: ScanOptions,
+    max_workers: usize,
+    cancelled: Arc<AtomicBool>,
+    prepare: P,
+) -> mpsc::Receiver<(usize, PooledScan<S>)>
+where
+    S: Send + 'static,
+    P: Fn(&ScanContainer) -> Result<S, String> + Send + Sync + 'static,
+{
+    let workers = thread::available_parallelism()
+        .map(|value| value.get().saturating_sub(2))
+        .unwrap_or(1)
+        .clamp(1, max_workers.max(1));
+    let pool = Arc::new(ScanPool {
+        queue: Mutex::new(ScanQueue {
+            jobs: (0..containers.len()).map(ScanJob::Container).collect(),
+            running_containers: 0,
+            pending: HashMap::new(),
+        }),
+        containers,
+        options,
+        cancelled,
+        prepare,
+        job_queued: Condvar::new(),
+    });
+
+    let (sender, receiver) = mpsc::channel();
+    for _ in 0..workers {
+        let pool = Arc::clone(&pool);
+        let sender = sender.clone();
+        thread::spawn(move || pool.run_worker(&sender));
+    }
+    receiver
+}
+
+impl<S, P> ScanPool<S, P>
+where
+    P: Fn(&ScanContainer) -> Result<S, String>,
+{
+    fn run_worker(&self, sender: &mpsc::Sender<(usize, PooledScan<S>)>) {
+        let should_cancel = || self.cancelled.load(AtomicOrdering::Relaxed);
+        while let Some(job) = self.next_job() {
+            let finished = match job {
+                ScanJob::Container(index) => {
+                    let _running = RunningContainer(self);
+                    self.scan_container_job(index, &should_cancel)
+                        .map(|outcome| (index, outcome))
+                }
+                ScanJob::Subtree(index, subtree, visited) => {
+                    let (container, _) = &self.containers[index];
+                    let found = walk_directory_subtree(
+                        container,
+                        self.options,
+                        &subtree,
+                        &visited,
+                        &should_cancel,
+                    );
+                    self.finish_subtree(index, found)
+                        .map(|outcome| (index, outcome))
+                }
+            };
+            if let Some(finished) = finished {
+                if sender.send(finished).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    fn next_job(&self) -> Option<ScanJob> {
+        let mut queue = self.queue.lock().ok()?;
+        loop {
+            if self.cancelled.load(AtomicOrdering::Relaxed) {
+                return None;
+            }
+            if let Some(job) = queue.jobs.pop_front() {
+                if matches!(job, ScanJob::Container(_)) {
+                    queue.running_containers += 1;
+                }
+                return Some(job);
+            }
+            if queue.running_containers == 0 {
+                return None;
+            }
+            queue = self
+                .job_queued
+                .wait_timeout(queue, Duration::from_millis(50))
+                .ok()?
+                .0;
+        }
+    }
+
+    /// Returns `None` when the container was split; its outcome then comes
+    /// from whichever worker finishes the last subtree.
+    fn scan_container_job(
+        &self,
+        index: usize,
+        should_cancel: &dyn Fn() -> bool,
+    ) -> Option<PooledScan<S>> {
+        let (container, entries) = &self.containers[index];
+        let prepared = match (self.prepare)(container) {
+            Ok(prepared) => prepared,
+            Err(error) => return Some(Err(error)),
+        };
+        let mut warnings = Vec::new();
+        let scanned = match (entries, &container.container_type) {
+            (Some(entries), _) => {
+                scan_directory_entries(container, self.options, entries, should_cancel)
+            }
+            (None, AssetContainerType::Directory) => {
+                let mut visited = VisitedDirectories::default();
+                let (loose_files, subtrees) = split_directory_subtrees(
+                    &container.container_path,
+                    DIRECTORY_SPLIT_DEPTH,
+                    self.options.follow_symlinks,
+                    &mut visited,
+                );
+                let visited = Arc::new(visited);
+                let mut candidates =
+                    directory_file_candidates(container, self.options, &loose_files);
+                if !subtrees.is_empty() {
+                    let mut queue = self.queue.lock().ok()?;
+                    queue.pending.insert(
+                        index,
+                        PendingDirectory {
+                            remaining: subtrees.len(),
+                            prepared,
+                            candidates,
+                            error: None,
+                        },
+                    );
+                    // Subtrees go first so started containers finish before new ones begin.
+                    for subtree in subtrees.into_iter().rev() {
+                        queue.jobs.push_front(ScanJob::Subtree(
+                            index,
+                            subtree,
+                            Arc::clone(&visited),
+                        ));
+                    }
+                    drop(queue);
+                    self.job_queued.notify_all();
+                    return None;
+                }
+                candidates.sort_by(|left, right| left.entry_path.cmp(&right.entry_path));
+                drop_symlink_aliases(container, self.options, &mut candidates);
+                Ok(candidates)
+            }
+            (None, _) => scan_container(container, self.options, should_cancel, &mut warnings),
+        };
+        Some(scanned.map(|candidates| (prepared, candidates, warnings)))
+    }
+
+    fn finish_subtree(
+        &self,
+        index: usize,
+        found: Result<Vec<AssetCandidate>, String>,
+    ) -> Option<PooledScan<S>> {
+        let mut queue = self.queue.lock().ok()?;
+        let Entry::Occupied(mut entry) = queue.pending.entry(index) else {
+            return None;
+        };
+        let pending = entry.get_mut();
+        pending.remaining -= 1;
+        match found {
+            Ok(found) => pending.candidates.extend(found),
+            Err(error) => {
+                pending.error.get_or_insert(error);
+            }
+        }
+        if pending.remaining > 0 {
+            return None;
+        }
+        let pending = entry.remove();
+        drop(queue);
+
+        if let Some(error) = pending.error {
+            return Some(Err(error));
+        }
+        let mut candidates = pending.candidates;
+        candidates.sort_by(|left, right| left.entry_path.cmp(&right.entry_path));
+        drop_symlink_aliases(&self.containers[index].0, self.options, &mut candidates);
+        Some(Ok((pending.prepared, candidates, Vec::new())))
+    }
+}
+
+/// Marks a container job done even if scanning it panicked, so idle workers
+/// stop waiting for subtrees it will never queue.
+struct RunningContainer<'a, S, P>(&'a ScanPool<S, P>);
+
+impl<S, P> Drop for RunningContainer<'_, S, P> {
+    fn drop(&mut self) {
+        if let Ok(mut queue) = self.0.queue.lock() {
+            queue.running_containers -= 1;
+        }
+        self.0.job_queued.notify_all();
+    }
+}
+
+/// Non-fatal problems, such as an archive that was only partly recovered, are
+/// pushed to `warnings`; an `Err` means nothing could be read at all.
+fn scan_container(
+    container: &ScanContainer,
+    options: ScanOptions,
+    should_cancel: &dyn Fn() -> bool,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<AssetCandidate>, String> {
+    match container.container_type {
+        AssetContainerType::Directory => {
+            scan_directory_container(container, options, should_cancel)
+        }
+        AssetContainerType::Zip | AssetContainerType::Jar => {
+            scan_archive_container(container, options, should_cancel, warnings)
+        }
+        AssetContainerType::AssetIndex => {
+            scan_vanilla_asset_index_container(container, options, should_cancel)
+        }
+    }
+}
+
+fn scan_vanilla_asset_index_container(
+    container: &ScanContainer,
+    options: ScanOptions,
+    should_cancel: &dyn Fn() -> bool,
+) -> Result<Vec<AssetCandidate>, String> {
+    let index = read_vanilla_asset_index(&container.container_path)?;
+    let objects_root = &index.objects_root;
+
+    let mut assets = Vec::new();
+    let mut processed = 0usize;
+    for (logical_path, object) in &index.file.objects {
+        processed = processed.saturating_add(1);
+        if processed % SCAN_CANCEL_CHECK_INTERVAL == 0 && should_cancel() {
+            return Err("Scan cancelled".to_string());
+        }
+
+        let Some(entry) = index.sound_entry(logical_path, object) else {
+            continue;
+        };
+
+        let absolute_path = objects_root.join(&entry.entry_path);
+        if !absolute_path.is_file() {
+            continue;
+        }
+
+        assets.push(AssetCandidate {
+            source_type: container.source_type.clone(),
+            source_name: container.source_name.clone(),
+            namespace: entry.namespace.to_string(),
+            relative_asset_path: entry.relative_asset_path.to_string(),
+            container_path: objects_root.clone(),
+            container_type: AssetContainerType::Directory,
+            entry_path: entry.entry_path,
+            extension: entry.extension,
+            is_image: false,
+            is_audio: true,
+            is_shader: false,
+            byte_size: object.size.filter(|_| options.collect_dimensions),
+            dimensions: None,
+            mod_display_name: None,
+            mod_id: None,
+        });
+    }
+
+    Ok(assets)
+}
+
+struct VanillaAssetIndex {
+    file: MinecraftAssetIndexFile,
+    objects_root: PathBuf,
+    /// Objects live under their logical paths rather than `<ab>/<hash>`.
+    mirrored: bool,
+}
+
+/// Parses an asset index and resolves where its objects are stored. Legacy
+/// indexes prefer the mirror the launcher copied them to (`resources` for
+/// pre-1.6, `assets/virtual` otherwise); without one the shared hashed `objects`
+/// directory is used.
+fn read_vanilla_asset_index(index_path: &Path) -> Result<VanillaAssetIndex, String> {
+    let content = fs::read_to_string(index_path).map_err(|error| {
+        format!(
+            "Failed to read vanilla asset index {}: {error}",
+            index_path.display()
+        )
+    })?;
+
+    let file: MinecraftAssetIndexFile = serde_json::from_str(&content).map_err(|error| {
+        format!(
+            "Failed to parse vanilla asset index {}: {error}",
+            index_path.display()
+        )
+    })?;
+
+    let assets_root = index_path.parent().and_then(Path::parent).ok_or_else(|| {
+        format!(
+            "Invalid asset index path (cannot resolve assets root): {}",
+            index_path.display()
+        )
+    })?;
+
+    let mirror_root = if file.map_to_resources {
+        legacy_resources_root(assets_root)
+    } else if file.is_virtual {
+        virtual_assets_root(assets_root, index_path)
+    } else {
+        None
+    };
+    Ok(match mirror_root {
+        Some(objects_root) => VanillaAssetIndex {
+            file,
+            objects_root,
+            mirrored: true,
+        },
+        None => VanillaAssetIndex {
+            file,
+            objects_root: assets_root.join("objects"),
+            mirrored: false,
+        },
+    })
+}
+
+/// `resources` sits next to `assets` in the game directory, as in the vanilla
+/// launcher's layout.
+fn legacy_resources_root(assets_root: &Path) -> Option<PathBuf> {
+    assets_root
+        .parent()
+        .map(|game_dir| game_dir.join("resources"))
+        .filter(|path| path.is_dir())
+}
+
+fn virtual_assets_root(assets_root: &Path, index_path: &Path) -> Option<PathBuf> {
+    let virtual_dir = assets_root.join("virtual");
+    index_path
+        .file_stem()
+        .map(|stem| virtual_dir.join(stem))
+        .into_iter()
+        .chain(std::iter::once(virtual_dir.join("legacy")))
+        .find(|path| path.is_dir())
+}
+
+struct VanillaSoundEntry<'a> {
+    namespace: &'a str,
+    relative_asset_path: &'a str,
+    extension: String,
+    entry_path: String,
+}
+
+impl VanillaAssetIndex {
+    /// Vanilla sounds are shipped via asset indexes/objects, not client jar
+    /// entries; everything else in the index is ignored. Pre-1.6 keys such as
+    /// `sound/step/grass1.ogg` carry no namespace and belong to `minecraft`.
+    fn sound_entry<'a>(
+        &self,
+        logical_path: &'a str,
+        object: &MinecraftAssetIndexObject,
+    ) -> Option<VanillaSoundEntry<'a>> {
+        let (namespace, relative_asset_path) = match logical_path.split_once('/') {
+            Some((namespace, relative)) if relative.starts_with("sounds/") => (namespace, relative),
+            _ if self.file.is_legacy() => ("minecraft", logical_path),
+            _ => return None,
+        };
+
+        let extension = relative_asset_path
+            .rsplit('.')
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        if !is_audio_extension(&extension) {
+            return None;
+        }
+
+        Some(VanillaSoundEntry {
+            namespace,
+            relative_asset_path,
+            extension,
+            entry_path: self.object_entry_path(logical_path, object)?,
+        })
+    }
+
+    /// Where an object lives below `objects_root`.
+    fn object_entry_path(
+        &self,
+        logical_path: &str,
+        object: &MinecraftAssetIndexObject,
+    ) -> Option<String> {
+        if self.mirrored {
+            Some(logical_path.to_string())
+        } else if object.hash.len() >= 2 {
+            Some(format!("{}/{}", &object.hash[0..2], object.hash))
+        } else {
+            None
+        }
+    }
+
+    /// `(namespace, file path)` of every `sounds.json` the index lists; the
+    /// legacy layout keeps a single one at the root for `minecraft`.
+    fn sound_definition_files(&self) -> Vec<(String, PathBuf)> {
+        let mut files = self
+            .file
+            .objects
+            .iter()
+            .filter_map(|(logical_path, object)| {
+                let namespace = match logical_path.strip_suffix("/sounds.json") {
+                    Some(namespace) if !namespace.contains('/') => namespace,
+                    None if logical_path == "sounds.json" => "minecraft",
+                    _ => return None,
+                };
+                let entry_path = self.object_entry_path(logical_path, object)?;
+                Some((namespace.to_string(), self.objects_root.join(entry_path)))
+            })
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    }
+}
+
+/// Walks a directory container on the calling thread; the scan pool queues
+/// the same subtrees as jobs of their own instead. Results are sorted by entry
+/// path to stay deterministic across runs.
+fn scan_directory_container(
+    container: &ScanContainer,
+    options: ScanOptions,
+    should_cancel: &dyn Fn() -> bool,
+) -> Result<Vec<AssetCandidate>, String> {
+    let mut visited = VisitedDirectories::default();
+    let (loose_files, subtrees) = split_directory_subtrees(
+        &container.container_path,
+        DIRECTORY_SPLIT_DEPTH,
+        options.follow_symlinks,
+        &mut visited,
+    );
+    let mut assets = directory_file_candidates(container, options, &loose_files);
+    for subtree in &subtrees {
+        assets.extend(walk_directory_subtree(
+            container,
+            options,
+            subtree,
+            &visited,
+            should_cancel,
+        )?);
+    }
+
+    assets.sort_by(|left, right| left.entry_path.cmp(&right.entry_path));
+    drop_symlink_aliases(container, options, &mut assets);
+    Ok(assets)
+}
+
+/// Subtree walks only share what the split entered, so a link from one subtree
+/// into another can reach a file twice. Of each file's entry paths the smallest
+/// is kept, which leaves the choice to the paths rather than to thread timing.
+/// `candidates` must be sorted by entry path.
+fn drop_symlink_aliases(
+    container: &ScanContainer,
+    options: ScanOptions,
+    candidates: &mut Vec<AssetCandidate>,
+) {
+    if !options.follow_symlinks {
+        return;
+    }
+    let mut seen = HashSet::new();
+    candidates.retain(|candidate| {
+        fs::canonicalize(container.container_path.join(&candidate.entry_path))
+            .map(|canonical| seen.insert(canonical))
+            .unwrap_or(true)
+    });
+}
+
+fn directory_file_candidates(
+    container: &ScanContainer,
+    options: ScanOptions,
+    files: &[PathBuf],
+) -> Vec<AssetCandidate> {
+    files
+        .iter()
+        .filter_map(|path| {
+            let relative = path.strip_prefix(&container.container_path).ok()?;
+            directory_entry_candidate(container, options, path, normalize_archive_path(relative))
+        })
+        .collect()
+}
+
+/// Canonical paths of the directories a symlink-following scan has entered, so
+/// a link back into the container, or several links to one folder, is read once.
+/// The split fills it on one thread; each subtree walk extends its own copy.
+type VisitedDirectories = HashSet<PathBuf>;
+
+/// `false` when `directory` was already entered under another path.
+fn first_visit(visited: &mut VisitedDirectories, directory: &Path) -> bool {
+    fs::canonicalize(directory)
+        .map(|canonical| visited.insert(canonical))
+        .unwrap_or(false)
+}
+
+/// Descends `depth` levels below `root`, returning the files met on the way and
+/// the directories left at the bottom level. When following symlinks, every
+/// directory entered is recorded in `visited` for the subtree walks.
+fn split_directory_subtrees(
+    root: &Path,
+    depth: usize,
+    follow_symlinks: bool,
+    visited: &mut VisitedDirectories,
+) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let mut loose_files = Vec::new();
+    let mut frontier = vec![root.to_path_buf()];
+    if follow_symlinks {
+        first_visit(visited, root);
+    }
+
+    for _ in 0..depth {
+        let mut next = Vec::new();
+        for directory in &frontier {
+            let Ok(read_dir) = fs::read_dir(directory) else {
+                continue;
+            };
+            // Sorted so the same alias of a linked folder wins on every run.
+            let mut entries = read_dir.flatten().collect::<Vec<_>>();
+            entries.sort_by_key(|entry| entry.file_name());
+            for entry in entries {
+                let file_type = if follow_symlinks {
+                    fs::metadata(entry.path()).map(|metadata| metadata.file_type())
+                } else {
+                    entry.file_type()
+                };
+                match file_type {
+                    Ok(file_type)
+                        if file_type.is_dir()
+                            && (!follow_symlinks || first_visit(visited, &entry.path())) =>
+                    {
+                        next.push(entry.path());
+                    }
+                    Ok(file_type) if file_type.is_file() => loose_files.push(entry.path()),
+                    _ => {}
+                }
+            }
+        }
+        frontier = next;
+    }
+
+    (loose_files, frontier)
+}
+
+fn walk_directory_subtree(
+    container: &ScanContainer,
+    options: ScanOptions,
+    subtree: &Path,
+    split_visited: &VisitedDirectories,
+    should_cancel: &dyn Fn() -> bool,
+) -> Result<Vec<AssetCandidate>, String> {
+    let mut assets = Vec::new();
+    let mut processed = 0usize;
+    let mut visited = split_visited.clone();
+
+    // The subtree root itself was recorded by the split.
+    let entries = WalkDir::new(subtree)
+        .follow_links(options.follow_symlinks)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            !options.follow_symlinks
+                || entry.depth() == 0
+                || !entry.file_type().is_dir()
+                || first_visit(&mut visited, entry.path())
+        });
+    for entry in entries.filter_map(Result::ok) {
+        processed = processed.saturating_add(1);
+        if processed % SCAN_CANCEL_CHECK_INTERVAL == 0 && should_cancel() {
+            return Err("Scan cancelled".to_string());
+        }
+
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let Ok(relative) = entry.path().strip_prefix(&container.container_path) else {
+            continue;
+        };
+
+        if let Some(candidate) = directory_entry_candidate(
+            container,
+            options,
+            entry.path(),
+            normalize_archive_path(relative),
+        ) {
+            assets.push(candidate);
+        }
+    }
+
+    Ok(assets)
+}
+
+/// Re-reads only the given entry paths of a directory container, used by the
+/// refresh when the per-entry signature shows a handful of touched files.
+fn scan_directory_entries(
+    container: &ScanContainer,
+    options: ScanOptions,
+    entry_paths: &[String],
+    should_cancel: &dyn Fn() -> bool,
+) -> Result<Vec<AssetCandidate>, String> {
+    let mut assets = Vec::new();
+
+    for (processed, entry_path) in entry_paths.iter().enumerate() {
+        if (processed + 1) % SCAN_CANCEL_CHECK_INTERVAL == 0 && should_cancel() {
+            return Err("Scan cancelled".to_string());
+        }
+
+        let path = container.container_path.join(entry_path);
+        if !path.is_file() {
+            continue;
+        }
+        if let Some(candidate) =
+            directory_entry_candidate(container, options, &path, entry_path.clone())
+        {
+            assets.push(candidate);
+        }
+    }
+
+    Ok(assets)
+}
+
+fn directory_entry_candidate(
+    container: &ScanContainer,
+    options: ScanOptions,
+    path: &Path,
+    relative_normalized: String,
+) -> Option<AssetCandidate> {
+    let parsed = parse_container_relative_path(&container.source_type, &relative_normalized)?;
+
+    let extension = parsed
+        .relative_asset_path
+        .rsplit('.')
+        .next()
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    let (byte_size, dimensions) = if options.collect_dimensions {
+        let dimensions = if extension == "png" {
+            fs::File::open(path)
+                .ok()
+                .and_then(|mut file| read_png_dimensions(&mut file))
+        } else {
+            None
+        };
+        (
+            fs::metadata(path).ok().map(|metadata| metadata.len()),
+            dimensions,
+        )
+    } else {
+        (None, None)
+    };
+
+    Some(AssetCandidate {
+        source_type: container.source_type.clone(),
+        source_name: container.source_name.clone(),
+        namespace: parsed.namespace,
+        relative_asset_path: parsed.relative_asset_path,
+        container_path: container.container_path.clone(),
+        container_type: container.container_type.clone(),
+        entry_path: relative_normalized,
+        is_image: is_image_extension(&extension),
+        is_audio: is_audio_extension(&extension),
+        is_shader: is_shader_extension(&extension),
+        extension,
+        byte_size,
+        dimensions,
+        mod_display_name: None,
+        mod_id: None,
+    })
+}
+
+fn scan_archive_container(
+    container: &ScanContainer,
+    options: ScanOptions,
+    should_cancel: &dyn Fn() -> bool,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<AssetCandidate>, String> {
+    let file = fs::File::open(&container.container_path).map_err(|error| {
+        format!(
+            "Failed to open archive {}: {error}",
+            container.container_path.display()
+        )
+    })?;
+
+    let mut archive = match ZipArchive::new(file) {
+        Ok(archive) => archive,
+        Err(error) => {
+            let archive_path = container.container_path.display();
+            let assets = salvage_archive_container(container, options, should_cancel).map_err(
+                |salvage_error| {
+                    format!("Failed to read archive {archive_path}: {error} ({salvage_error})")
+                },
+            )?;
+            warnings.push(format!(
+                "Central directory of {archive_path} is unreadable ({error}); \
+                 recovered {} assets from local file headers",
+                assets.len()
+            ));
+            return Ok(assets);
+        }
+    };
+
+    let mut assets = Vec::new();
+    scan_archive_entries(
+        &mut archive,
+        container,
+        options,
+        "",
+        0,
+        should_cancel,
+        &mut assets,
+    )?;
+    if matches!(container.source_type, AssetSourceType::Mod) {
+        apply_mod_metadata(&mut assets, &read_mod_metadata(&mut archive));
+    }
+    Ok(assets)
+}
+
+/// A mod's id and human name as declared in its loader metadata.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct ModMetadata {
+    mod_id: Option<String>,
+    display_name: Option<String>,
+}
+
+/// Reads Fabric, Quilt, Forge or NeoForge metadata; the display name falls
+/// back to the mod id. Jars without metadata keep their file stem.
+fn read_mod_metadata<R: Read + Seek>(archive: &mut ZipArchive<R>) -> ModMetadata {
+    let mut read_entry = |name: &str| {
+        let mut entry = archive.by_name(name).ok()?;
+        let mut text = String::new();
+        entry.read_to_string(&mut text).ok()?;
+        Some(text)
+    };
+
+    if let Some(text) = read_entry("fabric.mod.json") {
+        return parse_fabric_mod_metadata(&text);
+    }
+    if let Some(text) = read_entry("quilt.mod.json") {
+        return parse_quilt_mod_metadata(&text);
+    }
+    ["META-INF/neoforge.mods.toml", "META-INF/mods.toml"]
+        .into_iter()
+        .find_map(read_entry)
+        .map(|text| parse_mods_toml_metadata(&text))
+        .unwrap_or_default()
+}
+
+/// Fills in assets that no nested jar has claimed yet, so the innermost jar
+/// with metadata names its own assets.
+fn apply_mod_metadata(assets: &mut [AssetCandidate], metadata: &ModMetadata) {
+    if metadata == &ModMetadata::default() {
+        return;
+    }
+    for asset in assets
+        .iter_mut()
+        .filter(|asset| asset.mod_id.is_none() && asset.mod_display_name.is_none())
+    {
+        asset.mod_id.clone_from(&metadata.mod_id);
+        asset.mod_display_name.clone_from(&metadata.display_name);
+    }
+}
+
+fn parse_fabric_mod_metadata(text: &str) -> ModMetadata {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(text.trim_start_matches('\u{FEFF}'))
+    else {
+        return ModMetadata::default();
+    };
+    let field = |key: &str| value.get(key).and_then(serde_json::Value::as_str);
+    ModMetadata {
+        mod_id: first_mod_name([field("id")]),
+        display_name: first_mod_name([field("name"), field("id")]),
+    }
+}
+
+fn parse_quilt_mod_metadata(text: &str) -> ModMetadata {
+    let Some(loader) =
+        serde_json::from_str::<serde_json::Value>(text.trim_start_matches('\u{FEFF}'))
+            .ok()
+            .and_then(|value| value.get("quilt_loader").cloned())
+    else {
+        return ModMetadata::default();
+    };
+    let id = loader.get("id").and_then(serde_json::Value::as_str);
+    ModMetadata {
+        mod_id: first_mod_name([id]),
+        display_name: first_mod_name([
+            loader
+                .pointer("/metadata/name")
+                .and_then(serde_json::Value::as_str),
+            id,
+        ]),
+    }
+}
+
+fn parse_mods_toml_metadata(text: &str) -> ModMetadata {
+    let Some(first_mod) = text
+        .parse::<toml::Table>()
+        .ok()
+        .and_then(|table| table.get("mods")?.as_array()?.first()?.as_table().cloned())
+    else {
+        return ModMetadata::default();
+    };
+    let field = |key: &str| first_mod.get(key).and_then(toml::Value::as_str);
+    ModMetadata {
+        mod_id: first_mod_name([field("modId")]),
+        display_name: first_mod_name([field("displayName"), field("modId")]),
+    }
+}
+
+fn first_mod_name<'a>(candidates: impl IntoIterator<Item = Option<&'a str>>) -> Option<String> {
+    candidates
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        // `${file.jarVersion}`-style placeholders are only filled in by the loader.
+        .find(|name| !name.is_empty() && !name.contains("${"))
+        .map(str::to_string)
+}
+
+/// Walks one archive level; nested `.jar` entries (Fabric `META-INF/jars`, Forge
+/// jar-in-jar) are read into memory and recorded as `outer.jar!inner/path`.
+fn scan_archive_entries<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    container: &ScanContainer,
+    options: ScanOptions,
+    entry_prefix: &str,
+    depth: usize,
+    should_cancel: &dyn Fn() -> bool,
+    assets: &mut Vec<AssetCandidate>,
+) -> Result<(), String> {
+    for index in 0..archive.len() {
+        if index % SCAN_CANCEL_CHECK_INTERVAL == 0 && should_cancel() {
+            return Err("Scan cancelled".to_string());
+        }
+
+        // Class files and metadata make up most of a big mod jar; reject them
+        // by name before `by_index` seeks to and decodes the entry header.
+        let Some(name) = archive.name_for_index(index) else {
+            continue;
+        };
+        let is_nested_jar = depth < NESTED_ARCHIVE_MAX_DEPTH && has_jar_extension(name);
+        if !is_nested_jar && !may_hold_assets(&container.source_type, name) {
+            continue;
+        }
+
+        let Ok(mut entry) = archive.by_index(index) else {
+            continue;
+        };
+
+        if entry.is_dir() {
+            continue;
+        }
+
+        let path = normalize_archive_path(Path::new(entry.name()));
+        if is_nested_jar {
+            let mut bytes = Vec::new();
+            if entry.read_to_end(&mut bytes).is_err() {
+                continue;
+            }
+            drop(entry);
+            // Nested jars that fail to parse are skipped rather than failing the mod.
+            if let Ok(mut nested) = ZipArchive::new(Cursor::new(bytes)) {
+                let nested_prefix = format!("{entry_prefix}{path}!");
+                let nested_start = assets.len();
+                scan_archive_entries(
+                    &mut nested,
+                    container,
+                    options,
+                    &nested_prefix,
+                    depth + 1,
+                    should_cancel,
+                    assets,
+                )?;
+                if matches!(container.source_type, AssetSourceType::Mod) {
+                    apply_mod_metadata(
+                        &mut assets[nested_start..],
+                        &read_mod_metadata(&mut nested),
+                    );
+                }
+            }
+            continue;
+        }
+
+        if let Some(candidate) =
+            archive_entry_candidate(container, options, entry_prefix, &path, &mut entry)
+        {
+            assets.push(candidate);
+        }
+    }
+
+    Ok(())
+}
+
+fn archive_entry_candidate<R: Read>(
+    container: &ScanContainer,
+    options: ScanOptions,
+    entry_prefix: &str,
+    path: &str,
+    entry: &mut zip::read::ZipFile<'_, R>,
+) -> Option<AssetCandidate> {
+    let parsed = parse_container_relative_path(&container.source_type, path)?;
+
+    let extension = parsed
+        .relative_asset_path
+        .rsplit('.')
+        .next()
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    let (byte_size, dimensions) = if options.collect_dimensions {
+        let dimensions = if extension == "png" {
+            read_png_dimensions(entry)
+        } else {
+            None
+        };
+        (Some(entry.size()), dimensions)
+    } else {
+        (None, None)
+    };
+
+    Some(AssetCandidate {
+        source_type: container.source_type.clone(),
+        source_name: container.source_name.clone(),
+        namespace: parsed.namespace,
+        relative_asset_path: parsed.relative_asset_path,
+        container_path: container.container_path.clone(),
+        container_type: container.container_type.clone(),
+        entry_path: format!("{entry_prefix}{path}"),
+        is_image: is_image_extension(&extension),
+        is_audio: is_audio_extension(&extension),
+        is_shader: is_shader_extension(&extension),
+        extension,
+        byte_size,
+        dimensions,
+        mod_display_name: None,
+        mod_id: None,
+    })
+}
+
+/// Recovers what it can from an archive whose central directory is broken.
+/// Nested jars and the mod display name need random access and are skipped.
+fn salvage_archive_container(
+    container: &ScanContainer,
+    options: ScanOptions,
+    should_cancel: &dyn Fn() -> bool,
+) -> Result<Vec<AssetCandidate>, String> {
+    let mut assets = Vec::new();
+    let mut processed = 0usize;
+    let mut cancelled = false;
+    salvage_zip_entries(&container.container_path, |_, entry| {
+        processed = processed.saturating_add(1);
+        if processed.is_multiple_of(SCAN_CANCEL_CHECK_INTERVAL) && should_cancel() {
+            cancelled = true;
+            return false;
+        }
+        if entry.is_dir() || !may_hold_assets(&container.source_type, entry.name()) {
+            return true;
+        }
+        let path = normalize_archive_path(Path::new(entry.name()));
+        if let Some(candidate) = archive_entry_candidate(container, options, "", &path, entry) {
+            assets.push(candidate);
+        }
+        true
+    })?;
+    if cancelled {
+        return Err("Scan cancelled".to_string());
+    }
+    Ok(assets)
+}
+
+/// Walks local file headers front to back, for archives whose central
+/// directory can't be read. Stops quietly at the first entry it can't parse,
+/// including ones whose sizes live in a trailing data descriptor, and only
+/// fails when not even the first header is readable. `visit` returns `false`
+/// to stop early; it also gets the offset of the entry's local header.
+fn salvage_zip_entries(
+    path: &Path,
+    mut visit: impl FnMut(u64, &mut zip::read::ZipFile<'_, std::io::BufReader<fs::File>>) -> bool,
+) -> Result<(), String> {
+    let file = fs::File::open(path)
+        .map_err(|error| format!("Failed to open archive {}: {error}", path.display()))?;
+    let mut reader = std::io::BufReader::new(file);
+    let mut visited = 0usize;
+    loop {
+        let offset = reader
+            .stream_position()
+            .map_err(|error| format!("Failed to read archive {}: {error}", path.display()))?;
+        match zip::read::read_zipfile_from_stream(&mut reader) {
+            Ok(Some(mut entry)) => {
+                visited += 1;
+                if !visit(offset, &mut entry) {
+                    break;
+                }
+            }
+            Ok(None) => break,
+            Err(error) if visited == 0 => {
+                return Err(format!("No readable local file headers: {error}"));
+            }
+            Err(error) => {
+                log::debug!(
+                    "stopped salvaging {} after {visited} entries: {error}",
+                    path.display()
+                );
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Where each entry of a salvaged archive starts, found in one walk so later
+/// reads seek straight to their local header.
+struct SalvagedArchive {
+    path: PathBuf,
+    offsets: HashMap<String, u64>,
+}
+
+impl SalvagedArchive {
+    fn index(path: &Path) -> Result<Self, String> {
+        let mut offsets = HashMap::new();
+        salvage_zip_entries(path, |offset, entry| {
+            offsets
+                .entry(normalize_archive_path(Path::new(entry.name())))
+                .or_insert(offset);
+            true
+        })?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            offsets,
+        })
+    }
+
+    fn read(&self, entry_path: &str) -> Result<Vec<u8>, String> {
+        let offset = *self
+            .offsets
+            .get(entry_path)
+            .ok_or_else(|| format!("Archive entry {entry_path} not found"))?;
+        let file = fs::File::open(&self.path)
+            .map_err(|error| format!("Failed to open archive {}: {error}", self.path.display()))?;
+        let mut reader = std::io::BufReader::new(file);
+        reader
+            .seek(std::io::SeekFrom::Start(offset))
+            .map_err(|error| format!("Failed to read archive entry {entry_path}: {error}"))?;
+        let mut entry = zip::read::read_zipfile_from_stream(&mut reader)
+            .map_err(|error| format!("Failed to read archive entry {entry_path}: {error}"))?
+            .ok_or_else(|| format!("Archive entry {entry_path} not found"))?;
+        let mut buffer = Vec::new();
+        entry
+            .read_to_end(&mut buffer)
+            .map_err(|error| format!("Failed to read archive entry {entry_path}: {error}"))?;
+        Ok(buffer)
+    }
+}
+
+/// Reads width and height from a PNG's IHDR chunk without decoding pixel data.
+fn read_png_dimensions(reader: &mut impl Read) -> Option<(u32, u32)> {
+    const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    let mut header = [0u8; 24];
+    reader.read_exact(&mut header).ok()?;
+    if &header[..8] != PNG_SIGNATURE || &header[12..16] != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes(header[16..20].try_into().ok()?);
+    let height = u32::from_be_bytes(header[20..24].try_into().ok()?);
+    Some((width, height))
+}
+
+#[derive(Debug, Clone)]
+struct ParsedAssetPath {
+    namespace: String,
+    relative_asset_path: String,
+}
+
+/// Datapacks lay files out as `data/<namespace>/...` and shader packs use the
+/// pack root directly; everything else uses `assets/`.
+fn parse_container_relative_path(
+    source_type: &AssetSourceType,
+    path: &str,
+) -> Option<ParsedAssetPath> {
+    match source_type {
+        AssetSourceType::Datapack => parse_namespaced_path(path, "data"),
+        AssetSourceType::ShaderPack => parse_pack_root_path(path),
+        _ => parse_asset_relative_path(path),
+    }
+}
+
+fn has_jar_extension(entry_name: &str) -> bool {
+    let bytes = entry_name.as_bytes();
+    bytes.len() >= 4 && bytes[bytes.len() - 4..].eq_ignore_ascii_case(b".jar")
+}
+
+/// Cheap check that `entry_name` has the root segment
+/// `parse_container_relative_path` looks for.
+fn may_hold_assets(source_type: &AssetSourceType, entry_name: &str) -> bool {
+    let root_segment = match source_type {
+        AssetSourceType::ShaderPack => return true,
+        AssetSourceType::Datapack => "data",
+        _ => "assets",
+    };
+    entry_name
+        .split(['/', '\\'])
+        .any(|segment| segment == root_segment)
+}
+
+/// Keeps the whole path with an empty namespace, so the tree shows the pack's
+/// own folders right under its name.
+fn parse_pack_root_path(path: &str) -> Option<ParsedAssetPath> {
+    let segments = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+    if segments.is_empty() || !segments.iter().all(|segment| is_safe_path_segment(segment)) {
+        return None;
+    }
+    let relative_asset_path = segments.join("/");
+
+    Some(ParsedAssetPath {
+        namespace: String::new(),
+        relative_asset_path,
+    })
+}
+
+fn parse_asset_relative_path(path: &str) -> Option<ParsedAssetPath> {
+    parse_namespaced_path(path, "assets")
+}
+
+fn parse_namespaced_path(path: &str, root_segment: &str) -> Option<ParsedAssetPath> {
+    let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+    let root_index = segments
+        .iter()
+        .position(|segment| *segment == root_segment)?;
+
+    // Everything after the root ends up in export paths.
+    if segments.len() <= root_index + 2
+        || !segments[root_index + 1..]
+            .iter()
+            .all(|segment| is_safe_path_segment(segment))
+    {
+        return None;
+    }
+
+    let namespace = segments.get(root_index + 1)?.to_string();
+    let relative_asset_path = segments[root_index + 2..].join("/");
+
+    if relative_asset_path.is_empty() {
+        return None;
+    }
+
+    Some(ParsedAssetPath {
+        namespace,
+        relative_asset_path,
+    })
+}
+
+/// Archive entry names are untrusted; empty, `.`/`..`, drive and backslash
+/// segments could escape the destination once joined onto an export folder.
+fn is_safe_path_segment(segment: &str) -> bool {
+    !segment.is_empty() && segment != "." && segment != ".." && !segment.contains(['\\', ':'])
+}
+
+/// Second line of defence behind `is_safe_path_segment`: export writes must
+/// stay below the chosen folder.
+fn ensure_inside_destination(output_path: &Path, destination_dir: &Path) -> Result<(), String> {
+    let inside = output_path
+        .strip_prefix(destination_dir)
+        .map(|rest| {
+            rest.components()
+                .all(|component| matches!(component, Component::Normal(_)))
+        })
+        .unwrap_or(false);
+    if inside {
+        Ok(())
+    } else {
+        Err(format!(
+            "Refusing to write {} outside {}",
+            output_path.display(),
+            destination_dir.display()
+        ))
+    }
+}
+
+fn normalize_archive_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+fn finalize_assets(
+    candidates: Vec<AssetCandidate>,
+    key_counts: &mut HashMap<String, usize>,
+) -> Vec<AssetRecord> {
+    finalize_instance_assets(candidates, None, key_counts)
+}
+
+/// Keys of instance-tagged assets start with the instance folder, like their
+/// tree node ids, so a container shared by several instances gets one plain
+/// key per instance rather than `.dupN` copies.
+fn finalize_instance_assets(
+    candidates: Vec<AssetCandidate>,
+    instance: Option<&ScanInstance>,
+    key_counts: &mut HashMap<String, usize>,
+) -> Vec<AssetRecord> {
+    let mut assets = candidates
+        .into_iter()
+        .map(|candidate| {
+            let base_key = match instance {
+                Some(instance) => format!(
+                    "{}.{}",
+                    normalize_key_segment(&instance.folder),
+                    build_base_key(&candidate)
+                ),
+                None => build_base_key(&candidate),
+            };
+            let key = unique_key(base_key, key_counts);
+
+            AssetRecord {
+                asset_id: key.clone(),
+                key,
+                source_type: candidate.source_type,
+                source_name: candidate.source_name,
+                namespace: candidate.namespace,
+                relative_asset_path: candidate.relative_asset_path,
+                extension: candidate.extension,
+                is_image: candidate.is_image,
+                is_audio: candidate.is_audio,
+                is_shader: candidate.is_shader,
+                container_path: candidate.container_path.to_string_lossy().to_string(),
+                is_extractable: is_extractable_container_type(&candidate.container_type),
+                container_type: candidate.container_type,
+                entry_path: candidate.entry_path,
+                width: candidate.dimensions.map(|(width, _)| width),
+                height: candidate.dimensions.map(|(_, height)| height),
+                byte_size: candidate.byte_size,
+                mod_display_name: candidate.mod_display_name,
+                mod_id: candidate.mod_id,
+                instance_folder: instance.map(|instance| instance.folder.clone()),
+                instance_display_name: instance.and_then(|instance| instance.display_name.clone()),
+                disabled: is_disabled_container_path(&candidate.container_path),
+                shadowed_by: None,
+            }
+        })
+        .collect::<Vec<_>>();
+    mark_case_shadowed_assets(&mut assets);
+    assets
+}
+
+/// The game only resolves lowercase resource paths, so an all-lowercase entry
+/// wins over differently cased ones regardless of scan order.
+fn mark_case_shadowed_assets(assets: &mut [AssetRecord]) {
+    let mut winners = HashMap::<(String, String), usize>::new();
+    for (index, asset) in assets.iter().enumerate() {
+        let folded = asset.entry_path.to_lowercase();
+        let is_lowercase = folded == asset.entry_path;
+        match winners.entry((asset.container_path.clone(), folded)) {
+            Entry::Occupied(mut winner) => {
+                let winner_is_lowercase = assets[*winner.get()].entry_path == winner.key().1;
+                if is_lowercase && !winner_is_lowercase {
+                    winner.insert(index);
+                }
+            }
+            Entry::Vacant(slot) => {
+                slot.insert(index);
+            }
+        }
+    }
+
+    for index in 0..assets.len() {
+        let folded = (
+            assets[index].container_path.clone(),
+            assets[index].entry_path.to_lowercase(),
+        );
+        let winner = winners[&folded];
+        if assets[winner].entry_path != assets[index].entry_path {
+            assets[index].shadowed_by = Some(assets[winner].asset_id.clone());
+        }
+    }
+}
+
+fn build_base_key(candidate: &AssetCandidate) -> String {
+    let source = normalize_key_segment(&candidate.source_name);
+    let namespace = normalize_key_segment(&candidate.namespace);
+    let path = candidate
+        .relative_asset_path
+        .split('/')
+        .map(normalize_key_segment)
+        .collect::<Vec<_>>()
+        .join(".");
+
+    format!(
+        "{}.{}.{}.{}",
+        candidate.source_type.key_prefix(),
+        source,
+        namespace,
+        path
+    )
+}
+
+fn unique_key(base_key: String, key_counts: &mut HashMap<String, usize>) -> String {
+    let counter = key_counts.entry(base_key.clone()).or_insert(0);
+    if *counter == 0 {
+        *counter = 1;
+        return base_key;
+    }
+
+    let key = format!("{}.dup{}", base_key, *counter);
+    *counter += 1;
+    key
+}
+
+fn normalize_key_segment(value: &str) -> String {
+    let mut output = String::new();
+    let mut previous_was_separator = false;
+
+    for character in value.chars() {
+        if character.is_ascii_alphanumeric() {
+            output.push(character.to_ascii_lowercase());
+            previous_was_separator = false;
+        } else if !previous_was_separator {
+            output.push('_');
+            previous_was_separator = true;
+        }
+    }
+
+    output.trim_matches('_').to_string()
+}
+
+fn build_search_record(asset: &AssetRecord) -> AssetSearchRecord {
+    let filename = Path::new(&asset.relative_asset_path)
+        .file_name()
+        .map(|value| value.to_string_lossy().to_string())
+        .unwrap_or_else(|| asset.relative_asset_path.clone());
+
+    let filename_stem = Path::new(&filename)
+        .file_stem()
+        .map(|value| value.to_string_lossy().to_string())
+        .unwrap_or_else(|| filename.clone());
+
+    let filename_tokens = split_tokens(&filename);
+    let path_tokens = split_tokens(&asset.relative_asset_path);
+    let namespace_tokens = split_tokens(&asset.namespace);
+    let source_tokens = split_tokens(&asset.source_name);
+    let location = asset_resource_location(asset).unwrap_or_default();
+    let location_tokens = split_tokens(&location);
+
+    let mut token_set = HashSet::new();
+    for token in split_tokens(&asset.key) {
+        token_set.insert(token);
+    }
+    for token in &path_tokens {
+        token_set.insert(token.clone());
+    }
+    for token in &namespace_tokens {
+        token_set.insert(token.clone());
+    }
+    for token in &source_tokens {
+        token_set.insert(token.clone());
+    }
+
+    let mut all_tokens = token_set.into_iter().collect::<Vec<_>>();
+    all_tokens.sort();
+
+    AssetSearchRecord {
+        all_tokens,
+        filename_tokens,
+        path_tokens,
+        namespace_tokens,
+        source_tokens,
+        location_tokens,
+        compact_all: compact_text(&format!(
+            "{} {} {} {}",
+            asset.key, asset.source_name, asset.namespace, asset.relative_asset_path
+        )),
+        compact_filename: compact_text(&filename),
+        compact_filename_stem: compact_text(&filename_stem),
+        compact_location: compact_text(&location),
+        relative_path: asset.relative_asset_path.to_lowercase(),
+        key: asset.key.to_lowercase(),
+        folder_node_id: asset_folder_node_id(asset),
+    }
+}
+
+/// Resource pack folders whose files Minecraft names without the folder and
+/// extension, e.g. `textures/block/stone.png` is `minecraft:block/stone`.
+const RESOURCE_PACK_LOCATION_CATEGORIES: &[(&str, &str)] = &[
+    ("textures/", ".png"),
+    ("sounds/", ".ogg"),
+    ("models/", ".json"),
+    ("blockstates/", ".json"),
+    ("items/", ".json"),
+    ("font/", ".json"),
+    ("particles/", ".json"),
+    ("atlases/", ".json"),
+    ("equipment/", ".json"),
+    ("post_effect/", ".json"),
+];
+
+/// Derives the in-game `namespace:path` location, dropping the category
+/// prefix and extension where Minecraft omits them. Resource pack folders
+/// follow a fixed list; datapack files are always named by their path inside
+/// the registry folder, and tags gain the `#` prefix used in commands.
+fn asset_resource_location(asset: &AssetRecord) -> Option<String> {
+    if asset.namespace.is_empty() || asset.relative_asset_path.is_empty() {
+        return None;
+    }
+
+    let path = asset.relative_asset_path.as_str();
+    if matches!(asset.source_type, AssetSourceType::Datapack) {
+        let (is_tag, stripped) = data_resource_path(path).unwrap_or((false, path));
+        let tag_marker = if is_tag { "#" } else { "" };
+        return Some(format!("{tag_marker}{}:{stripped}", asset.namespace));
+    }
+
+    let stripped = RESOURCE_PACK_LOCATION_CATEGORIES
+        .iter()
+        .find_map(|(prefix, suffix)| {
+            path.strip_prefix(prefix)
+                .and_then(|rest| rest.strip_suffix(suffix))
+                .filter(|rest| !rest.is_empty())
+        })
+        .unwrap_or(path);
+
+    Some(format!("{}:{stripped}", asset.namespace))
+}
+
+/// Strips the registry folder (`recipe/`, `worldgen/biome/`, `tags/block/`,
+/// ...) and the extension from a datapack path. Returns whether it's a tag.
+fn data_resource_path(path: &str) -> Option<(bool, &str)> {
+    let (is_tag, path) = match path.strip_prefix("tags/") {
+        Some(rest) => (true, rest),
+        None => (false, path),
+    };
+    let registry_segments = if path.starts_with("worldgen/") { 2 } else { 1 };
+    let rest = path
+        .splitn(registry_segments + 1, '/')
+        .nth(registry_segments)?;
+    let (stem, _) = rest.rsplit_once('.')?;
+    (!stem.is_empty()).then_some((is_tag, stem))
+}
+
+fn compact_text(value: &str) -> String {
+    value
+        .chars()
+        .filter(|character| character.is_ascii_alphanumeric())
+        .map(|character| character.to_ascii_lowercase())
+        .collect()
+}
+
+fn split_tokens(value: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+
+    for character in value.chars() {
+        if character.is_ascii_alphanumeric() {
+            current.push(character.to_ascii_lowercase());
+        } else if !current.is_empty() {
+            tokens.push(current.clone());
+            current.clear();
+        }
+    }
+
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    tokens
+}
+
+/// A smart-search query broken into the pieces `score_query` weighs.
+#[derive(Debug, Clone)]
+struct SearchQuery {
+    tokens: Vec<String>,
+    compact: String,
+    normalized: String,
+    path_hints: Vec<String>,
+    /// Tokens from `-word` terms; any asset containing one is dropped.
+    excluded: Vec<String>,
+    /// Compacted `"quoted phrases"` that must appear contiguously.
+    phrases: Vec<String>,
+    fuzzy: FuzzyMode,
+}
+
+/// Pulls closed `"..."` pairs out of `raw`, returning the leftover text and the
+/// compacted phrases. A stray quote without its partner is treated as a space.
+fn split_quoted_phrases(raw: &str) -> (String, Vec<String>) {
+    let mut unquoted = String::new();
+    let mut phrases = Vec::new();
+    let mut remaining = raw;
+
+    while let Some(start) = remaining.find('"') {
+        let after_open = &remaining[start + 1..];
+        let Some(end) = after_open.find('"') else {
+            break;
+        };
+        unquoted.push_str(&remaining[..start]);
+        unquoted.push(' ');
+        let phrase = compact_text(&after_open[..end]);
+        if !phrase.is_empty() {
+            phrases.push(phrase);
+        }
+        remaining = &after_open[end + 1..];
+    }
+
+    unquoted.push_str(&remaining.replace('"', " "));
+    (unquoted, phrases)
+}
+
+/// Shorter exclusions would drop nearly everything through prefix matching.
+const MIN_EXCLUDED_TOKEN_LEN: usize = 2;
+
+impl SearchQuery {
+    fn parse(raw: &str) -> Self {
+        let (unquoted, phrases) = split_quoted_phrases(raw);
+        let mut positive_words = Vec::new();
+        let mut excluded = Vec::new();
+        for word in unquoted.split_whitespace() {
+            match word.strip_prefix('-') {
+                Some(rest) => excluded.extend(
+                    split_tokens(rest)
+                        .into_iter()
+                        .filter(|token| token.len() >= MIN_EXCLUDED_TOKEN_LEN),
+                ),
+                None => positive_words.push(word),
+            }
+        }
+        let raw = positive_words.join(" ");
+        let raw = raw.as_str();
+
+        let tokens = split_tokens(raw);
+        let normalized = tokens.join(" ");
+
+        // A word with `/` asks for that path fragment; a `namespace:` prefix
+        // never appears in relative asset paths, so it is dropped.
+        let path_hints = raw
+            .split_whitespace()
+            .filter(|word| word.contains('/'))
+            .map(|word| {
+                let word = word.rsplit_once(':').map(|(_, rest)| rest).unwrap_or(word);
+                word.replace('\\', "/").trim_matches('/').to_lowercase()
+            })
+            .filter(|hint| hint.contains('/'))
+            .collect();
+
+        Self {
+            compact: compact_text(raw),
+            tokens,
+            normalized,
+            path_hints,
+            excluded,
+            phrases,
+            fuzzy: FuzzyMode::default(),
+        }
+    }
+
+    /// True when nothing is left to rank by, so results use the idle ordering.
+    fn is_unranked(&self) -> bool {
+        self.tokens.is_empty() && self.phrases.is_empty()
+    }
+
+    fn excludes(&self, index: &AssetSearchRecord) -> bool {
+        self.excluded.iter().any(|excluded| {
+            index
+                .all_tokens
+                .iter()
+                .any(|token| token.starts_with(excluded.as_str()))
+        })
+    }
+}
+
+/// A non-fuzzy query tested against `asset.key` and `relative_asset_path`.
+#[derive(Debug, Clone)]
+enum KeyPattern {
+    Regex(regex::Regex),
+    Glob(globset::GlobMatcher),
+    Exact(String),
+}
+
+impl KeyPattern {
+    /// Returns `None` for smart mode and for blank queries, which list everything.
+    fn compile(mode: SearchMatchMode, raw: &str) -> Result<Option<Self>, String> {
+        let raw = raw.trim();
+        if raw.is_empty() {
+            return Ok(None);
+        }
+
+        match mode {
+            SearchMatchMode::Smart => Ok(None),
+            SearchMatchMode::Regex => regex::Regex::new(raw)
+                .map(|pattern| Some(Self::Regex(pattern)))
+                .map_err(|error| format!("Invalid regular expression: {error}")),
+            SearchMatchMode::Glob => globset::GlobBuilder::new(&raw.replace('\\', "/"))
+                .literal_separator(true)
+                .case_insensitive(true)
+                .build()
+                .map(|glob| Some(Self::Glob(glob.compile_matcher())))
+                .map_err(|error| format!("Invalid glob pattern: {error}")),
+            SearchMatchMode::Exact => Ok(Some(Self::Exact(raw.to_string()))),
+        }
+    }
+
+    fn matches(&self, asset: &AssetRecord) -> bool {
+        match self {
+            Self::Regex(pattern) => {
+                pattern.is_match(&asset.key) || pattern.is_match(&asset.relative_asset_path)
+            }
+            Self::Glob(pattern) => pattern.is_match(&asset.relative_asset_path),
+            Self::Exact(value) => asset.key == *value || asset.relative_asset_path == *value,
+        }
+    }
+}
+
+fn build_asset_highlights(
+    asset: &AssetRecord,
+    query: &SearchQuery,
+    pattern: Option<&KeyPattern>,
+) -> AssetHighlights {
+    let needles = query
+        .tokens
+        .iter()
+        .chain(&query.phrases)
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    let ranges = |text: &str| match pattern {
+        Some(KeyPattern::Regex(pattern)) => merge_highlight_ranges(
+            pattern
+                .find_iter(text)
+                .map(|found| HighlightRange {
+                    start: text[..found.start()].chars().count(),
+                    end: text[..found.end()].chars().count(),
+                })
+                .collect(),
+        ),
+        Some(_) => Vec::new(),
+        None => token_highlight_ranges(text, &needles),
+    };
+
+    AssetHighlights {
+        asset_id: asset.asset_id.clone(),
+        key: ranges(&asset.key),
+        relative_asset_path: ranges(&asset.relative_asset_path),
+    }
+}
+
+/// Finds each needle in the `compact_text` form of `text` and maps the hit back
+/// to original character offsets, so `grassblock` highlights `grass_block`.
+/// Fuzzy-only matches have no literal span and are not highlighted.
+fn token_highlight_ranges(text: &str, needles: &[&str]) -> Vec<HighlightRange> {
+    let positions = text
+        .chars()
+        .enumerate()
+        .filter(|(_, character)| character.is_ascii_alphanumeric())
+        .collect::<Vec<_>>();
+    let compact = positions
+        .iter()
+        .map(|(_, character)| character.to_ascii_lowercase())
+        .collect::<String>();
+
+    let mut ranges = Vec::new();
+    for needle in needles.iter().filter(|needle| !needle.is_empty()) {
+        let mut from = 0;
+        while let Some(found) = compact[from..].find(needle) {
+            let start = from + found;
+            let end = start + needle.len();
+            ranges.push(HighlightRange {
+                start: positions[start].0,
+                end: positions[end - 1].0 + 1,
+            });
+            from = end;
+        }
+    }
+
+    merge_highlight_ranges(ranges)
+}
+
+fn merge_highlight_ranges(mut ranges: Vec<HighlightRange>) -> Vec<HighlightRange> {
+    ranges.sort_unstable_by_key(|range| (range.start, range.end));
+    let mut merged = Vec::<HighlightRange>::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+fn score_query(index: &AssetSearchRecord, query: &SearchQuery) -> Option<i64> {
+    let query_tokens = &query.tokens;
+    let query_compact = query.compact.as_str();
+    let normalized_query = query.normalized.as_str();
+    if query.excludes(index) {
+        return None;
+    }
+
+    let mut phrase_score = 0i64;
+    for phrase in &query.phrases {
+        if index.compact_filename.contains(phrase.as_str()) {
+            phrase_score += 420;
+        } else if index.compact_all.contains(phrase.as_str()) {
+            phrase_score += 160;
+        } else {
+            return None;
+        }
+    }
+    if query_tokens.is_empty() {
+        return Some(phrase_score);
+    }
+
+    let mut score = 0i64;
+    let mut matched_tokens = 0usize;
+
+    for query_token in query_tokens {
+        let mut token_score = 0i64;
+
+        token_score = token_score.max(score_token_group_fast(
+            &index.filename_tokens,
+            query_token,
+            320,
+            250,
+            180,
+        ));
+        token_score = token_score.max(score_token_group_fast(
+            &index.location_tokens,
+            query_token,
+            300,
+            230,
+            160,
+        ));
+        token_score = token_score.max(score_token_group_fast(
+            &index.path_tokens,
+            query_token,
+            170,
+            130,
+            95,
+        ));
+        token_score = token_score.max(score_token_group_fast(
+            &index.namespace_tokens,
+            query_token,
+            140,
+            110,
+            80,
+        ));
+        token_score = token_score.max(score_token_group_fast(
+            &index.source_tokens,
+            query_token,
+            130,
+            100,
+            76,
+        ));
+        token_score = token_score.max(score_token_group_fast(
+            &index.all_tokens,
+            query_token,
+            100,
+            80,
+            60,
+        ));
+
+        if token_score == 0 && query.fuzzy != FuzzyMode::Off {
+            token_score = token_score.max(score_fuzzy_token_group(
+                &index.filename_tokens,
+                query_token,
+                72,
+                query.fuzzy,
+            ));
+            token_score = token_score.max(score_fuzzy_token_group(
+                &index.path_tokens,
+                query_token,
+                48,
+                query.fuzzy,
+            ));
+        }
+
+        if token_score == 0 {
+            score -= 100;
+            continue;
+        }
+
+        matched_tokens += 1;
+        score += token_score;
+    }
+
+    let token_count = query_tokens.len();
+    let required_matches = if token_count <= 2 {
+        token_count
+    } else {
+        (token_count * 3).div_ceil(5)
+    };
+
+    if matched_tokens < required_matches {
+        return None;
+    }
+
+    let missing_tokens = token_count.saturating_sub(matched_tokens);
+    if missing_tokens > 0 {
+        score -= (missing_tokens as i64) * 70;
+    } else {
+        score += 90;
+    }
+
+    score += (matched_tokens as i64) * 48;
+
+    if !query_compact.is_empty() {
+        if index.compact_filename_stem == query_compact {
+            score += 450;
+        } else if index.compact_filename_stem.starts_with(query_compact) {
+            score += 240;
+        } else if index.compact_filename.contains(query_compact) {
+            score += 190;
+        }
+
+        if index.compact_all.contains(query_compact) {
+            score += 120;
+        }
+
+        if index.compact_location == query_compact {
+            score += 500;
+        }
+    }
+
+    if !normalized_query.is_empty() && index.key.contains(normalized_query) {
+        score += 80;
+    }
+
+    for hint in &query.path_hints {
+        score += score_path_hint(&index.relative_path, hint);
+    }
+
+    let extra_filename_tokens = index.filename_tokens.len().saturating_sub(matched_tokens);
+    if extra_filename_tokens > 0 {
+        score -= (extra_filename_tokens as i64) * 8;
+    }
+
+    Some(score + phrase_score)
+}
+
+fn score_path_hint(relative_path: &str, hint: &str) -> i64 {
+    let mut best = 0;
+    for (position, _) in relative_path.match_indices(hint) {
+        let starts_segment = position == 0 || relative_path[..position].ends_with('/');
+        let next = relative_path[position + hint.len()..].chars().next();
+        let ends_segment = matches!(next, None | Some('/') | Some('.'));
+        let weight = match (starts_segment, ends_segment) {
+            (true, true) => 420,
+            (_, true) | (true, _) => 300,
+            _ => 220,
+        };
+        best = best.max(weight);
+    }
+    best
+}
+
+fn score_token_group_fast(
+    tokens: &[String],
+    query_token: &str,
+    exact_weight: i64,
+    prefix_weight: i64,
+    contains_weight: i64,
+) -> i64 {
+    let mut best = 0;
+    for token in tokens {
+        if token == query_token {
+            return exact_weight;
+        } else if token.starts_with(query_token) || query_token.starts_with(token) {
+            best = best.max(prefix_weight);
+        } else if token.contains(query_token) || query_token.contains(token) {
+            best = best.max(contains_weight);
+        }
+    }
+    best
+}
+
+fn score_fuzzy_token_group(
+    tokens: &[String],
+    query_token: &str,
+    max_weight: i64,
+    mode: FuzzyMode,
+) -> i64 {
+    if query_token.len() < mode.min_query_len() {
+        return 0;
+    }
+
+    let mut best = 0i64;
+    for token in tokens {
+        let score = score_fuzzy_token(token, query_token, mode);
+        if score > 0 {
+            best = best.max(max_weight.min(score));
+        }
+    }
+
+    best
+}
+
+fn score_fuzzy_token(token: &str, query_token: &str, mode: FuzzyMode) -> i64 {
+    let token_len = token.len();
+    let query_len = query_token.len();
+    if token_len < 3 || query_len < 3 {
+        return 0;
+    }
+
+    let len_delta = token_len.abs_diff(query_len);
+    if len_delta > mode.max_len_delta() {
+        return 0;
+    }
+
+    let token_bytes = token.as_bytes();
+    let query_bytes = query_token.as_bytes();
+    let same_start = token_bytes.first() == query_bytes.first();
+    let swap_start = token_bytes.len() > 1
+        && query_bytes.len() > 1
+        && token_bytes[0] == query_bytes[1]
+        && token_bytes[1] == query_bytes[0];
+    if !same_start && !swap_start {
+        return 0;
+    }
+
+    let distance = damerau_levenshtein(token, query_token);
+    let long_enough = mode
+        .min_len_for_distance(distance)
+        .is_some_and(|min_len| token_len >= min_len && query_len >= min_len);
+    match distance {
+        1 if long_enough => 72,
+        2 if long_enough => 54,
+        3 if long_enough => 40,
+        _ => 0,
+    }
+}
+
+fn idle_asset_cmp(left: &AssetRecord, right: &AssetRecord) -> CmpOrdering {
+    let left_name = idle_filename(left);
+    let right_name = idle_filename(right);
+    let left_token = last_filename_token(&left_name);
+    let right_token = last_filename_token(&right_name);
+
+    natural_compare(&left_token, &right_token)
+        .then_with(|| {
+            natural_compare(
+                &left_name.to_ascii_lowercase(),
+                &right_name.to_ascii_lowercase(),
+            )
+        })
+        .then_with(|| left.key.cmp(&right.key))
+}
+
+fn idle_filename(asset: &AssetRecord) -> String {
+    Path::new(&asset.relative_asset_path)
+        .file_name()
+        .map(|value| value.to_string_lossy().to_string())
+        .unwrap_or_else(|| asset.relative_asset_path.clone())
+}
+
+fn last_filename_token(file_name: &str) -> String {
+    let stem = Path::new(file_name)
+        .file_stem()
+        .map(|value| value.to_string_lossy().to_string())
+        .unwrap_or_else(|| file_name.to_string());
+    split_tokens(&stem)
+        .pop()
+        .unwrap_or_else(|| stem.to_ascii_lowercase())
+}
+
+fn natural_compare(left: &str, right: &str) -> CmpOrdering {
+    let left_chunks = natural_chunks(left);
+    let right_chunks = natural_chunks(right);
+    let max_len = left_chunks.len().max(right_chunks.len());
+
+    for index in 0..max_len {
+        let left_chunk = left_chunks.get(index);
+        let right_chunk = right_chunks.get(index);
+        let ordering = match (left_chunk, right_chunk) {
+            (Some(NaturalChunk::Number(a)), Some(NaturalChunk::Number(b))) => a.cmp(b),
+            (Some(NaturalChunk::Text(a)), Some(NaturalChunk::Text(b))) => a.cmp(b),
+            (Some(NaturalChunk::Number(_)), Some(NaturalChunk::Text(_))) => CmpOrdering::Less,
+            (Some(NaturalChunk::Text(_)), Some(NaturalChunk::Number(_))) => CmpOrdering::Greater,
+            (Some(_), None) => CmpOrdering::Greater,
+            (None, Some(_)) => CmpOrdering::Less,
+            (None, None) => CmpOrdering::Equal,
+        };
+
+        if ordering != CmpOrdering::Equal {
+            return ordering;
+        }
+    }
+
+    CmpOrdering::Equal
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum NaturalChunk {
+    Number(u64),
+    Text(String),
+}
+
+fn natural_chunks(value: &str) -> Vec<NaturalChunk> {
+    let mut chunks = Vec::<NaturalChunk>::new();
+    let mut current = String::new();
+    let mut is_number = false;
+
+    for ch in value.chars() {
+        if ch.is_ascii_digit() {
+            if !is_number && !current.is_empty() {
+                chunks.push(NaturalChunk::Text(current.to_ascii_lowercase()));
+                current.clear();
+            }
+            is_number = true;
+            current.push(ch);
+        } else {
+            if is_number && !current.is_empty() {
+                chunks.push(NaturalChunk::Number(
+                    current.parse::<u64>().unwrap_or(u64::MAX),
+                ));
+                current.clear();
+            }
+            is_number = false;
+            current.push(ch);
+        }
+    }
+
+    if !current.is_empty() {
+        if is_number {
+            chunks.push(NaturalChunk::Number(
+                current.parse::<u64>().unwrap_or(u64::MAX),
+            ));
+        } else {
+            chunks.push(NaturalChunk::Text(current.to_ascii_lowercase()));
+        }
+    }
+
+    chunks
+}
+
+/// Request filters resolved once per call; source scoping is handled separately
+/// through `scoped_candidate_indices` so it can use the per-source index.
+struct AssetFilter<'a> {
+    folder: Option<&'a str>,
+    include_images: bool,
+    include_audio: bool,
+    include_other: bool,
+    namespaces: Option<&'a [String]>,
+    extensions: Option<Vec<String>>,
+}
+
+impl<'a> AssetFilter<'a> {
+    fn from_options(options: &'a AssetFilterOptions) -> Self {
+        Self {
+            folder: options
+                .folder_node_id
+                .as_deref()
+                .filter(|value| !value.trim().is_empty() && *value != ROOT_NODE_ID),
+            include_images: options.include_images.unwrap_or(true),
+            include_audio: options.include_audio.unwrap_or(true),
+            include_other: options.include_other.unwrap_or(true),
+            namespaces: options
+                .namespace_filter
+                .as_deref()
+                .filter(|namespaces| !namespaces.is_empty()),
+            extensions: options
+                .extensions
+                .as_deref()
+                .filter(|extensions| !extensions.is_empty())
+                .map(|extensions| {
+                    extensions
+                        .iter()
+                        .map(|extension| {
+                            extension
+                                .trim()
+                                .trim_start_matches('.')
+                                .to_ascii_lowercase()
+                        })
+                        .collect()
+                }),
+        }
+    }
+
+    fn excludes_all_media(&self) -> bool {
+        !(self.include_images || self.include_audio || self.include_other)
+    }
+
+    fn matches(&self, asset: &AssetRecord, search_record: &AssetSearchRecord) -> bool {
+        asset_matches_media(
+            asset,
+            self.include_images,
+            self.include_audio,
+            self.include_other,
+        ) && asset_matches_folder(search_record, self.folder)
+            && self
+                .namespaces
+                .is_none_or(|namespaces| namespaces.contains(&asset.namespace))
+            && self
+                .extensions
+                .as_ref()
+                .is_none_or(|extensions| extensions.contains(&asset.extension))
+    }
+}
+
+/// Scoped requests only visit the selected sources' assets instead of the whole scan.
+fn scoped_candidate_indices(
+    scan: &mut ScanState,
+    options: &AssetFilterOptions,
+) -> Box<dyn Iterator<Item = usize>> {
+    match options
+        .source_name_filter
+        .as_deref()
+        .filter(|names| !names.is_empty())
+    {
+        Some(names) => Box::new(scan.indices_for_sources(names).into_iter()),
+        None => Box::new(0..scan.assets.len()),
+    }
+}
+
+fn group_assets_by_folder(assets: Vec<AssetRecord>) -> Vec<SearchResultGroup> {
+    let mut groups = Vec::<SearchResultGroup>::new();
+    let mut positions = HashMap::<String, usize>::new();
+    for asset in assets {
+        let folder_node_id = asset_folder_node_id(&asset);
+        let position = *positions.entry(folder_node_id.clone()).or_insert_with(|| {
+            let folder_name = build_asset_folder_segments(&asset)
+                .pop()
+                .filter(|segment| !segment.is_empty())
+                .unwrap_or_else(|| "(root)".to_string());
+            groups.push(SearchResultGroup {
+                folder_node_id,
+                folder_name,
+                assets: Vec::new(),
+            });
+            groups.len() - 1
+        });
+        groups[position].assets.push(asset);
+    }
+    groups
+}
+
+fn asset_matches_folder(index: &AssetSearchRecord, folder_filter: Option<&str>) -> bool {
+    let Some(folder_filter) = folder_filter else {
+        return true;
+    };
+
+    index.folder_node_id == folder_filter
+        || index
+            .folder_node_id
+            .starts_with(&format!("{folder_filter}/"))
+}
+
+fn asset_matches_media(
+    asset: &AssetRecord,
+    include_images: bool,
+    include_audio: bool,
+    include_other: bool,
+) -> bool {
+    if asset.is_image {
+        return include_images;
+    }
+
+    if asset.is_audio {
+        return include_audio;
+    }
+
+    include_other
+}
+
+/// `tree_index` must hold the position of every node in `tree_children`; see
+/// `build_tree_index`.
+fn add_asset_to_tree(
+    tree_children: &mut HashMap<String, Vec<TreeNode>>,
+    tree_index: &mut HashMap<String, usize>,
+    asset: &AssetRecord,
+) {
+    let file_parent_id = asset_folder_node_id(asset);
+    let file_node_id = format!("{file_parent_id}/file:{}", asset.asset_id);
+    // Re-adding a known asset must not inflate the folder counts.
+    if tree_index.contains_key(&file_node_id) {
+        return;
+    }
+
+    let mut parent_id = ROOT_NODE_ID.to_string();
+    let folders = build_asset_folder_segments(asset);
+    let source_depth = usize::from(asset.instance_folder.is_some()) + 1;
+    let source_label = source_display_name(asset);
+
+    for (depth, segment) in folders.into_iter().enumerate() {
+        let node_name = if segment.is_empty() {
+            "(root)"
+        } else {
+            &segment
+        };
+        let node_id = build_folder_node_id(&parent_id, node_name);
+        // Ids keep the folder and jar stem so folder filters stay stable; only
+        // the label changes.
+        let node_name = if depth == 0 && asset.instance_folder.is_some() {
+            asset.instance_display_name.as_deref().unwrap_or(node_name)
+        } else if depth == source_depth {
+            source_label.unwrap_or(node_name)
+        } else {
+            node_name
+        };
+
+        let node = upsert_tree_node(
+            tree_children,
+            tree_index,
+            &parent_id,
+            TreeNode {
+                id: node_id.clone(),
+                name: node_name.to_string(),
+                node_type: TreeNodeType::Folder,
+                has_children: true,
+                asset_id: None,
+                asset_count: 0,
+            },
+        );
+        node.asset_count += 1;
+        // A jar-in-jar asset may have created the source folder first.
+        if let Some(label) = source_label.filter(|_| depth == source_depth) {
+            node.name = label.to_string();
+        }
+
+        tree_children.entry(node_id.clone()).or_default();
+        parent_id = node_id;
+    }
+
+    let file_name = Path::new(&asset.relative_asset_path)
+        .file_name()
+        .map(|value| value.to_string_lossy().to_string())
+        .unwrap_or_else(|| asset.relative_asset_path.clone());
+
+    upsert_tree_node(
+        tree_children,
+        tree_index,
+        &parent_id,
+        TreeNode {
+            id: file_node_id,
+            name: file_name,
+            node_type: TreeNodeType::File,
+            has_children: false,
+            asset_id: Some(asset.asset_id.clone()),
+            asset_count: 1,
+        },
+    );
+}
+
+/// The mod name for the asset's source folder; jar-in-jar assets carry their
+/// nested mod's name, so only the outer jar's own assets name the source.
+fn source_display_name(asset: &AssetRecord) -> Option<&str> {
+    asset
+        .mod_display_name
+        .as_deref()
+        .filter(|_| split_nested_entry_path(&asset.entry_path).len() == 1)
+}
+
+fn asset_folder_node_id(asset: &AssetRecord) -> String {
+    let mut node_id = ROOT_NODE_ID.to_string();
+    for segment in build_asset_folder_segments(asset) {
+        let node_name = if segment.is_empty() {
+            "(root)"
+        } else {
+            &segment
+        };
+        node_id = build_folder_node_id(&node_id, node_name);
+    }
+    node_id
+}
+
+fn build_asset_folder_segments(asset: &AssetRecord) -> Vec<String> {
+    let mut folders = Vec::new();
+
+    folders.extend(asset.instance_folder.clone());
+    folders.push(asset.source_type.tree_root_name().to_string());
+    folders.push(asset.source_name.clone());
+    if !asset.namespace.is_empty() {
+        folders.push(asset.namespace.clone());
+    }
+
+    let path = Path::new(&asset.relative_asset_path);
+    if let Some(parent) = path.parent() {
+        for segment in parent.iter() {
+            folders.push(segment.to_string_lossy().to_string());
+        }
+    }
+
+    folders
+}
+
+/// Returns the namespace folder to list instead of `node_id` when it's a mod
+/// source folder holding exactly one namespace named after the mod.
+/// `parsed_mod_id` looks up the id from the jar's metadata by source name; the
+/// jar stem is only used to guess one when the metadata had none.
+fn collapsible_namespace_node(
+    tree_children: &HashMap<String, Vec<TreeNode>>,
+    node_id: &str,
+    parsed_mod_id: impl FnOnce(&str) -> Option<String>,
+) -> Option<String> {
+    let mods_root = build_folder_node_id(ROOT_NODE_ID, AssetSourceType::Mod.tree_root_name());
+    let source_segment = node_id
+        .strip_prefix(&mods_root)?
+        .strip_prefix('/')
+        .filter(|segment| !segment.contains('/'))?;
+
+    let [namespace] = tree_children.get(node_id)?.as_slice() else {
+        return None;
+    };
+    if !matches!(namespace.node_type, TreeNodeType::Folder) {
+        return None;
+    }
+    let compact = |name: &str| {
+        name.chars()
+            .filter(char::is_ascii_alphanumeric)
+            .collect::<String>()
+    };
+    let compact_namespace = compact(&namespace.name);
+    let mod_id = parsed_mod_id(&source_segment.replace('∕', "/"))
+        .map(|mod_id| compact(&mod_id))
+        .unwrap_or_else(|| derive_mod_id(source_segment));
+    (!compact_namespace.is_empty() && compact_namespace == mod_id).then(|| namespace.id.clone())
+}
+
+/// Guesses a mod id from a jar stem by joining the leading name words and
+/// stopping at the first version or loader tag: `create-fabric-1.20.1` -> `create`.
+fn derive_mod_id(source_name: &str) -> String {
+    const LOADER_TAGS: [&str; 5] = ["fabric", "forge", "neoforge", "quilt", "mc"];
+    let is_version = |segment: &str| {
+        segment
+            .strip_prefix('v')
+            .unwrap_or(segment)
+            .starts_with(|character: char| character.is_ascii_digit())
+    };
+
+    source_name
+        .split(|character: char| !character.is_ascii_alphanumeric())
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_ascii_lowercase)
+        .take_while(|segment| !is_version(segment) && !LOADER_TAGS.contains(&segment.as_str()))
+        .collect()
+}
+
+fn build_folder_node_id(parent: &str, segment: &str) -> String {
+    let escaped = segment.replace('/', "∕");
+    if parent == ROOT_NODE_ID {
+        format!("{ROOT_NODE_ID}/{escaped}")
+    } else {
+        format!("{parent}/{escaped}")
+    }
+}
+
+fn upsert_tree_node<'a>(
+    tree_children: &'a mut HashMap<String, Vec<TreeNode>>,
+    tree_index: &mut HashMap<String, usize>,
+    parent_id: &str,
+    node: TreeNode,
+) -> &'a mut TreeNode {
+    let children = tree_children.entry(parent_id.to_string()).or_default();
+    // Node ids spell out the whole path, so one map covers every parent.
+    let index = *tree_index.entry(node.id.clone()).or_insert_with(|| {
+        children.push(node);
+        children.len() - 1
+    });
+    &mut children[index]
+}
+
+fn build_tree_index(tree_children: &HashMap<String, Vec<TreeNode>>) -> HashMap<String, usize> {
+    tree_children
+        .values()
+        .flat_map(|children| {
+            children
+                .iter()
+                .enumerate()
+                .map(|(index, child)| (child.id.clone(), index))
+        })
+        .collect()
+}
+
+fn collect_assets(
+    state: &State<'_, AppState>,
+    scan_id: &str,
+    asset_ids: &[String],
+) -> Result<Vec<AssetRecord>, String> {
+    let scans = state
+        .scans
+        .lock()
+        .map_err(|_| "Failed to lock scans state".to_string())?;
+
+    let scan = scans
+        .get(scan_id)
+        .ok_or_else(|| format!("Unknown scan id: {scan_id}"))?;
+
+    let mut assets = Vec::new();
+
+    for asset_id in asset_ids {
+        let index = scan
+            .asset_index
+            .get(asset_id)
+            .ok_or_else(|| format!("Unknown asset id: {asset_id}"))?;
+
+        assets.push(scan.assets[*index].clone());
+    }
+
+    Ok(assets)
+}
+
+fn get_asset_from_state(
+    state: &State<'_, AppState>,
+    scan_id: &str,
+    asset_id: &str,
+) -> Result<AssetRecord, String> {
+    let asset_ids = vec![asset_id.to_string()];
+
+    collect_assets(state, scan_id, &asset_ids)
+        .map(|mut assets| assets.remove(0))
+        .map_err(|error| error.to_string())
+}
+
+fn resolve_operation_id(operation_id: Option<String>) -> String {
+    operation_id
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| Uuid::new_v4().to_string())
+}
+
+fn register_export_operation(
+    state: &State<'_, AppState>,
+    operation_id: &str,
+) -> Result<(), String> {
+    let mut operations = state
+        .export_operations
+        .lock()
+        .map_err(|_| "Failed to lock export operations state".to_string())?;
+    operations.insert(operation_id.to_string(), ExportOperationState::new());
+    Ok(())
+}
+
+fn unregister_export_operation(state: &State<'_, AppState>, operation_id: &str) {
+    if let Ok(mut operations) = state.export_operations.lock() {
+        operations.remove(operation_id);
+    }
+}
+
+fn is_export_cancelled(app: &AppHandle, operation_id: &str) -> bool {
+    let state = app.state::<AppState>();
+    let Ok(operations) = state.export_operations.lock() else {
+        return true;
+    };
+
+    operations
+        .get(operation_id)
+        .map(|operation| operation.cancelled)
+        .unwrap_or(false)
+}
+
+fn emit_scan_progress(app: &AppHandle, mut event: ScanProgressEvent) {
+    let state = app.state::<AppState>();
+    if let Ok(mut scans) = state.scans.lock() {
+        if let Some(scan) = scans.get_mut(&event.scan_id) {
+            scan.scanned_containers = event.scanned_containers;
+            scan.total_containers = event.total_containers;
+            if matches!(scan.status, ScanLifecycle::Scanning) {
+                scan.error = None;
+            }
+
+            let now = Instant::now();
+            scan.timing.record(now, event.scanned_containers);
+            event.elapsed_ms = scan.timing.elapsed_ms(now);
+            event.containers_per_second = scan.timing.containers_per_second;
+        }
+    }
+
+    let _ = app.emit("scan://progress", event);
+}
+
+fn emit_export_progress(app: &AppHandle, event: ExportProgressEvent) {
+    let _ = app.emit("export://progress", event);
+}
+
+fn emit_export_byte_progress(app: &AppHandle, event: ExportByteProgressEvent) {
+    let _ = app.emit("export://byte-progress", event);
+}
+
+fn emit_export_completed(app: &AppHandle, event: ExportCompletedEvent) {
+    let _ = app.emit("export://completed", event);
+}
+
+#[derive(Debug, Clone)]
+struct ExportOptions {
+    audio_format: AudioFormat,
+    audio_quality: AudioQuality,
+    normalize_audio: bool,
+    audio_resample: AudioResample,
+    image_format: ImageFormat,
+    /// JPEG has no alpha; transparent pixels are flattened onto this color.
+    image_background: [u8; 3],
+    naming: ExportNaming,
+    name_template: Option<NameTemplate>,
+    layout: ExportLayout,
+    /// Removes the run's output files when it ends cancelled.
+    cleanup_on_cancel: bool,
+    /// Leaves `export://completed` to the caller, which has more to do once
+    /// the files are written.
+    defer_completion: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NamePlaceholder {
+    Namespace,
+    Source,
+    Stem,
+    Ext,
+    Key,
+    /// 1-based position of the asset in the export batch.
+    Index,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum NameTemplatePart {
+    Literal(String),
+    Placeholder(NamePlaceholder),
+}
+
+/// A user filename pattern expanded per asset into the output file stem. The
+/// (possibly converted) extension is always appended, so a trailing `.{ext}`
+/// is accepted and dropped rather than doubling it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct NameTemplate {
+    parts: Vec<NameTemplatePart>,
+}
+
+impl NameTemplate {
+    fn parse(template: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while let Some(open) = rest.find('{') {
+            if open > 0 {
+                parts.push(NameTemplatePart::Literal(rest[..open].to_string()));
+            }
+            let close = rest[open..]
+                .find('}')
+                .map(|offset| open + offset)
+                .ok_or_else(|| format!("Unclosed placeholder in name template: {template}"))?;
+            let placeholder = match &rest[open + 1..close] {
+                "namespace" => NamePlaceholder::Namespace,
+                "source" => NamePlaceholder::Source,
+                "stem" => NamePlaceholder::Stem,
+                "ext" => NamePlaceholder::Ext,
+                "key" => NamePlaceholder::Key,
+                "index" => NamePlaceholder::Index,
+                other => {
+                    return Err(format!(
+                        "Unknown name template placeholder {{{other}}}; expected one of {{namespace}}, {{source}}, {{stem}}, {{ext}}, {{key}}, {{index}}"
+                    ))
+                }
+            };
+            parts.push(NameTemplatePart::Placeholder(placeholder));
+            rest = &rest[close + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(NameTemplatePart::Literal(rest.to_string()));
+        }
+
+        if let [.., NameTemplatePart::Literal(literal), NameTemplatePart::Placeholder(NamePlaceholder::Ext)] =
+            parts.as_mut_slice()
+        {
+            if let Some(trimmed) = literal.strip_suffix('.') {
+                *literal = trimmed.to_string();
+                parts.pop();
+            }
+        }
+        if parts.is_empty() {
+            return Err("Name template must not be empty".to_string());
+        }
+
+        Ok(Self { parts })
+    }
+
+    fn render(&self, asset: &AssetRecord, stem: &str, extension: &str, index: usize) -> String {
+        let mut output = String::new();
+        for part in &self.parts {
+            match part {
+                NameTemplatePart::Literal(literal) => output.push_str(literal),
+                NameTemplatePart::Placeholder(placeholder) => match placeholder {
+                    NamePlaceholder::Namespace => output.push_str(&asset.namespace),
+                    NamePlaceholder::Source => output.push_str(&asset.source_name),
+                    NamePlaceholder::Stem => output.push_str(stem),
+                    NamePlaceholder::Ext => output.push_str(extension),
+                    NamePlaceholder::Key => {
+                        let (_, original_extension) = split_file_name(&asset.relative_asset_path);
+                        output.push_str(&key_file_stem(asset, &original_extension));
+                    }
+                    NamePlaceholder::Index => output.push_str(&(index + 1).to_string()),
+                },
+            }
+        }
+        sanitize_file_stem(&output)
+    }
+}
+
+/// Replaces characters Windows, macOS or Linux reject in file names and trims
+/// the trailing dots and spaces Windows silently drops.
+fn sanitize_file_stem(value: &str) -> String {
+    value
+        .chars()
+        .map(|character| {
+            if character.is_control() || "<>:\"/\\|?*".contains(character) {
+                '_'
+            } else {
+                character
+            }
+        })
+        .collect::<String>()
+        .trim_end_matches(['.', ' '])
+        .trim_start()
+        .to_string()
+}
+
+/// Where each exported file lands below the destination directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExportLayout {
+    Flat,
+    /// `<relative_asset_path>`, as the files sit inside `assets/<namespace>/`.
+    AssetPath,
+    /// `assets/<namespace>/<relative_asset_path>`, ready to drop into a resource pack.
+    /// Datapack files go under `data/<namespace>/` instead, where they came from.
+    ResourcePack,
+    /// `<source_name>/<namespace>/<relative_asset_path>`, one folder per source.
+    SourceTree,
+}
+
+impl ExportLayout {
+    fn target_dir(self, destination_dir: &Path, asset: &AssetRecord) -> PathBuf {
+        let parent = Path::new(&asset.relative_asset_path)
+            .parent()
+            .unwrap_or_else(|| Path::new(""));
+        match self {
+            ExportLayout::Flat => destination_dir.to_path_buf(),
+            ExportLayout::AssetPath => destination_dir.join(parent),
+            ExportLayout::ResourcePack => {
+                let root = match asset.source_type {
+                    AssetSourceType::Datapack => "data",
+                    _ => "assets",
+                };
+                destination_dir
+                    .join(root)
+                    .join(&asset.namespace)
+                    .join(parent)
+            }
+            ExportLayout::SourceTree => destination_dir
+                .join(&asset.source_name)
+                .join(&asset.namespace)
+                .join(parent),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ExportJob {
+    index: usize,
+    asset: AssetRecord,
+    output_path: PathBuf,
+}
+
+#[derive(Debug)]
+struct ExportRunOutcome {
+    output_files: Vec<String>,
+    /// Position in the input assets of each entry in `output_files`.
+    output_indices: Vec<usize>,
+    processed_count: usize,
+    success_count: usize,
+    failed_count: usize,
+    cancelled: bool,
+    failures: Vec<ExportFailure>,
+}
+
+#[derive(Debug)]
+enum ExportWorkerResult {
+    Success {
+        index: usize,
+        output_path: PathBuf,
+    },
+    Failure {
+        index: usize,
+        failure: ExportFailure,
+    },
+}
+
+fn plan_export_jobs(
+    assets: Vec<AssetRecord>,
+    destination_dir: &Path,
+    options: &ExportOptions,
+) -> Vec<ExportJob> {
+    let mut used_names = HashMap::<PathBuf, HashSet<String>>::new();
+    let mut jobs = Vec::new();
+
+    for (index, asset) in assets.into_iter().enumerate() {
+        let target_dir = options.layout.target_dir(destination_dir, &asset);
+        let original_name = Path::new(&asset.relative_asset_path)
+            .file_name()
+            .map(|value| value.to_string_lossy().to_string())
+            .unwrap_or_else(|| asset.asset_id.clone());
+
+        let (mut base_stem, mut extension) = split_file_name(&original_name);
+        if options.naming == ExportNaming::Key {
+            base_stem = key_file_stem(&asset, &extension);
+        }
+        if asset.is_audio {
+            if let Some(audio_extension) = options.audio_format.extension() {
+                extension = audio_extension.to_string();
+            }
+        }
+        if asset.is_image {
+            if let Some(image_extension) = options.image_format.extension() {
+                extension = image_extension.to_string();
+            }
+        }
+        if let Some(template) = &options.name_template {
+            let (original_stem, _) = split_file_name(&original_name);
+            let rendered = template.render(&asset, &original_stem, &extension, index);
+            if !rendered.is_empty() {
+                base_stem = rendered;
+            }
+        }
+
+        // Structured layouts mirror the source paths and overwrite on re-export;
+        // only fall back to suffixing when two assets in this batch would land on
+        // the same file (e.g. step.ogg and step.wav both converted to mp3).
+        let used_in_dir = used_names.entry(target_dir.clone()).or_default();
+        let plain_name = if extension.is_empty() {
+            base_stem.clone()
+        } else {
+            format!("{base_stem}.{extension}")
+        };
+        let target_name =
+            if options.layout != ExportLayout::Flat && used_in_dir.insert(plain_name.clone()) {
+                plain_name
+            } else {
+                dedupe_file_name(&base_stem, &extension, &target_dir, used_in_dir)
+            };
+        jobs.push(ExportJob {
+            index,
+            asset,
+            output_path: target_dir.join(target_name),
+        });
+    }
+
+    jobs
+}
+
+fn write_export_manifest(
+    destination_dir: &Path,
+    assets: &[AssetRecord],
+    outcome: &ExportRunOutcome,
+) -> Result<(), String> {
+    let manifest = build_export_manifest(destination_dir, assets, outcome);
+    let json = serde_json::to_vec_pretty(&manifest)
+        .map_err(|error| format!("Failed to serialize export manifest: {error}"))?;
+    let manifest_path = destination_dir.join(EXPORT_MANIFEST_FILE_NAME);
+    fs::write(&manifest_path, json).map_err(|error| {
+        format!(
+            "Failed to write export manifest {}: {error}",
+            manifest_path.display()
+        )
+    })
+}
+
+fn build_export_manifest(
+    destination_dir: &Path,
+    assets: &[AssetRecord],
+    outcome: &ExportRunOutcome,
+) -> ExportManifest {
+    let files = outcome
+        .output_indices
+        .iter()
+        .zip(&outcome.output_files)
+        .filter_map(|(index, output_file)| {
+            let asset = assets.get(*index)?;
+            let output_path = Path::new(output_file);
+            let file = output_path
+                .strip_prefix(destination_dir)
+                .unwrap_or(output_path);
+            Some(ExportManifestEntry {
+                file: normalize_archive_path(file),
+                asset_id: asset.asset_id.clone(),
+                key: asset.key.clone(),
+                namespace: asset.namespace.clone(),
+                source_name: asset.source_name.clone(),
+                source_type: asset.source_type.clone(),
+                container_path: asset.container_path.clone(),
+                relative_asset_path: asset.relative_asset_path.clone(),
+                resource_location: asset_resource_location(asset),
+            })
+        })
+        .collect();
+
+    ExportManifest { files }
+}
+
+/// Builds a filename stem from the asset key, dropping the trailing extension
+/// segment and turning internal `.dupN` bookkeeping into a plain `_N` suffix.
+fn key_file_stem(asset: &AssetRecord, extension: &str) -> String {
+    let (base_key, dup_index) = parse_dup_suffix(&asset.key);
+    let extension_segment = format!("_{}", normalize_key_segment(extension));
+    let stem = if extension.is_empty() {
+        base_key.as_str()
+    } else {
+        base_key
+            .strip_suffix(&extension_segment)
+            .unwrap_or(base_key.as_str())
+    };
+
+    match dup_index {
+        Some(index) => format!("{stem}_{index}"),
+        None => stem.to_string(),
+    }
+}
+
+fn run_export_operation(
+    app: &AppHandle,
+    kind: ExportOperationKind,
+    operation_id: &str,
+    assets: Vec<AssetRecord>,
+    destination_dir: &Path,
+    options: ExportOptions,
+) -> Result<ExportRunOutcome, String> {
+    let jobs = plan_export_jobs(assets, destination_dir, &options);
+    let audio_format = &options.audio_format;
+    let image_format = options.image_format;
+    let requested_count = jobs.len();
+
+    if requested_count == 0 {
+        emit_export_progress(
+            app,
+            ExportProgressEvent {
+                operation_id: operation_id.to_string(),
+                kind: kind.clone(),
+                requested_count,
+                processed_count: 0,
+                success_count: 0,
+                failed_count: 0,
+                cancelled: false,
+            },
+        );
+        if !options.defer_completion {
+            emit_export_completed(
+                app,
+                ExportCompletedEvent {
+                    operation_id: operation_id.to_string(),
+                    kind,
+                    requested_count,
+                    processed_count: 0,
+                    success_count: 0,
+                    failed_count: 0,
+                    cancelled: false,
+                    failures: Vec::new(),
+                },
+            );
+        }
+        return Ok(ExportRunOutcome {
+            output_files: Vec::new(),
+            output_indices: Vec::new(),
+            processed_count: 0,
+            success_count: 0,
+            failed_count: 0,
+            cancelled: false,
+            failures: Vec::new(),
+        });
+    }
+
+    let should_convert_audio =
+        *audio_format != AudioFormat::Original && jobs.iter().any(|job| job.asset.is_audio);
+    let should_convert_webp =
+        image_format == ImageFormat::Webp && jobs.iter().any(|job| job.asset.is_image);
+    let ffmpeg_path = if should_convert_audio || should_convert_webp {
+        Some(resolve_ffmpeg_path(app)?)
+    } else {
+        None
+    };
+
+    emit_export_progress(
+        app,
+        ExportProgressEvent {
+            operation_id: operation_id.to_string(),
+            kind: kind.clone(),
+            requested_count,
+            processed_count: 0,
+            success_count: 0,
+            failed_count: 0,
+            cancelled: false,
+        },
+    );
+
+    let workers = thread::available_parallelism()
+        .map(|value| value.get())
+        .unwrap_or(1)
+        .clamp(1, worker_limits(app).export_workers)
+        .min(requested_count);
+
+    let mut archives = HashMap::new();
+    let job_sizes = jobs
+        .iter()
+        .map(|job| asset_source_size(&job.asset, &mut archives))
+        .collect::<Vec<_>>();
+    drop(archives);
+    let total_bytes = job_sizes.iter().copied().sum::<Option<u64>>();
+    let (sender, receiver) = mpsc::channel::<ExportWorkerResult>();
+    let jobs = Arc::new(jobs);
+    let job_sizes = Arc::new(job_sizes);
+    let next_index = Arc::new(AtomicUsize::new(0));
+    let processed_bytes = Arc::new(AtomicU64::new(0));
+    let operation_id_owned = operation_id.to_string();
+
+    for _ in 0..workers {
+        let sender = sender.clone();
+        let jobs = Arc::clone(&jobs);
+        let job_sizes = Arc::clone(&job_sizes);
+        let next_index = Arc::clone(&next_index);
+        let processed_bytes = Arc::clone(&processed_bytes);
+        let app = app.clone();
+        let operation_id = operation_id_owned.clone();
+        let ffmpeg_path = ffmpeg_path.clone();
+        let options = options.clone();
+        let destination_dir = destination_dir.to_path_buf();
+
+        thread::spawn(move || {
+            let mut archive_cache = ArchiveCache::new();
+
+            loop {
+                if is_export_cancelled(&app, &operation_id) {
+                    break;
+                }
+
+                let index = next_index.fetch_add(1, AtomicOrdering::Relaxed);
+                if index >= jobs.len() {
+                    break;
+                }
+
+                let job = &jobs[index];
+                let progress = ByteProgress::new(&processed_bytes);
+                let result = materialize_export_job(
+                    job,
+                    &destination_dir,
+                    &options,
+                    ffmpeg_path.as_deref(),
+                    &mut archive_cache,
+                    &progress,
+                );
+                // Failed jobs count too, so the bytes reach the total with the count.
+                progress.finish(job_sizes[index]);
+
+                let worker_message = match result {
+                    Ok(path) => ExportWorkerResult::Success {
+                        index: job.index,
+                        output_path: path,
+                    },
+                    Err(error) => ExportWorkerResult::Failure {
+                        index: job.index,
+                        failure: ExportFailure {
+                            asset_id: job.asset.asset_id.clone(),
+                            key: job.asset.key.clone(),
+                            error,
+                        },
+                    },
+                };
+
+                if sender.send(worker_message).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    drop(sender);
+
+    let mut processed_count = 0usize;
+    let mut success_count = 0usize;
+    let mut failed_count = 0usize;
+    let mut failures = Vec::<ExportFailure>::new();
+    let mut output_files = vec![None; requested_count];
+    // Progress events are throttled; the last state is always flushed below.
+    let mut last_progress_emit_at: Option<Instant> = None;
+    let mut emitted_processed_count = 0usize;
+    let mut last_emitted_bytes = 0u64;
+    let mut last_byte_emit_at: Option<Instant> = None;
+    let mut emit_byte_progress = |force: bool| {
+        let bytes = processed_bytes.load(AtomicOrdering::Relaxed);
+        let due = last_byte_emit_at
+            .map(|last| last.elapsed() >= EXPORT_PROGRESS_THROTTLE)
+            .unwrap_or(true);
+        if bytes == last_emitted_bytes || !(force || due) {
+            return;
+        }
+        last_emitted_bytes = bytes;
+        last_byte_emit_at = Some(Instant::now());
+        emit_export_byte_progress(
+            app,
+            ExportByteProgressEvent {
+                operation_id: operation_id.to_string(),
+                kind: kind.clone(),
+                processed_bytes: bytes,
+                total_bytes,
+            },
+        );
+    };
+
+    while processed_count < requested_count {
+        emit_byte_progress(false);
+        match receiver.recv_timeout(Duration::from_millis(100)) {
+            Ok(ExportWorkerResult::Success { index, output_path }) => {
+                processed_count += 1;
+                success_count += 1;
+                output_files[index] = Some(output_path.to_string_lossy().to_string());
+            }
+            Ok(ExportWorkerResult::Failure { index, failure }) => {
+                processed_count += 1;
+                failed_count += 1;
+                output_files[index] = None;
+                failures.push(failure);
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                if is_export_cancelled(app, operation_id) {
+                    continue;
+                }
+                continue;
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+
+        let now = Instant::now();
+        let elapsed = last_progress_emit_at
+            .map(|last| now.saturating_duration_since(last))
+            .unwrap_or(EXPORT_PROGRESS_THROTTLE);
+        if processed_count < requested_count && elapsed < EXPORT_PROGRESS_THROTTLE {
+            continue;
+        }
+        last_progress_emit_at = Some(now);
+        emitted_processed_count = processed_count;
+        emit_export_progress(
+            app,
+            ExportProgressEvent {
+                operation_id: operation_id.to_string(),
+                kind: kind.clone(),
+                requested_count,
+                processed_count,
+                success_count,
+                failed_count,
+                cancelled: is_export_cancelled(app, operation_id),
+            },
+        );
+    }
+
+    while let Ok(result) = receiver.try_recv() {
+        match result {
+            ExportWorkerResult::Success { index, output_path } => {
+                processed_count += 1;
+                success_count += 1;
+                output_files[index] = Some(output_path.to_string_lossy().to_string());
+            }
+            ExportWorkerResult::Failure { index, failure } => {
+                processed_count += 1;
+                failed_count += 1;
+                output_files[index] = None;
+                failures.push(failure);
+            }
+        }
+    }
+
+    emit_byte_progress(true);
+
+    let cancelled = is_export_cancelled(app, operation_id);
+    if processed_count != emitted_processed_count {
+        emit_export_progress(
+            app,
+            ExportProgressEvent {
+                operation_id: operation_id.to_string(),
+                kind: kind.clone(),
+                requested_count,
+                processed_count,
+                success_count,
+                failed_count,
+                cancelled,
+            },
+        );
+    }
+    if processed_count < requested_count && !cancelled {
+        return Err("Export workers disconnected before processing all assets".to_string());
+    }
+    // Workers finish their in-flight job before stopping, so by now every file
+    // this run wrote has been reported.
+    if cancelled && options.cleanup_on_cancel {
+        remove_export_outputs(output_files.iter_mut().map(Option::take));
+    }
+
+    let (output_indices, output_files) = output_files
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, path)| Some((index, path?)))
+        .unzip::<_, _, Vec<_>, Vec<_>>();
+    if !options.defer_completion {
+        emit_export_completed(
+            app,
+            ExportCompletedEvent {
+                operation_id: operation_id.to_string(),
+                kind: kind.clone(),
+                requested_count,
+                processed_count,
+                success_count,
+                failed_count,
+                cancelled,
+                failures: failures.clone(),
+            },
+        );
+    }
+
+    Ok(ExportRunOutcome {
+        output_files,
+        output_indices,
+        processed_count,
+        success_count,
+        failed_count,
+        cancelled,
+        failures,
+    })
+}
+
+fn remove_export_outputs(paths: impl Iterator<Item = Option<String>>) {
+    for path in paths.flatten() {
+        if let Err(error) = fs::remove_file(&path) {
+            log::warn!("failed to remove cancelled export output {path}: {error}");
+        }
+    }
+}
+
+fn materialize_export_job(
+    job: &ExportJob,
     destination_dir: &Path,
-    audio_format: AudioFormat,
-) -> Result<ExportRunOutcome, String> {
-    let jobs = plan_export_jobs(assets, destination_dir, audio_format.clone());
-    let requested_count = jobs.len();
+    options: &ExportOptions,
+    ffmpeg_path: Option<&Path>,
+    archive_cache: &mut ArchiveCache,
+    progress: &ByteProgress,
+) -> Result<PathBuf, String> {
+    ensure_inside_destination(&job.output_path, destination_dir)?;
+    let audio_format = &options.audio_format;
+    let image_format = options.image_format;
+    let bytes = extract_asset_bytes_with_archive_cache(&job.asset, archive_cache)?;
+    if let Some(parent) = job.output_path.parent() {
+        fs::create_dir_all(parent).map_err(|error| {
+            format!(
+                "Failed to create output directory {}: {error}",
+                parent.display()
+            )
+        })?;
+    }
+
+    if job.asset.is_audio && *audio_format != AudioFormat::Original {
+        let ffmpeg_path = ffmpeg_path.ok_or_else(|| "FFmpeg path was not resolved".to_string())?;
+        convert_audio_bytes_to_file(
+            ffmpeg_path,
+            &bytes,
+            &job.output_path,
+            AudioConversion {
+                format: audio_format,
+                quality: options.audio_quality,
+                normalize: options.normalize_audio,
+                resample: options.audio_resample,
+                trim: AudioTrim::default(),
+            },
+            Some(progress),
+        )?;
+    } else if job.asset.is_image && image_format != ImageFormat::Original {
+        convert_image_bytes_to_file(
+            &bytes,
+            image_format,
+            options.image_background,
+            ffmpeg_path,
+            &job.output_path,
+        )?;
+    } else {
+        fs::File::create(&job.output_path)
+            .and_then(|file| CountingWriter::new(file, Some(progress)).write_all(&bytes))
+            .map_err(|error| {
+                format!(
+                    "Failed to write output file {}: {error}",
+                    job.output_path.display()
+                )
+            })?;
+    }
+
+    Ok(job.output_path.clone())
+}
+
+/// One export job's share of the operation's byte progress.
+struct ByteProgress<'a> {
+    processed: &'a AtomicU64,
+    counted: Cell<u64>,
+}
+
+impl<'a> ByteProgress<'a> {
+    fn new(processed: &'a AtomicU64) -> Self {
+        Self {
+            processed,
+            counted: Cell::new(0),
+        }
+    }
+
+    fn add(&self, bytes: u64) {
+        self.counted.set(self.counted.get() + bytes);
+        self.processed.fetch_add(bytes, AtomicOrdering::Relaxed);
+    }
+
+    /// Tops the job up to its source size; image conversions report nothing
+    /// until here.
+    fn finish(&self, source_size: Option<u64>) {
+        let remaining = source_size.unwrap_or(0).saturating_sub(self.counted.get());
+        self.add(remaining);
+    }
+}
+
+/// Splits writes into `EXPORT_WRITE_CHUNK_BYTES` pieces and reports each one.
+struct CountingWriter<'a, W> {
+    inner: W,
+    progress: Option<&'a ByteProgress<'a>>,
+}
+
+impl<'a, W: Write> CountingWriter<'a, W> {
+    fn new(inner: W, progress: Option<&'a ByteProgress<'a>>) -> Self {
+        Self { inner, progress }
+    }
+}
+
+impl<W: Write> Write for CountingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let chunk = &buf[..buf.len().min(EXPORT_WRITE_CHUNK_BYTES)];
+        let written = self.inner.write(chunk)?;
+        if let Some(progress) = self.progress {
+            progress.add(written as u64);
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// How one ffmpeg run encodes its output, built from already validated options.
+#[derive(Debug, Clone, Copy)]
+struct AudioConversion<'a> {
+    format: &'a AudioFormat,
+    quality: AudioQuality,
+    normalize: bool,
+    resample: AudioResample,
+    trim: AudioTrim,
+}
+
+fn convert_audio_bytes_to_file(
+    ffmpeg_path: &Path,
+    input_bytes: &[u8],
+    output_path: &Path,
+    conversion: AudioConversion,
+    progress: Option<&ByteProgress>,
+) -> Result<(), String> {
+    let AudioConversion {
+        format,
+        quality,
+        normalize,
+        resample,
+        trim,
+    } = conversion;
+    let mut command = Command::new(ffmpeg_path);
+    command.arg("-y");
+    command.arg("-hide_banner");
+    command.arg("-loglevel");
+    command.arg("error");
+    // Both bounds are input options so `-to` stays relative to the source
+    // rather than to the already-seeked output; stream copies can't be cut.
+    if *format != AudioFormat::Original {
+        if let Some(start_ms) = trim.start_ms {
+            command.arg("-ss");
+            command.arg(ffmpeg_seconds(start_ms));
+        }
+        if let Some(end_ms) = trim.end_ms {
+            command.arg("-to");
+            command.arg(ffmpeg_seconds(end_ms));
+        }
+    }
+    command.arg("-i");
+    command.arg("pipe:0");
+    command.arg("-vn");
+    if normalize {
+        command.arg("-af");
+        command.arg(AUDIO_LOUDNORM_FILTER);
+    }
+    if *format != AudioFormat::Original {
+        if let Some(sample_rate) = resample.sample_rate {
+            command.arg("-ar");
+            command.arg(sample_rate.to_string());
+        }
+        if let Some(channels) = resample.channels {
+            command.arg("-ac");
+            command.arg(channels.to_string());
+        }
+    }
+
+    match format {
+        AudioFormat::Original => {
+            command.arg("-c:a");
+            command.arg("copy");
+        }
+        AudioFormat::Mp3 => {
+            command.arg("-c:a");
+            command.arg("libmp3lame");
+            match quality.bitrate_kbps {
+                Some(bitrate) => {
+                    command.arg("-b:a");
+                    command.arg(format!("{bitrate}k"));
+                }
+                None => {
+                    command.arg("-q:a");
+                    command.arg(quality.mp3_vbr_quality.unwrap_or(2).to_string());
+                }
+            }
+        }
+        AudioFormat::Wav => {
+            command.arg("-c:a");
+            command.arg("pcm_s16le");
+        }
+        AudioFormat::Flac => {
+            command.arg("-c:a");
+            command.arg("flac");
+        }
+        AudioFormat::Opus => {
+            command.arg("-c:a");
+            command.arg("libopus");
+            command.arg("-b:a");
+            command.arg(format!("{}k", quality.bitrate_kbps.unwrap_or(96)));
+        }
+    }
+
+    command.arg(output_path);
+    run_ffmpeg_with_input(command, input_bytes, progress).map_err(|error| {
+        // Minimal ffmpeg builds may ship without libopus; say so instead of
+        // surfacing only ffmpeg's terse "Unknown encoder".
+        if error.contains("Unknown encoder") {
+            format!("{error} (this ffmpeg build has no encoder for {format:?})")
+        } else {
+            error
+        }
+    })
+}
+
+/// Decoding failures surface as per-asset errors so one bad texture does not
+/// abort the whole export. The bundled `image` build has no WebP encoder, so
+/// WebP goes through ffmpeg as lossless (alpha preserved) from a PNG pipe.
+fn convert_image_bytes_to_file(
+    input_bytes: &[u8],
+    format: ImageFormat,
+    background: [u8; 3],
+    ffmpeg_path: Option<&Path>,
+    output_path: &Path,
+) -> Result<(), String> {
+    let image = decode_rgba_image(input_bytes)?;
+    let encoded = match format {
+        ImageFormat::Original => input_bytes.to_vec(),
+        ImageFormat::Png => encode_rgba_image(&image, RasterFormat::Png)?,
+        ImageFormat::Jpeg => {
+            let flattened = composite_over_background(&image, background);
+            let rgb = image::DynamicImage::ImageRgba8(flattened).to_rgb8();
+            let mut buffer = Cursor::new(Vec::new());
+            rgb.write_to(&mut buffer, RasterFormat::Jpeg)
+                .map_err(|error| format!("Failed to encode image: {error}"))?;
+            buffer.into_inner()
+        }
+        ImageFormat::Webp => {
+            let ffmpeg_path =
+                ffmpeg_path.ok_or_else(|| "FFmpeg path was not resolved".to_string())?;
+            let png = encode_rgba_image(&image, RasterFormat::Png)?;
+            let mut command = Command::new(ffmpeg_path);
+            command.args(["-y", "-hide_banner", "-loglevel", "error"]);
+            command.args(["-f", "image2pipe", "-c:v", "png", "-i", "pipe:0"]);
+            command.args(["-c:v", "libwebp", "-lossless", "1", "-frames:v", "1"]);
+            command.arg(output_path);
+            return run_ffmpeg_with_input(command, &png, None);
+        }
+    };
+
+    fs::write(output_path, encoded).map_err(|error| {
+        format!(
+            "Failed to write output file {}: {error}",
+            output_path.display()
+        )
+    })
+}
+
+/// Like `run_ffmpeg_with_input` but returns stdout. Input is fed from a separate
+/// thread so a large decoded output can't deadlock against a full stdin pipe.
+fn run_ffmpeg_capture(mut command: Command, input_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    command.stdin(Stdio::piped());
+    command.stdout(Stdio::piped());
+    command.stderr(Stdio::piped());
+
+    let mut child = command
+        .spawn()
+        .map_err(|error| format!("Failed to start ffmpeg: {error}"))?;
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| "Failed to open ffmpeg stdin".to_string())?;
+
+    let output = thread::scope(|scope| {
+        // ffmpeg may stop reading early on bad input; its stderr explains why.
+        scope.spawn(move || {
+            let _ = stdin.write_all(input_bytes);
+        });
+        child.wait_with_output()
+    })
+    .map_err(|error| format!("Failed to wait for ffmpeg: {error}"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("FFmpeg decoding failed: {}", stderr.trim()));
+    }
+
+    Ok(output.stdout)
+}
+
+fn run_ffmpeg_with_input(
+    mut command: Command,
+    input_bytes: &[u8],
+    progress: Option<&ByteProgress>,
+) -> Result<(), String> {
+    command.stdin(Stdio::piped());
+    command.stdout(Stdio::null());
+    command.stderr(Stdio::piped());
+
+    let mut child = command
+        .spawn()
+        .map_err(|error| format!("Failed to start ffmpeg: {error}"))?;
+
+    {
+        let stdin = child
+            .stdin
+            .as_mut()
+            .ok_or_else(|| "Failed to open ffmpeg stdin".to_string())?;
+        CountingWriter::new(stdin, progress)
+            .write_all(input_bytes)
+            .map_err(|error| format!("Failed to stream audio data to ffmpeg: {error}"))?;
+    }
+
+    let output = child
+        .wait_with_output()
+        .map_err(|error| format!("Failed to wait for ffmpeg: {error}"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("FFmpeg conversion failed: {}", stderr.trim()));
+    }
+
+    Ok(())
+}
+
+fn split_file_name(file_name: &str) -> (String, String) {
+    let path = Path::new(file_name);
+    let stem = path
+        .file_stem()
+        .map(|value| value.to_string_lossy().to_string())
+        .unwrap_or_else(|| "asset".to_string());
+    let extension = path
+        .extension()
+        .map(|value| value.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+
+    (stem, extension)
+}
+
+fn dedupe_file_name(
+    base_stem: &str,
+    extension: &str,
+    destination_dir: &Path,
+    used_names: &mut HashSet<String>,
+) -> String {
+    let mut index = 0;
+
+    loop {
+        let candidate = if index == 0 {
+            if extension.is_empty() {
+                base_stem.to_string()
+            } else {
+                format!("{base_stem}.{extension}")
+            }
+        } else if extension.is_empty() {
+            format!("{base_stem}_{index}")
+        } else {
+            format!("{base_stem}_{index}.{extension}")
+        };
+
+        if used_names.contains(&candidate) || destination_dir.join(&candidate).exists() {
+            index += 1;
+            continue;
+        }
+
+        used_names.insert(candidate.clone());
+        return candidate;
+    }
+}
+
+fn resolve_ffmpeg_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if ffmpeg_works(Path::new("ffmpeg")) {
+        return Ok(PathBuf::from("ffmpeg"));
+    }
+
+    let base_dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|error| format!("Failed to resolve app cache directory: {error}"))?
+        .join("ffmpeg-runtime");
+
+    fs::create_dir_all(&base_dir)
+        .map_err(|error| format!("Failed to create FFmpeg runtime directory: {error}"))?;
+
+    let ffmpeg_binary = if cfg!(windows) {
+        base_dir.join("ffmpeg.exe")
+    } else {
+        base_dir.join("ffmpeg")
+    };
+
+    if ffmpeg_works(&ffmpeg_binary) {
+        return Ok(ffmpeg_binary);
+    }
+
+    let url =
+        ffmpeg_download_url().map_err(|error| format!("Failed to resolve FFmpeg URL: {error}"))?;
+    let archive_path = download_ffmpeg_package(url, &base_dir)
+        .map_err(|error| format!("Failed to download FFmpeg runtime: {error}"))?;
+    unpack_ffmpeg(&archive_path, &base_dir)
+        .map_err(|error| format!("Failed to unpack FFmpeg runtime: {error}"))?;
+
+    if !ffmpeg_works(&ffmpeg_binary) {
+        return Err(
+            "FFmpeg was downloaded but is not executable. Install FFmpeg manually and add it to PATH."
+                .to_string(),
+        );
+    }
+
+    Ok(ffmpeg_binary)
+}
+
+/// Prefers ffprobe on PATH, then the one unpacked next to the resolved ffmpeg.
+fn resolve_ffprobe_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if ffmpeg_works(Path::new("ffprobe")) {
+        return Ok(PathBuf::from("ffprobe"));
+    }
+
+    let ffprobe_binary = resolve_ffmpeg_path(app)?.with_file_name(if cfg!(windows) {
+        "ffprobe.exe"
+    } else {
+        "ffprobe"
+    });
+    if !ffmpeg_works(&ffprobe_binary) {
+        return Err(
+            "FFprobe was not found next to FFmpeg. Install FFmpeg with ffprobe and add it to PATH."
+                .to_string(),
+        );
+    }
+
+    Ok(ffprobe_binary)
+}
+
+fn ffmpeg_works(path: &Path) -> bool {
+    let mut command = Command::new(path);
+    command.arg("-version");
+
+    command
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+/// Reader behind cached archives. Exports pull many entries out of one jar, and
+/// reading them from a memory map avoids a seek and read syscall per entry;
+/// files that can't be mapped (on some network filesystems, for one) are read
+/// through the plain file handle instead.
+enum ArchiveReader {
+    Mapped(Cursor<Mmap>),
+    File(fs::File),
+}
+
+impl ArchiveReader {
+    /// Maps the archive for a short-lived reader, such as one export worker's.
+    fn open(path: &Path) -> std::io::Result<Self> {
+        let file = fs::File::open(path)?;
+        // SAFETY: the map is read-only. A jar truncated in place while mapped
+        // faults on access (SIGBUS), which nothing here can rule out, so maps
+        // only back per-operation handles and never `ArchiveHandleCache`.
+        match unsafe { Mmap::map(&file) } {
+            Ok(map) => Ok(Self::Mapped(Cursor::new(map))),
+            Err(_) => Ok(Self::File(file)),
+        }
+    }
+
+    /// For handles kept across commands.
+    fn open_file(path: &Path) -> std::io::Result<Self> {
+        fs::File::open(path).map(Self::File)
+    }
+}
+
+impl Read for ArchiveReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Mapped(reader) => reader.read(buf),
+            Self::File(reader) => reader.read(buf),
+        }
+    }
+}
+
+impl Seek for ArchiveReader {
+    fn seek(&mut self, position: std::io::SeekFrom) -> std::io::Result<u64> {
+        match self {
+            Self::Mapped(reader) => reader.seek(position),
+            Self::File(reader) => reader.seek(position),
+        }
+    }
+}
+
+#[cfg(test)]
+fn extract_asset_bytes(asset: &AssetRecord) -> Result<Vec<u8>, String> {
+    let mut archive_cache = ArchiveCache::new();
+    extract_asset_bytes_with_archive_cache(asset, &mut archive_cache)
+}
+
+fn extract_asset_bytes_with_archive_cache(
+    asset: &AssetRecord,
+    archive_cache: &mut ArchiveCache,
+) -> Result<Vec<u8>, String> {
+    let container_path = PathBuf::from(&asset.container_path);
+
+    match asset.container_type {
+        AssetContainerType::Directory => {
+            let file_path = container_path.join(Path::new(&asset.entry_path));
+            fs::read(&file_path)
+                .map_err(|error| format!("Failed to read file {}: {error}", file_path.display()))
+        }
+        AssetContainerType::AssetIndex => Err(
+            "AssetIndex container type is metadata-only and cannot be extracted directly"
+                .to_string(),
+        ),
+        AssetContainerType::Zip | AssetContainerType::Jar => {
+            if !archive_cache.contains_key(&asset.container_path) {
+                let reader = ArchiveReader::open(&container_path).map_err(|error| {
+                    format!(
+                        "Failed to open archive {}: {error}",
+                        container_path.display()
+                    )
+                })?;
+                let archive = match ZipArchive::new(reader) {
+                    Ok(archive) => CachedArchive::Zip(archive),
+                    // Assets salvaged by `salvage_archive_container` are read back
+                    // from the same local headers.
+                    Err(error) => SalvagedArchive::index(&container_path)
+                        .map(CachedArchive::Salvaged)
+                        .map_err(|salvage_error| {
+                            format!(
+                                "Failed to read archive {}: {error} ({salvage_error})",
+                                container_path.display()
+                            )
+                        })?,
+                };
+                archive_cache.insert(asset.container_path.clone(), archive);
+            }
+
+            let archive = match archive_cache
+                .get_mut(&asset.container_path)
+                .ok_or_else(|| "Failed to get cached archive".to_string())?
+            {
+                CachedArchive::Zip(archive) => archive,
+                CachedArchive::Salvaged(archive) => return archive.read(&asset.entry_path),
+            };
+
+            let mut segments = split_nested_entry_path(&asset.entry_path).into_iter();
+            let first = segments.next().unwrap_or_default();
+            let mut buffer = read_archive_entry(archive, first)?;
+            for segment in segments {
+                let mut nested = ZipArchive::new(Cursor::new(buffer)).map_err(|error| {
+                    format!(
+                        "Failed to read nested archive in {}: {error}",
+                        asset.entry_path
+                    )
+                })?;
+                buffer = read_archive_entry(&mut nested, segment)?;
+            }
+
+            Ok(buffer)
+        }
+    }
+}
+
+fn read_archive_entry<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    entry_path: &str,
+) -> Result<Vec<u8>, String> {
+    let mut entry = archive
+        .by_name(entry_path)
+        .map_err(|error| format!("Failed to open archive entry {entry_path}: {error}"))?;
+
+    let mut buffer = Vec::new();
+    entry
+        .read_to_end(&mut buffer)
+        .map_err(|error| format!("Failed to read archive entry {entry_path}: {error}"))?;
+
+    Ok(buffer)
+}
+
+/// Splits `outer.jar!inner/path` at `!` separators that follow a `.jar` segment,
+/// leaving any other `!` in entry names untouched.
+fn split_nested_entry_path(entry_path: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    for (position, _) in entry_path.match_indices('!') {
+        if entry_path[start..position]
+            .to_ascii_lowercase()
+            .ends_with(".jar")
+        {
+            segments.push(&entry_path[start..position]);
+            start = position + 1;
+        }
+    }
+    segments.push(&entry_path[start..]);
+    segments
+}
+
+/// Metadata-only containers (asset indexes) describe assets but hold no bytes.
+fn is_extractable_container_type(container_type: &AssetContainerType) -> bool {
+    !matches!(container_type, AssetContainerType::AssetIndex)
+}
+
+/// Accepts `#rgb` or `#rrggbb` (the `#` is optional).
+fn parse_background_color(value: &str) -> Result<[u8; 3], String> {
+    let hex = value.trim().trim_start_matches('#');
+    if !hex.chars().all(|ch| ch.is_ascii_hexdigit()) {
+        return Err(format!("Invalid background color: {value}"));
+    }
+    let expanded = match hex.len() {
+        3 => hex.chars().flat_map(|ch| [ch, ch]).collect::<String>(),
+        6 => hex.to_string(),
+        _ => return Err(format!("Invalid background color: {value}")),
+    };
+
+    let mut color = [0u8; 3];
+    for (index, channel) in color.iter_mut().enumerate() {
+        *channel = u8::from_str_radix(&expanded[index * 2..index * 2 + 2], 16)
+            .map_err(|_| format!("Invalid background color: {value}"))?;
+    }
+    Ok(color)
+}
+
+/// TGA has no magic number to guess from, so it's tried last.
+fn decode_rgba_image(bytes: &[u8]) -> Result<RgbaImage, String> {
+    image::load_from_memory(bytes)
+        .or_else(|error| {
+            image::load_from_memory_with_format(bytes, RasterFormat::Tga).map_err(|_| error)
+        })
+        .map(|image| image.to_rgba8())
+        .map_err(|error| format!("Failed to decode image: {error}"))
+}
+
+fn encode_rgba_image(image: &RgbaImage, format: RasterFormat) -> Result<Vec<u8>, String> {
+    let mut buffer = Cursor::new(Vec::new());
+    image
+        .write_to(&mut buffer, format)
+        .map_err(|error| format!("Failed to encode image: {error}"))?;
+    Ok(buffer.into_inner())
+}
+
+fn export_image_background(background: Option<&str>) -> Result<[u8; 3], String> {
+    background
+        .map(parse_background_color)
+        .transpose()
+        .map(|color| color.unwrap_or(JPEG_DEFAULT_BACKGROUND))
+}
+
+/// Flattens alpha onto a solid color so transparent pixels take the background.
+fn composite_over_background(image: &RgbaImage, background: [u8; 3]) -> RgbaImage {
+    let mut output = image.clone();
+    for pixel in output.pixels_mut() {
+        let alpha = u16::from(pixel[3]);
+        for channel in 0..3 {
+            let foreground = u16::from(pixel[channel]) * alpha;
+            let backdrop = u16::from(background[channel]) * (255 - alpha);
+            pixel[channel] = ((foreground + backdrop + 127) / 255) as u8;
+        }
+        pixel[3] = 255;
+    }
+    output
+}
+
+fn mime_for_extension(extension: &str) -> &'static str {
+    match extension {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "tif" | "tiff" => "image/tiff",
+        "ico" => "image/x-icon",
+        "tga" => "image/x-tga",
+        "ogg" | "oga" => "audio/ogg",
+        "wav" => "audio/wav",
+        "mp3" => "audio/mpeg",
+        "flac" => "audio/flac",
+        "opus" => "audio/opus",
+        "m4a" => "audio/mp4",
+        "aac" => "audio/aac",
+        "json" | "mcmeta" => "application/json",
+        extension if is_text_extension(extension) => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+fn is_image_extension(extension: &str) -> bool {
+    matches!(
+        extension,
+        "png" | "jpg" | "jpeg" | "gif" | "webp" | "bmp" | "tga" | "tif" | "tiff" | "ico"
+    )
+}
+
+/// Image formats shader packs and mods ship that neither the webview nor the
+/// `image` features enabled here can decode.
+fn is_unpreviewable_extension(extension: &str) -> bool {
+    matches!(
+        extension,
+        "exr" | "hdr" | "dds" | "psd" | "ktx" | "ktx2" | "jxl" | "heic"
+    )
+}
+
+fn is_audio_extension(extension: &str) -> bool {
+    matches!(
+        extension,
+        "ogg" | "wav" | "mp3" | "flac" | "m4a" | "aac" | "opus" | "oga"
+    )
+}
+
+fn is_shader_extension(extension: &str) -> bool {
+    matches!(extension, "fsh" | "vsh" | "gsh" | "csh" | "glsl")
+}
+
+fn is_json_extension(extension: &str) -> bool {
+    matches!(extension, "json" | "mcmeta")
+}
+
+fn is_text_extension(extension: &str) -> bool {
+    matches!(
+        extension,
+        "txt"
+            | "lang"
+            | "properties"
+            | "cfg"
+            | "toml"
+            | "md"
+            | "fsh"
+            | "vsh"
+            | "glsl"
+            | "obj"
+            | "mtl"
+    )
+}
+
+/// Decodes at most `TEXT_PREVIEW_MAX_BYTES` as UTF-8, replacing invalid
+/// sequences (including a character split by the cut) rather than failing.
+fn text_preview(bytes: &[u8]) -> (String, bool) {
+    let truncated = bytes.len() > TEXT_PREVIEW_MAX_BYTES;
+    let shown = &bytes[..bytes.len().min(TEXT_PREVIEW_MAX_BYTES)];
+    (String::from_utf8_lossy(shown).into_owned(), truncated)
+}
+
+/// Re-indents JSON with two spaces, keeping key order. Parse errors carry the
+/// line and column so the preview can point at the broken spot.
+fn pretty_print_json(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    // Some packs are saved by editors that prepend a UTF-8 BOM.
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    let value = serde_json::from_slice::<serde_json::Value>(bytes).map_err(|error| {
+        format!(
+            "Invalid JSON at line {}, column {}: {error}",
+            error.line(),
+            error.column()
+        )
+    })?;
+    serde_json::to_vec_pretty(&value).map_err(|error| format!("Failed to format JSON: {error}"))
+}
+
+fn dedupe_candidates(
+    candidates: Vec<PrismRootCandidate>,
+) -> Result<Vec<PrismRootCandidate>, String> {
+    let mut seen = HashSet::new();
+    let mut deduped = Vec::new();
+
+    for candidate in candidates {
+        if seen.insert(candidate.path.clone()) {
+            deduped.push(candidate);
+        }
+    }
+
+    if deduped.is_empty() {
+        return Err("No launcher candidates were found on this machine".to_string());
+    }
+
+    Ok(deduped)
+}
+
+fn build_candidate(path: PathBuf, source: &str, launcher: LauncherKind) -> PrismRootCandidate {
+    let exists = path.exists();
+    let valid = launcher.is_valid_root(&path);
+
+    PrismRootCandidate {
+        path: path.to_string_lossy().to_string(),
+        exists,
+        valid,
+        source: source.to_string(),
+        launcher,
+    }
+}
+
+fn resolve_instance_dir(
+    launcher_root: &Path,
+    instances_dir_name: &str,
+    instance_folder: &str,
+) -> Result<PathBuf, String> {
+    let requested = expand_home(instance_folder);
+    if requested.is_dir()
+        && requested
+            .parent()
+            .map(|parent| parent.ends_with(instances_dir_name))
+            .unwrap_or(false)
+    {
+        return Ok(requested);
+    }
+
+    let folder_name = Path::new(instance_folder)
+        .file_name()
+        .map(|value| value.to_string_lossy().to_string())
+        .unwrap_or_else(|| instance_folder.to_string());
+
+    let path = launcher_root.join(instances_dir_name).join(folder_name);
+    if !path.is_dir() {
+        return Err(format!("Instance directory not found: {}", path.display()));
+    }
+
+    Ok(path)
+}
+
+/// Prism and MultiMC share the same root layout, so either passes this check.
+fn validate_prism_root(path: &Path) -> Result<(), String> {
+    if !is_valid_instance_root(path) {
+        return Err(format!(
+            "Invalid Prism/MultiMC root: {} (expected folders: instances and libraries)",
+            path.to_string_lossy()
+        ));
+    }
+
+    Ok(())
+}
+
+fn validate_launcher_root(path: &Path, launcher_kind: LauncherKind) -> Result<(), String> {
+    match launcher_kind {
+        LauncherKind::Prism | LauncherKind::MultiMc => validate_prism_root(path),
+        _ if launcher_kind.is_valid_root(path) => Ok(()),
+        _ => Err(format!(
+            "Invalid {} root: {}",
+            launcher_kind.label(),
+            path.to_string_lossy()
+        )),
+    }
+}
+
+fn is_valid_instance_root(path: &Path) -> bool {
+    path.is_dir() && path.join("instances").is_dir() && path.join("libraries").is_dir()
+}
+
+fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME").map(PathBuf::from)
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    if let Some(stripped) = path.strip_prefix("~/") {
+        if let Some(home) = home_dir() {
+            return home.join(stripped);
+        }
+    }
+
+    if path == "~" {
+        if let Some(home) = home_dir() {
+            return home;
+        }
+    }
+
+    PathBuf::from(path)
+}
+
+fn instance_display_name(instance_dir: &Path) -> Option<String> {
+    let config_path = instance_dir.join("instance.cfg");
+    let content = fs::read_to_string(config_path).ok()?;
+
+    let mut in_general_section = false;
+
+    for raw_line in content.lines() {
+        let line = raw_line.trim();
+
+        if line.starts_with('[') && line.ends_with(']') {
+            in_general_section = line.eq_ignore_ascii_case("[General]");
+            continue;
+        }
+
+        if in_general_section && line.starts_with("name=") {
+            let name = line.trim_start_matches("name=").trim();
+            if !name.is_empty() {
+                return Some(name.to_string());
+            }
+        }
+    }
+
+    None
+}
+
+fn is_launcher_instance_dir(instance_dir: &Path, launcher_kind: LauncherKind) -> bool {
+    match launcher_kind {
+        // Real Prism instances should contain profile metadata and minecraft folder.
+        LauncherKind::Prism | LauncherKind::MultiMc => {
+            instance_dir.join("mmc-pack.json").is_file() && instance_game_dir(instance_dir).is_dir()
+        }
+        LauncherKind::Technic => instance_dir.join("bin").join("version.json").is_file(),
+        LauncherKind::AtLauncher => instance_dir.join("instance.json").is_file(),
+        LauncherKind::Vanilla => vanilla_profile_path(instance_dir).is_file(),
+    }
+}
+
+fn launcher_instance_display_name(
+    instance_dir: &Path,
+    launcher_kind: LauncherKind,
+) -> Option<String> {
+    match launcher_kind {
+        LauncherKind::Prism | LauncherKind::MultiMc => instance_display_name(instance_dir),
+        LauncherKind::Technic | LauncherKind::Vanilla => None,
+        LauncherKind::AtLauncher => read_at_launcher_instance(instance_dir)?
+            .launcher?
+            .name
+            .filter(|name| !name.trim().is_empty()),
+    }
+}
+
+fn launcher_instance_version(instance_dir: &Path, launcher_kind: LauncherKind) -> Option<String> {
+    match launcher_kind {
+        LauncherKind::Prism | LauncherKind::MultiMc => {
+            parse_minecraft_version(&instance_dir.join("mmc-pack.json"))
+        }
+        LauncherKind::Technic => {
+            read_minecraft_meta_version(&instance_dir.join("bin").join("version.json"))?.id
+        }
+        LauncherKind::AtLauncher => read_at_launcher_instance(instance_dir)?.version.id,
+        LauncherKind::Vanilla => {
+            let profile = read_minecraft_meta_version(&vanilla_profile_path(instance_dir))?;
+            profile.inherits_from.or(profile.id)
+        }
+    }
+}
+
+fn vanilla_profile_path(version_dir: &Path) -> PathBuf {
+    let version_name = version_dir
+        .file_name()
+        .map(|value| value.to_string_lossy().to_string())
+        .unwrap_or_default();
+    version_dir.join(format!("{version_name}.json"))
+}
+
+/// MultiMC (and older Prism) instances keep the game folder as `.minecraft`.
+fn instance_game_dir(instance_dir: &Path) -> PathBuf {
+    let game_dir = instance_dir.join("minecraft");
+    if game_dir.is_dir() {
+        return game_dir;
+    }
+    let legacy_dir = instance_dir.join(".minecraft");
+    if legacy_dir.is_dir() {
+        legacy_dir
+    } else {
+        game_dir
+    }
+}
+
+fn read_minecraft_meta_version(path: &Path) -> Option<MinecraftMetaVersion> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn read_at_launcher_instance(instance_dir: &Path) -> Option<AtLauncherInstance> {
+    let content = fs::read_to_string(instance_dir.join("instance.json")).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn parse_minecraft_version(mmc_pack_path: &Path) -> Option<String> {
+    let content = fs::read_to_string(mmc_pack_path).ok()?;
+    let parsed: MmcPack = serde_json::from_str(&content).ok()?;
+
+    parsed
+        .components
+        .into_iter()
+        .find(|component| component.uid == "net.minecraft")
+        .and_then(|component| component.version)
+}
+
+fn resolve_vanilla_asset_index_path(prism_root: &Path, mc_version: &str) -> Option<PathBuf> {
+    let meta_path = prism_root
+        .join("meta")
+        .join("net.minecraft")
+        .join(format!("{mc_version}.json"));
+    let index_id = read_minecraft_meta_version(&meta_path)?.asset_index_id()?;
+    shared_asset_index_path(prism_root, &index_id)
+}
+
+fn shared_asset_index_path(launcher_root: &Path, index_id: &str) -> Option<PathBuf> {
+    let index_path = launcher_root
+        .join("assets")
+        .join("indexes")
+        .join(format!("{index_id}.json"));
+
+    if index_path.is_file() {
+        Some(index_path)
+    } else {
+        None
+    }
+}
+
+fn cleanup_temp_paths(state: &AppState) {
+    let Ok(mut paths) = state.temp_paths.lock() else {
+        return;
+    };
+
+    for path in &*paths {
+        if path.is_dir() {
+            let _ = fs::remove_dir_all(path);
+        } else if path.is_file() {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    paths.clear();
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    let app = tauri::Builder::default()
+        .manage(AppState::default())
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_dialog::init())
+        .setup(|app| {
+            app.remove_menu()?;
+
+            // Logging is best-effort; a read-only or full data dir must not stop the app.
+            if let Err(error) = FileLogger::install(app.handle()) {
+                eprintln!("{error}");
+            }
+
+            match settings_path(app.handle()) {
+                Ok(path) => {
+                    if let Ok(mut settings) = app.state::<AppState>().settings.lock() {
+                        *settings = load_settings(&path);
+                    }
+                }
+                Err(error) => log::warn!("{error}"),
+            }
+
+            #[cfg(target_os = "macos")]
+            {
+                let app_menu = SubmenuBuilder::new(app, "Minecraft Asset Explorer")
+                    .about(None)
+                    .separator()
+                    .quit()
+                    .build()?;
+                let menu = MenuBuilder::new(app).item(&app_menu).build()?;
+                app.set_menu(menu)?;
+            }
+
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            detect_prism_roots,
+            detect_launcher_roots,
+            list_instances,
+            start_scan,
+            start_multi_scan,
+            get_last_scan_options,
+            validate_instance,
+            get_scan_status,
+            get_empty_sources,
+            cancel_scan,
+            cancel_export,
+            open_cache_directory,
+            open_config_directory,
+            clear_scan_cache,
+            repair_scan_cache,
+            get_scan_cache_stats,
+            get_cache_limit,
+            set_cache_limit,
+            get_worker_limits,
+            set_worker_limits,
+            get_settings,
+            update_settings,
+            get_log_path,
+            set_log_level,
+            list_tree_children,
+            resolve_tree_path,
+            search_assets,
+            count_assets,
+            get_scan_facets,
+            find_duplicate_assets,
+            verify_vanilla_assets,
+            reveal_asset_in_explorer,
+            get_asset_overrides,
+            get_asset_preview,
+            get_asset_thumbnail,
+            get_asset_previews,
+            split_texture_frames,
+            get_asset_record,
+            reconcile_asset_ids,
+            save_assets,
+            save_search_results,
+            copy_assets_to_clipboard,
+            export_namespace,
+            save_assets_as_zip,
+            export_as_resource_pack,
+            copy_resource_locations,
+            copy_text_to_clipboard,
+            get_asset_resource_location,
+            get_asset_info,
+            copy_image_to_clipboard,
+            convert_audio_asset,
+            get_audio_waveform,
+            get_audio_metadata,
+            get_model_references,
+            get_sound_events,
+        ])
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application");
+
+    app.run(|app_handle, event| {
+        if matches!(event, tauri::RunEvent::Exit) {
+            let state = app_handle.state::<AppState>();
+            cleanup_temp_paths(&state);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_minecraft_component_version() {
+        let data = r#"{
+            "components": [
+                { "uid": "foo", "version": "1.0.0" },
+                { "uid": "net.minecraft", "version": "1.20.1" }
+            ]
+        }"#;
+
+        let parsed: MmcPack = serde_json::from_str(data).expect("valid json");
+        let version = parsed
+            .components
+            .into_iter()
+            .find(|component| component.uid == "net.minecraft")
+            .and_then(|component| component.version);
+
+        assert_eq!(version.as_deref(), Some("1.20.1"));
+    }
+
+    #[test]
+    fn log_level_parses_case_insensitively() {
+        assert_eq!(parse_log_level(" Debug "), Ok(log::LevelFilter::Debug));
+        assert_eq!(parse_log_level("off"), Ok(log::LevelFilter::Off));
+        assert!(parse_log_level("loud").is_err());
+    }
+
+    #[test]
+    fn log_file_rotates_once_it_reaches_the_cap() {
+        let root = std::env::temp_dir().join(format!("mae-log-{}", Uuid::new_v4()));
+        fs::create_dir_all(&root).expect("must create temp dir");
+        let path = root.join(LOG_FILE_NAME);
+        fs::write(&path, "old\n").expect("must seed log");
+
+        let mut file = LogFile::open(path.clone(), 4).expect("must open log");
+        assert_eq!(
+            fs::read_to_string(root.join("mae.log.1")).expect("must keep backup"),
+            "old\n"
+        );
+        file.write_line("first").expect("must write");
+        file.write_line("second").expect("must write");
+        drop(file);
+
+        assert_eq!(
+            fs::read_to_string(&path).expect("must read log"),
+            "second\n"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("mae.log.1")).expect("must read backup"),
+            "first\n"
+        );
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn smart_search_scores_atm_star_query() {
+        let asset = sample_asset(
+            "mod.allthemodium.allthemodium.textures.item.atm_star.png",
+            AssetSourceType::Mod,
+            "allthemodium",
+            "allthemodium",
+            "textures/item/atm_star.png",
+        );
+        let record = build_search_record(&asset);
+
+        let score = score_query(&record, &SearchQuery::parse("atm star"));
+
+        assert!(score.is_some());
+    }
+
+    #[test]
+    fn parse_assets_path_from_nested_prefix() {
+        let parsed =
+            parse_asset_relative_path("nested/content/assets/example/textures/item/star.png")
+                .expect("must parse");
+
+        assert_eq!(parsed.namespace, "example");
+        assert_eq!(parsed.relative_asset_path, "textures/item/star.png");
+
+        for unsafe_path in [
+            "assets/../textures/evil.png",
+            "assets/example/../../evil.png",
+            "assets/example//etc/evil.png",
+            "assets/example/./evil.png",
+            "assets/C:/evil.png",
+            "assets/example/..\\evil.png",
+        ] {
+            assert!(
+                parse_asset_relative_path(unsafe_path).is_none(),
+                "{unsafe_path}"
+            );
+        }
+        assert!(parse_pack_root_path("shaders/../../evil.fsh").is_none());
+        assert!(ensure_inside_destination(Path::new("/out/a/b.png"), Path::new("/out")).is_ok());
+        assert!(ensure_inside_destination(Path::new("/out/../b.png"), Path::new("/out")).is_err());
+        assert!(
+            ensure_inside_destination(Path::new("/elsewhere/b.png"), Path::new("/out")).is_err()
+        );
+    }
+
+    #[test]
+    fn exact_filename_scores_higher_than_long_variant() {
+        let vanilla = sample_asset(
+            "vanilla.minecraft.minecraft.textures.item.nether_star.png",
+            AssetSourceType::Vanilla,
+            "minecraft-1.21.1",
+            "minecraft",
+            "textures/item/nether_star.png",
+        );
+        let modded = sample_asset(
+            "mod.atc.atc.blockstates.nether_star_block_2x.json",
+            AssetSourceType::Mod,
+            "allthecompressed",
+            "allthecompressed",
+            "blockstates/nether_star_block_2x.json",
+        );
+
+        let query = SearchQuery::parse("nether star");
+
+        let vanilla_score =
+            score_query(&build_search_record(&vanilla), &query).expect("vanilla must match");
+        let modded_score =
+            score_query(&build_search_record(&modded), &query).expect("modded must match");
+
+        assert!(vanilla_score > modded_score);
+    }
+
+    #[test]
+    fn query_with_extra_token_still_matches_best_pair() {
+        let expected = sample_asset(
+            "vanilla.minecraft.minecraft.sounds.block.grass.step1.ogg",
+            AssetSourceType::Vanilla,
+            "minecraft-1.21.1",
+            "minecraft",
+            "sounds/block/grass/step1.ogg",
+        );
+
+        let unrelated = sample_asset(
+            "mod.example.example.sounds.block.stone.step1.ogg",
+            AssetSourceType::Mod,
+            "example-mod",
+            "example",
+            "sounds/block/stone/step1.ogg",
+        );
+
+        let query = SearchQuery::parse("grass block step");
+
+        let expected_score =
+            score_query(&build_search_record(&expected), &query).expect("expected must match");
+        let unrelated_score = score_query(&build_search_record(&unrelated), &query)
+            .expect("unrelated should still match with weaker score");
 
-    if requested_count == 0 {
-        emit_export_progress(
-            app,
-            ExportProgressEvent {
-                operation_id: operation_id.to_string(),
-                kind: kind.clone(),
-                requested_count,
-                processed_count: 0,
-                success_count: 0,
-                failed_count: 0,
-                cancelled: false,
-            },
+        assert!(expected_score > unrelated_score);
+    }
+
+    #[test]
+    fn resource_location_query_prefers_exact_location() {
+        let stone = sample_asset(
+            "vanilla.minecraft.minecraft.textures.block.stone.png",
+            AssetSourceType::Vanilla,
+            "minecraft-1.21.1",
+            "minecraft",
+            "textures/block/stone.png",
+        );
+        let stone_bricks = sample_asset(
+            "vanilla.minecraft.minecraft.textures.block.stone_bricks.png",
+            AssetSourceType::Vanilla,
+            "minecraft-1.21.1",
+            "minecraft",
+            "textures/block/stone_bricks.png",
+        );
+        assert_eq!(
+            asset_resource_location(&stone).as_deref(),
+            Some("minecraft:block/stone")
+        );
+
+        let mut unnamed = stone.clone();
+        unnamed.namespace.clear();
+        let (locations, result) =
+            collect_resource_locations(&[stone.clone(), stone.clone(), unnamed]);
+        assert_eq!(locations, vec!["minecraft:block/stone".to_string()]);
+        assert_eq!(result.copied_count, 1);
+        assert_eq!(result.skipped_count, 1);
+        assert_eq!(result.duplicate_count, 1);
+
+        let mut logs_tag = sample_asset(
+            "datapack.tweaks.minecraft.tags.block.logs.json",
+            AssetSourceType::Datapack,
+            "tweaks",
+            "minecraft",
+            "tags/block/logs.json",
+        );
+        assert_eq!(
+            asset_resource_location(&logs_tag).as_deref(),
+            Some("#minecraft:logs")
+        );
+        logs_tag.relative_asset_path = "worldgen/biome/plains.json".to_string();
+        assert_eq!(
+            asset_resource_location(&logs_tag).as_deref(),
+            Some("minecraft:plains")
+        );
+        logs_tag.relative_asset_path = "function/tick.mcfunction".to_string();
+        assert_eq!(
+            asset_resource_location(&logs_tag).as_deref(),
+            Some("minecraft:tick")
+        );
+        logs_tag.relative_asset_path = "pack.mcmeta".to_string();
+        assert_eq!(
+            asset_resource_location(&logs_tag).as_deref(),
+            Some("minecraft:pack.mcmeta")
+        );
+        let animation = sample_asset(
+            "vanilla.minecraft.minecraft.textures.block.magma.png.mcmeta",
+            AssetSourceType::Vanilla,
+            "minecraft-1.21.1",
+            "minecraft",
+            "textures/block/magma.png.mcmeta",
+        );
+        assert_eq!(
+            asset_resource_location(&animation).as_deref(),
+            Some("minecraft:textures/block/magma.png.mcmeta")
+        );
+
+        let query = SearchQuery::parse("minecraft:block/stone");
+        let stone_score =
+            score_query(&build_search_record(&stone), &query).expect("stone must match");
+        let bricks_score = score_query(&build_search_record(&stone_bricks), &query)
+            .expect("stone bricks must match");
+
+        assert!(stone_score > bricks_score);
+    }
+
+    #[test]
+    fn regex_mode_matches_keys_and_reports_invalid_patterns() {
+        let ore = sample_asset(
+            "vanilla.minecraft.textures.block.iron_ore.png",
+            AssetSourceType::Vanilla,
+            "minecraft",
+            "minecraft",
+            "textures/block/iron_ore.png",
+        );
+        let ingot = sample_asset(
+            "vanilla.minecraft.textures.item.iron_ingot.png",
+            AssetSourceType::Vanilla,
+            "minecraft",
+            "minecraft",
+            "textures/item/iron_ingot.png",
+        );
+
+        let pattern =
+            KeyPattern::compile(SearchMatchMode::Regex, r"^vanilla\.minecraft\..*_ore\.png$")
+                .expect("must compile")
+                .expect("regex mode must produce a pattern");
+        assert!(pattern.matches(&ore));
+        assert!(!pattern.matches(&ingot));
+
+        let by_path = KeyPattern::compile(SearchMatchMode::Regex, "^textures/item/")
+            .expect("must compile")
+            .expect("regex mode must produce a pattern");
+        assert!(by_path.matches(&ingot));
+
+        let error =
+            KeyPattern::compile(SearchMatchMode::Regex, "iron_(ore").expect_err("must fail");
+        assert!(error.starts_with("Invalid regular expression"));
+        assert!(KeyPattern::compile(SearchMatchMode::Smart, "iron")
+            .expect("smart mode never fails")
+            .is_none());
+    }
+
+    #[test]
+    fn glob_mode_keeps_single_star_within_a_folder() {
+        let asset = |relative_asset_path: &str| {
+            sample_asset(
+                "key",
+                AssetSourceType::Vanilla,
+                "minecraft",
+                "minecraft",
+                relative_asset_path,
+            )
+        };
+        let compile = |raw: &str| {
+            KeyPattern::compile(SearchMatchMode::Glob, raw)
+                .expect("must compile")
+                .expect("glob mode must produce a pattern")
+        };
+
+        let ores = compile("textures/block/*_ore.png");
+        assert!(ores.matches(&asset("textures/block/iron_ore.png")));
+        assert!(!ores.matches(&asset("textures/block/deepslate/iron_ore.png")));
+
+        let sounds = compile("**/*.ogg");
+        assert!(sounds.matches(&asset("sounds/mob/zombie/say1.ogg")));
+        assert!(sounds.matches(&asset("step.ogg")));
+        assert!(!sounds.matches(&asset("sounds/mob/zombie/say1.png")));
+
+        let spaced = compile("textures/block/*ore *.PNG");
+        assert!(spaced.matches(&asset("textures/block/iron ore (old).png")));
+        assert!(!spaced.matches(&asset("textures/block/iron_ore.png")));
+
+        assert!(
+            KeyPattern::compile(SearchMatchMode::Glob, "textures/[block")
+                .expect_err("must fail")
+                .starts_with("Invalid glob pattern")
+        );
+    }
+
+    #[test]
+    fn namespace_filter_and_facets_follow_asset_namespaces() {
+        let assets = vec![
+            sample_asset(
+                "mod.create.create.textures.block.gear.png",
+                AssetSourceType::Mod,
+                "create",
+                "create",
+                "textures/block/gear.png",
+            ),
+            sample_asset(
+                "mod.create.minecraft.textures.block.stone.png",
+                AssetSourceType::Mod,
+                "create",
+                "minecraft",
+                "textures/block/stone.png",
+            ),
+            sample_asset(
+                "vanilla.minecraft.minecraft.textures.block.dirt.png",
+                AssetSourceType::Vanilla,
+                "minecraft-1.21.1",
+                "minecraft",
+                "textures/block/dirt.png",
+            ),
+        ];
+        let records = assets.iter().map(build_search_record).collect::<Vec<_>>();
+
+        let options = AssetFilterOptions {
+            namespace_filter: Some(vec!["minecraft".to_string()]),
+            ..AssetFilterOptions::default()
+        };
+        let filter = AssetFilter::from_options(&options);
+        let matched = assets
+            .iter()
+            .zip(&records)
+            .filter(|(asset, record)| filter.matches(asset, record))
+            .map(|(asset, _)| asset.relative_asset_path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            matched,
+            vec!["textures/block/stone.png", "textures/block/dirt.png"]
+        );
+
+        let pngs_in_create = AssetFilterOptions {
+            extensions: Some(vec![".PNG".to_string()]),
+            namespace_filter: Some(vec!["create".to_string()]),
+            ..AssetFilterOptions::default()
+        };
+        let filter = AssetFilter::from_options(&pngs_in_create);
+        assert_eq!(
+            assets
+                .iter()
+                .zip(&records)
+                .filter(|(asset, record)| filter.matches(asset, record))
+                .count(),
+            1
+        );
+        let only_json = AssetFilterOptions {
+            extensions: Some(vec!["json".to_string()]),
+            ..AssetFilterOptions::default()
+        };
+        let filter = AssetFilter::from_options(&only_json);
+        assert!(!assets
+            .iter()
+            .zip(&records)
+            .any(|(asset, record)| filter.matches(asset, record)));
+
+        let empty = AssetFilterOptions {
+            namespace_filter: Some(Vec::new()),
+            extensions: Some(Vec::new()),
+            ..AssetFilterOptions::default()
+        };
+        let filter = AssetFilter::from_options(&empty);
+        assert!(assets
+            .iter()
+            .zip(&records)
+            .all(|(asset, record)| filter.matches(asset, record)));
+
+        let facets = collect_scan_facets(&assets);
+        assert_eq!(
+            facets
+                .namespaces
+                .iter()
+                .map(|facet| (facet.name.as_str(), facet.count))
+                .collect::<Vec<_>>(),
+            vec![("create", 1), ("minecraft", 2)]
+        );
+        assert_eq!(
+            facets
+                .sources
+                .iter()
+                .map(|facet| (
+                    facet.name.as_str(),
+                    facet.source_type.tree_root_name(),
+                    facet.count
+                ))
+                .collect::<Vec<_>>(),
+            vec![("create", "mods", 2), ("minecraft-1.21.1", "vanilla", 1)]
+        );
+        assert_eq!(facets.extensions.len(), 1);
+        assert_eq!(facets.extensions[0].ext, "png");
+        assert_eq!(facets.extensions[0].count, 3);
+    }
+
+    #[test]
+    fn minus_terms_exclude_assets_and_ignore_short_fragments() {
+        let stone = build_search_record(&sample_asset(
+            "vanilla.minecraft.minecraft.textures.block.stone.png",
+            AssetSourceType::Vanilla,
+            "minecraft-1.21.1",
+            "minecraft",
+            "textures/block/stone.png",
+        ));
+        let cobble = build_search_record(&sample_asset(
+            "vanilla.minecraft.minecraft.textures.block.cobblestone.png",
+            AssetSourceType::Vanilla,
+            "minecraft-1.21.1",
+            "minecraft",
+            "textures/block/cobblestone.png",
+        ));
+
+        let query = SearchQuery::parse("stone -cobble");
+        assert_eq!(query.tokens, vec!["stone".to_string()]);
+        assert!(score_query(&stone, &query).is_some());
+        assert!(score_query(&cobble, &query).is_none());
+
+        // Prefix matches count, so `-cob` drops cobblestone too.
+        assert!(score_query(&cobble, &SearchQuery::parse("stone -cob")).is_none());
+
+        let lenient = SearchQuery::parse("stone - -x");
+        assert!(lenient.excluded.is_empty());
+        assert_eq!(lenient.tokens, vec!["stone".to_string()]);
+        assert!(score_query(&cobble, &lenient).is_some());
+
+        let only_excluded = SearchQuery::parse("-cobble");
+        assert!(only_excluded.tokens.is_empty());
+        assert!(only_excluded.excludes(&cobble));
+        assert!(!only_excluded.excludes(&stone));
+    }
+
+    #[test]
+    fn quoted_phrases_require_adjacent_words() {
+        let grass_block = build_search_record(&sample_asset(
+            "vanilla.minecraft.minecraft.textures.block.grass_block_top.png",
+            AssetSourceType::Vanilla,
+            "minecraft-1.21.1",
+            "minecraft",
+            "textures/block/grass_block_top.png",
+        ));
+        let tall_grass = build_search_record(&sample_asset(
+            "vanilla.minecraft.minecraft.textures.block.tall_grass_bottom.png",
+            AssetSourceType::Vanilla,
+            "minecraft-1.21.1",
+            "minecraft",
+            "textures/block/tall_grass_bottom.png",
+        ));
+        let in_path = build_search_record(&sample_asset(
+            "mod.decor.decor.textures.grass_block.side.png",
+            AssetSourceType::Mod,
+            "decor",
+            "decor",
+            "textures/grass_block/side.png",
+        ));
+
+        let query = SearchQuery::parse("\"grass block\"");
+        assert_eq!(query.phrases, vec!["grassblock".to_string()]);
+        assert!(query.tokens.is_empty());
+        let filename_score = score_query(&grass_block, &query).expect("phrase in filename");
+        let path_score = score_query(&in_path, &query).expect("phrase in path");
+        assert!(filename_score > path_score);
+        assert!(score_query(&tall_grass, &query).is_none());
+
+        let mixed = SearchQuery::parse("top \"grass block\" \"unclosed");
+        assert_eq!(mixed.phrases, vec!["grassblock".to_string()]);
+        assert_eq!(
+            mixed.tokens,
+            vec!["top".to_string(), "unclosed".to_string()]
+        );
+    }
+
+    #[test]
+    fn highlights_map_compact_matches_back_to_source_offsets() {
+        let asset = sample_asset(
+            "vanilla.minecraft.minecraft.textures.block.grass_block_top.png",
+            AssetSourceType::Vanilla,
+            "minecraft-1.21.1",
+            "minecraft",
+            "textures/block/grass_block_top.png",
+        );
+
+        let highlights =
+            build_asset_highlights(&asset, &SearchQuery::parse("\"grass block\" top"), None);
+        assert_eq!(
+            highlights.relative_asset_path,
+            vec![
+                HighlightRange { start: 15, end: 26 },
+                HighlightRange { start: 27, end: 30 }
+            ]
+        );
+
+        // Overlapping hits collapse into one span.
+        assert_eq!(
+            token_highlight_ranges("stonestone", &["stone", "onest"]),
+            vec![HighlightRange { start: 0, end: 10 }]
+        );
+
+        let regex = KeyPattern::compile(SearchMatchMode::Regex, "block")
+            .expect("must compile")
+            .expect("regex mode must produce a pattern");
+        let highlights = build_asset_highlights(&asset, &SearchQuery::parse("block"), Some(&regex));
+        assert_eq!(
+            highlights.relative_asset_path,
+            vec![
+                HighlightRange { start: 9, end: 14 },
+                HighlightRange { start: 21, end: 26 }
+            ]
+        );
+    }
+
+    #[test]
+    fn slashed_query_boosts_matching_path_fragment() {
+        let zombie = sample_asset(
+            "vanilla.minecraft.minecraft.textures.entity.zombie.zombie.png",
+            AssetSourceType::Vanilla,
+            "minecraft-1.21.1",
+            "minecraft",
+            "textures/entity/zombie/zombie.png",
+        );
+        let villager = sample_asset(
+            "vanilla.minecraft.minecraft.textures.entity.zombie_villager.zombie_villager.png",
+            AssetSourceType::Vanilla,
+            "minecraft-1.21.1",
+            "minecraft",
+            "textures/entity/zombie_villager/zombie_villager.png",
+        );
+
+        let slashed = SearchQuery::parse("entity/zombie");
+        assert_eq!(slashed.path_hints, vec!["entity/zombie".to_string()]);
+        let record = build_search_record(&zombie);
+        let slashed_score = score_query(&record, &slashed).expect("zombie must match");
+        let spaced_score =
+            score_query(&record, &SearchQuery::parse("entity zombie")).expect("zombie must match");
+        let villager_score =
+            score_query(&build_search_record(&villager), &slashed).expect("villager must match");
+
+        assert!(slashed_score > spaced_score);
+        assert!(slashed_score > villager_score);
+    }
+
+    #[test]
+    fn damerau_fuzzy_match_accepts_transposed_token() {
+        let asset = sample_asset(
+            "vanilla.minecraft.minecraft.sounds.block.grass.step1.ogg",
+            AssetSourceType::Vanilla,
+            "minecraft-1.21.1",
+            "minecraft",
+            "sounds/block/grass/step1.ogg",
+        );
+        let record = build_search_record(&asset);
+        let score = score_query(&record, &SearchQuery::parse("stpe"));
+        assert!(score.is_some());
+    }
+
+    #[test]
+    fn fuzzy_mode_controls_typo_tolerance() {
+        let asset = sample_asset(
+            "vanilla.minecraft.minecraft.textures.block.diamond_ore.png",
+            AssetSourceType::Vanilla,
+            "minecraft-1.21.1",
+            "minecraft",
+            "textures/block/diamond_ore.png",
+        );
+        let record = build_search_record(&asset);
+        let query = |raw: &str, fuzzy: FuzzyMode| SearchQuery {
+            fuzzy,
+            ..SearchQuery::parse(raw)
+        };
+
+        assert!(score_query(&record, &query("diamnod", FuzzyMode::Conservative)).is_some());
+        assert!(score_query(&record, &query("diamnod", FuzzyMode::Off)).is_none());
+        // Three edits on a seven-letter token needs the looser mode.
+        assert!(score_query(&record, &query("dimanodd", FuzzyMode::Conservative)).is_none());
+        assert!(score_query(&record, &query("dimanodd", FuzzyMode::Aggressive)).is_some());
+    }
+
+    #[test]
+    fn folder_filter_matches_subtree() {
+        let asset = sample_asset(
+            "mod.sample.sample.textures.item.star.png",
+            AssetSourceType::Mod,
+            "sample-mod",
+            "sample",
+            "textures/item/star.png",
+        );
+
+        let index = build_search_record(&asset);
+        let folder = asset_folder_node_id(&asset);
+        let parent = folder.split('/').take(4).collect::<Vec<_>>().join("/");
+
+        assert!(asset_matches_folder(&index, Some(&folder)));
+        assert!(asset_matches_folder(&index, Some(&parent)));
+        assert!(!asset_matches_folder(&index, Some("root/vanilla")));
+    }
+
+    #[test]
+    fn source_index_scopes_to_requested_sources() {
+        let mut scan = ScanState::new();
+        scan.assets = vec![
+            sample_asset(
+                "mod.alpha.alpha.textures.item.a.png",
+                AssetSourceType::Mod,
+                "alpha",
+                "alpha",
+                "textures/item/a.png",
+            ),
+            sample_asset(
+                "mod.beta.beta.textures.item.b.png",
+                AssetSourceType::Mod,
+                "beta",
+                "beta",
+                "textures/item/b.png",
+            ),
+            sample_asset(
+                "mod.alpha.alpha.textures.item.c.png",
+                AssetSourceType::Mod,
+                "alpha",
+                "alpha",
+                "textures/item/c.png",
+            ),
+        ];
+
+        assert_eq!(scan.indices_for_sources(&["alpha".to_string()]), vec![0, 2]);
+        assert_eq!(
+            scan.indices_for_sources(&["beta".to_string(), "missing".to_string()]),
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn ordered_search_ignores_paging_and_reuses_cached_ranking() {
+        let mut scan = ScanState::new();
+        scan.assets = ["iron_ore", "gold_ore", "stone"]
+            .iter()
+            .map(|name| {
+                sample_asset(
+                    &format!("vanilla.minecraft.minecraft.textures.block.{name}.png"),
+                    AssetSourceType::Vanilla,
+                    "minecraft-1.21.1",
+                    "minecraft",
+                    &format!("textures/block/{name}.png"),
+                )
+            })
+            .collect();
+        scan.search_records = scan.assets.iter().map(build_search_record).collect();
+        let req: SearchRequest = serde_json::from_str(
+            r#"{ "scanId": "scan", "query": "textures/block/*_ore.png", "matchMode": "glob", "limit": 1 }"#,
+        )
+        .expect("valid search request");
+
+        let ordered = ordered_search_matches(&mut scan, &req, usize::MAX).expect("glob compiles");
+        let mut matched = ordered.page(0, usize::MAX).collect::<Vec<_>>();
+        matched.sort_unstable();
+        assert_eq!(matched, vec![0, 1]);
+        let cached = ordered_search_matches(&mut scan, &req, 1).expect("cached ranking");
+        assert!(Arc::ptr_eq(&ordered, &cached));
+    }
+
+    #[test]
+    fn search_order_sorts_the_first_page_then_the_rest_on_demand() {
+        let assets = ["e", "d", "c", "b", "a"]
+            .iter()
+            .map(|name| {
+                sample_asset(
+                    &format!("vanilla.minecraft.minecraft.textures.block.{name}.png"),
+                    AssetSourceType::Vanilla,
+                    "minecraft-1.21.1",
+                    "minecraft",
+                    &format!("textures/block/{name}.png"),
+                )
+            })
+            .collect::<Vec<_>>();
+        let matches = (0..assets.len())
+            .map(|index| (0, index))
+            .collect::<Vec<_>>();
+        let mut full = SearchOrder::new(matches.clone(), false);
+        full.sort_prefix(&assets, usize::MAX);
+        let full = full.page(0, usize::MAX).collect::<Vec<_>>();
+        assert_eq!(full, vec![4, 3, 2, 1, 0]);
+
+        let mut order = SearchOrder::new(matches, false);
+        order.sort_prefix(&assets, 2);
+        assert_eq!(order.sorted_len, 2);
+        assert_eq!(order.page(0, 2).collect::<Vec<_>>(), full[..2]);
+        order.sort_prefix(&assets, usize::MAX);
+        assert_eq!(order.sorted_len, assets.len());
+        assert_eq!(order.page(0, usize::MAX).collect::<Vec<_>>(), full);
+    }
+
+    #[test]
+    fn matching_indices_score_smart_queries_and_zero_patterns() {
+        let mut scan = ScanState::new();
+        scan.assets = vec![
+            sample_asset(
+                "vanilla.minecraft.minecraft.textures.block.iron_ore.png",
+                AssetSourceType::Vanilla,
+                "minecraft-1.21.1",
+                "minecraft",
+                "textures/block/iron_ore.png",
+            ),
+            sample_asset(
+                "vanilla.minecraft.minecraft.sounds.block.stone.step1.ogg",
+                AssetSourceType::Vanilla,
+                "minecraft-1.21.1",
+                "minecraft",
+                "sounds/block/stone/step1.ogg",
+            ),
+        ];
+        scan.search_records = scan.assets.iter().map(build_search_record).collect();
+        let request = |json: &str| -> SearchRequest {
+            serde_json::from_str(json).expect("valid search request")
+        };
+
+        let smart = request(r#"{ "scanId": "scan", "query": "iron ore" }"#);
+        let search = CompiledSearch::new(&smart).expect("smart query");
+        let matches = matching_asset_indices(&mut scan, &search);
+        assert!(search.ranked());
+        assert_eq!(matches.len(), 1);
+        assert!(matches[0].0 > 0 && matches[0].1 == 0);
+
+        let regex = request(r#"{ "scanId": "scan", "query": "block", "matchMode": "regex" }"#);
+        let search = CompiledSearch::new(&regex).expect("valid regex");
+        assert!(!search.ranked());
+        assert_eq!(
+            matching_asset_indices(&mut scan, &search),
+            vec![(0, 0), (0, 1)]
         );
-        emit_export_completed(
-            app,
-            ExportCompletedEvent {
-                operation_id: operation_id.to_string(),
-                kind,
-                requested_count,
-                processed_count: 0,
-                success_count: 0,
-                failed_count: 0,
-                cancelled: false,
-                failures: Vec::new(),
-            },
+    }
+
+    #[test]
+    fn search_request_reads_flattened_filters() {
+        let req: SearchRequest = serde_json::from_str(
+            r#"{
+                "scanId": "scan",
+                "query": "stone",
+                "folderNodeId": "root/mods",
+                "includeAudio": false,
+                "sourceNameFilter": ["create"]
+            }"#,
+        )
+        .expect("valid search request");
+        let filter = AssetFilter::from_options(&req.filters);
+
+        assert_eq!(filter.folder, Some("root/mods"));
+        assert!(filter.include_images && !filter.include_audio && filter.include_other);
+        assert_eq!(
+            req.filters.source_name_filter,
+            Some(vec!["create".to_string()])
         );
-        return Ok(ExportRunOutcome {
-            output_files: Vec::new(),
-            processed_count: 0,
-            success_count: 0,
-            failed_count: 0,
-            cancelled: false,
-            failures: Vec::new(),
-        });
     }
 
-    let should_convert_audio =
-        audio_format != AudioFormat::Original && jobs.iter().any(|job| job.asset.is_audio);
-    let ffmpeg_path = if should_convert_audio {
-        Some(resolve_ffmpeg_path(app)?)
-    } else {
-        None
-    };
+    #[test]
+    fn idle_sort_uses_natural_last_filename_token() {
+        let a1 = sample_asset(
+            "mod.sample.sample.sounds.entity.test.active1.ogg",
+            AssetSourceType::Mod,
+            "sample",
+            "sample",
+            "sounds/entity/test/active1.ogg",
+        );
+        let a2 = sample_asset(
+            "mod.sample.sample.sounds.entity.test.active2.ogg",
+            AssetSourceType::Mod,
+            "sample",
+            "sample",
+            "sounds/entity/test/active2.ogg",
+        );
+        let a10 = sample_asset(
+            "mod.sample.sample.sounds.entity.test.active10.ogg",
+            AssetSourceType::Mod,
+            "sample",
+            "sample",
+            "sounds/entity/test/active10.ogg",
+        );
 
-    emit_export_progress(
-        app,
-        ExportProgressEvent {
-            operation_id: operation_id.to_string(),
-            kind: kind.clone(),
-            requested_count,
-            processed_count: 0,
-            success_count: 0,
-            failed_count: 0,
-            cancelled: false,
-        },
-    );
+        assert_eq!(idle_asset_cmp(&a1, &a2), CmpOrdering::Less);
+        assert_eq!(idle_asset_cmp(&a2, &a10), CmpOrdering::Less);
+    }
 
-    let workers = thread::available_parallelism()
-        .map(|value| value.get())
-        .unwrap_or(1)
-        .clamp(1, MAX_EXPORT_WORKERS)
-        .min(requested_count);
+    #[test]
+    fn rebuild_key_counts_preserves_dup_suffix_progression() {
+        let assets = vec![
+            sample_asset(
+                "mod.sample.sample.sounds.block.grass.step.ogg",
+                AssetSourceType::Mod,
+                "sample",
+                "sample",
+                "sounds/block/grass/step.ogg",
+            ),
+            sample_asset(
+                "mod.sample.sample.sounds.block.grass.step.ogg.dup1",
+                AssetSourceType::Mod,
+                "sample",
+                "sample",
+                "sounds/block/grass/step.ogg",
+            ),
+        ];
 
-    let (sender, receiver) = mpsc::channel::<ExportWorkerResult>();
-    let jobs = Arc::new(jobs);
-    let next_index = Arc::new(AtomicUsize::new(0));
-    let operation_id_owned = operation_id.to_string();
+        let mut counts = rebuild_key_counts_from_assets(&assets);
+        let next = unique_key(
+            "mod.sample.sample.sounds.block.grass.step.ogg".to_string(),
+            &mut counts,
+        );
 
-    for _ in 0..workers {
-        let sender = sender.clone();
-        let jobs = Arc::clone(&jobs);
-        let next_index = Arc::clone(&next_index);
-        let app = app.clone();
-        let operation_id = operation_id_owned.clone();
-        let ffmpeg_path = ffmpeg_path.clone();
-        let audio_format = audio_format.clone();
+        assert_eq!(next, "mod.sample.sample.sounds.block.grass.step.ogg.dup2");
+    }
 
-        thread::spawn(move || {
-            let mut archive_cache = HashMap::<String, ZipArchive<fs::File>>::new();
+    #[test]
+    fn resolve_operation_id_generates_uuid_when_missing() {
+        let generated = resolve_operation_id(None);
+        assert!(
+            Uuid::parse_str(&generated).is_ok(),
+            "generated operation id should be a UUID: {generated}"
+        );
 
-            loop {
-                if is_export_cancelled(&app, &operation_id) {
-                    break;
-                }
+        let generated_from_empty = resolve_operation_id(Some("   ".to_string()));
+        assert!(
+            Uuid::parse_str(&generated_from_empty).is_ok(),
+            "generated operation id from empty input should be a UUID: {generated_from_empty}"
+        );
+    }
 
-                let index = next_index.fetch_add(1, AtomicOrdering::Relaxed);
-                if index >= jobs.len() {
-                    break;
-                }
+    #[test]
+    fn plan_export_jobs_dedupes_filenames_and_applies_audio_extension() {
+        let temp_root = std::env::temp_dir().join(format!("mae-export-plan-{}", Uuid::new_v4()));
+        fs::create_dir_all(&temp_root).expect("must create temp export directory");
 
-                let job = &jobs[index];
-                let result = materialize_export_job(
-                    job,
-                    &audio_format,
-                    ffmpeg_path.as_deref(),
-                    &mut archive_cache,
-                );
+        let audio_one = sample_audio_asset(
+            "mod.audio.one.sounds.block.test.step.ogg",
+            "audio-one",
+            "sample",
+            "sounds/block/test/step.ogg",
+        );
+        let audio_two = sample_audio_asset(
+            "mod.audio.two.sounds.block.test.step.ogg",
+            "audio-two",
+            "sample",
+            "sounds/block/test/step.ogg",
+        );
 
-                let worker_message = match result {
-                    Ok(path) => ExportWorkerResult::Success {
-                        index: job.index,
-                        output_path: path,
-                    },
-                    Err(error) => ExportWorkerResult::Failure {
-                        index: job.index,
-                        failure: ExportFailure {
-                            asset_id: job.asset.asset_id.clone(),
-                            key: job.asset.key.clone(),
-                            error,
-                        },
-                    },
-                };
+        let jobs = plan_export_jobs(
+            vec![audio_one, audio_two],
+            &temp_root,
+            &ExportOptions {
+                audio_format: AudioFormat::Mp3,
+                audio_quality: AudioQuality::default(),
+                normalize_audio: false,
+                audio_resample: AudioResample::default(),
+                image_format: ImageFormat::Original,
+                image_background: JPEG_DEFAULT_BACKGROUND,
+                naming: ExportNaming::Original,
+                name_template: None,
+                layout: ExportLayout::Flat,
+                cleanup_on_cancel: true,
+                defer_completion: false,
+            },
+        );
+        let names = jobs
+            .iter()
+            .map(|job| {
+                job.output_path
+                    .file_name()
+                    .expect("output name must exist")
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
 
-                if sender.send(worker_message).is_err() {
-                    break;
-                }
-            }
-        });
+        assert_eq!(
+            names,
+            vec!["step.mp3".to_string(), "step_1.mp3".to_string()]
+        );
+        let _ = fs::remove_dir_all(&temp_root);
     }
 
-    drop(sender);
+    #[test]
+    fn name_templates_expand_placeholders_and_sanitize() {
+        let temp_root =
+            std::env::temp_dir().join(format!("mae-export-template-{}", Uuid::new_v4()));
+        let alloy = sample_asset(
+            "mod.create.create.textures.item.andesite_alloy.png",
+            AssetSourceType::Mod,
+            "create",
+            "create",
+            "textures/item/andesite_alloy.png",
+        );
+        let mut odd = alloy.clone();
+        odd.source_name = "a:b?".to_string();
 
-    let mut processed_count = 0usize;
-    let mut success_count = 0usize;
-    let mut failed_count = 0usize;
-    let mut failures = Vec::<ExportFailure>::new();
-    let mut output_files = vec![None; requested_count];
+        let template = NameTemplate::parse("{namespace}__{stem}.{ext}").expect("must parse");
+        let indexed = NameTemplate::parse("{index}-{source}").expect("must parse");
+        assert_eq!(
+            template.render(&alloy, "andesite_alloy", "png", 0),
+            "create__andesite_alloy"
+        );
+        assert_eq!(indexed.render(&odd, "andesite_alloy", "png", 2), "3-a_b_");
+        assert!(NameTemplate::parse("{mod}")
+            .expect_err("unknown placeholder")
+            .contains("{mod}"));
+        assert!(NameTemplate::parse("{stem").is_err());
+
+        let jobs = plan_export_jobs(
+            vec![alloy.clone(), alloy],
+            &temp_root,
+            &ExportOptions {
+                audio_format: AudioFormat::Original,
+                audio_quality: AudioQuality::default(),
+                normalize_audio: false,
+                audio_resample: AudioResample::default(),
+                image_format: ImageFormat::Webp,
+                image_background: JPEG_DEFAULT_BACKGROUND,
+                naming: ExportNaming::Original,
+                name_template: Some(template),
+                layout: ExportLayout::Flat,
+                cleanup_on_cancel: true,
+                defer_completion: false,
+            },
+        );
+        assert_eq!(
+            jobs.iter()
+                .map(|job| job.output_path.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                temp_root.join("create__andesite_alloy.webp"),
+                temp_root.join("create__andesite_alloy_1.webp"),
+            ]
+        );
+    }
 
-    while processed_count < requested_count {
-        match receiver.recv_timeout(Duration::from_millis(100)) {
-            Ok(ExportWorkerResult::Success { index, output_path }) => {
-                processed_count += 1;
-                success_count += 1;
-                output_files[index] = Some(output_path.to_string_lossy().to_string());
-            }
-            Ok(ExportWorkerResult::Failure { index, failure }) => {
-                processed_count += 1;
-                failed_count += 1;
-                output_files[index] = None;
-                failures.push(failure);
-            }
-            Err(mpsc::RecvTimeoutError::Timeout) => {
-                if is_export_cancelled(app, operation_id) {
-                    continue;
-                }
-                continue;
-            }
-            Err(mpsc::RecvTimeoutError::Disconnected) => break,
-        }
+    #[test]
+    fn export_manifest_pairs_saved_files_with_their_assets() {
+        let destination = PathBuf::from("/tmp/export");
+        let assets = vec![
+            sample_asset(
+                "mod.create.create.textures.item.andesite_alloy.png",
+                AssetSourceType::Mod,
+                "create",
+                "create",
+                "textures/item/andesite_alloy.png",
+            ),
+            sample_audio_asset(
+                "mod.audio.one.sounds.block.test.step.ogg",
+                "audio-one",
+                "sample",
+                "sounds/block/test/step.ogg",
+            ),
+        ];
+        let outcome = ExportRunOutcome {
+            output_files: vec![destination
+                .join("sample")
+                .join("step.ogg")
+                .to_string_lossy()
+                .to_string()],
+            output_indices: vec![1],
+            processed_count: 2,
+            success_count: 1,
+            failed_count: 1,
+            cancelled: false,
+            failures: Vec::new(),
+        };
+
+        let manifest = build_export_manifest(&destination, &assets, &outcome);
+        assert_eq!(manifest.files.len(), 1);
+        assert_eq!(manifest.files[0].file, "sample/step.ogg");
+        assert_eq!(manifest.files[0].source_name, "audio-one");
+        assert_eq!(
+            manifest.files[0].resource_location.as_deref(),
+            Some("sample:block/test/step")
+        );
+    }
+
+    #[test]
+    fn structured_export_mirrors_sources_and_guards_collisions() {
+        let temp_root = std::env::temp_dir().join(format!("mae-export-tree-{}", Uuid::new_v4()));
+        let first = sample_audio_asset(
+            "mod.audio.one.sounds.block.test.step.ogg",
+            "audio-one",
+            "sample",
+            "sounds/block/test/step.ogg",
+        );
+        let second = sample_audio_asset(
+            "mod.audio.two.sounds.block.test.step.ogg",
+            "audio-two",
+            "sample",
+            "sounds/block/test/step.ogg",
+        );
+        let converted = sample_audio_asset(
+            "mod.audio.one.sounds.block.test.step.wav",
+            "audio-one",
+            "sample",
+            "sounds/block/test/step.wav",
+        );
 
-        emit_export_progress(
-            app,
-            ExportProgressEvent {
-                operation_id: operation_id.to_string(),
-                kind: kind.clone(),
-                requested_count,
-                processed_count,
-                success_count,
-                failed_count,
-                cancelled: is_export_cancelled(app, operation_id),
+        let jobs = plan_export_jobs(
+            vec![first, second, converted],
+            &temp_root,
+            &ExportOptions {
+                audio_format: AudioFormat::Mp3,
+                audio_quality: AudioQuality::default(),
+                normalize_audio: false,
+                audio_resample: AudioResample::default(),
+                image_format: ImageFormat::Original,
+                image_background: JPEG_DEFAULT_BACKGROUND,
+                naming: ExportNaming::Original,
+                name_template: None,
+                layout: ExportLayout::SourceTree,
+                cleanup_on_cancel: true,
+                defer_completion: false,
             },
         );
-    }
+        let paths = jobs
+            .iter()
+            .map(|job| job.output_path.clone())
+            .collect::<Vec<_>>();
 
-    while let Ok(result) = receiver.try_recv() {
-        match result {
-            ExportWorkerResult::Success { index, output_path } => {
-                processed_count += 1;
-                success_count += 1;
-                output_files[index] = Some(output_path.to_string_lossy().to_string());
-            }
-            ExportWorkerResult::Failure { index, failure } => {
-                processed_count += 1;
-                failed_count += 1;
-                output_files[index] = None;
-                failures.push(failure);
-            }
-        }
+        let step_dir = |source: &str| {
+            temp_root
+                .join(source)
+                .join("sample")
+                .join("sounds/block/test")
+        };
+        assert_eq!(
+            paths,
+            vec![
+                step_dir("audio-one").join("step.mp3"),
+                step_dir("audio-two").join("step.mp3"),
+                step_dir("audio-one").join("step_1.mp3"),
+            ]
+        );
     }
 
-    let cancelled = is_export_cancelled(app, operation_id);
-    if processed_count < requested_count && !cancelled {
-        return Err("Export workers disconnected before processing all assets".to_string());
+    #[test]
+    fn audio_quality_validates_vbr_and_bitrate_ranges() {
+        assert!(AudioQuality::default().validate().is_ok());
+        assert!(AudioQuality {
+            mp3_vbr_quality: Some(0),
+            bitrate_kbps: Some(320),
+        }
+        .validate()
+        .is_ok());
+        assert!(AudioQuality {
+            mp3_vbr_quality: Some(10),
+            bitrate_kbps: None,
+        }
+        .validate()
+        .is_err());
+        assert!(AudioQuality {
+            mp3_vbr_quality: None,
+            bitrate_kbps: Some(8),
+        }
+        .validate()
+        .is_err());
     }
 
-    let output_files = output_files.into_iter().flatten().collect::<Vec<_>>();
-    emit_export_completed(
-        app,
-        ExportCompletedEvent {
-            operation_id: operation_id.to_string(),
-            kind: kind.clone(),
-            requested_count,
-            processed_count,
-            success_count,
-            failed_count,
-            cancelled,
-            failures: failures.clone(),
-        },
-    );
-
-    Ok(ExportRunOutcome {
-        output_files,
-        processed_count,
-        success_count,
-        failed_count,
-        cancelled,
-        failures,
-    })
-}
-
-fn materialize_export_job(
-    job: &ExportJob,
-    audio_format: &AudioFormat,
-    ffmpeg_path: Option<&Path>,
-    archive_cache: &mut HashMap<String, ZipArchive<fs::File>>,
-) -> Result<PathBuf, String> {
-    let bytes = extract_asset_bytes_with_archive_cache(&job.asset, archive_cache)?;
-
-    if job.asset.is_audio && *audio_format != AudioFormat::Original {
-        let ffmpeg_path = ffmpeg_path.ok_or_else(|| "FFmpeg path was not resolved".to_string())?;
-        convert_audio_bytes_to_file(ffmpeg_path, &bytes, &job.output_path, audio_format)?;
-    } else {
-        fs::write(&job.output_path, bytes).map_err(|error| {
-            format!(
-                "Failed to write output file {}: {error}",
-                job.output_path.display()
-            )
-        })?;
+    #[test]
+    fn audio_resample_validates_sample_rate_and_channels() {
+        let resample: AudioResample = serde_json::from_str(r#"{"sampleRate":48000,"channels":1}"#)
+            .expect("must parse resample options");
+        assert_eq!(resample.validate(), Ok(resample));
+        let namespace_request: ExportNamespaceRequest = serde_json::from_str(
+            r#"{"scanId":"s","namespace":"minecraft","destinationDir":"/tmp","sampleRate":22050}"#,
+        )
+        .expect("must parse namespace export request");
+        assert_eq!(namespace_request.audio_resample.sample_rate, Some(22_050));
+        let zip_request: SaveAssetsAsZipRequest = serde_json::from_str(
+            r#"{"scanId":"s","assetIds":[],"destinationZipPath":"/tmp/a.zip","channels":2}"#,
+        )
+        .expect("must parse zip export request");
+        assert_eq!(zip_request.audio_resample.channels, Some(2));
+        assert!(AudioResample::default().validate().is_ok());
+        assert!(AudioResample {
+            sample_rate: Some(4_000),
+            channels: None,
+        }
+        .validate()
+        .is_err());
+        assert!(AudioResample {
+            sample_rate: None,
+            channels: Some(0),
+        }
+        .validate()
+        .is_err());
+        assert!(AudioResample {
+            sample_rate: None,
+            channels: Some(9),
+        }
+        .validate()
+        .is_err());
     }
 
-    Ok(job.output_path.clone())
-}
-
-fn convert_audio_bytes_to_file(
-    ffmpeg_path: &Path,
-    input_bytes: &[u8],
-    output_path: &Path,
-    format: &AudioFormat,
-) -> Result<(), String> {
-    let mut command = Command::new(ffmpeg_path);
-    command.arg("-y");
-    command.arg("-hide_banner");
-    command.arg("-loglevel");
-    command.arg("error");
-    command.arg("-i");
-    command.arg("pipe:0");
-    command.arg("-vn");
-
-    match format {
-        AudioFormat::Original => {
-            command.arg("-c:a");
-            command.arg("copy");
+    #[test]
+    fn audio_trim_rejects_empty_ranges() {
+        let trim: AudioTrim = serde_json::from_str(r#"{"startMs":1500,"endMs":62005}"#).unwrap();
+        assert_eq!(trim.validate(), Ok(trim));
+        assert_eq!(ffmpeg_seconds(62_005), "62.005");
+        assert!(AudioTrim {
+            start_ms: Some(2_000),
+            end_ms: Some(2_000),
         }
-        AudioFormat::Mp3 => {
-            command.arg("-c:a");
-            command.arg("libmp3lame");
-            command.arg("-q:a");
-            command.arg("2");
+        .validate()
+        .is_err());
+        assert!(AudioTrim {
+            start_ms: None,
+            end_ms: Some(0),
         }
-        AudioFormat::Wav => {
-            command.arg("-c:a");
-            command.arg("pcm_s16le");
+        .validate()
+        .is_err());
+        assert!(AudioTrim {
+            start_ms: Some(500),
+            end_ms: None,
         }
+        .validate()
+        .is_ok());
     }
 
-    command.arg(output_path);
-    command.stdin(Stdio::piped());
-    command.stdout(Stdio::null());
-    command.stderr(Stdio::piped());
-
-    let mut child = command
-        .spawn()
-        .map_err(|error| format!("Failed to start ffmpeg: {error}"))?;
-
-    {
-        let stdin = child
-            .stdin
-            .as_mut()
-            .ok_or_else(|| "Failed to open ffmpeg stdin".to_string())?;
-        stdin
-            .write_all(input_bytes)
-            .map_err(|error| format!("Failed to stream audio data to ffmpeg: {error}"))?;
+    #[test]
+    fn search_cursors_round_trip_and_expire_with_the_scan() {
+        let key = SearchCacheKey {
+            query: "stone".to_string(),
+            match_mode: SearchMatchMode::Smart,
+            fuzzy: FuzzyMode::Conservative,
+            filters: AssetFilterOptions::default(),
+        };
+        let token = SearchCursor {
+            generation: 3,
+            signature: key.signature(),
+            position: 640,
+        }
+        .encode();
+
+        assert_eq!(SearchCursor::resolve(&token, 3, &key), Ok(640));
+        assert!(SearchCursor::resolve(&token, 4, &key)
+            .expect_err("stale cursor")
+            .contains("scan changed"));
+        let other = SearchCacheKey {
+            query: "dirt".to_string(),
+            ..key.clone()
+        };
+        assert!(SearchCursor::resolve(&token, 3, &other).is_err());
+        assert!(SearchCursor::resolve("not a cursor", 3, &key).is_err());
     }
 
-    let output = child
-        .wait_with_output()
-        .map_err(|error| format!("Failed to wait for ffmpeg: {error}"))?;
+    #[test]
+    fn scan_timing_smooths_container_rate_and_resets_per_phase() {
+        let start = Instant::now();
+        let mut timing = ScanTiming::start(start);
+        timing.record(start + Duration::from_secs(1), 0);
+        assert_eq!(timing.containers_per_second, None);
+
+        let phase_start = timing.started_at;
+        timing.record(phase_start + Duration::from_secs(1), 10);
+        assert_eq!(timing.containers_per_second, Some(10.0));
+        timing.record(phase_start + Duration::from_secs(2), 30);
+        let smoothed = timing
+            .containers_per_second
+            .expect("rate after two samples");
+        assert!((smoothed - 13.0).abs() < 1e-9);
+        assert_eq!(
+            timing.elapsed_ms(phase_start + Duration::from_secs(2)),
+            2000
+        );
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("FFmpeg conversion failed: {}", stderr.trim()));
+        timing.record(phase_start + Duration::from_secs(3), 0);
+        assert_eq!(timing.containers_per_second, None);
+        assert_eq!(timing.started_at, phase_start + Duration::from_secs(3));
     }
 
-    Ok(())
-}
+    #[test]
+    fn corrupt_containers_surface_as_scan_warnings() {
+        let temp_root = std::env::temp_dir().join(format!("mae-scan-warn-{}", Uuid::new_v4()));
+        fs::create_dir_all(&temp_root).expect("must create temp directory");
+        let container = ScanContainer {
+            source_type: AssetSourceType::Mod,
+            source_name: "broken.jar".to_string(),
+            container_type: AssetContainerType::Jar,
+            container_path: temp_root.join("broken.jar"),
+        };
+        fs::write(&container.container_path, b"not a zip").expect("must write jar");
+
+        let pack_dir = temp_root.join("pack");
+        fs::create_dir_all(pack_dir.join("assets/demo/lang")).expect("must create pack");
+        fs::write(pack_dir.join("assets/demo/lang/en_us.json"), b"{}").expect("must write lang");
+        let pack = ScanContainer {
+            source_type: AssetSourceType::ResourcePack,
+            source_name: "pack".to_string(),
+            container_type: AssetContainerType::Directory,
+            container_path: pack_dir,
+        };
 
-fn split_file_name(file_name: &str) -> (String, String) {
-    let path = Path::new(file_name);
-    let stem = path
-        .file_stem()
-        .map(|value| value.to_string_lossy().to_string())
-        .unwrap_or_else(|| "asset".to_string());
-    let extension = path
-        .extension()
-        .map(|value| value.to_string_lossy().to_ascii_lowercase())
-        .unwrap_or_default();
+        let containers = [(container, Vec::new()), (pack, Vec::new())];
+        let mut outcomes = spawn_scan_pool(
+            Arc::new(
+                containers
+                    .iter()
+                    .map(|(container, _)| (container.clone(), None))
+                    .collect(),
+            ),
+            ScanOptions::default(),
+            MAX_SCAN_WORKERS,
+            Arc::new(AtomicBool::new(false)),
+            |container| {
+                container_signature_for_path(
+                    &container.container_path,
+                    &container.container_type,
+                    false,
+                )
+            },
+        )
+        .iter()
+        .collect::<Vec<_>>();
+        outcomes.sort_by_key(|(index, _)| *index);
+
+        let mut scan = ScanState::new();
+        let mut key_counts = HashMap::new();
+        let mut emitted = Vec::new();
+        for (index, outcome) in outcomes {
+            let (container, instances) = &containers[index];
+            emitted.extend(scan.record_scanned_container(
+                container,
+                instances,
+                outcome,
+                &mut key_counts,
+            ));
+        }
+        // The broken jar only costs its own assets.
+        assert_eq!(emitted.len(), 1);
+        assert_eq!(scan.warnings.len(), 1);
+        assert_eq!(scan.assets.len(), 1);
+        assert_eq!(scan.assets[0].source_name, "pack");
+
+        let status = serde_json::to_value(scan.as_status("scan")).expect("status must serialize");
+        assert_eq!(status["warnings"][0]["sourceName"], "broken.jar");
+        assert!(status["error"].is_null());
+        let _ = fs::remove_dir_all(&temp_root);
+    }
 
-    (stem, extension)
-}
+    #[test]
+    fn empty_sources_list_containers_without_assets() {
+        let container = |source_name: &str| ScanContainer {
+            source_type: AssetSourceType::Mod,
+            source_name: source_name.to_string(),
+            container_type: AssetContainerType::Jar,
+            container_path: PathBuf::from(format!("/mods/{source_name}.jar")),
+        };
+        let code_only = container("lib");
+        let textured = container("gems");
+        let containers_by_key = [&code_only, &textured]
+            .into_iter()
+            .map(|container| (scan_container_key(container), container.clone()))
+            .collect::<HashMap<_, _>>();
+        let mut container_assets = HashMap::new();
+        container_assets.insert(scan_container_key(&code_only), Vec::new());
+        container_assets.insert(
+            scan_container_key(&textured),
+            vec![sample_asset(
+                "gems:item/ruby",
+                AssetSourceType::Mod,
+                "gems",
+                "gems",
+                "textures/item/ruby.png",
+            )],
+        );
+        // Keys without a matching container have no name to report and are skipped.
+        container_assets.insert("mod::gone::jar::/mods/gone.jar".to_string(), Vec::new());
 
-fn dedupe_file_name(
-    base_stem: &str,
-    extension: &str,
-    destination_dir: &Path,
-    used_names: &mut HashSet<String>,
-) -> String {
-    let mut index = 0;
+        assert_eq!(
+            collect_empty_sources(&container_assets, &containers_by_key),
+            vec![EmptySource {
+                container_key: scan_container_key(&code_only),
+                source_name: "lib".to_string(),
+            }]
+        );
+    }
 
-    loop {
-        let candidate = if index == 0 {
-            if extension.is_empty() {
-                base_stem.to_string()
-            } else {
-                format!("{base_stem}.{extension}")
-            }
-        } else if extension.is_empty() {
-            format!("{base_stem}_{index}")
-        } else {
-            format!("{base_stem}_{index}.{extension}")
+    #[test]
+    fn search_cache_evicts_least_recently_used_and_clears_on_change() {
+        let key = |query: &str| SearchCacheKey {
+            query: query.to_string(),
+            match_mode: SearchMatchMode::Smart,
+            fuzzy: FuzzyMode::Conservative,
+            filters: AssetFilterOptions::default(),
         };
-
-        if used_names.contains(&candidate) || destination_dir.join(&candidate).exists() {
-            index += 1;
-            continue;
+        let mut scan = ScanState::new();
+        let order = |index: usize| Arc::new(SearchOrder::new(vec![(0, index)], false));
+        for index in 0..SEARCH_CACHE_CAPACITY {
+            scan.cache_search(key(&index.to_string()), order(index));
         }
+        // Touching "0" makes "1" the eviction candidate.
+        assert_eq!(scan.cached_search(&key("0")), Some(order(0)));
+        scan.cache_search(key("new"), order(0));
+        assert!(scan.cached_search(&key("1")).is_none());
+        assert!(scan.cached_search(&key("0")).is_some());
 
-        used_names.insert(candidate.clone());
-        return candidate;
+        scan.invalidate_derived_indexes();
+        assert!(scan.cached_search(&key("new")).is_none());
     }
-}
 
-fn resolve_ffmpeg_path(app: &AppHandle) -> Result<PathBuf, String> {
-    if ffmpeg_works(Path::new("ffmpeg")) {
-        return Ok(PathBuf::from("ffmpeg"));
-    }
+    #[test]
+    fn archive_handle_cache_evicts_oldest_and_drops_changed_archives() {
+        let temp_root = std::env::temp_dir().join(format!("mae-handles-{}", Uuid::new_v4()));
+        fs::create_dir_all(&temp_root).expect("must create temp directory");
+        let write_archive = |name: &str, payload: &[u8]| {
+            let path = temp_root.join(name);
+            let mut writer =
+                zip::ZipWriter::new(fs::File::create(&path).expect("must create archive"));
+            writer
+                .start_file("pack.mcmeta", zip::write::SimpleFileOptions::default())
+                .expect("must start zip entry");
+            writer.write_all(payload).expect("must write zip entry");
+            writer.finish().expect("must finish zip");
+            path.to_string_lossy().to_string()
+        };
+        let open = |path: &str| {
+            CachedArchive::Zip(
+                ZipArchive::new(
+                    ArchiveReader::open_file(Path::new(path)).expect("must open archive"),
+                )
+                .expect("must read archive"),
+            )
+        };
 
-    let base_dir = app
-        .path()
-        .app_cache_dir()
-        .map_err(|error| format!("Failed to resolve app cache directory: {error}"))?
-        .join("ffmpeg-runtime");
+        let paths = (0..=ARCHIVE_HANDLE_CACHE_CAPACITY)
+            .map(|index| write_archive(&format!("{index}.zip"), b"{}"))
+            .collect::<Vec<_>>();
+        let mut cache = ArchiveHandleCache::default();
+        for path in &paths {
+            cache.check_in(path.clone(), open(path));
+        }
+        assert_eq!(cache.entries.len(), ARCHIVE_HANDLE_CACHE_CAPACITY);
+        assert!(cache.check_out(&paths[0]).is_none());
+        assert!(cache.check_out(&paths[1]).is_some());
 
-    fs::create_dir_all(&base_dir)
-        .map_err(|error| format!("Failed to create FFmpeg runtime directory: {error}"))?;
+        write_archive("2.zip", b"{\"pack\": {\"pack_format\": 34}}");
+        assert!(cache.check_out(&paths[2]).is_none());
+        let _ = fs::remove_dir_all(&temp_root);
+    }
 
-    let ffmpeg_binary = if cfg!(windows) {
-        base_dir.join("ffmpeg.exe")
-    } else {
-        base_dir.join("ffmpeg")
-    };
+    #[test]
+    fn grouped_results_keep_rank_order_per_folder() {
+        let asset = |key: &str, path: &str| {
+            sample_asset(
+                key,
+                AssetSourceType::Vanilla,
+                "minecraft-1.21.1",
+                "minecraft",
+                path,
+            )
+        };
+        let groups = group_assets_by_folder(vec![
+            asset("stone", "textures/block/stone.png"),
+            asset("stick", "textures/item/stick.png"),
+            asset("granite", "textures/block/granite.png"),
+        ]);
 
-    if ffmpeg_works(&ffmpeg_binary) {
-        return Ok(ffmpeg_binary);
+        let summary = groups
+            .iter()
+            .map(|group| {
+                (
+                    group.folder_name.as_str(),
+                    group
+                        .assets
+                        .iter()
+                        .map(|asset| asset.key.as_str())
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![("block", vec!["stone", "granite"]), ("item", vec!["stick"])]
+        );
+        assert_eq!(
+            groups[0].folder_node_id,
+            "root/vanilla/minecraft-1.21.1/minecraft/textures/block"
+        );
     }
 
-    let url =
-        ffmpeg_download_url().map_err(|error| format!("Failed to resolve FFmpeg URL: {error}"))?;
-    let archive_path = download_ffmpeg_package(url, &base_dir)
-        .map_err(|error| format!("Failed to download FFmpeg runtime: {error}"))?;
-    unpack_ffmpeg(&archive_path, &base_dir)
-        .map_err(|error| format!("Failed to unpack FFmpeg runtime: {error}"))?;
+    #[test]
+    fn tree_path_uses_display_names_and_rejects_unknown_nodes() {
+        let mut scan = ScanState::new();
+        let mut asset = sample_asset(
+            "mod.create.create.textures.block.crate.png",
+            AssetSourceType::Mod,
+            "create-1.20.1",
+            "create",
+            "textures/block/crate.png",
+        );
+        asset.mod_display_name = Some("Create: Above/Beyond".to_string());
+        add_asset_to_tree(&mut scan.tree_children, &mut scan.tree_index, &asset);
+
+        let folder = asset_folder_node_id(&asset);
+        let names = scan
+            .tree_path(&folder)
+            .expect("folder should resolve")
+            .into_iter()
+            .map(|crumb| crumb.name)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "mods",
+                "Create: Above/Beyond",
+                "create",
+                "textures",
+                "block"
+            ]
+        );
+        assert_eq!(scan.tree_path(ROOT_NODE_ID), Some(Vec::new()));
+        assert_eq!(scan.tree_path("root/mods/missing"), None);
+    }
 
-    if !ffmpeg_works(&ffmpeg_binary) {
-        return Err(
-            "FFmpeg was downloaded but is not executable. Install FFmpeg manually and add it to PATH."
-                .to_string(),
+    #[test]
+    fn tree_sort_modes_order_children() {
+        let node = |name: &str, node_type: TreeNodeType| TreeNode {
+            id: name.to_string(),
+            name: name.to_string(),
+            has_children: matches!(node_type, TreeNodeType::Folder),
+            node_type,
+            asset_id: None,
+            asset_count: 1,
+        };
+        let names = |sort: TreeSort| {
+            let mut children = vec![
+                node("frame10.png", TreeNodeType::File),
+                node("Frame2.png", TreeNodeType::File),
+                node("zombie", TreeNodeType::Folder),
+                node("armor", TreeNodeType::Folder),
+            ];
+            sort_tree_nodes(&mut children, sort);
+            children
+                .into_iter()
+                .map(|node| node.name)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(TreeSort::TypeThenName),
+            ["armor", "zombie", "frame10.png", "Frame2.png"]
+        );
+        assert_eq!(
+            names(TreeSort::Natural),
+            ["armor", "zombie", "Frame2.png", "frame10.png"]
+        );
+        assert_eq!(
+            names(TreeSort::NameAsc),
+            ["armor", "frame10.png", "Frame2.png", "zombie"]
+        );
+        assert_eq!(
+            names(TreeSort::NameDesc),
+            ["zombie", "Frame2.png", "frame10.png", "armor"]
         );
     }
 
-    Ok(ffmpeg_binary)
-}
+    #[test]
+    fn single_namespace_mods_collapse_when_namespace_matches_jar() {
+        let mut tree_children = HashMap::new();
+        let mut tree_index = HashMap::new();
+        for asset in [
+            sample_asset(
+                "a",
+                AssetSourceType::Mod,
+                "sophisticated-backpacks-1.20.1-3.20",
+                "sophisticatedbackpacks",
+                "textures/item/backpack.png",
+            ),
+            sample_asset(
+                "b",
+                AssetSourceType::Mod,
+                "jei-forge-15.2",
+                "jei",
+                "textures/gui/icons.png",
+            ),
+            sample_asset(
+                "c",
+                AssetSourceType::Mod,
+                "jei-forge-15.2",
+                "minecraft",
+                "lang/en_us.json",
+            ),
+            sample_asset(
+                "d",
+                AssetSourceType::Mod,
+                "storage",
+                "create",
+                "textures/block/crate.png",
+            ),
+        ] {
+            add_asset_to_tree(&mut tree_children, &mut tree_index, &asset);
+        }
 
-fn ffmpeg_works(path: &Path) -> bool {
-    let mut command = Command::new(path);
-    command.arg("-version");
+        assert_eq!(
+            collapsible_namespace_node(
+                &tree_children,
+                "root/mods/sophisticated-backpacks-1.20.1-3.20",
+                |_| None
+            )
+            .as_deref(),
+            Some("root/mods/sophisticated-backpacks-1.20.1-3.20/sophisticatedbackpacks")
+        );
+        // Two namespaces, or one that isn't the mod's own, keep the extra level.
+        assert_eq!(
+            collapsible_namespace_node(&tree_children, "root/mods/jei-forge-15.2", |_| None),
+            None
+        );
+        assert_eq!(
+            collapsible_namespace_node(&tree_children, "root/mods/storage", |_| None),
+            None
+        );
+        // The parsed id wins over the jar stem in both directions.
+        assert_eq!(
+            collapsible_namespace_node(&tree_children, "root/mods/storage", |source_name| {
+                assert_eq!(source_name, "storage");
+                Some("create".to_string())
+            })
+            .as_deref(),
+            Some("root/mods/storage/create")
+        );
+        assert_eq!(
+            collapsible_namespace_node(
+                &tree_children,
+                "root/mods/sophisticated-backpacks-1.20.1-3.20",
+                |_| Some("backpacks".to_string())
+            ),
+            None
+        );
+        assert_eq!(derive_mod_id("Xaeros_Minimap_24.0_Forge"), "xaerosminimap");
 
-    command
-        .status()
-        .map(|status| status.success())
-        .unwrap_or(false)
-}
+        let mods = &tree_children["root"][0];
+        assert_eq!((mods.name.as_str(), mods.asset_count), ("mods", 4));
+        let jei = tree_children["root/mods"]
+            .iter()
+            .find(|node| node.name == "jei-forge-15.2")
+            .expect("jei folder");
+        assert_eq!(jei.asset_count, 2);
+        // Adding the same asset again leaves the counts alone.
+        add_asset_to_tree(
+            &mut tree_children,
+            &mut tree_index,
+            &sample_asset(
+                "d",
+                AssetSourceType::Mod,
+                "storage",
+                "create",
+                "textures/block/crate.png",
+            ),
+        );
+        assert_eq!(tree_children["root"][0].asset_count, 4);
+        assert_eq!(derive_mod_id("create-fabric-0.5.1.f"), "create");
+    }
 
-fn extract_asset_bytes(asset: &AssetRecord) -> Result<Vec<u8>, String> {
-    let mut archive_cache = HashMap::<String, ZipArchive<fs::File>>::new();
-    extract_asset_bytes_with_archive_cache(asset, &mut archive_cache)
-}
+    #[test]
+    fn mod_display_names_come_from_loader_metadata() {
+        let metadata = |mod_id: &str, display_name: &str| ModMetadata {
+            mod_id: Some(mod_id.to_string()),
+            display_name: Some(display_name.to_string()),
+        };
+        assert_eq!(
+            parse_fabric_mod_metadata(r#"{"schemaVersion": 1, "id": "create", "name": "Create"}"#),
+            metadata("create", "Create")
+        );
+        assert_eq!(
+            parse_fabric_mod_metadata(r#"{"id": "sodium", "name": " "}"#),
+            metadata("sodium", "sodium")
+        );
+        assert_eq!(
+            parse_quilt_mod_metadata(
+                r#"{"quilt_loader": {"id": "qsl", "metadata": {"name": "QSL"}}}"#
+            ),
+            metadata("qsl", "QSL")
+        );
 
-fn extract_asset_bytes_with_archive_cache(
-    asset: &AssetRecord,
-    archive_cache: &mut HashMap<String, ZipArchive<fs::File>>,
-) -> Result<Vec<u8>, String> {
-    let container_path = PathBuf::from(&asset.container_path);
+        let mods_toml = r#"
+modLoader = "javafml"
+loaderVersion = "[47,)"
 
-    match asset.container_type {
-        AssetContainerType::Directory => {
-            let file_path = container_path.join(Path::new(&asset.entry_path));
-            fs::read(&file_path)
-                .map_err(|error| format!("Failed to read file {}: {error}", file_path.display()))
-        }
-        AssetContainerType::AssetIndex => Err(
-            "AssetIndex container type is metadata-only and cannot be extracted directly"
-                .to_string(),
-        ),
-        AssetContainerType::Zip | AssetContainerType::Jar => {
-            if !archive_cache.contains_key(&asset.container_path) {
-                let file = fs::File::open(&container_path).map_err(|error| {
-                    format!(
-                        "Failed to open archive {}: {error}",
-                        container_path.display()
-                    )
-                })?;
-                let archive = ZipArchive::new(file).map_err(|error| {
-                    format!(
-                        "Failed to read archive {}: {error}",
-                        container_path.display()
-                    )
-                })?;
-                archive_cache.insert(asset.container_path.clone(), archive);
-            }
+[[mods]]
+modId = "jei"
+displayName = "Just Enough Items"
 
-            let archive = archive_cache
-                .get_mut(&asset.container_path)
-                .ok_or_else(|| "Failed to get cached archive".to_string())?;
+[[mods]]
+modId = "jei_addon"
+"#;
+        assert_eq!(
+            parse_mods_toml_metadata(mods_toml),
+            metadata("jei", "Just Enough Items")
+        );
+        assert_eq!(
+            parse_mods_toml_metadata(
+                "[[mods]]\nmodId = \"ftbquests\"\ndisplayName = \"${mod_name}\""
+            ),
+            metadata("ftbquests", "ftbquests")
+        );
+        assert_eq!(
+            parse_mods_toml_metadata("not toml ["),
+            ModMetadata::default()
+        );
+    }
 
-            let mut entry = archive.by_name(&asset.entry_path).map_err(|error| {
-                format!("Failed to open archive entry {}: {error}", asset.entry_path)
-            })?;
+    #[test]
+    fn nested_jars_name_their_own_assets() {
+        fn write_zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+            let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+            for (name, bytes) in entries {
+                writer
+                    .start_file(*name, zip::write::SimpleFileOptions::default())
+                    .expect("must start zip entry");
+                writer.write_all(bytes).expect("must write zip entry");
+            }
+            writer.finish().expect("must finish zip").into_inner()
+        }
 
-            let mut buffer = Vec::new();
-            entry.read_to_end(&mut buffer).map_err(|error| {
-                format!("Failed to read archive entry {}: {error}", asset.entry_path)
-            })?;
+        let library = write_zip(&[("assets/library/lang/en_us.json", b"{}")]);
+        let inner = write_zip(&[
+            (
+                "fabric.mod.json",
+                br#"{"id": "inner", "name": "Inner Mod"}"#,
+            ),
+            ("assets/inner/textures/item/gem.png", b"gem"),
+        ]);
+        let outer = write_zip(&[
+            (
+                "fabric.mod.json",
+                br#"{"id": "outer", "name": "Outer Mod"}"#,
+            ),
+            ("META-INF/jars/inner-1.0.jar", &inner),
+            ("META-INF/jars/library-2.0.jar", &library),
+            ("assets/outer/textures/item/ore.png", b"ore"),
+        ]);
+        let temp_root = std::env::temp_dir().join(format!("mae-nested-names-{}", Uuid::new_v4()));
+        fs::create_dir_all(&temp_root).expect("must create temp directory");
+        let jar_path = temp_root.join("outer-1.0.jar");
+        fs::write(&jar_path, outer).expect("must write outer jar");
+
+        let container = ScanContainer {
+            source_type: AssetSourceType::Mod,
+            source_name: "outer-1.0".to_string(),
+            container_type: AssetContainerType::Jar,
+            container_path: jar_path,
+        };
+        let candidates = scan_archive_container(
+            &container,
+            ScanOptions::default(),
+            &|| false,
+            &mut Vec::new(),
+        )
+        .expect("must scan jar");
+        let assets = finalize_assets(candidates, &mut HashMap::new());
+        let names = |namespace: &str| {
+            let asset = assets
+                .iter()
+                .find(|asset| asset.namespace == namespace)
+                .expect("asset must be found");
+            (asset.mod_id.as_deref(), asset.mod_display_name.as_deref())
+        };
+        assert_eq!(names("outer"), (Some("outer"), Some("Outer Mod")));
+        assert_eq!(names("inner"), (Some("inner"), Some("Inner Mod")));
+        // Nested jars without metadata belong to the outer mod.
+        assert_eq!(names("library"), (Some("outer"), Some("Outer Mod")));
 
-            Ok(buffer)
+        // Whichever asset comes first, the source folder shows the outer mod.
+        let mut tree_children = HashMap::new();
+        let mut tree_index = HashMap::new();
+        for asset in &assets {
+            add_asset_to_tree(&mut tree_children, &mut tree_index, asset);
         }
+        assert_eq!(tree_children["root/mods"][0].name, "Outer Mod");
+        let _ = fs::remove_dir_all(&temp_root);
     }
-}
 
-fn mime_for_extension(extension: &str) -> &'static str {
-    match extension {
-        "png" => "image/png",
-        "jpg" | "jpeg" => "image/jpeg",
-        "gif" => "image/gif",
-        "webp" => "image/webp",
-        "bmp" => "image/bmp",
-        "tif" | "tiff" => "image/tiff",
-        "ico" => "image/x-icon",
-        "tga" => "image/x-tga",
-        "ogg" | "oga" => "audio/ogg",
-        "wav" => "audio/wav",
-        "mp3" => "audio/mpeg",
-        "flac" => "audio/flac",
-        "opus" => "audio/opus",
-        "m4a" => "audio/mp4",
-        "aac" => "audio/aac",
-        "json" | "mcmeta" => "application/json",
-        _ => "application/octet-stream",
+    #[test]
+    fn duplicate_assets_group_identical_content_only() {
+        let temp_root = std::env::temp_dir().join(format!("mae-duplicates-{}", Uuid::new_v4()));
+        let files = [
+            ("a.png", b"same-bytes".as_slice()),
+            ("b.png", b"same-bytes"),
+            ("c.png", b"diff-bytes"),
+            ("d.png", b"short"),
+            ("e.png", b"same-bytes"),
+        ];
+        let assets = files
+            .iter()
+            .map(|(name, bytes)| {
+                fs::create_dir_all(&temp_root).expect("temp dir should be created");
+                fs::write(temp_root.join(name), bytes).expect("fixture should be written");
+                let mut asset = sample_asset(
+                    name,
+                    AssetSourceType::ResourcePack,
+                    "pack",
+                    "minecraft",
+                    &format!("textures/{name}"),
+                );
+                asset.container_type = AssetContainerType::Directory;
+                asset.container_path = temp_root.to_string_lossy().to_string();
+                asset.entry_path = name.to_string();
+                // `e.png` has no recorded size so it is read from the file system.
+                asset.byte_size = (*name != "e.png").then_some(bytes.len() as u64);
+                asset
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            group_duplicate_assets(&assets, MAX_EXPORT_WORKERS),
+            vec![DuplicateAssetGroup {
+                byte_size: 10,
+                asset_ids: vec![
+                    "a.png".to_string(),
+                    "b.png".to_string(),
+                    "e.png".to_string()
+                ],
+            }]
+        );
+        let _ = fs::remove_dir_all(&temp_root);
     }
-}
 
-fn is_image_extension(extension: &str) -> bool {
-    matches!(
-        extension,
-        "png" | "jpg" | "jpeg" | "gif" | "webp" | "bmp" | "tga" | "tif" | "tiff" | "ico"
-    )
-}
+    #[test]
+    fn model_textures_follow_variables_through_parent_chain() {
+        let child = serde_json::json!({
+            "parent": "block/cube_all",
+            "textures": { "all": "examplemod:block/ore", "particle": "#all" }
+        });
+        let parent = serde_json::json!({
+            "textures": { "particle": "#missing", "down": "#all", "side": "#nowhere" }
+        });
 
-fn is_audio_extension(extension: &str) -> bool {
-    matches!(
-        extension,
-        "ogg" | "wav" | "mp3" | "flac" | "m4a" | "aac" | "opus" | "oga"
-    )
-}
+        let mut textures = Vec::new();
+        collect_model_textures(&child, &mut textures);
+        collect_model_textures(&parent, &mut textures);
+        assert_eq!(
+            resolve_model_textures(&textures),
+            vec![
+                ("all".to_string(), "examplemod:block/ore".to_string()),
+                ("particle".to_string(), "examplemod:block/ore".to_string()),
+                ("down".to_string(), "examplemod:block/ore".to_string()),
+            ]
+        );
+        assert_eq!(
+            parse_resource_location("block/cube_all"),
+            ("minecraft".to_string(), "block/cube_all".to_string())
+        );
+    }
 
-fn is_json_extension(extension: &str) -> bool {
-    matches!(extension, "json" | "mcmeta")
-}
+    #[test]
+    fn asset_index_paths_come_from_scanned_index_containers() {
+        let container = |source_name: &str, container_type, path: &str| ScanContainer {
+            source_type: AssetSourceType::Vanilla,
+            source_name: source_name.to_string(),
+            container_type,
+            container_path: PathBuf::from(path),
+        };
+        let containers = [
+            container(
+                "minecraft-1.21.1",
+                AssetContainerType::AssetIndex,
+                "/a/17.json",
+            ),
+            container("minecraft-1.21.1", AssetContainerType::Jar, "/a/1.21.1.jar"),
+            container(
+                "minecraft-1.20.1",
+                AssetContainerType::AssetIndex,
+                "/a/5.json",
+            ),
+            // Source names may contain the key separator.
+            container("odd::name", AssetContainerType::AssetIndex, "/a/odd.json"),
+        ];
+        assert_eq!(
+            scan_asset_index_paths(
+                containers
+                    .iter()
+                    .filter(|container| container.source_name == "minecraft-1.21.1")
+            ),
+            vec![PathBuf::from("/a/17.json")]
+        );
+        assert_eq!(
+            scan_asset_index_paths(
+                containers
+                    .iter()
+                    .filter(|container| container.source_name == "odd::name")
+            ),
+            vec![PathBuf::from("/a/odd.json")]
+        );
+    }
 
-fn dedupe_candidates(
-    candidates: Vec<PrismRootCandidate>,
-) -> Result<Vec<PrismRootCandidate>, String> {
-    let mut seen = HashSet::new();
-    let mut deduped = Vec::new();
+    #[test]
+    fn sound_events_list_file_and_event_variants() {
+        let events = parse_sound_events(
+            "examplemod",
+            br#"{
+                "block.ore.break": {
+                    "subtitle": "subtitles.block.ore.break",
+                    "sounds": [
+                        "examplemod:block/ore/break1",
+                        { "name": "block/ore/break2", "volume": 0.8 },
+                        { "name": "minecraft:block.stone.break", "type": "event" }
+                    ]
+                },
+                "block.ore.silent": { "replace": true }
+            }"#,
+        )
+        .expect("valid sounds.json");
 
-    for candidate in candidates {
-        if seen.insert(candidate.path.clone()) {
-            deduped.push(candidate);
-        }
+        let variant = |kind, location: &str| SoundVariant {
+            kind,
+            location: location.to_string(),
+            asset_id: None,
+        };
+        assert_eq!(
+            events,
+            vec![
+                SoundEvent {
+                    event: "examplemod:block.ore.break".to_string(),
+                    subtitle: Some("subtitles.block.ore.break".to_string()),
+                    variants: vec![
+                        variant(SoundVariantKind::File, "examplemod:block/ore/break1"),
+                        variant(SoundVariantKind::File, "minecraft:block/ore/break2"),
+                        variant(SoundVariantKind::Event, "minecraft:block.stone.break"),
+                    ],
+                },
+                SoundEvent {
+                    event: "examplemod:block.ore.silent".to_string(),
+                    subtitle: None,
+                    variants: Vec::new(),
+                },
+            ]
+        );
+        assert!(parse_sound_events("examplemod", b"[]").is_err());
     }
 
-    if deduped.is_empty() {
-        return Err("No Prism Launcher candidates were found on this machine".to_string());
+    #[test]
+    fn preview_errors_serialize_with_a_code() {
+        assert!(is_unpreviewable_extension("exr") && !is_unpreviewable_extension("png"));
+        assert_eq!(
+            serde_json::to_value(PreviewError::UnsupportedPreview {
+                extension: "exr".to_string(),
+            })
+            .expect("serializable"),
+            serde_json::json!({ "code": "unsupported_preview", "extension": "exr" })
+        );
+        assert_eq!(
+            serde_json::to_value(PreviewError::from("Unknown asset id: x".to_string()))
+                .expect("serializable"),
+            serde_json::json!({ "code": "failed", "message": "Unknown asset id: x" })
+        );
     }
 
-    Ok(deduped)
-}
+    #[test]
+    fn text_preview_caps_size_and_decodes_lossily() {
+        assert_eq!(
+            text_preview(b"item.stick.name=Stick\xFF"),
+            ("item.stick.name=Stick\u{FFFD}".to_string(), false)
+        );
 
-fn build_candidate(path: PathBuf, source: &str) -> PrismRootCandidate {
-    let exists = path.exists();
-    let valid = is_valid_prism_root(&path);
+        let large = vec![b'a'; TEXT_PREVIEW_MAX_BYTES + 10];
+        let (text, truncated) = text_preview(&large);
+        assert!(truncated);
+        assert_eq!(text.len(), TEXT_PREVIEW_MAX_BYTES);
+        assert_eq!(mime_for_extension("fsh"), "text/plain");
+    }
 
-    PrismRootCandidate {
-        path: path.to_string_lossy().to_string(),
-        exists,
-        valid,
-        source: source.to_string(),
+    #[test]
+    fn pretty_print_json_keeps_key_order_and_reports_position() {
+        let pretty = pretty_print_json(
+            b"\xEF\xBB\xBF{\"parent\":\"block/cube\",\"ambientocclusion\":false}",
+        )
+        .expect("valid json should format");
+        assert_eq!(
+            String::from_utf8(pretty).expect("utf8"),
+            "{\n  \"parent\": \"block/cube\",\n  \"ambientocclusion\": false\n}"
+        );
+
+        let error = pretty_print_json(b"{\n  \"parent\": }").expect_err("broken json");
+        assert!(
+            error.starts_with("Invalid JSON at line 2, column 13"),
+            "{error}"
+        );
     }
-}
 
-fn resolve_instance_dir(prism_root: &Path, instance_folder: &str) -> Result<PathBuf, String> {
-    let requested = expand_home(instance_folder);
-    if requested.is_dir()
-        && requested
-            .parent()
-            .map(|parent| parent.ends_with("instances"))
-            .unwrap_or(false)
-    {
-        return Ok(requested);
+    #[test]
+    fn waveform_peaks_track_min_and_max_per_bucket() {
+        let samples = [0, 16384, -16384, 0, 32767, -32768];
+        assert_eq!(
+            waveform_peaks(&samples, 3),
+            vec![0.0, 0.5, -0.5, 0.0, -1.0, 32767.0 / 32768.0]
+        );
+        // More buckets than samples pads with silence instead of panicking.
+        assert_eq!(waveform_peaks(&[], 2), vec![0.0; 4]);
+        assert_eq!(waveform_peaks(&[8192], 2), vec![0.0, 0.0, 0.25, 0.25]);
+    }
+
+    #[test]
+    fn ffprobe_metadata_reads_stream_and_format_fields() {
+        let metadata = parse_ffprobe_metadata(
+            br#"{
+                "streams": [
+                    { "codec_name": "vorbis", "sample_rate": "44100", "channels": 1 }
+                ],
+                "format": { "duration": "2.345000", "bit_rate": "96000" }
+            }"#,
+        )
+        .expect("valid ffprobe output");
+
+        assert_eq!(
+            metadata,
+            AudioMetadata {
+                duration_ms: Some(2345),
+                sample_rate: Some(44_100),
+                channels: Some(1),
+                codec: Some("vorbis".to_string()),
+                bitrate: Some(96_000),
+            }
+        );
+        assert!(parse_ffprobe_metadata(br#"{ "streams": [] }"#).is_err());
     }
 
-    let folder_name = Path::new(instance_folder)
-        .file_name()
-        .map(|value| value.to_string_lossy().to_string())
-        .unwrap_or_else(|| instance_folder.to_string());
+    #[test]
+    fn normalization_is_rejected_for_stream_copies() {
+        assert!(audio_normalization_requested(&AudioFormat::Original, Some(true)).is_err());
+        assert!(!audio_normalization_requested(&AudioFormat::Original, None).expect("no-op"));
+        assert!(audio_normalization_requested(&AudioFormat::Mp3, Some(true)).expect("re-encode"));
+    }
 
-    let path = prism_root.join("instances").join(folder_name);
-    if !path.is_dir() {
-        return Err(format!("Instance directory not found: {}", path.display()));
+    #[test]
+    fn lossless_and_opus_targets_use_their_own_extensions() {
+        let temp_root = std::env::temp_dir().join(format!("mae-export-codec-{}", Uuid::new_v4()));
+        let sound = || {
+            sample_audio_asset(
+                "mod.audio.one.sounds.block.test.step.ogg",
+                "audio-one",
+                "sample",
+                "sounds/block/test/step.ogg",
+            )
+        };
+        for (format, expected) in [
+            (AudioFormat::Flac, "step.flac"),
+            (AudioFormat::Opus, "step.opus"),
+        ] {
+            let jobs = plan_export_jobs(
+                vec![sound()],
+                &temp_root,
+                &ExportOptions {
+                    audio_format: format,
+                    audio_quality: AudioQuality::default(),
+                    normalize_audio: false,
+                    audio_resample: AudioResample::default(),
+                    image_format: ImageFormat::Original,
+                    image_background: JPEG_DEFAULT_BACKGROUND,
+                    naming: ExportNaming::Original,
+                    name_template: None,
+                    layout: ExportLayout::Flat,
+                    cleanup_on_cancel: true,
+                    defer_completion: false,
+                },
+            );
+            assert_eq!(jobs[0].output_path, temp_root.join(expected));
+        }
     }
 
-    Ok(path)
-}
+    #[test]
+    fn namespace_export_prefers_resource_packs_and_keeps_asset_paths() {
+        let temp_root = std::env::temp_dir().join(format!("mae-export-ns-{}", Uuid::new_v4()));
+        let vanilla = sample_asset(
+            "vanilla.minecraft.minecraft.textures.block.stone.png",
+            AssetSourceType::Vanilla,
+            "minecraft-1.21.1",
+            "minecraft",
+            "textures/block/stone.png",
+        );
+        let pack = sample_asset(
+            "resourcepack.faithful.minecraft.textures.block.stone.png",
+            AssetSourceType::ResourcePack,
+            "faithful",
+            "minecraft",
+            "textures/block/stone.png",
+        );
+        let other = sample_asset(
+            "mod.create.create.textures.block.gear.png",
+            AssetSourceType::Mod,
+            "create",
+            "create",
+            "textures/block/gear.png",
+        );
 
-fn validate_prism_root(path: &Path) -> Result<(), String> {
-    if !is_valid_prism_root(path) {
-        return Err(format!(
-            "Invalid Prism root: {} (expected folders: instances and libraries)",
-            path.to_string_lossy()
-        ));
-    }
+        let (selected, conflicts) =
+            select_namespace_assets(vec![&vanilla, &pack, &other], "minecraft");
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].source_name, "faithful");
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].skipped_source_names, vec!["minecraft-1.21.1"]);
+
+        let datapack = sample_asset(
+            "datapack.tweaks.minecraft.textures.block.stone.png",
+            AssetSourceType::Datapack,
+            "tweaks",
+            "minecraft",
+            "textures/block/stone.png",
+        );
+        let assets = [vanilla.clone(), datapack, pack.clone()];
+        let providers = asset_override_providers(&assets, "minecraft", "textures/block/stone.png");
+        assert_eq!(
+            providers
+                .iter()
+                .map(|asset| asset.source_name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["faithful", "minecraft-1.21.1"]
+        );
 
-    Ok(())
-}
+        let jobs = plan_export_jobs(
+            selected,
+            &temp_root,
+            &ExportOptions {
+                audio_format: AudioFormat::Original,
+                audio_quality: AudioQuality::default(),
+                normalize_audio: false,
+                audio_resample: AudioResample::default(),
+                image_format: ImageFormat::Original,
+                image_background: JPEG_DEFAULT_BACKGROUND,
+                naming: ExportNaming::Original,
+                name_template: None,
+                layout: ExportLayout::AssetPath,
+                cleanup_on_cancel: true,
+                defer_completion: false,
+            },
+        );
+        assert_eq!(
+            jobs[0].output_path,
+            temp_root.join("textures").join("block").join("stone.png")
+        );
+    }
 
-fn is_valid_prism_root(path: &Path) -> bool {
-    path.is_dir() && path.join("instances").is_dir() && path.join("libraries").is_dir()
-}
+    #[test]
+    fn image_format_changes_extension_and_flattens_jpeg() {
+        let temp_root = std::env::temp_dir().join(format!("mae-export-image-{}", Uuid::new_v4()));
+        fs::create_dir_all(&temp_root).expect("must create temp export directory");
+        let texture = sample_asset(
+            "vanilla.minecraft.minecraft.textures.block.glass.png",
+            AssetSourceType::Vanilla,
+            "minecraft-1.21.1",
+            "minecraft",
+            "textures/block/glass.png",
+        );
 
-fn home_dir() -> Option<PathBuf> {
-    env::var_os("HOME").map(PathBuf::from)
-}
+        let jobs = plan_export_jobs(
+            vec![texture],
+            &temp_root,
+            &ExportOptions {
+                audio_format: AudioFormat::Original,
+                audio_quality: AudioQuality::default(),
+                normalize_audio: false,
+                audio_resample: AudioResample::default(),
+                image_format: ImageFormat::Jpeg,
+                image_background: JPEG_DEFAULT_BACKGROUND,
+                naming: ExportNaming::Original,
+                name_template: None,
+                layout: ExportLayout::Flat,
+                cleanup_on_cancel: true,
+                defer_completion: false,
+            },
+        );
+        assert_eq!(jobs[0].output_path, temp_root.join("glass.jpg"));
+
+        let mut transparent = RgbaImage::new(2, 2);
+        transparent.put_pixel(0, 0, image::Rgba([10, 20, 30, 255]));
+        let png = encode_rgba_image(&transparent, RasterFormat::Png).expect("must encode png");
+        let background = export_image_background(Some("#000000")).expect("must parse color");
+        convert_image_bytes_to_file(
+            &png,
+            ImageFormat::Jpeg,
+            background,
+            None,
+            &jobs[0].output_path,
+        )
+        .expect("must convert to jpeg");
+        let written = fs::read(&jobs[0].output_path).expect("must read jpeg");
+        assert_eq!(&written[..2], &[0xFF, 0xD8]);
+        let flattened = image::load_from_memory(&written)
+            .expect("must decode jpeg")
+            .to_rgb8();
+        assert!(flattened
+            .get_pixel(1, 1)
+            .0
+            .iter()
+            .all(|channel| *channel < 64));
+        assert_eq!(export_image_background(None), Ok(JPEG_DEFAULT_BACKGROUND));
+
+        assert!(convert_image_bytes_to_file(
+            b"not an image",
+            ImageFormat::Png,
+            JPEG_DEFAULT_BACKGROUND,
+            None,
+            &temp_root.join("broken.png"),
+        )
+        .is_err());
+        let _ = fs::remove_dir_all(&temp_root);
+    }
 
-fn expand_home(path: &str) -> PathBuf {
-    if let Some(stripped) = path.strip_prefix("~/") {
-        if let Some(home) = home_dir() {
-            return home.join(stripped);
-        }
+    #[test]
+    fn tga_textures_decode_and_convert_to_png() {
+        let temp_root = std::env::temp_dir().join(format!("mae-export-tga-{}", Uuid::new_v4()));
+        fs::create_dir_all(&temp_root).expect("must create temp export directory");
+        let mut texture = RgbaImage::new(2, 1);
+        texture.put_pixel(0, 0, image::Rgba([200, 100, 50, 255]));
+        texture.put_pixel(1, 0, image::Rgba([0, 0, 0, 0]));
+        let tga = encode_rgba_image(&texture, RasterFormat::Tga).expect("must encode tga");
+
+        assert_eq!(decode_rgba_image(&tga).expect("must decode tga"), texture);
+        let output_path = temp_root.join("gui.png");
+        convert_image_bytes_to_file(
+            &tga,
+            ImageFormat::Png,
+            JPEG_DEFAULT_BACKGROUND,
+            None,
+            &output_path,
+        )
+        .expect("must convert tga to png");
+        let written = fs::read(&output_path).expect("must read png");
+        assert_eq!(
+            image::guess_format(&written).expect("known format"),
+            RasterFormat::Png
+        );
+        let _ = fs::remove_dir_all(&temp_root);
     }
 
-    if path == "~" {
-        if let Some(home) = home_dir() {
-            return home;
+    #[test]
+    fn zip_export_keeps_resource_pack_layout() {
+        let temp_root = std::env::temp_dir().join(format!("mae-export-zip-{}", Uuid::new_v4()));
+        let staging_root = temp_root.join("staging");
+        let texture = sample_asset(
+            "mod.create.create.textures.block.gear.png",
+            AssetSourceType::Mod,
+            "create",
+            "create",
+            "textures/block/gear.png",
+        );
+        let function = sample_asset(
+            "datapack.tweaks.tweaks.functions.init.mcfunction",
+            AssetSourceType::Datapack,
+            "tweaks",
+            "tweaks",
+            "functions/init.mcfunction",
+        );
+        let jobs = plan_export_jobs(
+            vec![texture, function],
+            &staging_root,
+            &ExportOptions {
+                audio_format: AudioFormat::Original,
+                audio_quality: AudioQuality::default(),
+                normalize_audio: false,
+                audio_resample: AudioResample::default(),
+                image_format: ImageFormat::Original,
+                image_background: JPEG_DEFAULT_BACKGROUND,
+                naming: ExportNaming::Original,
+                name_template: None,
+                layout: ExportLayout::ResourcePack,
+                cleanup_on_cancel: true,
+                defer_completion: false,
+            },
+        );
+        for (job, contents) in jobs.iter().zip([&b"gear"[..], b"say hi"]) {
+            fs::create_dir_all(job.output_path.parent().expect("must have parent"))
+                .expect("must create staging directories");
+            fs::write(&job.output_path, contents).expect("must stage file");
         }
+
+        let zip_path = temp_root.join("out").join("selection.zip");
+        let staged = jobs
+            .iter()
+            .map(|job| job.output_path.to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        let entries =
+            write_export_zip(&staging_root, &staged, &zip_path, &|| false).expect("must write zip");
+        assert_eq!(
+            entries,
+            Some(vec![
+                "assets/create/textures/block/gear.png".to_string(),
+                "data/tweaks/functions/init.mcfunction".to_string(),
+            ])
+        );
+        let cancelled_zip = temp_root.join("out").join("cancelled.zip");
+        assert_eq!(
+            write_export_zip(&staging_root, &staged, &cancelled_zip, &|| true)
+                .expect("cancel is not an error"),
+            None
+        );
+
+        let mut archive =
+            ZipArchive::new(fs::File::open(&zip_path).expect("must open zip")).expect("valid zip");
+        assert_eq!(
+            read_archive_entry(&mut archive, "assets/create/textures/block/gear.png")
+                .expect("must read entry"),
+            b"gear"
+        );
+        let _ = fs::remove_dir_all(&temp_root);
     }
 
-    PathBuf::from(path)
-}
+    #[test]
+    fn pack_metadata_writes_mcmeta_and_png_icon() {
+        let temp_root = std::env::temp_dir().join(format!("mae-export-pack-{}", Uuid::new_v4()));
+        fs::create_dir_all(&temp_root).expect("must create temp pack directory");
+        let icon_source = temp_root.join("icon.jpg");
+        image::DynamicImage::ImageRgb8(image::RgbImage::new(4, 4))
+            .save_with_format(&icon_source, image::ImageFormat::Jpeg)
+            .expect("must write icon");
+
+        let icon = read_pack_icon(&icon_source).expect("must read icon");
+        let written = write_pack_metadata(&temp_root, 34, "Stone override", Some(&icon))
+            .expect("must write pack metadata");
+        assert_eq!(written.len(), 2);
+
+        let mcmeta: serde_json::Value = serde_json::from_slice(
+            &fs::read(temp_root.join("pack.mcmeta")).expect("must read pack.mcmeta"),
+        )
+        .expect("must be valid json");
+        assert_eq!(mcmeta["pack"]["pack_format"], 34);
+        assert_eq!(mcmeta["pack"]["description"], "Stone override");
+        let icon_bytes = fs::read(temp_root.join("pack.png")).expect("must read pack.png");
+        assert!(icon_bytes.starts_with(b"\x89PNG"));
+        let _ = fs::remove_dir_all(&temp_root);
+    }
 
-fn instance_display_name(instance_dir: &Path) -> Option<String> {
-    let config_path = instance_dir.join("instance.cfg");
-    let content = fs::read_to_string(config_path).ok()?;
+    #[test]
+    fn clearing_scan_cache_removes_snapshots_and_resets_manifest() {
+        let cache_root = std::env::temp_dir().join(format!("mae-scan-cache-{}", Uuid::new_v4()));
+        fs::create_dir_all(&cache_root).expect("must create cache root");
+
+        let mut manifest = ScanCacheManifest::default();
+        for (cache_key, last_accessed_at) in [("older", 10), ("newer", 20)] {
+            let file_name = scan_cache_snapshot_file_name(cache_key);
+            fs::write(cache_root.join(&file_name), [0u8; 8]).expect("must write snapshot");
+            manifest.entries.insert(
+                cache_key.to_string(),
+                ScanCacheManifestEntry {
+                    file_name,
+                    size_bytes: 8,
+                    last_accessed_at,
+                    created_at: 5,
+                },
+            );
+        }
+        fs::write(
+            scan_cache_snapshot_legacy_path(&cache_root, "legacy"),
+            b"{}",
+        )
+        .expect("must write legacy snapshot");
+        save_scan_cache_manifest(&cache_root, &manifest).expect("must save manifest");
 
-    let mut in_general_section = false;
+        let stats = scan_cache_entry_stats(&manifest);
+        assert_eq!(
+            stats
+                .iter()
+                .map(|entry| entry.cache_key.as_str())
+                .collect::<Vec<_>>(),
+            vec!["newer", "older"]
+        );
 
-    for raw_line in content.lines() {
-        let line = raw_line.trim();
+        let result = clear_scan_cache_dir(&cache_root).expect("must clear cache");
+        assert_eq!(result.removed_entries, 3);
+        assert_eq!(result.freed_bytes, 18);
+        assert!(load_scan_cache_manifest(&cache_root)
+            .expect("manifest must load")
+            .entries
+            .is_empty());
+        assert_eq!(
+            fs::read_dir(&cache_root).expect("must list cache").count(),
+            1
+        );
+        let _ = fs::remove_dir_all(&cache_root);
+    }
 
-        if line.starts_with('[') && line.ends_with(']') {
-            in_general_section = line.eq_ignore_ascii_case("[General]");
-            continue;
+    #[test]
+    fn repairing_scan_cache_drops_orphans_and_dangling_entries() {
+        let cache_root = std::env::temp_dir().join(format!("mae-scan-repair-{}", Uuid::new_v4()));
+        fs::create_dir_all(&cache_root).expect("must create cache root");
+
+        let mut manifest = ScanCacheManifest::default();
+        for cache_key in ["kept", "dangling"] {
+            manifest.entries.insert(
+                cache_key.to_string(),
+                ScanCacheManifestEntry {
+                    file_name: scan_cache_snapshot_file_name(cache_key),
+                    size_bytes: 8,
+                    last_accessed_at: 1,
+                    created_at: 1,
+                },
+            );
         }
-
-        if in_general_section && line.starts_with("name=") {
-            let name = line.trim_start_matches("name=").trim();
-            if !name.is_empty() {
-                return Some(name.to_string());
+        fs::write(scan_cache_snapshot_path(&cache_root, "kept"), [0u8; 8])
+            .expect("must write snapshot");
+        fs::write(scan_cache_snapshot_path(&cache_root, "orphan"), [0u8; 4])
+            .expect("must write snapshot");
+        fs::write(cache_root.join("pending.tmp-1"), [0u8; 2]).expect("must write temp file");
+        save_scan_cache_manifest(&cache_root, &manifest).expect("must save manifest");
+
+        let result = repair_scan_cache_dir(&cache_root, &mut manifest).expect("must repair");
+        assert_eq!(
+            result,
+            RepairScanCacheResult {
+                removed_files: 1,
+                removed_entries: 1,
+                freed_bytes: 4,
             }
-        }
+        );
+        assert_eq!(manifest.entries.keys().collect::<Vec<_>>(), vec!["kept"]);
+        assert!(scan_cache_snapshot_path(&cache_root, "kept").is_file());
+        assert!(cache_root.join("pending.tmp-1").is_file());
+        assert!(scan_cache_manifest_path(&cache_root).is_file());
+        let _ = fs::remove_dir_all(&cache_root);
     }
 
-    None
-}
-
-fn parse_minecraft_version(mmc_pack_path: &Path) -> Option<String> {
-    let content = fs::read_to_string(mmc_pack_path).ok()?;
-    let parsed: MmcPack = serde_json::from_str(&content).ok()?;
+    #[test]
+    fn settings_load_defaults_and_clamp_cache_limit() {
+        let temp_root = std::env::temp_dir().join(format!("mae-settings-{}", Uuid::new_v4()));
+        fs::create_dir_all(&temp_root).expect("must create temp directory");
+        let path = temp_root.join(SETTINGS_FILE_NAME);
 
-    parsed
-        .components
-        .into_iter()
-        .find(|component| component.uid == "net.minecraft")
-        .and_then(|component| component.version)
-}
+        assert_eq!(
+            load_settings(&path).max_cache_bytes(),
+            DEFAULT_SCAN_CACHE_MAX_BYTES
+        );
 
-fn resolve_vanilla_asset_index_path(prism_root: &Path, mc_version: &str) -> Option<PathBuf> {
-    let meta_path = prism_root
-        .join("meta")
-        .join("net.minecraft")
-        .join(format!("{mc_version}.json"));
-    let content = fs::read_to_string(meta_path).ok()?;
-    let parsed: MinecraftMetaVersion = serde_json::from_str(&content).ok()?;
+        fs::write(&path, br#"{"maxCacheBytes": 1024}"#).expect("must write settings");
+        assert_eq!(load_settings(&path).max_cache_bytes(), SCAN_CACHE_MIN_BYTES);
 
-    let index_id = parsed
-        .asset_index
-        .map(|asset_index| asset_index.id)
-        .or(parsed.assets)?;
+        fs::write(&path, b"not json").expect("must write settings");
+        assert!(load_settings(&path).max_cache_bytes.is_none());
 
-    let index_path = prism_root
-        .join("assets")
-        .join("indexes")
-        .join(format!("{index_id}.json"));
+        fs::write(&path, br#"{"scanWorkers": 0, "exportWorkers": 1000}"#)
+            .expect("must write settings");
+        assert_eq!(
+            load_settings(&path).worker_limits(),
+            WorkerLimits {
+                scan_workers: 1,
+                export_workers: WORKER_LIMIT_CEILING,
+            }
+        );
+        fs::write(
+            &path,
+            br#"{"lastScanOptions": {"/prism::pack": {
+                "includeVanilla": true, "includeMods": false, "includeResourcepacks": true,
+                "includeServerPacks": null, "includeDatapacks": true, "includeShaderpacks": null,
+                "collectDimensions": null, "launcherKind": "prism", "vanillaVersionOverride": null
+            }}}"#,
+        )
+        .expect("must write settings");
+        let stored = load_settings(&path);
+        let options = stored
+            .last_scan_options
+            .get(&last_scan_options_key(" /prism ", "pack"))
+            .expect("options must be keyed by root and folder");
+        assert!(!options.include_mods);
+        assert_eq!(options.include_datapacks, Some(true));
 
-    if index_path.is_file() {
-        Some(index_path)
-    } else {
-        None
+        assert_eq!(
+            AppSettings::default().worker_limits(),
+            WorkerLimits {
+                scan_workers: MAX_SCAN_WORKERS,
+                export_workers: MAX_EXPORT_WORKERS,
+            }
+        );
+        let _ = fs::remove_dir_all(&temp_root);
     }
-}
 
-fn cleanup_temp_paths(state: &AppState) {
-    let Ok(mut paths) = state.temp_paths.lock() else {
-        return;
-    };
+    #[test]
+    fn settings_patch_merges_only_present_fields() {
+        let temp_root = std::env::temp_dir().join(format!("mae-settings-{}", Uuid::new_v4()));
+        fs::create_dir_all(&temp_root).expect("must create temp directory");
+        let path = temp_root.join(SETTINGS_FILE_NAME);
+
+        let mut settings = AppSettings {
+            scan_workers: Some(3),
+            fuzzy_mode: Some(FuzzyMode::Off),
+            ..AppSettings::default()
+        };
+        let patch: SettingsPatch = serde_json::from_str(
+            r#"{"maxCacheBytes": 1024, "defaultAudioFormat": "flac", "fuzzyMode": "aggressive"}"#,
+        )
+        .expect("patch must parse");
+        settings.apply(patch);
+        write_json_atomically(&path, &settings).expect("must persist settings");
+
+        let stored = load_settings(&path);
+        assert_eq!(stored.max_cache_bytes, Some(SCAN_CACHE_MIN_BYTES));
+        assert_eq!(stored.scan_workers, Some(3));
+        assert_eq!(stored.fuzzy_mode, Some(FuzzyMode::Aggressive));
+        assert_eq!(stored.default_audio_format, Some(AudioFormat::Flac));
+        assert!(stored.default_image_format.is_none());
+        let _ = fs::remove_dir_all(&temp_root);
+    }
 
-    for path in &*paths {
-        if path.is_dir() {
-            let _ = fs::remove_dir_all(path);
-        } else if path.is_file() {
-            let _ = fs::remove_file(path);
-        }
+    #[test]
+    fn lowercase_entry_wins_over_differently_cased_twin() {
+        let temp_root = std::env::temp_dir().join(format!("mae-case-{}", Uuid::new_v4()));
+        let block_dir = temp_root.join("assets/minecraft/textures/block");
+        fs::create_dir_all(&block_dir).expect("must create block directory");
+        fs::write(block_dir.join("Stone.png"), b"upper").expect("must write texture");
+        fs::write(block_dir.join("stone.png"), b"lower").expect("must write texture");
+
+        let container = ScanContainer {
+            source_type: AssetSourceType::ResourcePack,
+            source_name: "pack".to_string(),
+            container_type: AssetContainerType::Directory,
+            container_path: temp_root.clone(),
+        };
+        let candidates = scan_directory_container(&container, ScanOptions::default(), &|| false)
+            .expect("must scan directory");
+        let assets = finalize_assets(candidates, &mut HashMap::new());
+        // Directory walks sort `Stone.png` first.
+        assert_eq!(
+            assets[0].entry_path,
+            "assets/minecraft/textures/block/Stone.png"
+        );
+        assert_eq!(
+            assets[0].shadowed_by.as_deref(),
+            Some(assets[1].asset_id.as_str())
+        );
+        assert!(assets[1].shadowed_by.is_none());
+        let _ = fs::remove_dir_all(&temp_root);
     }
 
-    paths.clear();
-}
+    #[test]
+    fn key_naming_flattens_dup_suffixes() {
+        let temp_root = std::env::temp_dir().join(format!("mae-export-keys-{}", Uuid::new_v4()));
+        fs::create_dir_all(&temp_root).expect("must create temp export directory");
 
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    let app = tauri::Builder::default()
-        .manage(AppState::default())
-        .plugin(tauri_plugin_opener::init())
-        .plugin(tauri_plugin_dialog::init())
-        .setup(|app| {
-            app.remove_menu()?;
+        let candidate = AssetCandidate {
+            source_type: AssetSourceType::Mod,
+            source_name: "sample".to_string(),
+            namespace: "sample".to_string(),
+            relative_asset_path: "sounds/block/grass/step.ogg".to_string(),
+            container_path: PathBuf::from("/tmp/container"),
+            container_type: AssetContainerType::Jar,
+            entry_path: "assets/sample/sounds/block/grass/step.ogg".to_string(),
+            extension: "ogg".to_string(),
+            is_image: false,
+            is_audio: true,
+            is_shader: false,
+            byte_size: None,
+            dimensions: None,
+            mod_display_name: None,
+            mod_id: None,
+        };
+        let assets = finalize_assets(
+            vec![candidate.clone(), candidate.clone()],
+            &mut HashMap::new(),
+        );
+        assert!(assets[1].key.ends_with(".dup1"));
+        assert!(assets[1].shadowed_by.is_none());
 
-            #[cfg(target_os = "macos")]
-            {
-                let app_menu = SubmenuBuilder::new(app, "Minecraft Asset Explorer")
-                    .about(None)
-                    .separator()
-                    .quit()
-                    .build()?;
-                let menu = MenuBuilder::new(app).item(&app_menu).build()?;
-                app.set_menu(menu)?;
-            }
+        let upper_case = AssetCandidate {
+            relative_asset_path: "sounds/block/grass/Step.ogg".to_string(),
+            entry_path: "assets/sample/sounds/block/grass/Step.ogg".to_string(),
+            ..candidate.clone()
+        };
+        let shadowed = finalize_assets(vec![candidate.clone(), upper_case], &mut HashMap::new());
+        assert!(shadowed[0].shadowed_by.is_none());
+        assert_eq!(
+            shadowed[1].shadowed_by.as_deref(),
+            Some(shadowed[0].asset_id.as_str())
+        );
 
-            Ok(())
-        })
-        .invoke_handler(tauri::generate_handler![
-            detect_prism_roots,
-            list_instances,
-            start_scan,
-            get_scan_status,
-            cancel_scan,
-            cancel_export,
-            list_tree_children,
-            search_assets,
-            get_asset_preview,
-            get_asset_record,
-            reconcile_asset_ids,
-            save_assets,
-            copy_assets_to_clipboard,
-            convert_audio_asset,
-        ])
-        .build(tauri::generate_context!())
-        .expect("error while building tauri application");
+        let jobs = plan_export_jobs(
+            assets,
+            &temp_root,
+            &ExportOptions {
+                audio_format: AudioFormat::Original,
+                audio_quality: AudioQuality::default(),
+                normalize_audio: false,
+                audio_resample: AudioResample::default(),
+                image_format: ImageFormat::Original,
+                image_background: JPEG_DEFAULT_BACKGROUND,
+                naming: ExportNaming::Key,
+                name_template: None,
+                layout: ExportLayout::Flat,
+                cleanup_on_cancel: true,
+                defer_completion: false,
+            },
+        );
+        let names = jobs
+            .iter()
+            .map(|job| {
+                job.output_path
+                    .file_name()
+                    .expect("output name must exist")
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
 
-    app.run(|app_handle, event| {
-        if matches!(event, tauri::RunEvent::Exit) {
-            let state = app_handle.state::<AppState>();
-            cleanup_temp_paths(&state);
+        assert_eq!(
+            names,
+            vec![
+                "mod.sample.sample.sounds.block.grass.step.ogg".to_string(),
+                "mod.sample.sample.sounds.block.grass.step_1.ogg".to_string(),
+            ]
+        );
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn directory_scan_merges_split_subtrees_in_entry_order() {
+        let temp_root = std::env::temp_dir().join(format!("mae-dir-split-{}", Uuid::new_v4()));
+        for relative in [
+            "assets/minecraft/textures/item/apple.png",
+            "assets/minecraft/textures/block/stone.png",
+            "assets/minecraft/textures/block/deepslate/ore.png",
+            "assets/minecraft/sounds.json",
+            "assets/create/lang/en_us.json",
+        ] {
+            let path = temp_root.join(relative);
+            fs::create_dir_all(path.parent().expect("must have parent"))
+                .expect("must create directories");
+            fs::write(&path, b"x").expect("must write file");
         }
-    });
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let container = ScanContainer {
+            source_type: AssetSourceType::ResourcePack,
+            source_name: "pack".to_string(),
+            container_type: AssetContainerType::Directory,
+            container_path: temp_root.clone(),
+        };
+        let (loose_files, subtrees) = split_directory_subtrees(
+            &temp_root,
+            DIRECTORY_SPLIT_DEPTH,
+            false,
+            &mut HashSet::new(),
+        );
+        // `sounds.json` and `lang/en_us.json` sit above the split depth.
+        assert_eq!(loose_files.len(), 2);
+        assert_eq!(subtrees.len(), 2);
 
-    #[test]
-    fn parses_minecraft_component_version() {
-        let data = r#"{
-            "components": [
-                { "uid": "foo", "version": "1.0.0" },
-                { "uid": "net.minecraft", "version": "1.20.1" }
+        let candidates = scan_directory_container(&container, ScanOptions::default(), &|| false)
+            .expect("must scan directory");
+        assert_eq!(
+            candidates
+                .iter()
+                .map(|candidate| candidate.entry_path.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "assets/create/lang/en_us.json",
+                "assets/minecraft/sounds.json",
+                "assets/minecraft/textures/block/deepslate/ore.png",
+                "assets/minecraft/textures/block/stone.png",
+                "assets/minecraft/textures/item/apple.png",
             ]
-        }"#;
+        );
 
-        let parsed: MmcPack = serde_json::from_str(data).expect("valid json");
-        let version = parsed
-            .components
+        // The pool queues the same subtrees as separate jobs and reassembles them.
+        let broken = ScanContainer {
+            source_type: AssetSourceType::Mod,
+            source_name: "broken".to_string(),
+            container_type: AssetContainerType::Jar,
+            container_path: temp_root.join("broken.jar"),
+        };
+        fs::write(&broken.container_path, b"not a zip").expect("must write jar");
+        let pool_containers = Arc::new(vec![(container, None), (broken, None)]);
+        let scan_pool = |cancelled: bool| {
+            let mut outcomes = spawn_scan_pool(
+                Arc::clone(&pool_containers),
+                ScanOptions::default(),
+                MAX_SCAN_WORKERS,
+                Arc::new(AtomicBool::new(cancelled)),
+                |container| Ok(container.source_name.clone()),
+            )
+            .iter()
+            .collect::<Vec<_>>();
+            outcomes.sort_by_key(|(index, _)| *index);
+            outcomes
+        };
+        let outcomes = scan_pool(false);
+        assert_eq!(outcomes.len(), 2);
+        let (prepared, pooled, warnings) = outcomes[0].1.as_ref().expect("must scan directory");
+        assert_eq!(prepared, "pack");
+        assert!(warnings.is_empty());
+        assert_eq!(
+            pooled
+                .iter()
+                .map(|candidate| &candidate.entry_path)
+                .collect::<Vec<_>>(),
+            candidates
+                .iter()
+                .map(|candidate| &candidate.entry_path)
+                .collect::<Vec<_>>()
+        );
+        assert!(outcomes[1].1.is_err());
+        assert!(scan_pool(true).is_empty());
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_folders_are_scanned_only_when_followed() {
+        let temp_root = std::env::temp_dir().join(format!("mae-symlinks-{}", Uuid::new_v4()));
+        let library = temp_root.join("library").join("textures").join("block");
+        let pack = temp_root.join("pack");
+        let namespace = pack.join("assets").join("minecraft");
+        fs::create_dir_all(&library).expect("must create shared library");
+        fs::create_dir_all(&namespace).expect("must create pack directories");
+        fs::write(library.join("stone.png"), b"stone").expect("must write stone");
+        std::os::unix::fs::symlink(library.parent().unwrap(), namespace.join("textures"))
+            .expect("must link textures");
+        std::os::unix::fs::symlink(&pack, namespace.join("loop")).expect("must link loop");
+        // A second way into the library, below the split depth.
+        let models = namespace.join("models").join("block");
+        fs::create_dir_all(&models).expect("must create models directory");
+        std::os::unix::fs::symlink(library.parent().unwrap(), models.join("alias"))
+            .expect("must link alias");
+        // A link from one subtree into a folder deep inside another.
+        let cave = namespace.join("sounds").join("ambient").join("cave");
+        fs::create_dir_all(&cave).expect("must create sounds directory");
+        fs::write(cave.join("cave1.ogg"), b"cave").expect("must write sound");
+        std::os::unix::fs::symlink(&cave, models.join("shortcut")).expect("must link shortcut");
+
+        let container = ScanContainer {
+            source_type: AssetSourceType::ResourcePack,
+            source_name: "pack".to_string(),
+            container_type: AssetContainerType::Directory,
+            container_path: pack.clone(),
+        };
+        let scan = |follow_symlinks| {
+            let options = ScanOptions {
+                follow_symlinks,
+                ..ScanOptions::default()
+            };
+            scan_directory_container(&container, options, &|| false)
+                .expect("must scan directory")
+                .into_iter()
+                .map(|candidate| candidate.entry_path)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            scan(false),
+            vec!["assets/minecraft/sounds/ambient/cave/cave1.ogg"]
+        );
+        // `loop` leads back into the pack and `alias` to a folder already read;
+        // the sound reached twice keeps its smallest entry path.
+        let expected = vec![
+            "assets/minecraft/models/block/shortcut/cave1.ogg",
+            "assets/minecraft/textures/block/stone.png",
+        ];
+        assert_eq!(scan(true), expected);
+        let pooled = || {
+            spawn_scan_pool(
+                Arc::new(vec![(container.clone(), None)]),
+                ScanOptions {
+                    follow_symlinks: true,
+                    ..ScanOptions::default()
+                },
+                MAX_SCAN_WORKERS,
+                Arc::new(AtomicBool::new(false)),
+                |_| Ok(()),
+            )
+            .recv()
+            .expect("pool must report the pack")
+            .1
+            .expect("must scan directory")
+            .1
             .into_iter()
-            .find(|component| component.uid == "net.minecraft")
-            .and_then(|component| component.version);
+            .map(|candidate| candidate.entry_path)
+            .collect::<Vec<_>>()
+        };
+        for _ in 0..4 {
+            assert_eq!(pooled(), expected);
+        }
 
-        assert_eq!(version.as_deref(), Some("1.20.1"));
+        let signature = |follow_symlinks| {
+            container_signature_for_path(&pack, &AssetContainerType::Directory, follow_symlinks)
+                .expect("signature for pack")
+                .file_count
+        };
+        assert_eq!(signature(false), 1);
+        assert!(signature(true) >= 2);
+        let _ = fs::remove_dir_all(&temp_root);
     }
 
     #[test]
-    fn smart_search_scores_atm_star_query() {
-        let asset = sample_asset(
-            "mod.allthemodium.allthemodium.textures.item.atm_star.png",
-            AssetSourceType::Mod,
-            "allthemodium",
-            "allthemodium",
-            "textures/item/atm_star.png",
+    fn refresh_plan_rereads_only_touched_directory_entries() {
+        let temp_root = std::env::temp_dir().join(format!("mae-refresh-dir-{}", Uuid::new_v4()));
+        let textures = temp_root.join("assets/minecraft/textures/block");
+        fs::create_dir_all(&textures).expect("must create pack directories");
+        fs::write(textures.join("stone.png"), b"stone").expect("must write stone");
+        fs::write(textures.join("dirt.png"), b"dirt").expect("must write dirt");
+        fs::write(textures.join("sand.png"), b"sand").expect("must write sand");
+
+        let container = ScanContainer {
+            source_type: AssetSourceType::ResourcePack,
+            source_name: "pack".to_string(),
+            container_type: AssetContainerType::Directory,
+            container_path: temp_root.clone(),
+        };
+        let mut cached_signatures = HashMap::new();
+        cached_signatures.insert(
+            scan_container_key(&container),
+            container_signature_for_path(&temp_root, &AssetContainerType::Directory, false)
+                .expect("signature for pack"),
         );
-        let record = build_search_record(&asset);
 
-        let tokens = split_tokens("atm star");
-        let score = score_query(
-            &record,
-            &tokens,
-            &compact_text("atm star"),
-            &tokens.join(" "),
+        fs::write(textures.join("stone.png"), b"stone, but edited").expect("must edit stone");
+        fs::write(textures.join("gravel.png"), b"gravel").expect("must write gravel");
+        fs::remove_file(textures.join("sand.png")).expect("must remove sand");
+
+        let plan =
+            build_scan_refresh_plan(&cached_signatures, std::slice::from_ref(&container), false)
+                .expect("refresh plan");
+        assert!(plan.changed_or_new.is_empty());
+        assert_eq!(plan.partial.len(), 1);
+        let refresh = &plan.partial[0];
+        assert_eq!(
+            refresh.changed_entries,
+            vec![
+                "assets/minecraft/textures/block/gravel.png".to_string(),
+                "assets/minecraft/textures/block/stone.png".to_string(),
+            ]
+        );
+        assert_eq!(
+            refresh.removed_entries,
+            vec!["assets/minecraft/textures/block/sand.png".to_string()]
         );
 
-        assert!(score.is_some());
+        let candidates = scan_directory_entries(
+            &container,
+            ScanOptions::default(),
+            &refresh.changed_entries,
+            &|| false,
+        )
+        .expect("must rescan entries");
+        assert_eq!(
+            candidates
+                .iter()
+                .map(|candidate| candidate.relative_asset_path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["textures/block/gravel.png", "textures/block/stone.png"]
+        );
+
+        let _ = fs::remove_dir_all(&temp_root);
     }
 
     #[test]
-    fn parse_assets_path_from_nested_prefix() {
-        let parsed =
-            parse_asset_relative_path("nested/content/assets/example/textures/item/star.png")
-                .expect("must parse");
+    fn refresh_plan_detects_changed_new_and_removed_containers() {
+        let temp_root = std::env::temp_dir().join(format!("mae-refresh-plan-{}", Uuid::new_v4()));
+        fs::create_dir_all(&temp_root).expect("must create temp root");
 
-        assert_eq!(parsed.namespace, "example");
-        assert_eq!(parsed.relative_asset_path, "textures/item/star.png");
+        let container_a = temp_root.join("a.jar");
+        let container_b = temp_root.join("b.jar");
+        let container_c = temp_root.join("c.jar");
+        fs::write(&container_a, b"a").expect("must write a");
+        fs::write(&container_b, b"b").expect("must write b");
+
+        let cached_a = ScanContainer {
+            source_type: AssetSourceType::Mod,
+            source_name: "a".to_string(),
+            container_type: AssetContainerType::Jar,
+            container_path: container_a.clone(),
+        };
+        let cached_b = ScanContainer {
+            source_type: AssetSourceType::Mod,
+            source_name: "b".to_string(),
+            container_type: AssetContainerType::Jar,
+            container_path: container_b.clone(),
+        };
+        let mut cached_signatures = HashMap::new();
+        cached_signatures.insert(
+            scan_container_key(&cached_a),
+            container_signature_for_path(&container_a, &AssetContainerType::Jar, false)
+                .expect("signature for a"),
+        );
+        cached_signatures.insert(
+            scan_container_key(&cached_b),
+            container_signature_for_path(&container_b, &AssetContainerType::Jar, false)
+                .expect("signature for b"),
+        );
+
+        fs::write(&container_c, b"c").expect("must write c");
+        let current = vec![
+            cached_a.clone(),
+            ScanContainer {
+                source_type: AssetSourceType::Mod,
+                source_name: "c".to_string(),
+                container_type: AssetContainerType::Jar,
+                container_path: container_c.clone(),
+            },
+        ];
+
+        let plan =
+            build_scan_refresh_plan(&cached_signatures, &current, false).expect("refresh plan");
+        assert_eq!(plan.unchanged_keys.len(), 1);
+        assert_eq!(plan.changed_or_new.len(), 1);
+        assert_eq!(plan.removed_keys.len(), 1);
+        assert!(plan.removed_keys.contains(&scan_container_key(&cached_b)));
+
+        let _ = fs::remove_dir_all(&temp_root);
     }
 
     #[test]
-    fn exact_filename_scores_higher_than_long_variant() {
-        let vanilla = sample_asset(
-            "vanilla.minecraft.minecraft.textures.item.nether_star.png",
-            AssetSourceType::Vanilla,
-            "minecraft-1.21.1",
-            "minecraft",
-            "textures/item/nether_star.png",
+    fn asset_index_sounds_are_directory_backed_and_extractable() {
+        let temp_root = std::env::temp_dir().join(format!("mae-asset-index-{}", Uuid::new_v4()));
+        let indexes_dir = temp_root.join("indexes");
+        let object_dir = temp_root.join("objects").join("ab");
+        fs::create_dir_all(&indexes_dir).expect("must create indexes dir");
+        fs::create_dir_all(&object_dir).expect("must create objects dir");
+        fs::write(object_dir.join("abcdef"), b"ogg").expect("must write object");
+
+        let index_path = indexes_dir.join("17.json");
+        fs::write(
+            &index_path,
+            r#"{ "objects": {
+                "minecraft/sounds/block/grass/step1.ogg": { "hash": "abcdef" },
+                "minecraft/lang/en_us.json": { "hash": "abcdef" }
+            } }"#,
+        )
+        .expect("must write index");
+
+        let container = ScanContainer {
+            source_type: AssetSourceType::Vanilla,
+            source_name: "minecraft-1.21.1".to_string(),
+            container_type: AssetContainerType::AssetIndex,
+            container_path: index_path,
+        };
+        let candidates =
+            scan_vanilla_asset_index_container(&container, ScanOptions::default(), &|| false)
+                .expect("index scan");
+        let assets = finalize_assets(candidates, &mut HashMap::new());
+
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets[0].container_type, AssetContainerType::Directory);
+        assert!(assets[0].is_extractable);
+        assert_eq!(
+            extract_asset_bytes(&assets[0]).expect("must extract"),
+            b"ogg".to_vec()
         );
-        let modded = sample_asset(
-            "mod.atc.atc.blockstates.nether_star_block_2x.json",
-            AssetSourceType::Mod,
-            "allthecompressed",
-            "allthecompressed",
-            "blockstates/nether_star_block_2x.json",
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn legacy_virtual_index_reads_from_mirror() {
+        let temp_root = std::env::temp_dir().join(format!("mae-asset-legacy-{}", Uuid::new_v4()));
+        let indexes_dir = temp_root.join("indexes");
+        let mirror_dir = temp_root
+            .join("virtual")
+            .join("legacy")
+            .join("sound")
+            .join("step");
+        fs::create_dir_all(&indexes_dir).expect("must create indexes dir");
+        fs::create_dir_all(&mirror_dir).expect("must create mirror dir");
+        fs::write(mirror_dir.join("grass1.ogg"), b"ogg").expect("must write mirrored file");
+
+        let index_path = indexes_dir.join("legacy.json");
+        fs::write(
+            &index_path,
+            r#"{ "virtual": true, "objects": {
+                "sound/step/grass1.ogg": { "hash": "abcdef" },
+                "lang/en_US.lang": { "hash": "012345" }
+            } }"#,
+        )
+        .expect("must write index");
+
+        let container = ScanContainer {
+            source_type: AssetSourceType::Vanilla,
+            source_name: "minecraft-1.6.4".to_string(),
+            container_type: AssetContainerType::AssetIndex,
+            container_path: index_path,
+        };
+        let candidates =
+            scan_vanilla_asset_index_container(&container, ScanOptions::default(), &|| false)
+                .expect("index scan");
+        let assets = finalize_assets(candidates, &mut HashMap::new());
+
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets[0].namespace, "minecraft");
+        assert_eq!(assets[0].relative_asset_path, "sound/step/grass1.ogg");
+        assert_eq!(
+            extract_asset_bytes(&assets[0]).expect("must extract"),
+            b"ogg".to_vec()
         );
 
-        let query = "nether star";
-        let tokens = split_tokens(query);
-        let compact = compact_text(query);
-        let normalized = tokens.join(" ");
+        let _ = fs::remove_dir_all(&temp_root);
+    }
 
-        let vanilla_score = score_query(
-            &build_search_record(&vanilla),
-            &tokens,
-            &compact,
-            &normalized,
+    #[test]
+    fn pre_1_6_index_reads_from_game_resources() {
+        let temp_root = std::env::temp_dir().join(format!("mae-asset-pre16-{}", Uuid::new_v4()));
+        let assets_root = temp_root.join("assets");
+        let indexes_dir = assets_root.join("indexes");
+        let resources_dir = temp_root.join("resources").join("sound").join("step");
+        let virtual_dir = assets_root
+            .join("virtual")
+            .join("legacy")
+            .join("sound")
+            .join("step");
+        fs::create_dir_all(&indexes_dir).expect("must create indexes dir");
+        fs::create_dir_all(&resources_dir).expect("must create resources dir");
+        fs::create_dir_all(&virtual_dir).expect("must create virtual dir");
+        fs::write(resources_dir.join("grass1.ogg"), b"resources").expect("must write resource");
+        fs::write(virtual_dir.join("grass1.ogg"), b"virtual").expect("must write mirror");
+
+        let index_path = indexes_dir.join("pre-1.6.json");
+        fs::write(
+            &index_path,
+            r#"{ "map_to_resources": true, "objects": {
+                "sound/step/grass1.ogg": { "hash": "abcdef" }
+            } }"#,
         )
-        .expect("vanilla must match");
-        let modded_score = score_query(
-            &build_search_record(&modded),
-            &tokens,
-            &compact,
-            &normalized,
+        .expect("must write index");
+
+        let container = ScanContainer {
+            source_type: AssetSourceType::Vanilla,
+            source_name: "minecraft-1.5.2".to_string(),
+            container_type: AssetContainerType::AssetIndex,
+            container_path: index_path,
+        };
+        let candidates =
+            scan_vanilla_asset_index_container(&container, ScanOptions::default(), &|| false)
+                .expect("index scan");
+        let assets = finalize_assets(candidates, &mut HashMap::new());
+
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets[0].relative_asset_path, "sound/step/grass1.ogg");
+        assert_eq!(
+            extract_asset_bytes(&assets[0]).expect("must extract"),
+            b"resources".to_vec()
+        );
+
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn asset_index_verification_reports_missing_objects() {
+        let temp_root = std::env::temp_dir().join(format!("mae-asset-verify-{}", Uuid::new_v4()));
+        let indexes_dir = temp_root.join("indexes");
+        let object_dir = temp_root.join("objects").join("ab");
+        fs::create_dir_all(&indexes_dir).expect("must create indexes dir");
+        fs::create_dir_all(&object_dir).expect("must create objects dir");
+        fs::write(object_dir.join("abcdef"), b"ogg").expect("must write object");
+
+        let index_path = indexes_dir.join("17.json");
+        fs::write(
+            &index_path,
+            r#"{ "objects": {
+                "minecraft/sounds/block/grass/step1.ogg": { "hash": "abcdef" },
+                "minecraft/sounds/block/grass/step2.ogg": { "hash": "cd0123" },
+                "minecraft/lang/en_us.json": { "hash": "ef4567" }
+            } }"#,
         )
-        .expect("modded must match");
+        .expect("must write index");
 
-        assert!(vanilla_score > modded_score);
+        let container = ScanContainer {
+            source_type: AssetSourceType::Vanilla,
+            source_name: "minecraft-1.21.1".to_string(),
+            container_type: AssetContainerType::AssetIndex,
+            container_path: index_path.clone(),
+        };
+        let containers = [
+            container,
+            ScanContainer {
+                source_type: AssetSourceType::Mod,
+                source_name: "a".to_string(),
+                container_type: AssetContainerType::Jar,
+                container_path: PathBuf::from("/a.jar"),
+            },
+        ];
+        assert_eq!(scan_asset_index_paths(containers.iter()), vec![index_path]);
+
+        let verification =
+            verify_asset_index_objects(&scan_asset_index_paths(containers.iter())).expect("verify");
+        assert_eq!(verification.checked_count, 2);
+        assert_eq!(verification.missing_count, 1);
+        assert_eq!(
+            verification.missing_paths,
+            vec!["minecraft/sounds/block/grass/step2.ogg".to_string()]
+        );
+
+        let _ = fs::remove_dir_all(&temp_root);
     }
 
     #[test]
-    fn query_with_extra_token_still_matches_best_pair() {
-        let expected = sample_asset(
-            "vanilla.minecraft.minecraft.sounds.block.grass.step1.ogg",
-            AssetSourceType::Vanilla,
-            "minecraft-1.21.1",
+    fn reveal_path_selects_loose_files_and_whole_archives() {
+        let mut asset = sample_asset(
+            "mod:a:minecraft:textures/a.png",
+            AssetSourceType::Mod,
+            "a",
             "minecraft",
-            "sounds/block/grass/step1.ogg",
+            "textures/a.png",
         );
+        assert_eq!(asset_reveal_path(&asset), PathBuf::from("/tmp/container"));
 
-        let unrelated = sample_asset(
-            "mod.example.example.sounds.block.stone.step1.ogg",
-            AssetSourceType::Mod,
-            "example-mod",
-            "example",
-            "sounds/block/stone/step1.ogg",
+        asset.container_type = AssetContainerType::Directory;
+        assert_eq!(
+            asset_reveal_path(&asset),
+            PathBuf::from("/tmp/container/assets/minecraft/textures/a.png")
         );
+    }
 
-        let query = "grass block step";
-        let tokens = split_tokens(query);
-        let compact = compact_text(query);
-        let normalized = tokens.join(" ");
+    #[test]
+    fn audio_durations_come_from_container_headers() {
+        let ogg_page = |granule: u64, packet: &[u8]| {
+            let mut page = b"OggS\0\0".to_vec();
+            page.extend(granule.to_le_bytes());
+            page.extend([0u8; 12]);
+            page.push(1);
+            page.push(packet.len() as u8);
+            page.extend(packet);
+            page
+        };
+        let mut identification = b"\x01vorbis".to_vec();
+        identification.extend(0u32.to_le_bytes());
+        identification.push(2);
+        identification.extend(44_100u32.to_le_bytes());
+        let mut vorbis = ogg_page(0, &identification);
+        vorbis.extend(ogg_page(88_200, b"audio"));
+        assert_eq!(audio_duration_ms(&vorbis), Some(2000));
+
+        let mut opus_head = b"OpusHead\x01\x02".to_vec();
+        opus_head.extend(312u16.to_le_bytes());
+        let mut opus = ogg_page(0, &opus_head);
+        opus.extend(ogg_page(48_312, b"audio"));
+        assert_eq!(audio_duration_ms(&opus), Some(1000));
+        for granule in [u64::MAX, u64::MAX - 1] {
+            let mut corrupt = ogg_page(0, &identification);
+            corrupt.extend(ogg_page(granule, b"audio"));
+            assert_eq!(audio_duration_ms(&corrupt), None);
+        }
 
-        let expected_score = score_query(
-            &build_search_record(&expected),
-            &tokens,
-            &compact,
-            &normalized,
-        )
-        .expect("expected must match");
-        let unrelated_score = score_query(
-            &build_search_record(&unrelated),
-            &tokens,
-            &compact,
-            &normalized,
-        )
-        .expect("unrelated should still match with weaker score");
+        let mut wav = b"RIFF\0\0\0\0WAVE".to_vec();
+        wav.extend(b"LIST\x03\0\0\0abc\0");
+        wav.extend(b"fmt \x10\0\0\0\x01\0\x01\0");
+        wav.extend(22_050u32.to_le_bytes());
+        wav.extend(44_100u32.to_le_bytes());
+        wav.extend(b"\x02\0\x10\0data");
+        wav.extend(22_050u32.to_le_bytes());
+        assert_eq!(audio_duration_ms(&wav), Some(500));
 
-        assert!(expected_score > unrelated_score);
+        assert_eq!(audio_duration_ms(b"ID3 mp3 data"), None);
     }
 
     #[test]
-    fn damerau_fuzzy_match_accepts_transposed_token() {
-        let asset = sample_asset(
-            "vanilla.minecraft.minecraft.sounds.block.grass.step1.ogg",
-            AssetSourceType::Vanilla,
-            "minecraft-1.21.1",
-            "minecraft",
-            "sounds/block/grass/step1.ogg",
+    fn png_dimensions_come_from_ihdr() {
+        let mut png = Vec::new();
+        RgbaImage::new(16, 32)
+            .write_to(&mut Cursor::new(&mut png), RasterFormat::Png)
+            .expect("must encode png");
+
+        assert_eq!(read_png_dimensions(&mut png.as_slice()), Some((16, 32)));
+        assert_eq!(read_png_dimensions(&mut b"GIF89a".as_slice()), None);
+
+        // Only `.png` entries are read for dimensions, whatever their bytes.
+        let temp_root = std::env::temp_dir().join(format!("mae-png-dims-{}", Uuid::new_v4()));
+        let lang_dir = temp_root.join("assets/demo/lang");
+        fs::create_dir_all(&lang_dir).expect("must create lang directory");
+        fs::write(lang_dir.join("icon.png"), &png).expect("must write png");
+        fs::write(lang_dir.join("en_us.json"), &png).expect("must write json");
+        let container = ScanContainer {
+            source_type: AssetSourceType::ResourcePack,
+            source_name: "pack".to_string(),
+            container_type: AssetContainerType::Directory,
+            container_path: temp_root.clone(),
+        };
+        let options = ScanOptions {
+            collect_dimensions: true,
+            ..ScanOptions::default()
+        };
+        let dimensions = |entry_path: &str| {
+            directory_entry_candidate(
+                &container,
+                options,
+                &temp_root.join(entry_path),
+                entry_path.to_string(),
+            )
+            .expect("must be an asset")
+            .dimensions
+        };
+        assert_eq!(dimensions("assets/demo/lang/icon.png"), Some((16, 32)));
+        assert_eq!(dimensions("assets/demo/lang/en_us.json"), None);
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn nested_jar_assets_are_scanned_and_extractable() {
+        fn write_zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+            let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+            for (name, bytes) in entries {
+                writer
+                    .start_file(*name, zip::write::SimpleFileOptions::default())
+                    .expect("must start zip entry");
+                writer.write_all(bytes).expect("must write zip entry");
+            }
+            writer.finish().expect("must finish zip").into_inner()
+        }
+
+        let inner = write_zip(&[("assets/inner/textures/item/gem.png", b"gem")]);
+        let outer = write_zip(&[
+            ("assets/outer/textures/item/ore.png", b"ore"),
+            ("com/example/OuterMod.class", b"\xca\xfe"),
+            ("META-INF/jars/inner-1.0.jar", &inner),
+        ]);
+        let temp_root = std::env::temp_dir().join(format!("mae-nested-jar-{}", Uuid::new_v4()));
+        fs::create_dir_all(&temp_root).expect("must create temp directory");
+        let jar_path = temp_root.join("outer.jar");
+        fs::write(&jar_path, outer).expect("must write outer jar");
+
+        let container = ScanContainer {
+            source_type: AssetSourceType::Mod,
+            source_name: "outer".to_string(),
+            container_type: AssetContainerType::Jar,
+            container_path: jar_path,
+        };
+        let mut warnings = Vec::new();
+        let candidates =
+            scan_archive_container(&container, ScanOptions::default(), &|| false, &mut warnings)
+                .expect("must scan jar");
+        assert!(warnings.is_empty());
+        let assets = finalize_assets(candidates, &mut HashMap::new());
+        let gem = assets
+            .iter()
+            .find(|asset| asset.namespace == "inner")
+            .expect("nested asset must be found");
+
+        assert_eq!(
+            gem.entry_path,
+            "META-INF/jars/inner-1.0.jar!assets/inner/textures/item/gem.png"
         );
-        let record = build_search_record(&asset);
-        let query = "stpe";
-        let tokens = split_tokens(query);
+        assert_eq!(extract_asset_bytes(gem).expect("must extract"), b"gem");
+        assert_eq!(split_nested_entry_path("a/b!c.png"), vec!["a/b!c.png"]);
+        assert!(!may_hold_assets(
+            &AssetSourceType::Mod,
+            "com/example/OuterMod.class"
+        ));
+        assert!(may_hold_assets(
+            &AssetSourceType::Mod,
+            "assets\\outer\\lang\\en_us.json"
+        ));
+        assert!(!may_hold_assets(
+            &AssetSourceType::Datapack,
+            "assets/outer/x.json"
+        ));
+        let _ = fs::remove_dir_all(&temp_root);
+    }
 
-        let score = score_query(&record, &tokens, &compact_text(query), &tokens.join(" "));
-        assert!(score.is_some());
+    #[test]
+    fn archives_without_a_central_directory_are_salvaged() {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, bytes) in [
+            ("assets/demo/textures/block/slate.png", b"slate".as_slice()),
+            ("com/example/Demo.class", b"\xca\xfe".as_slice()),
+            ("assets/demo/sounds/drip.ogg", b"drip".as_slice()),
+        ] {
+            writer
+                .start_file(name, zip::write::SimpleFileOptions::default())
+                .expect("must start zip entry");
+            writer.write_all(bytes).expect("must write zip entry");
+        }
+        let mut bytes = writer.finish().expect("must finish zip").into_inner();
+        let central_directory = bytes
+            .windows(4)
+            .position(|window| window == b"PK\x01\x02")
+            .expect("zip must have a central directory");
+        bytes.truncate(central_directory);
+
+        let temp_root = std::env::temp_dir().join(format!("mae-salvage-{}", Uuid::new_v4()));
+        fs::create_dir_all(&temp_root).expect("must create temp directory");
+        let container = ScanContainer {
+            source_type: AssetSourceType::Mod,
+            source_name: "partial".to_string(),
+            container_type: AssetContainerType::Jar,
+            container_path: temp_root.join("partial.jar"),
+        };
+        fs::write(&container.container_path, bytes).expect("must write jar");
+
+        let mut warnings = Vec::new();
+        let candidates =
+            scan_archive_container(&container, ScanOptions::default(), &|| false, &mut warnings)
+                .expect("salvage must recover entries");
+        assert_eq!(warnings.len(), 1);
+        let assets = finalize_assets(candidates, &mut HashMap::new());
+        assert_eq!(assets.len(), 2);
+        let mut archive_cache = ArchiveCache::new();
+        for (extension, expected) in [("png", b"slate".as_slice()), ("ogg", b"drip")] {
+            let asset = assets
+                .iter()
+                .find(|asset| asset.extension == extension)
+                .expect("asset must be salvaged");
+            let bytes = extract_asset_bytes_with_archive_cache(asset, &mut archive_cache)
+                .expect("must extract");
+            assert_eq!(bytes, expected);
+        }
+        // Both reads share the offsets from a single walk of the archive.
+        let Some(CachedArchive::Salvaged(salvaged)) =
+            archive_cache.get(&container.container_path.to_string_lossy().to_string())
+        else {
+            panic!("salvaged archive must be cached");
+        };
+        assert_eq!(salvaged.offsets.len(), 3);
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn datapacks_are_collected_per_world_and_parsed_from_data() {
+        let temp_root = std::env::temp_dir().join(format!("mae-datapacks-{}", Uuid::new_v4()));
+        let world_packs = temp_root.join("saves").join("Survival").join("datapacks");
+        let pack_dir = world_packs.join("tweaks");
+        fs::create_dir_all(pack_dir.join("data").join("tweaks").join("functions"))
+            .expect("must create datapack");
+        fs::write(
+            pack_dir
+                .join("data")
+                .join("tweaks")
+                .join("functions")
+                .join("tick.mcfunction"),
+            b"say hi",
+        )
+        .expect("must write function");
+        fs::write(world_packs.join("readme.txt"), b"not a pack").expect("must write file");
+
+        let containers = collect_datapack_containers(&temp_root);
+        assert_eq!(containers.len(), 1);
+        assert_eq!(containers[0].source_name, "tweaks (Survival)");
+
+        let candidates =
+            scan_directory_container(&containers[0], ScanOptions::default(), &|| false)
+                .expect("must scan datapack");
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].namespace, "tweaks");
+        assert_eq!(
+            candidates[0].relative_asset_path,
+            "functions/tick.mcfunction"
+        );
+        let _ = fs::remove_dir_all(&temp_root);
     }
 
     #[test]
-    fn folder_filter_matches_subtree() {
-        let asset = sample_asset(
-            "mod.sample.sample.textures.item.star.png",
-            AssetSourceType::Mod,
-            "sample-mod",
-            "sample",
-            "textures/item/star.png",
+    fn shaderpacks_are_scanned_from_the_pack_root() {
+        let temp_root = std::env::temp_dir().join(format!("mae-shaderpacks-{}", Uuid::new_v4()));
+        let pack_dir = temp_root.join("shaderpacks").join("BSL");
+        fs::create_dir_all(pack_dir.join("shaders")).expect("must create shader pack");
+        fs::write(
+            pack_dir.join("shaders").join("gbuffers_basic.fsh"),
+            b"void main() {}",
+        )
+        .expect("must write shader");
+        fs::write(temp_root.join("shaderpacks").join("notes.txt"), b"x").expect("must write file");
+
+        let containers = collect_shaderpack_containers(&temp_root);
+        assert_eq!(containers.len(), 1);
+        assert_eq!(containers[0].source_name, "BSL");
+
+        let candidates =
+            scan_directory_container(&containers[0], ScanOptions::default(), &|| false)
+                .expect("must scan shader pack");
+        let assets = finalize_assets(candidates, &mut HashMap::new());
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets[0].namespace, "");
+        assert_eq!(assets[0].relative_asset_path, "shaders/gbuffers_basic.fsh");
+        assert!(assets[0].is_shader);
+        assert_eq!(
+            build_asset_folder_segments(&assets[0]),
+            vec!["shaderpacks", "BSL", "shaders"]
         );
-
-        let index = build_search_record(&asset);
-        let folder = asset_folder_node_id(&asset);
-        let parent = folder.split('/').take(4).collect::<Vec<_>>().join("/");
-
-        assert!(asset_matches_folder(&index, Some(&folder)));
-        assert!(asset_matches_folder(&index, Some(&parent)));
-        assert!(!asset_matches_folder(&index, Some("root/vanilla")));
+        let _ = fs::remove_dir_all(&temp_root);
     }
 
     #[test]
-    fn idle_sort_uses_natural_last_filename_token() {
-        let a1 = sample_asset(
-            "mod.sample.sample.sounds.entity.test.active1.ogg",
-            AssetSourceType::Mod,
-            "sample",
-            "sample",
-            "sounds/entity/test/active1.ogg",
-        );
-        let a2 = sample_asset(
-            "mod.sample.sample.sounds.entity.test.active2.ogg",
-            AssetSourceType::Mod,
-            "sample",
-            "sample",
-            "sounds/entity/test/active2.ogg",
+    fn instance_level_nests_assets_under_the_instance() {
+        let req = StartMultiScanRequest {
+            prism_root: "/prism".to_string(),
+            instance_folders: vec!["pack-a".to_string(), "pack-b".to_string()],
+            include_vanilla: true,
+            include_mods: false,
+            include_resourcepacks: true,
+            include_server_packs: None,
+            include_datapacks: None,
+            include_shaderpacks: None,
+            include_disabled_mods: None,
+            collect_dimensions: None,
+            launcher_kind: None,
+            follow_symlinks: None,
+        };
+        let instance_req = req.instance_request("pack-b");
+        assert_eq!(instance_req.instance_folder, "pack-b");
+        assert!(instance_req.include_resourcepacks && !instance_req.include_mods);
+
+        let mut asset = sample_asset(
+            "stone",
+            AssetSourceType::Vanilla,
+            "minecraft-1.21.1",
+            "minecraft",
+            "textures/block/stone.png",
         );
-        let a10 = sample_asset(
-            "mod.sample.sample.sounds.entity.test.active10.ogg",
-            AssetSourceType::Mod,
-            "sample",
-            "sample",
-            "sounds/entity/test/active10.ogg",
+        assert_eq!(build_asset_folder_segments(&asset)[0], "vanilla");
+        asset.instance_folder = Some("pack-b".to_string());
+        asset.instance_display_name = Some("Pack B".to_string());
+        assert_eq!(
+            build_asset_folder_segments(&asset),
+            vec![
+                "pack-b",
+                "vanilla",
+                "minecraft-1.21.1",
+                "minecraft",
+                "textures",
+                "block"
+            ]
         );
 
-        assert_eq!(idle_asset_cmp(&a1, &a2), CmpOrdering::Less);
-        assert_eq!(idle_asset_cmp(&a2, &a10), CmpOrdering::Less);
+        let mut tree_children = HashMap::new();
+        let mut tree_index = HashMap::new();
+        add_asset_to_tree(&mut tree_children, &mut tree_index, &asset);
+        let instance_node = &tree_children[ROOT_NODE_ID][0];
+        assert_eq!(instance_node.name, "Pack B");
+        assert_eq!(
+            instance_node.id,
+            build_folder_node_id(ROOT_NODE_ID, "pack-b")
+        );
     }
 
     #[test]
-    fn rebuild_key_counts_preserves_dup_suffix_progression() {
-        let assets = vec![
-            sample_asset(
-                "mod.sample.sample.sounds.block.grass.step.ogg",
-                AssetSourceType::Mod,
-                "sample",
-                "sample",
-                "sounds/block/grass/step.ogg",
-            ),
-            sample_asset(
-                "mod.sample.sample.sounds.block.grass.step.ogg.dup1",
-                AssetSourceType::Mod,
-                "sample",
-                "sample",
-                "sounds/block/grass/step.ogg",
+    fn containers_shared_by_instances_are_listed_under_each_instance() {
+        let temp_root = std::env::temp_dir().join(format!("mae-shared-{}", Uuid::new_v4()));
+        let container = |name: &str| {
+            let pack = temp_root.join(name);
+            let textures = pack.join("assets").join("minecraft").join("textures");
+            fs::create_dir_all(&textures).expect("must create pack");
+            fs::write(textures.join("stone.png"), b"stone").expect("must write texture");
+            ScanContainer {
+                source_type: AssetSourceType::ResourcePack,
+                source_name: name.to_string(),
+                container_type: AssetContainerType::Directory,
+                container_path: pack,
+            }
+        };
+        let instance = |folder: &str| ScanInstance {
+            folder: folder.to_string(),
+            display_name: None,
+        };
+        let shared = container("shared");
+        let merged = merge_instance_containers(vec![
+            (
+                instance("pack-a"),
+                vec![shared.clone(), container("only-a")],
             ),
-        ];
+            (instance("pack-b"), vec![shared.clone()]),
+        ]);
+        assert_eq!(merged.len(), 2);
+        let instance_folders = |instances: &[ScanInstance]| {
+            instances
+                .iter()
+                .map(|instance| instance.folder.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(merged[0].0.source_name, "shared");
+        assert_eq!(instance_folders(&merged[0].1), vec!["pack-a", "pack-b"]);
+        assert_eq!(instance_folders(&merged[1].1), vec!["pack-a"]);
 
-        let mut counts = rebuild_key_counts_from_assets(&assets);
-        let next = unique_key(
-            "mod.sample.sample.sounds.block.grass.step.ogg".to_string(),
-            &mut counts,
+        let candidates = scan_directory_container(&shared, ScanOptions::default(), &|| false)
+            .expect("must scan shared pack");
+        let assets = finalize_container_assets(candidates, &merged[0].1, &mut HashMap::new());
+        assert_eq!(
+            assets
+                .iter()
+                .map(|asset| asset.key.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "pack_a.resourcepack.shared.minecraft.textures.stone_png",
+                "pack_b.resourcepack.shared.minecraft.textures.stone_png",
+            ]
         );
+        let mut tree_children = HashMap::new();
+        let mut tree_index = HashMap::new();
+        for asset in &assets {
+            add_asset_to_tree(&mut tree_children, &mut tree_index, asset);
+        }
+        let instance_nodes = tree_children[ROOT_NODE_ID]
+            .iter()
+            .map(|node| (node.name.as_str(), node.asset_count))
+            .collect::<Vec<_>>();
+        assert_eq!(instance_nodes, vec![("pack-a", 1), ("pack-b", 1)]);
+        let _ = fs::remove_dir_all(&temp_root);
+    }
 
-        assert_eq!(next, "mod.sample.sample.sounds.block.grass.step.ogg.dup2");
+    #[test]
+    fn server_pack_cache_yields_zip_containers_by_signature() {
+        let temp_root = std::env::temp_dir().join(format!("mae-server-packs-{}", Uuid::new_v4()));
+        let cache_dir = temp_root.join("server-resource-packs");
+        fs::create_dir_all(&cache_dir).expect("must create server pack cache");
+        fs::write(cache_dir.join("0a1b2c3d"), b"PK\x03\x04rest").expect("must write pack");
+        fs::write(cache_dir.join("index.json"), b"{}").expect("must write non-pack");
+
+        let containers = collect_server_resource_pack_containers(&temp_root);
+
+        assert_eq!(containers.len(), 1);
+        assert_eq!(containers[0].source_name, "server-0a1b2c3d");
+        assert_eq!(containers[0].container_type, AssetContainerType::Zip);
+        let _ = fs::remove_dir_all(&temp_root);
     }
 
     #[test]
-    fn resolve_operation_id_generates_uuid_when_missing() {
-        let generated = resolve_operation_id(None);
-        assert!(
-            Uuid::parse_str(&generated).is_ok(),
-            "generated operation id should be a UUID: {generated}"
-        );
+    fn multimc_instances_use_dot_minecraft_game_dir() {
+        let temp_root = std::env::temp_dir().join(format!("mae-multimc-{}", Uuid::new_v4()));
+        let instance_dir = temp_root.join("instances").join("vanilla-plus");
+        fs::create_dir_all(instance_dir.join(".minecraft")).expect("must create game dir");
+        fs::create_dir_all(temp_root.join("libraries")).expect("must create libraries");
+        fs::write(
+            instance_dir.join("mmc-pack.json"),
+            r#"{ "components": [{ "uid": "net.minecraft", "version": "1.20.1" }] }"#,
+        )
+        .expect("must write mmc-pack.json");
 
-        let generated_from_empty = resolve_operation_id(Some("   ".to_string()));
-        assert!(
-            Uuid::parse_str(&generated_from_empty).is_ok(),
-            "generated operation id from empty input should be a UUID: {generated_from_empty}"
+        let candidate = build_candidate(temp_root.clone(), "test", LauncherKind::MultiMc);
+        assert!(candidate.valid);
+        assert!(is_launcher_instance_dir(
+            &instance_dir,
+            LauncherKind::MultiMc
+        ));
+        assert_eq!(
+            instance_game_dir(&instance_dir),
+            instance_dir.join(".minecraft")
+        );
+        assert_eq!(
+            serde_json::to_value(candidate.launcher).expect("must serialize"),
+            "multimc"
         );
+        let _ = fs::remove_dir_all(&temp_root);
     }
 
     #[test]
-    fn plan_export_jobs_dedupes_filenames_and_applies_audio_extension() {
-        let temp_root = std::env::temp_dir().join(format!("mae-export-plan-{}", Uuid::new_v4()));
-        fs::create_dir_all(&temp_root).expect("must create temp export directory");
+    fn vanilla_loader_profile_resolves_base_version_files() {
+        let temp_root = std::env::temp_dir().join(format!("mae-dot-minecraft-{}", Uuid::new_v4()));
+        let versions_dir = temp_root.join("versions");
+        let base_dir = versions_dir.join("1.20.1");
+        let profile_dir = versions_dir.join("fabric-loader-0.15.0-1.20.1");
+        fs::create_dir_all(&base_dir).expect("must create base version");
+        fs::create_dir_all(&profile_dir).expect("must create loader profile");
+        fs::create_dir_all(temp_root.join("assets").join("indexes"))
+            .expect("must create asset indexes");
+        fs::write(
+            base_dir.join("1.20.1.json"),
+            r#"{ "id": "1.20.1", "assetIndex": { "id": "5" } }"#,
+        )
+        .expect("must write base profile");
+        fs::write(base_dir.join("1.20.1.jar"), b"PK\x03\x04").expect("must write client jar");
+        fs::write(
+            profile_dir.join("fabric-loader-0.15.0-1.20.1.json"),
+            r#"{ "id": "fabric-loader-0.15.0-1.20.1", "inheritsFrom": "1.20.1" }"#,
+        )
+        .expect("must write loader profile");
+        fs::write(
+            temp_root.join("assets").join("indexes").join("5.json"),
+            b"{}",
+        )
+        .expect("must write asset index");
+
+        let req = StartScanRequest {
+            prism_root: temp_root.to_string_lossy().to_string(),
+            instance_folder: "fabric-loader-0.15.0-1.20.1".to_string(),
+            include_vanilla: true,
+            include_mods: true,
+            include_resourcepacks: true,
+            include_server_packs: None,
+            include_datapacks: None,
+            include_shaderpacks: None,
+            include_disabled_mods: None,
+            collect_dimensions: None,
+            force_rescan: None,
+            launcher_kind: Some(LauncherKind::Vanilla),
+            vanilla_version_override: None,
+            group_by_instance: None,
+            follow_symlinks: None,
+        };
+        let layout = resolve_instance_layout(&req).expect("must resolve vanilla layout");
 
-        let audio_one = sample_audio_asset(
-            "mod.audio.one.sounds.block.test.step.ogg",
-            "audio-one",
-            "sample",
-            "sounds/block/test/step.ogg",
-        );
-        let audio_two = sample_audio_asset(
-            "mod.audio.two.sounds.block.test.step.ogg",
-            "audio-two",
-            "sample",
-            "sounds/block/test/step.ogg",
+        assert_eq!(layout.mc_version, "1.20.1");
+        assert_eq!(layout.game_dir, temp_root);
+        assert_eq!(layout.client_jar, Some(base_dir.join("1.20.1.jar")));
+        assert!(layout.asset_index_path.is_some());
+
+        let snapshot_req = StartScanRequest {
+            vanilla_version_override: Some(" 24w14a ".to_string()),
+            ..req.clone()
+        };
+        let error = resolve_instance_layout(&snapshot_req).expect_err("snapshot jar is missing");
+        assert!(error.contains("24w14a client jar not found"));
+        for invalid in ["../1.20.1", "1.20.1/..", "a\\b"] {
+            let invalid_req = StartScanRequest {
+                vanilla_version_override: Some(invalid.to_string()),
+                ..req.clone()
+            };
+            let error =
+                resolve_instance_layout(&invalid_req).expect_err("must reject path-like overrides");
+            assert!(error.contains("Invalid Minecraft version override"));
+        }
+        assert_ne!(
+            scan_cache_key_for_request(&req),
+            scan_cache_key_for_request(&snapshot_req)
         );
 
-        let jobs = plan_export_jobs(vec![audio_one, audio_two], &temp_root, AudioFormat::Mp3);
-        let names = jobs
+        let snapshot_dir = versions_dir.join("24w14a");
+        fs::create_dir_all(&snapshot_dir).expect("must create snapshot version");
+        fs::write(snapshot_dir.join("24w14a.jar"), b"PK\x03\x04").expect("must write jar");
+        let layout = resolve_instance_layout(&snapshot_req).expect("must resolve snapshot");
+        assert_eq!(layout.mc_version, "24w14a");
+        assert_eq!(layout.client_jar, Some(snapshot_dir.join("24w14a.jar")));
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn technic_layout_reads_bin_version_and_modpack_jar() {
+        let temp_root = std::env::temp_dir().join(format!("mae-technic-{}", Uuid::new_v4()));
+        let pack_dir = temp_root.join("modpacks").join("tekkit");
+        let bin_dir = pack_dir.join("bin");
+        fs::create_dir_all(&bin_dir).expect("must create bin directory");
+        fs::create_dir_all(pack_dir.join("mods")).expect("must create mods directory");
+        fs::create_dir_all(temp_root.join("assets").join("indexes"))
+            .expect("must create asset indexes");
+        fs::write(
+            bin_dir.join("version.json"),
+            r#"{ "id": "1.12.2", "assetIndex": { "id": "1.12" } }"#,
+        )
+        .expect("must write version.json");
+        fs::write(bin_dir.join("modpack.jar"), b"PK\x03\x04").expect("must write modpack.jar");
+        fs::write(pack_dir.join("mods").join("addon.zip"), b"PK\x03\x04").expect("must write zip");
+        fs::write(pack_dir.join("mods").join("notes.txt"), b"x").expect("must write notes");
+        fs::write(
+            pack_dir.join("mods").join("old.jar.disabled"),
+            b"PK\x03\x04",
+        )
+        .expect("must write disabled mod");
+        fs::write(bin_dir.join("minecraft.jar"), b"PK\x03\x04").expect("must write client jar");
+        fs::write(
+            temp_root.join("assets").join("indexes").join("1.12.json"),
+            b"{}",
+        )
+        .expect("must write asset index");
+
+        let req = StartScanRequest {
+            prism_root: temp_root.to_string_lossy().to_string(),
+            instance_folder: "tekkit".to_string(),
+            include_vanilla: true,
+            include_mods: true,
+            include_resourcepacks: true,
+            include_server_packs: None,
+            include_datapacks: None,
+            include_shaderpacks: None,
+            include_disabled_mods: None,
+            collect_dimensions: None,
+            force_rescan: None,
+            launcher_kind: Some(LauncherKind::Technic),
+            vanilla_version_override: None,
+            group_by_instance: None,
+            follow_symlinks: None,
+        };
+        let layout = resolve_instance_layout(&req).expect("must resolve technic layout");
+        let containers = collect_scan_containers(&layout, &req).expect("must collect containers");
+
+        assert_eq!(layout.mc_version, "1.12.2");
+        assert!(containers
             .iter()
-            .map(|job| {
-                job.output_path
-                    .file_name()
-                    .expect("output name must exist")
-                    .to_string_lossy()
-                    .to_string()
-            })
+            .any(|container| container.source_name == "modpack"));
+        let zipped_mods = containers
+            .iter()
+            .filter(|container| matches!(container.container_type, AssetContainerType::Zip))
             .collect::<Vec<_>>();
+        assert_eq!(zipped_mods.len(), 1);
+        assert_eq!(zipped_mods[0].source_name, "addon");
+        assert!(matches!(zipped_mods[0].source_type, AssetSourceType::Mod));
+        assert!(!containers
+            .iter()
+            .any(|container| is_disabled_container_path(&container.container_path)));
 
+        let disabled_req = StartScanRequest {
+            include_disabled_mods: Some(true),
+            ..req.clone()
+        };
+        let containers =
+            collect_scan_containers(&layout, &disabled_req).expect("must collect containers");
+        let disabled = containers
+            .iter()
+            .find(|container| is_disabled_container_path(&container.container_path))
+            .expect("disabled mod must be collected");
+        assert_eq!(disabled.source_name, "old");
+        assert!(matches!(disabled.container_type, AssetContainerType::Jar));
         assert_eq!(
-            names,
-            vec!["step.mp3".to_string(), "step_1.mp3".to_string()]
+            strip_disabled_suffix("pack.zip.DISABLED"),
+            ("pack.zip", true)
         );
+        assert_eq!(strip_disabled_suffix(".disabled"), (".disabled", false));
+        assert_eq!(
+            containers
+                .iter()
+                .filter(|container| matches!(container.source_type, AssetSourceType::Vanilla))
+                .count(),
+            2
+        );
+        assert!(scan_cache_key_for_request(&req).ends_with("::technic"));
         let _ = fs::remove_dir_all(&temp_root);
     }
 
     #[test]
-    fn refresh_plan_detects_changed_new_and_removed_containers() {
-        let temp_root = std::env::temp_dir().join(format!("mae-refresh-plan-{}", Uuid::new_v4()));
-        fs::create_dir_all(&temp_root).expect("must create temp root");
+    fn atlauncher_layout_prefers_shared_versions_and_honours_overrides() {
+        let temp_root = std::env::temp_dir().join(format!("mae-atlauncher-{}", Uuid::new_v4()));
+        let instance_dir = temp_root.join("instances").join("CoolPack");
+        let bin_dir = instance_dir.join("bin");
+        let indexes_dir = temp_root.join("assets").join("indexes");
+        fs::create_dir_all(&bin_dir).expect("must create bin directory");
+        fs::create_dir_all(instance_dir.join("mods")).expect("must create mods directory");
+        fs::create_dir_all(temp_root.join("configs")).expect("must create configs directory");
+        fs::create_dir_all(&indexes_dir).expect("must create asset indexes");
+        fs::write(
+            instance_dir.join("instance.json"),
+            r#"{ "id": "1.20.1", "assetIndex": { "id": "8" }, "launcher": { "name": "Pack" } }"#,
+        )
+        .expect("must write instance.json");
+        fs::write(bin_dir.join("minecraft.jar"), b"PK\x03\x04").expect("must write client jar");
+        fs::write(instance_dir.join("mods").join("addon.jar"), b"PK\x03\x04")
+            .expect("must write mod");
+        fs::write(indexes_dir.join("8.json"), b"{}").expect("must write asset index");
+
+        let req = StartScanRequest {
+            prism_root: temp_root.to_string_lossy().to_string(),
+            instance_folder: "CoolPack".to_string(),
+            include_vanilla: true,
+            include_mods: true,
+            include_resourcepacks: false,
+            include_server_packs: None,
+            include_datapacks: None,
+            include_shaderpacks: None,
+            include_disabled_mods: None,
+            collect_dimensions: None,
+            force_rescan: None,
+            launcher_kind: Some(LauncherKind::AtLauncher),
+            vanilla_version_override: None,
+            group_by_instance: None,
+            follow_symlinks: None,
+        };
+        assert_eq!(
+            launcher_instance_display_name(&instance_dir, LauncherKind::AtLauncher).as_deref(),
+            Some("Pack")
+        );
 
-        let container_a = temp_root.join("a.jar");
-        let container_b = temp_root.join("b.jar");
-        let container_c = temp_root.join("c.jar");
-        fs::write(&container_a, b"a").expect("must write a");
-        fs::write(&container_b, b"b").expect("must write b");
+        // Without a shared versions/ jar the instance's own bin/minecraft.jar is used.
+        let layout = resolve_instance_layout(&req).expect("must resolve atlauncher layout");
+        assert_eq!(layout.mc_version, "1.20.1");
+        assert_eq!(layout.game_dir, instance_dir);
+        assert_eq!(layout.client_jar, Some(bin_dir.join("minecraft.jar")));
+        assert_eq!(layout.asset_index_path, Some(indexes_dir.join("8.json")));
+        let containers = collect_scan_containers(&layout, &req).expect("must collect containers");
+        assert!(containers.iter().any(|container| {
+            container.source_name == "addon"
+                && matches!(container.source_type, AssetSourceType::Mod)
+        }));
+
+        let versions_dir = temp_root.join("versions").join("1.20.1");
+        fs::create_dir_all(&versions_dir).expect("must create version directory");
+        fs::write(versions_dir.join("1.20.1.jar"), b"PK\x03\x04").expect("must write jar");
+        let layout = resolve_instance_layout(&req).expect("must resolve atlauncher layout");
+        assert_eq!(layout.client_jar, Some(versions_dir.join("1.20.1.jar")));
+
+        // An override never falls back to bin/minecraft.jar, which belongs to 1.20.1.
+        let override_req = StartScanRequest {
+            vanilla_version_override: Some("1.19.2".to_string()),
+            ..req.clone()
+        };
+        let error = resolve_instance_layout(&override_req).expect_err("override jar is missing");
+        assert!(error.contains("1.19.2 client jar not found"));
+
+        let override_dir = temp_root.join("versions").join("1.19.2");
+        fs::create_dir_all(&override_dir).expect("must create override version");
+        fs::write(
+            override_dir.join("1.19.2.json"),
+            r#"{ "id": "1.19.2", "assetIndex": { "id": "1.19" } }"#,
+        )
+        .expect("must write version profile");
+        fs::write(indexes_dir.join("1.19.json"), b"{}").expect("must write asset index");
+        let layout = resolve_instance_layout(&StartScanRequest {
+            include_vanilla: false,
+            ..override_req.clone()
+        })
+        .expect("a missing jar is fine without vanilla assets");
+        assert_eq!(layout.client_jar, Some(override_dir.join("1.19.2.jar")));
+        assert_eq!(layout.asset_index_path, Some(indexes_dir.join("1.19.json")));
+
+        fs::write(override_dir.join("1.19.2.jar"), b"PK\x03\x04").expect("must write jar");
+        let layout = resolve_instance_layout(&override_req).expect("must resolve override");
+        assert_eq!(layout.mc_version, "1.19.2");
+        assert!(scan_cache_key_for_request(&req).ends_with("::atlauncher"));
+        let _ = fs::remove_dir_all(&temp_root);
+    }
 
-        let cached_a = ScanContainer {
-            source_type: AssetSourceType::Mod,
-            source_name: "a".to_string(),
-            container_type: AssetContainerType::Jar,
-            container_path: container_a.clone(),
+    #[test]
+    fn instance_validation_reports_version_jar_and_counts() {
+        let temp_root = std::env::temp_dir().join(format!("mae-validate-{}", Uuid::new_v4()));
+        let pack_dir = temp_root.join("modpacks").join("tekkit");
+        let bin_dir = pack_dir.join("bin");
+        fs::create_dir_all(&bin_dir).expect("must create bin directory");
+        fs::create_dir_all(pack_dir.join("mods")).expect("must create mods directory");
+        fs::write(pack_dir.join("mods").join("addon.jar"), b"PK\x03\x04").expect("must write mod");
+
+        let options = LastScanOptions {
+            include_vanilla: true,
+            include_mods: false,
+            include_resourcepacks: false,
+            include_server_packs: None,
+            include_datapacks: None,
+            include_shaderpacks: None,
+            include_disabled_mods: None,
+            collect_dimensions: None,
+            launcher_kind: Some(LauncherKind::Technic),
+            vanilla_version_override: None,
+            group_by_instance: None,
+            follow_symlinks: None,
         };
-        let cached_b = ScanContainer {
-            source_type: AssetSourceType::Mod,
-            source_name: "b".to_string(),
-            container_type: AssetContainerType::Jar,
-            container_path: container_b.clone(),
+        let root = temp_root.to_string_lossy().to_string();
+        let validate = || {
+            validate_instance(root.clone(), "tekkit".to_string(), options.clone())
+                .expect("root and instance are valid")
         };
-        let mut cached_signatures = HashMap::new();
-        cached_signatures.insert(
-            scan_container_key(&cached_a),
-            container_signature_for_path(&container_a, &AssetContainerType::Jar)
-                .expect("signature for a"),
+
+        let report = validate();
+        assert!(report.version_error.is_some());
+        assert_eq!(report.mod_count, 0);
+
+        fs::write(bin_dir.join("version.json"), r#"{ "id": "1.12.2" }"#)
+            .expect("must write version.json");
+        let report = validate();
+        assert_eq!(report.mc_version.as_deref(), Some("1.12.2"));
+        assert!(!report.client_jar_exists && !report.asset_index_exists);
+        assert!(report.mods_dir_exists && !report.resourcepacks_dir_exists);
+        assert_eq!(report.mod_count, 1);
+
+        assert!(validate_instance(root, "missing".to_string(), options).is_err());
+        let _ = fs::remove_dir_all(&temp_root);
+    }
+
+    #[test]
+    fn background_compositing_flattens_alpha() {
+        assert_eq!(
+            parse_background_color("#f80").expect("short hex"),
+            [255, 136, 0]
         );
-        cached_signatures.insert(
-            scan_container_key(&cached_b),
-            container_signature_for_path(&container_b, &AssetContainerType::Jar)
-                .expect("signature for b"),
+        assert_eq!(
+            parse_background_color("102030").expect("long hex"),
+            [16, 32, 48]
         );
+        assert!(parse_background_color("#12").is_err());
 
-        fs::write(&container_c, b"c").expect("must write c");
-        let current = vec![
-            cached_a.clone(),
-            ScanContainer {
-                source_type: AssetSourceType::Mod,
-                source_name: "c".to_string(),
-                container_type: AssetContainerType::Jar,
-                container_path: container_c.clone(),
-            },
-        ];
+        let mut image = RgbaImage::new(2, 1);
+        image.put_pixel(0, 0, image::Rgba([0, 0, 0, 0]));
+        image.put_pixel(1, 0, image::Rgba([200, 100, 0, 255]));
+        let flattened = composite_over_background(&image, [255, 255, 255]);
 
-        let plan = build_scan_refresh_plan(&cached_signatures, &current).expect("refresh plan");
-        assert_eq!(plan.unchanged_keys.len(), 1);
-        assert_eq!(plan.changed_or_new.len(), 1);
-        assert_eq!(plan.removed_keys.len(), 1);
-        assert!(plan
-            .removed_keys
-            .contains(&scan_container_key(&cached_b)));
+        assert_eq!(flattened.get_pixel(0, 0).0, [255, 255, 255, 255]);
+        assert_eq!(flattened.get_pixel(1, 0).0, [200, 100, 0, 255]);
+    }
 
-        let _ = fs::remove_dir_all(&temp_root);
+    #[test]
+    fn texture_animation_reads_frames_and_defaults() {
+        let animation = serde_json::json!({
+            "frametime": 2,
+            "interpolate": true,
+            "frames": [1, { "index": 0, "time": 5 }, 9]
+        });
+        let parsed = parse_texture_animation(&animation, 16, 64).expect("must parse");
+        assert_eq!((parsed.frame_width, parsed.frame_height), (16, 16));
+        assert!(parsed.interpolate);
+        assert_eq!(
+            parsed.frames,
+            vec![
+                AnimationFrame { index: 1, time: 2 },
+                AnimationFrame { index: 0, time: 5 },
+            ]
+        );
+
+        let implicit = parse_texture_animation(&serde_json::json!({}), 16, 48).expect("must parse");
+        assert_eq!(implicit.frames.len(), 3);
+        assert_eq!(implicit.frames[2].time, 1);
+    }
+
+    #[test]
+    fn texture_frames_are_sliced_in_strip_order() {
+        let mut strip = RgbaImage::new(32, 32);
+        strip.put_pixel(16, 0, image::Rgba([255, 0, 0, 255]));
+        strip.put_pixel(0, 16, image::Rgba([0, 255, 0, 255]));
+
+        let frames = slice_texture_frames(&strip, 16, 16);
+        assert_eq!(frames.len(), 4);
+        assert!(frames.iter().all(|frame| frame.dimensions() == (16, 16)));
+        assert_eq!(frames[1].get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(frames[2].get_pixel(0, 0).0, [0, 255, 0, 255]);
+        assert!(slice_texture_frames(&strip, 0, 16).is_empty());
+    }
+
+    #[test]
+    fn thumbnails_use_first_frame_and_fit_max_edge() {
+        let strip = RgbaImage::new(16, 64);
+        let first_frame = build_thumbnail(&strip, Some((16, 16)), 128);
+        assert_eq!(first_frame.dimensions(), (16, 16));
+
+        let large = RgbaImage::new(512, 256);
+        let shrunk = build_thumbnail(&large, None, 128);
+        assert_eq!(shrunk.dimensions(), (128, 64));
     }
 
     #[test]
@@ -4548,9 +15774,20 @@ mod tests {
                 .unwrap_or_default(),
             is_image: true,
             is_audio: false,
+            is_shader: false,
             container_path: "/tmp/container".to_string(),
             container_type: AssetContainerType::Jar,
             entry_path: format!("assets/{namespace}/{relative_asset_path}"),
+            is_extractable: true,
+            width: None,
+            height: None,
+            byte_size: None,
+            mod_display_name: None,
+            mod_id: None,
+            instance_folder: None,
+            instance_display_name: None,
+            disabled: false,
+            shadowed_by: None,
         }
     }
 
@@ -4570,9 +15807,156 @@ mod tests {
             extension: "ogg".to_string(),
             is_image: false,
             is_audio: true,
+            is_shader: false,
             container_path: "/tmp/container".to_string(),
             container_type: AssetContainerType::Jar,
             entry_path: format!("assets/{namespace}/{relative_asset_path}"),
+            is_extractable: true,
+            width: None,
+            height: None,
+            byte_size: None,
+            mod_display_name: None,
+            mod_id: None,
+            instance_folder: None,
+            instance_display_name: None,
+            disabled: false,
+            shadowed_by: None,
+        }
+    }
+
+    #[test]
+    fn cached_archives_read_entries_through_a_memory_map() {
+        let temp_root = std::env::temp_dir().join(format!("mae-archive-reader-{}", Uuid::new_v4()));
+        fs::create_dir_all(&temp_root).expect("must create temp directory");
+        let jar_path = temp_root.join("pack.jar");
+        let mut writer = zip::ZipWriter::new(fs::File::create(&jar_path).expect("must create jar"));
+        writer
+            .start_file(
+                "assets/demo/lang/en_us.json",
+                zip::write::SimpleFileOptions::default(),
+            )
+            .expect("must start zip entry");
+        writer.write_all(b"{}").expect("must write zip entry");
+        writer.finish().expect("must finish zip");
+
+        let reader = ArchiveReader::open(&jar_path).expect("must open jar");
+        assert!(matches!(reader, ArchiveReader::Mapped(_)));
+        let mut archive = ZipArchive::new(reader).expect("must read mapped jar");
+        assert_eq!(
+            read_archive_entry(&mut archive, "assets/demo/lang/en_us.json").expect("must read"),
+            b"{}"
+        );
+
+        let _ = fs::remove_dir_all(temp_root);
+    }
+
+    /// `cargo test --lib archive_reader_benchmark -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn archive_reader_benchmark() {
+        let temp_root = std::env::temp_dir().join(format!("mae-archive-bench-{}", Uuid::new_v4()));
+        fs::create_dir_all(&temp_root).expect("must create temp directory");
+        let jar_path = temp_root.join("pack.jar");
+        let mut writer = zip::ZipWriter::new(fs::File::create(&jar_path).expect("must create jar"));
+        let texture = encode_rgba_image(&RgbaImage::new(64, 64), RasterFormat::Png)
+            .expect("must encode texture");
+        let names = (0..500)
+            .map(|index| format!("assets/demo/textures/block/block_{index}.png"))
+            .collect::<Vec<_>>();
+        for name in &names {
+            writer
+                .start_file(name, zip::write::SimpleFileOptions::default())
+                .expect("must start zip entry");
+            writer.write_all(&texture).expect("must write zip entry");
         }
+        writer.finish().expect("must finish zip");
+
+        let measure = |label: &str, open: &dyn Fn() -> ArchiveReader| {
+            let started = Instant::now();
+            for _ in 0..20 {
+                let mut archive = ZipArchive::new(open()).expect("must read jar");
+                for name in &names {
+                    read_archive_entry(&mut archive, name).expect("must read entry");
+                }
+            }
+            println!("{label}: {:?} per 500 entries", started.elapsed() / 20);
+        };
+        measure("mapped", &|| {
+            ArchiveReader::open(&jar_path).expect("must open jar")
+        });
+        measure("file", &|| {
+            ArchiveReader::File(fs::File::open(&jar_path).expect("must open jar"))
+        });
+
+        let _ = fs::remove_dir_all(temp_root);
+    }
+
+    #[test]
+    fn export_free_space_check_uses_estimated_sizes() {
+        let temp_root = std::env::temp_dir().join(format!("mae-free-space-{}", Uuid::new_v4()));
+        let texture_dir = temp_root.join("pack/assets/demo/textures");
+        fs::create_dir_all(&texture_dir).expect("must create pack directory");
+        fs::write(texture_dir.join("stone.png"), [0u8; 40]).expect("must write texture");
+        let jar_path = temp_root.join("mod.jar");
+        let mut writer = zip::ZipWriter::new(fs::File::create(&jar_path).expect("must create jar"));
+        writer
+            .start_file(
+                "assets/demo/textures/dirt.png",
+                zip::write::SimpleFileOptions::default(),
+            )
+            .expect("must start zip entry");
+        writer.write_all(&[0u8; 25]).expect("must write zip entry");
+        writer.finish().expect("must finish zip");
+
+        let asset = |path: &str| {
+            let mut asset = sample_asset(path, AssetSourceType::Mod, "mod", "demo", path);
+            asset.container_path = jar_path.to_string_lossy().to_string();
+            asset
+        };
+        let mut folder = asset("textures/stone.png");
+        folder.container_type = AssetContainerType::Directory;
+        folder.container_path = temp_root.join("pack").to_string_lossy().to_string();
+        let mut sized = asset("textures/sand.png");
+        sized.byte_size = Some(100);
+        let assets = [
+            folder,
+            asset("textures/dirt.png"),
+            sized,
+            asset("textures/gone.png"),
+        ];
+        assert_eq!(estimated_export_bytes(&assets), 165);
+
+        assert!(ensure_free_space(&temp_root, 0).is_ok());
+        let error = ensure_free_space(&temp_root, u64::MAX).expect_err("must refuse huge exports");
+        assert!(error.contains("Not enough free space"));
+        assert!(ensure_free_space(&temp_root.join("not/created/yet"), u64::MAX).is_err());
+
+        let _ = fs::remove_dir_all(temp_root);
+    }
+    #[test]
+    fn export_byte_progress_moves_within_a_single_file() {
+        let processed = AtomicU64::new(0);
+        let progress = ByteProgress::new(&processed);
+        let mut output = Vec::new();
+        let mut writer = CountingWriter::new(&mut output, Some(&progress));
+        let written = writer
+            .write(&vec![7u8; EXPORT_WRITE_CHUNK_BYTES * 2 + 10])
+            .expect("must write first chunk");
+        assert_eq!(written, EXPORT_WRITE_CHUNK_BYTES);
+        assert_eq!(
+            processed.load(AtomicOrdering::Relaxed),
+            EXPORT_WRITE_CHUNK_BYTES as u64
+        );
+
+        writer
+            .write_all(&vec![7u8; EXPORT_WRITE_CHUNK_BYTES + 10])
+            .expect("must write remaining chunks");
+        let counted = (EXPORT_WRITE_CHUNK_BYTES * 2 + 10) as u64;
+        assert_eq!(processed.load(AtomicOrdering::Relaxed), counted);
+
+        progress.finish(Some(counted + 5));
+        assert_eq!(processed.load(AtomicOrdering::Relaxed), counted + 5);
+        progress.finish(Some(1));
+        assert_eq!(processed.load(AtomicOrdering::Relaxed), counted + 5);
     }
 }
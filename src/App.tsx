@@ -26,13 +26,16 @@ import type {
   AssetPreviewResponse,
   AssetRecord,
   AudioFormat,
+  CopyAssetsRequest,
   CopyResult,
   ExportCompletedEvent,
   ExportProgressEvent,
   ExportFailure,
   InstanceInfo,
   PrismRootCandidate,
+  PreviewError,
   ReconcileAssetIdsResponse,
+  SaveAssetsRequest,
   SaveAssetsResult,
   StartScanResponse,
   ScanCompletedEvent,
@@ -55,6 +58,7 @@ const PROGRESS_STATUS_THROTTLE_MS = 250;
 const SCAN_STATUS_POLL_MS = 1000;
 const PREVIEW_TOP_GAP_PX = 14;
 const PREVIEW_CACHE_LIMIT = 24;
+const TEXT_PREVIEW_EXTENSIONS = new Set(["txt", "lang", "properties", "cfg", "toml", "md", "fsh", "vsh", "glsl", "obj", "mtl"]);
 const RELEASES_LATEST_API_URL =
   "https://api.github.com/repos/przxmus/minecraft-asset-explorer/releases/latest";
 const RELEASES_FALLBACK_URL = "https://github.com/przxmus/minecraft-asset-explorer/releases/latest";
@@ -150,6 +154,17 @@ function formatScanProgressLine(progress: Pick<ScanProgressEvent, "scannedContai
   return `${progress.scannedContainers}/${progress.totalContainers} containers · ${progress.assetCount} assets`;
 }
 
+function formatPreviewError(error: unknown): string {
+  const previewError = error as PreviewError | null;
+  if (previewError?.code === "unsupported_preview") {
+    return `Preview unavailable for .${previewError.extension} files, export to view`;
+  }
+  if (previewError?.code === "failed") {
+    return previewError.message;
+  }
+  return String(error);
+}
+
 async function openSavedDestination(destinationPath: string, savedFiles: string[]) {
   try {
     await openPath(destinationPath);
@@ -525,6 +540,8 @@ function App() {
           assetCount,
           phase: inferredPhase,
           currentSource: currentProgress?.currentSource,
+          elapsedMs: currentProgress?.elapsedMs ?? 0,
+          containersPerSecond: currentProgress?.containersPerSecond ?? null,
         };
         setProgress(nextProgress);
         setIsRefreshing(status.isRefreshing);
@@ -660,6 +677,8 @@ function App() {
           assetCount: 0,
           phase: "refreshing",
           currentSource: "cache",
+          elapsedMs: 0,
+          containersPerSecond: null,
         });
         setStatusLine("Loading cached index...");
       } else {
@@ -669,6 +688,8 @@ function App() {
           totalContainers: 0,
           assetCount: 0,
           phase: "estimating",
+          elapsedMs: 0,
+          containersPerSecond: null,
         });
         setStatusLine("Estimating containers · 0/0 containers · 0 assets");
       }
@@ -784,7 +805,7 @@ function App() {
             destinationDir: selectedPath,
             audioFormat,
             operationId,
-          },
+          } satisfies SaveAssetsRequest,
         });
 
         setStatusLine(
@@ -842,7 +863,7 @@ function App() {
             assetIds,
             audioFormat,
             operationId,
-          },
+          } satisfies CopyAssetsRequest,
         });
 
         setStatusLine(
@@ -1171,7 +1192,10 @@ function App() {
         activeAsset.extension.toLowerCase() === "json" ||
         activeAsset.extension.toLowerCase() === "mcmeta";
 
-      if (!activeAsset.isImage && !activeAsset.isAudio && !isJsonAsset) {
+      const isTextAsset =
+        activeAsset.isShader || TEXT_PREVIEW_EXTENSIONS.has(activeAsset.extension.toLowerCase());
+
+      if (!activeAsset.isImage && !activeAsset.isAudio && !isJsonAsset && !isTextAsset) {
         return;
       }
 
@@ -1183,6 +1207,7 @@ function App() {
         const preview = await invoke<AssetPreviewResponse>("get_asset_preview", {
           scanId: resolvedScanId,
           assetId: activeAsset.assetId,
+          pretty: true,
         });
 
         setPreviewCache((current) => {
@@ -1198,7 +1223,7 @@ function App() {
           return Object.fromEntries([...keptEntries, [activeAsset.assetId, preview]]);
         });
       } catch (error) {
-        setStatusLine(String(error));
+        setStatusLine(formatPreviewError(error));
       }
     };
 
@@ -1387,6 +1412,8 @@ function App() {
     !!activeAsset &&
     (activeAsset.extension.toLowerCase() === "json" ||
       activeAsset.extension.toLowerCase() === "mcmeta");
+  const activeAssetIsText =
+    !!activeAsset && (activeAsset.isShader || TEXT_PREVIEW_EXTENSIONS.has(activeAsset.extension.toLowerCase()));
   const jsonPreviewText = useMemo(() => {
     if (!activeAsset || !activeAssetIsJson || !currentPreview) {
       return null;
@@ -1420,6 +1447,8 @@ function App() {
           totalContainers: 0,
           assetCount: 0,
           phase: "estimating",
+          elapsedMs: 0,
+          containersPerSecond: null,
         }
       : null;
   const effectiveScanPhase = effectiveScanProgress?.phase ?? "estimating";
@@ -1591,6 +1620,7 @@ function App() {
           activeAsset={activeAsset}
           currentPreview={currentPreview}
           activeAssetIsJson={activeAssetIsJson}
+          activeAssetIsText={activeAssetIsText}
           highlightedJson={highlightedJson}
           previewPanelRef={previewPanelRef}
           previewContentRef={previewContentRef}
@@ -1,10 +1,12 @@
 import type { ReactElement, RefObject } from "react";
 import type { AssetPreviewResponse, AssetRecord } from "../types/assets";
+import { decodePreviewText } from "../utils/jsonPreview";
 
 type PreviewPanelProps = {
   activeAsset: AssetRecord | null;
   currentPreview: AssetPreviewResponse | undefined;
   activeAssetIsJson: boolean;
+  activeAssetIsText: boolean;
   highlightedJson: ReactElement[] | null;
   previewPanelRef: RefObject<HTMLElement | null>;
   previewContentRef: RefObject<HTMLDivElement | null>;
@@ -16,6 +18,7 @@ export function PreviewPanel({
   activeAsset,
   currentPreview,
   activeAssetIsJson,
+  activeAssetIsText,
   highlightedJson,
   previewPanelRef,
   previewContentRef,
@@ -58,12 +61,20 @@ export function PreviewPanel({
 
           {activeAssetIsJson && highlightedJson ? <pre className="json-preview">{highlightedJson}</pre> : null}
 
-          {!currentPreview && (activeAsset.isImage || activeAsset.isAudio || activeAssetIsJson) ? (
+          {activeAssetIsText && currentPreview ? (
+            <pre className="json-preview">{decodePreviewText(currentPreview.base64)}</pre>
+          ) : null}
+
+          {activeAssetIsText && currentPreview?.truncated ? (
+            <div className="preview-fallback">Showing the first 256 KB.</div>
+          ) : null}
+
+          {!currentPreview && (activeAsset.isImage || activeAsset.isAudio || activeAssetIsJson || activeAssetIsText) ? (
             <div className="preview-fallback">Loading preview...</div>
           ) : null}
 
-          {!activeAsset.isImage && !activeAsset.isAudio && !activeAssetIsJson ? (
-            <div className="preview-fallback">Preview available for images, audio, and JSON.</div>
+          {!activeAsset.isImage && !activeAsset.isAudio && !activeAssetIsJson && !activeAssetIsText ? (
+            <div className="preview-fallback">Preview available for images, audio, JSON, and text.</div>
           ) : null}
 
           <div className="preview-actions">
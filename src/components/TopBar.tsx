@@ -198,6 +198,8 @@ export function TopBar({
             <option value="original">Original</option>
             <option value="mp3">MP3</option>
             <option value="wav">WAV</option>
+            <option value="flac">FLAC</option>
+            <option value="opus">Opus</option>
           </select>
         </div>
 
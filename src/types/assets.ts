@@ -1,8 +1,16 @@
+export type LauncherKind =
+  | "prism"
+  | "multimc"
+  | "technic"
+  | "atlauncher"
+  | "vanilla";
+
 export type PrismRootCandidate = {
   path: string;
   exists: boolean;
   valid: boolean;
   source: string;
+  launcher: LauncherKind;
 };
 
 export type InstanceInfo = {
@@ -12,7 +20,39 @@ export type InstanceInfo = {
   minecraftVersion: string | null;
 };
 
-export type AssetSourceType = "vanilla" | "mod" | "resourcePack";
+export type LastScanOptions = {
+  includeVanilla: boolean;
+  includeMods: boolean;
+  includeResourcepacks: boolean;
+  includeServerPacks: boolean | null;
+  includeDatapacks: boolean | null;
+  includeShaderpacks: boolean | null;
+  includeDisabledMods: boolean | null;
+  collectDimensions: boolean | null;
+  launcherKind: LauncherKind | null;
+  vanillaVersionOverride: string | null;
+  groupByInstance: boolean | null;
+  followSymlinks: boolean | null;
+};
+
+export type InstanceValidation = {
+  mcVersion: string | null;
+  versionError: string | null;
+  clientJarPath: string | null;
+  clientJarExists: boolean;
+  assetIndexExists: boolean;
+  modsDirExists: boolean;
+  modCount: number;
+  resourcepacksDirExists: boolean;
+  resourcepackCount: number;
+};
+
+export type AssetSourceType =
+  | "vanilla"
+  | "mod"
+  | "resourcePack"
+  | "datapack"
+  | "shaderPack";
 export type AssetContainerType = "directory" | "zip" | "jar" | "assetIndex";
 
 export type AssetRecord = {
@@ -25,9 +65,20 @@ export type AssetRecord = {
   extension: string;
   isImage: boolean;
   isAudio: boolean;
+  isShader: boolean;
   containerPath: string;
   containerType: AssetContainerType;
   entryPath: string;
+  isExtractable: boolean;
+  width: number | null;
+  height: number | null;
+  byteSize: number | null;
+  modDisplayName: string | null;
+  modId: string | null;
+  instanceFolder: string | null;
+  instanceDisplayName: string | null;
+  disabled: boolean;
+  shadowedBy: string | null;
 };
 
 export type ScanLifecycle = "scanning" | "completed" | "cancelled" | "error";
@@ -40,7 +91,15 @@ export type StartScanResponse = {
   refreshMode?: "incremental" | "full";
 };
 
+export type ScanCacheEvent = {
+  scanId: string;
+  cacheHit: boolean;
+  refreshStarted: boolean;
+  refreshMode: "incremental" | null;
+};
+
 export type TreeNodeType = "folder" | "file";
+export type TreeSort = "nameAsc" | "nameDesc" | "natural" | "typeThenName";
 
 export type TreeNode = {
   id: string;
@@ -48,6 +107,12 @@ export type TreeNode = {
   nodeType: TreeNodeType;
   hasChildren: boolean;
   assetId: string | null;
+  assetCount: number;
+};
+
+export type TreeBreadcrumb = {
+  id: string;
+  name: string;
 };
 
 export type ScanProgressEvent = {
@@ -57,6 +122,8 @@ export type ScanProgressEvent = {
   assetCount: number;
   phase: ScanPhase;
   currentSource?: string;
+  elapsedMs: number;
+  containersPerSecond: number | null;
 };
 
 export type ScanStatus = {
@@ -67,6 +134,23 @@ export type ScanStatus = {
   totalContainers: number;
   assetCount: number;
   error?: string;
+  warnings: ScanWarning[];
+};
+
+export type ScanWarning = {
+  containerKey: string;
+  sourceName: string;
+  error: string;
+};
+
+export type EmptySource = {
+  containerKey: string;
+  sourceName: string;
+};
+
+export type ScanWarningEvent = {
+  scanId: string;
+  warning: ScanWarning;
 };
 
 export type ScanCompletedEvent = {
@@ -76,12 +160,132 @@ export type ScanCompletedEvent = {
   error?: string;
 };
 
+export type AnimationFrame = {
+  index: number;
+  time: number;
+};
+
+export type TextureAnimation = {
+  frameWidth: number;
+  frameHeight: number;
+  interpolate: boolean;
+  frames: AnimationFrame[];
+};
+
+export type TextureFrames = {
+  frameWidth: number;
+  frameHeight: number;
+  frames: string[];
+};
+
 export type AssetPreviewResponse = {
   mime: string;
   base64: string;
+  animation: TextureAnimation | null;
+  truncated: boolean;
+};
+
+export type AssetInfo = {
+  asset: AssetRecord;
+  resourceLocation: string | null;
+  byteSize: number | null;
+  width: number | null;
+  height: number | null;
+  durationMs: number | null;
+  hasMcmeta: boolean;
+  errors: Record<string, string>;
 };
 
-export type AudioFormat = "original" | "mp3" | "wav";
+export type PreviewError =
+  | { code: "unsupported_preview"; extension: string }
+  | { code: "failed"; message: string };
+
+export type AssetPreviewItem = {
+  assetId: string;
+  mime: string | null;
+  base64: string | null;
+  error: string | null;
+};
+
+export type AudioFormat = "original" | "mp3" | "wav" | "flac" | "opus";
+
+export type AudioQuality = {
+  mp3VbrQuality?: number | null;
+  bitrateKbps?: number | null;
+};
+
+export type ImageFormat = "original" | "png" | "webp" | "jpeg";
+export type ExportNaming = "original" | "key";
+
+export type AudioResample = {
+  sampleRate?: number | null;
+  channels?: number | null;
+};
+
+export type SaveAssetsRequest = AudioResample & {
+  scanId: string;
+  assetIds: string[];
+  destinationDir: string;
+  audioFormat?: AudioFormat | null;
+  audioQuality?: AudioQuality | null;
+  normalizeAudio?: boolean | null;
+  imageFormat?: ImageFormat | null;
+  background?: string | null;
+  naming?: ExportNaming | null;
+  nameTemplate?: string | null;
+  preserveStructure?: boolean | null;
+  includeManifest?: boolean | null;
+  dryRun?: boolean | null;
+  cleanupOnCancel?: boolean | null;
+  operationId?: string | null;
+};
+
+export type SaveAssetsAsZipRequest = AudioResample & {
+  scanId: string;
+  assetIds: string[];
+  destinationZipPath: string;
+  preserveStructure?: boolean | null;
+  audioFormat?: AudioFormat | null;
+  imageFormat?: ImageFormat | null;
+  background?: string | null;
+  operationId?: string | null;
+};
+
+export type ExportResourcePackRequest = AudioResample & {
+  scanId: string;
+  assetIds: string[];
+  destinationDir: string;
+  packFormat: number;
+  description: string;
+  packIconPath?: string | null;
+  audioFormat?: AudioFormat | null;
+  imageFormat?: ImageFormat | null;
+  background?: string | null;
+  cleanupOnCancel?: boolean | null;
+  operationId?: string | null;
+};
+
+export type ExportNamespaceRequest = AudioResample & {
+  scanId: string;
+  namespace: string;
+  destinationDir: string;
+  sourceFilter?: string[] | null;
+  audioFormat?: AudioFormat | null;
+  cleanupOnCancel?: boolean | null;
+  operationId?: string | null;
+};
+
+export type CopyAssetsRequest = AudioResample & {
+  scanId: string;
+  assetIds: string[];
+  audioFormat?: AudioFormat | null;
+  audioQuality?: AudioQuality | null;
+  normalizeAudio?: boolean | null;
+  imageFormat?: ImageFormat | null;
+  background?: string | null;
+  naming?: ExportNaming | null;
+  operationId?: string | null;
+};
 
 export type ExportOperationKind = "save" | "copy";
 
@@ -101,6 +305,13 @@ export type ExportProgressEvent = {
   cancelled: boolean;
 };
 
+export type ExportByteProgressEvent = {
+  operationId: string;
+  kind: ExportOperationKind;
+  processedBytes: number;
+  totalBytes: number | null;
+};
+
 export type ExportCompletedEvent = {
   operationId: string;
   kind: ExportOperationKind;
@@ -121,6 +332,22 @@ export type SaveAssetsResult = {
   cancelled: boolean;
   failures: ExportFailure[];
   savedFiles: string[];
+  manifestWarning: string | null;
+};
+
+export type SaveAssetsAsZipResult = SaveAssetsResult & {
+  zipPath: string | null;
+};
+
+export type NamespaceExportConflict = {
+  relativeAssetPath: string;
+  keptAssetId: string;
+  keptSourceName: string;
+  skippedSourceNames: string[];
+};
+
+export type ExportNamespaceResult = SaveAssetsResult & {
+  conflicts: NamespaceExportConflict[];
 };
 
 export type CopyResult = {
@@ -134,9 +361,61 @@ export type CopyResult = {
   copiedFiles: string[];
 };
 
+export type CopyResourceLocationsResult = {
+  copiedCount: number;
+  skippedCount: number;
+  duplicateCount: number;
+};
+
+export type SearchMatchMode = "smart" | "regex" | "glob" | "exact";
+export type FuzzyMode = "off" | "conservative" | "aggressive";
+
+export type HighlightRange = {
+  start: number;
+  end: number;
+};
+
+export type AssetHighlights = {
+  assetId: string;
+  key: HighlightRange[];
+  relativeAssetPath: HighlightRange[];
+};
+
+export type SearchResultGroup = {
+  folderNodeId: string;
+  folderName: string;
+  assets: AssetRecord[];
+};
+
 export type SearchResponse = {
   total: number;
   assets: AssetRecord[];
+  highlights: AssetHighlights[] | null;
+  groups: SearchResultGroup[] | null;
+  nextCursor: string | null;
+};
+
+export type NamespaceFacet = {
+  name: string;
+  count: number;
+};
+
+export type SourceFacet = {
+  name: string;
+  displayName: string | null;
+  sourceType: AssetSourceType;
+  count: number;
+};
+
+export type ExtensionFacet = {
+  ext: string;
+  count: number;
+};
+
+export type ScanFacets = {
+  namespaces: NamespaceFacet[];
+  sources: SourceFacet[];
+  extensions: ExtensionFacet[];
 };
 
 export type ReconcileAssetIdsResponse = {
@@ -149,3 +428,101 @@ export type SelectionModifiers = {
   metaKey: boolean;
   ctrlKey: boolean;
 };
+
+export type ScanCacheEntryStats = {
+  cacheKey: string;
+  sizeBytes: number;
+  lastAccessedAt: number;
+  createdAt: number;
+};
+
+export type ClearScanCacheResult = {
+  removedEntries: number;
+  freedBytes: number;
+};
+
+export type RepairScanCacheResult = {
+  removedFiles: number;
+  removedEntries: number;
+  freedBytes: number;
+};
+
+export type CacheInvalidationReason =
+  | "parse_error"
+  | "schema_mismatch"
+  | "missing_file";
+
+export type CacheInvalidatedEvent = {
+  scanId: string;
+  cacheKey: string;
+  reason: CacheInvalidationReason;
+};
+
+export type WorkerLimits = {
+  scanWorkers: number;
+  exportWorkers: number;
+};
+
+export type AppSettings = {
+  maxCacheBytes: number | null;
+  scanWorkers: number | null;
+  exportWorkers: number | null;
+  fuzzyMode: FuzzyMode | null;
+  defaultAudioFormat: AudioFormat | null;
+  defaultImageFormat: ImageFormat | null;
+  lastScanOptions: Record<string, LastScanOptions>;
+};
+
+export type SettingsPatch = Partial<Omit<AppSettings, "lastScanOptions">>;
+
+export type AudioWaveform = {
+  peaks: number[];
+  durationSeconds: number;
+};
+
+export type AudioMetadata = {
+  durationMs: number | null;
+  sampleRate: number | null;
+  channels: number | null;
+  codec: string | null;
+  bitrate: number | null;
+};
+
+export type ModelReference = {
+  location: string;
+  assetId: string | null;
+};
+
+export type ModelTextureReference = ModelReference & {
+  variable: string;
+};
+
+export type ModelReferences = {
+  parents: ModelReference[];
+  textures: ModelTextureReference[];
+};
+
+export type SoundVariantKind = "file" | "event";
+
+export type SoundVariant = {
+  kind: SoundVariantKind;
+  location: string;
+  assetId: string | null;
+};
+
+export type SoundEvent = {
+  event: string;
+  subtitle: string | null;
+  variants: SoundVariant[];
+};
+
+export type DuplicateAssetGroup = {
+  byteSize: number;
+  assetIds: string[];
+};
+
+export type VanillaAssetVerification = {
+  checkedCount: number;
+  missingCount: number;
+  missingPaths: string[];
+};
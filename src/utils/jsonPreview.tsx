@@ -12,6 +12,12 @@ export function decodePreviewJson(base64: string): string {
   }
 }
 
+export function decodePreviewText(base64: string): string {
+  const binary = atob(base64);
+  const bytes = Uint8Array.from(binary, (character) => character.charCodeAt(0));
+  return new TextDecoder().decode(bytes);
+}
+
 export function renderHighlightedJson(value: string): ReactElement[] {
   const tokenRegex =
     /(\"(?:\\u[a-fA-F0-9]{4}|\\[^u]|[^\\\"])*\"\s*:?)|\\b(true|false|null)\\b|-?\\d+(?:\\.\\d+)?(?:[eE][+\\-]?\\d+)?/g;